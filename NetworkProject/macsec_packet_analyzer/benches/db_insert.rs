@@ -0,0 +1,88 @@
+//! Flow persistence: one `insert_flow` per flow vs one `insert_flow_batch`
+//!
+//! Writes 10,000 flows to a fresh on-disk SQLite database each way, the
+//! end-of-capture case. Uses only `std::time::Instant`:
+//!
+//! ```text
+//! cargo bench --bench db_insert
+//! ```
+
+use macsec_packet_analyzer::db::{Database, DatabaseConfig};
+use macsec_packet_analyzer::types::{FlowId, FlowStats};
+use std::time::{Duration, Instant};
+
+const FLOWS: u64 = 10_000;
+
+fn flow_stats(sci: u64) -> FlowStats {
+    FlowStats {
+        flow_id: FlowId::MACsec { sci, an: 0 },
+        packets_received: 1_000 + sci,
+        gaps_detected: sci % 3,
+        total_lost_packets: sci % 7,
+        first_sequence: Some(1),
+        last_sequence: Some(1_000 + sci as u32),
+        min_gap: Some(1),
+        max_gap: Some(4),
+        an_rotations: 0,
+        total_bytes: 100_000 + sci,
+        first_timestamp: None,
+        last_timestamp: None,
+        min_inter_arrival: None,
+        max_inter_arrival: None,
+        avg_inter_arrival: None,
+        protocol_distribution: Default::default(),
+        protocol_version_distribution: Default::default(),
+        dscp_distribution: Default::default(),
+        ecn_congestion_signals: 0,
+        estimated_owd_us: None,
+        owd_std_dev_us: None,
+        inter_arrival_variance_us2: 0,
+        std_dev_us: 0,
+        burstiness_index: 0.0,
+        pct_gaps_recovered: 0.0,
+        duplicate_packets: 0,
+        loss_rate_ema: 0.0,
+        loss_rate_instantaneous: 0.0,
+    }
+}
+
+/// Time `write` against a new database file, removed afterwards
+fn run(name: &str, flows: &[FlowStats], write: impl Fn(&mut Database, &[FlowStats])) -> Duration {
+    let path = std::env::temp_dir().join(format!("db_insert_{}_{}.db", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut db = Database::open(&DatabaseConfig::sqlite(path.to_str().unwrap())).unwrap();
+    db.initialize().unwrap();
+
+    let start = Instant::now();
+    write(&mut db, flows);
+    let elapsed = start.elapsed();
+
+    drop(db);
+    let _ = std::fs::remove_file(&path);
+    elapsed
+}
+
+fn main() {
+    let flows: Vec<FlowStats> = (0..FLOWS).map(flow_stats).collect();
+
+    let single = run("single", &flows, |db, flows| {
+        for stats in flows {
+            db.insert_flow(stats).unwrap();
+        }
+    });
+    let batch = run("batch", &flows, |db, flows| {
+        assert_eq!(db.insert_flow_batch(flows).unwrap(), FLOWS);
+    });
+
+    for (label, elapsed) in [("insert_flow", single), ("insert_flow_batch", batch)] {
+        println!(
+            "{:<18} {:>10.1} ms  {:>8.2} µs/flow",
+            label,
+            elapsed.as_secs_f64() * 1e3,
+            elapsed.as_nanos() as f64 / FLOWS as f64 / 1e3
+        );
+    }
+    let speedup = single.as_secs_f64() / batch.as_secs_f64();
+    println!("batch speedup: {:.1}x", speedup);
+}
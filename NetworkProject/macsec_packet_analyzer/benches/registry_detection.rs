@@ -0,0 +1,87 @@
+//! Registry detection latency: priority ordering vs priority/cost ordering
+//!
+//! Runs the full detection path (flow cache cleared every pass) over a mixed
+//! MACsec / IPsec / TCP / UDP stream. Uses only `std::time::Instant` so it runs
+//! on stable without extra dependencies:
+//!
+//! ```text
+//! cargo bench --bench registry_detection
+//! ```
+
+use macsec_packet_analyzer::{ParserOrdering, ProtocolRegistry};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const PASSES: usize = 2_000;
+
+fn ipv4_frame(protocol: u8, flow: u16) -> Vec<u8> {
+    let mut packet = vec![0u8; 64];
+    packet[12] = 0x08; // EtherType: IPv4
+    packet[13] = 0x00;
+    packet[14] = 0x45; // Version 4, IHL 5
+    packet[23] = protocol;
+    packet[26..30].copy_from_slice(&[192, 168, 1, 10]);
+    packet[30..34].copy_from_slice(&[10, 0, 0, 1]);
+    // SPI for ESP, source port for TCP/UDP
+    packet[34..36].copy_from_slice(&flow.to_be_bytes());
+    packet[36..38].copy_from_slice(&80u16.to_be_bytes());
+    packet[41] = 1; // ESP sequence = 1
+    packet
+}
+
+fn macsec_frame(flow: u16) -> Vec<u8> {
+    let mut packet = vec![0u8; 64];
+    packet[12] = 0x88; // EtherType: MACsec
+    packet[13] = 0xE5;
+    packet[14] = 0x20; // TCI: SC bit set
+    packet[17] = 1; // Packet number = 1
+    packet[18..20].copy_from_slice(&flow.to_be_bytes());
+    packet
+}
+
+fn mixed_stream() -> Vec<Vec<u8>> {
+    (0..64u16)
+        .flat_map(|flow| {
+            [
+                macsec_frame(flow),
+                ipv4_frame(50, flow), // ESP
+                ipv4_frame(6, flow),  // TCP
+                ipv4_frame(17, flow), // UDP
+            ]
+        })
+        .collect()
+}
+
+fn run(ordering: ParserOrdering, stream: &[Vec<u8>]) -> Duration {
+    let registry = ProtocolRegistry::with_ordering(ordering);
+
+    // Warm up
+    for packet in stream {
+        let _ = black_box(registry.detect_and_parse(packet));
+    }
+
+    let start = Instant::now();
+    for _ in 0..PASSES {
+        registry.clear_cache();
+        for packet in stream {
+            let _ = black_box(registry.detect_and_parse(black_box(packet)));
+        }
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let stream = mixed_stream();
+    let packets = (PASSES * stream.len()) as f64;
+
+    for ordering in [ParserOrdering::Priority, ParserOrdering::PriorityPerCost] {
+        let registry = ProtocolRegistry::with_ordering(ordering);
+        let elapsed = run(ordering, &stream);
+        println!(
+            "{:<16?} order={:?}  {:>8.1} ns/packet",
+            ordering,
+            registry.parser_order(),
+            elapsed.as_nanos() as f64 / packets
+        );
+    }
+}
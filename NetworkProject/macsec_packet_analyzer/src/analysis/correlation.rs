@@ -0,0 +1,291 @@
+//! Gap correlation across multiple capture points
+//!
+//! When the same traffic is captured on both sides of a device (e.g. the
+//! ingress and egress of a firewall), comparing the gaps seen at each tap
+//! tells us where the packets went missing:
+//!
+//! | Ingress gap | Egress gap | Cause          |
+//! |-------------|------------|----------------|
+//! | yes         | yes        | `Upstream`     |
+//! | no          | yes        | `FirewallDrop` |
+//! | yes         | no         | `TapArtifact`  |
+//!
+//! A gap seen only at ingress cannot be a real loss: the packets made it
+//! through to the egress tap, so the ingress tap itself missed them.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::capture::{FileCapture, PacketSource};
+use crate::error::{AnalysisError, CaptureError};
+use crate::protocol::SequenceParser;
+use crate::types::{AnalyzedPacket, FlowId, SequenceGap};
+
+use super::flow::FlowTracker;
+
+/// Default maximum timestamp difference for two gaps to be the same event
+pub const DEFAULT_TIMESTAMP_TOLERANCE: Duration = Duration::from_millis(100);
+
+/// Where a correlated gap most likely originated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapCause {
+    /// Packets were already missing before the ingress tap
+    Upstream,
+    /// Packets reached the ingress tap but not the egress tap
+    FirewallDrop,
+    /// Only the ingress tap missed the packets (capture loss, not network loss)
+    TapArtifact,
+}
+
+/// A gap matched (or not) between the ingress and egress captures
+#[derive(Debug, Clone)]
+pub struct CorrelatedGap {
+    pub ingress_gap: Option<SequenceGap>,
+    pub egress_gap: Option<SequenceGap>,
+    pub cause: GapCause,
+}
+
+/// Correlates gaps between an ingress and an egress pcap file
+///
+/// The first capture added is treated as ingress, the second as egress.
+pub struct MultiFileAnalyzer<P: SequenceParser> {
+    captures: Vec<(String, FileCapture)>,
+    parser: P,
+    timestamp_tolerance: Duration,
+}
+
+impl<P: SequenceParser> MultiFileAnalyzer<P> {
+    pub fn new(parser: P) -> Self {
+        Self {
+            captures: Vec::new(),
+            parser,
+            timestamp_tolerance: DEFAULT_TIMESTAMP_TOLERANCE,
+        }
+    }
+
+    /// Add a labelled capture (ingress first, then egress)
+    pub fn add_capture(&mut self, label: impl Into<String>, capture: FileCapture) -> &mut Self {
+        self.captures.push((label.into(), capture));
+        self
+    }
+
+    /// Set how far apart two gap timestamps may be and still match
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.timestamp_tolerance = tolerance;
+        self
+    }
+
+    /// Labels of the captures in the order they were added
+    pub fn labels(&self) -> Vec<&str> {
+        self.captures.iter().map(|(label, _)| label.as_str()).collect()
+    }
+
+    /// Read both captures to the end and correlate their gaps
+    pub fn analyze(&mut self) -> Result<Vec<CorrelatedGap>, AnalysisError> {
+        if self.captures.len() != 2 {
+            return Err(CaptureError::UnsupportedOperation(format!(
+                "MultiFileAnalyzer needs exactly 2 captures (ingress, egress), got {}",
+                self.captures.len()
+            ))
+            .into());
+        }
+
+        let mut gaps_per_capture = Vec::with_capacity(2);
+        for (_, capture) in self.captures.iter_mut() {
+            let mut packets = Vec::new();
+            while let Some(raw_packet) = capture.next_packet()? {
+                if let Some(seq_info) = self.parser.parse_sequence(&raw_packet.data)? {
+                    packets.push(AnalyzedPacket {
+                        sequence_number: seq_info.sequence_number,
+                        flow_id: seq_info.flow_id,
+                        timestamp: raw_packet.timestamp,
+                        payload_length: seq_info.payload_length,
+                        proto_version: seq_info.proto_version,
+                        dscp: seq_info.dscp,
+                        ecn: seq_info.ecn,
+                        raw_data: None,
+                    });
+                }
+            }
+            gaps_per_capture.push(detect_gaps(packets));
+        }
+
+        let egress = gaps_per_capture.pop().unwrap_or_default();
+        let ingress = gaps_per_capture.pop().unwrap_or_default();
+        Ok(correlate_gaps(ingress, egress, self.timestamp_tolerance))
+    }
+}
+
+/// Run packets through a fresh `FlowTracker` and collect the gaps
+///
+/// Gap timestamps are set to the capture time of the packet that revealed
+/// the gap, so gaps from different files can be compared in time.
+pub fn detect_gaps<I: IntoIterator<Item = AnalyzedPacket>>(packets: I) -> Vec<SequenceGap> {
+    #[allow(unused_mut)] // process_packet takes &mut self without the async feature
+    let mut tracker = FlowTracker::new();
+    let mut gaps = Vec::new();
+
+    for packet in packets {
+        let timestamp = packet.timestamp;
+        if let Some(mut gap) = tracker.process_packet(packet) {
+            gap.timestamp = timestamp;
+            gaps.push(gap);
+        }
+    }
+
+    gaps
+}
+
+/// Match ingress and egress gaps by flow, expected sequence and timestamp
+///
+/// Each gap is used at most once. Results are ordered by gap timestamp.
+pub fn correlate_gaps(
+    ingress: Vec<SequenceGap>,
+    egress: Vec<SequenceGap>,
+    tolerance: Duration,
+) -> Vec<CorrelatedGap> {
+    // Index egress gaps by (flow, expected) for lookup
+    let mut egress_index: HashMap<(FlowId, u32), Vec<usize>> = HashMap::new();
+    for (idx, gap) in egress.iter().enumerate() {
+        egress_index
+            .entry((gap.flow_id.clone(), gap.expected))
+            .or_default()
+            .push(idx);
+    }
+
+    let mut egress_used = vec![false; egress.len()];
+    let mut correlated = Vec::new();
+
+    for ingress_gap in ingress {
+        let matched = egress_index
+            .get(&(ingress_gap.flow_id.clone(), ingress_gap.expected))
+            .and_then(|candidates| {
+                candidates.iter().copied().find(|&idx| {
+                    !egress_used[idx]
+                        && within_tolerance(&ingress_gap, &egress[idx], tolerance)
+                })
+            });
+
+        match matched {
+            Some(idx) => {
+                egress_used[idx] = true;
+                correlated.push(CorrelatedGap {
+                    ingress_gap: Some(ingress_gap),
+                    egress_gap: Some(egress[idx].clone()),
+                    cause: GapCause::Upstream,
+                });
+            }
+            None => correlated.push(CorrelatedGap {
+                ingress_gap: Some(ingress_gap),
+                egress_gap: None,
+                cause: GapCause::TapArtifact,
+            }),
+        }
+    }
+
+    for (idx, egress_gap) in egress.into_iter().enumerate() {
+        if !egress_used[idx] {
+            correlated.push(CorrelatedGap {
+                ingress_gap: None,
+                egress_gap: Some(egress_gap),
+                cause: GapCause::FirewallDrop,
+            });
+        }
+    }
+
+    correlated.sort_by_key(|c| {
+        c.ingress_gap
+            .as_ref()
+            .or(c.egress_gap.as_ref())
+            .map(|g| g.timestamp)
+    });
+    correlated
+}
+
+fn within_tolerance(a: &SequenceGap, b: &SequenceGap, tolerance: Duration) -> bool {
+    let diff = match a.timestamp.duration_since(b.timestamp) {
+        Ok(d) => d,
+        Err(e) => e.duration(),
+    };
+    diff <= tolerance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn flow() -> FlowId {
+        FlowId::MACsec { sci: 0xABCD, an: 0 }
+    }
+
+    /// Build a packet stream with 1ms spacing, offset by `offset_us`
+    fn sequence(seqs: &[u32], offset_us: u64) -> Vec<AnalyzedPacket> {
+        let base = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        seqs.iter()
+            .map(|&seq| AnalyzedPacket {
+                sequence_number: seq,
+                flow_id: flow(),
+                timestamp: base
+                    + Duration::from_millis(seq as u64)
+                    + Duration::from_micros(offset_us),
+                payload_length: 100,
+                proto_version: None,
+                dscp: None,
+                ecn: None,
+                raw_data: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_gaps_uses_packet_timestamp() {
+        let gaps = detect_gaps(sequence(&[1, 2, 3, 5], 0));
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].expected, 4);
+        assert_eq!(
+            gaps[0].timestamp,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_000) + Duration::from_millis(5)
+        );
+    }
+
+    #[test]
+    fn test_upstream_and_firewall_drop() {
+        // Ingress lost 4 upstream; egress also lost 4, plus 8 at the firewall
+        let ingress = detect_gaps(sequence(&[1, 2, 3, 5, 6, 7, 8, 9], 0));
+        let egress = detect_gaps(sequence(&[1, 2, 3, 5, 6, 7, 9], 50));
+
+        let correlated = correlate_gaps(ingress, egress, DEFAULT_TIMESTAMP_TOLERANCE);
+        assert_eq!(correlated.len(), 2);
+
+        assert_eq!(correlated[0].cause, GapCause::Upstream);
+        assert_eq!(correlated[0].ingress_gap.as_ref().unwrap().expected, 4);
+        assert_eq!(correlated[0].egress_gap.as_ref().unwrap().expected, 4);
+
+        assert_eq!(correlated[1].cause, GapCause::FirewallDrop);
+        assert!(correlated[1].ingress_gap.is_none());
+        assert_eq!(correlated[1].egress_gap.as_ref().unwrap().expected, 8);
+    }
+
+    #[test]
+    fn test_ingress_only_gap_is_tap_artifact() {
+        let ingress = detect_gaps(sequence(&[1, 2, 4, 5], 0));
+        let egress = detect_gaps(sequence(&[1, 2, 3, 4, 5], 0));
+
+        let correlated = correlate_gaps(ingress, egress, DEFAULT_TIMESTAMP_TOLERANCE);
+        assert_eq!(correlated.len(), 1);
+        assert_eq!(correlated[0].cause, GapCause::TapArtifact);
+        assert!(correlated[0].egress_gap.is_none());
+    }
+
+    #[test]
+    fn test_gaps_outside_tolerance_do_not_match() {
+        let ingress = detect_gaps(sequence(&[1, 2, 3, 5], 0));
+        // Same gap, but seen 2 seconds later at egress
+        let egress = detect_gaps(sequence(&[1, 2, 3, 5], 2_000_000));
+
+        let correlated = correlate_gaps(ingress, egress, Duration::from_millis(10));
+        let causes: Vec<_> = correlated.iter().map(|c| c.cause).collect();
+        assert_eq!(causes, vec![GapCause::TapArtifact, GapCause::FirewallDrop]);
+    }
+}
@@ -1,25 +1,86 @@
-use std::collections::{BTreeMap, HashMap};
-use std::time::{SystemTime, Duration};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::{SystemTime, Duration, UNIX_EPOCH};
 
 #[cfg(feature = "async")]
 use dashmap::DashMap;
 
-use crate::types::{AnalyzedPacket, FlowId, FlowStats, SequenceGap};
+use std::io::Write;
+
+use crate::capture::PcapNgWriter;
+use crate::error::CaptureError;
+use crate::types::{
+    top_n_by_score, AnalyzedPacket, FlowGraph, FlowId, FlowMetric, FlowStats, SequenceGap,
+};
 
 /// Tracks packet sequences for multiple flows with reordering support
 #[cfg(not(feature = "async"))]
 pub struct FlowTracker {
     flows: HashMap<FlowId, FlowState>,
+    /// MACsec AN history per SCI, for key rotation counting
+    an_history: HashMap<u64, AnHistory>,
     #[allow(dead_code)]
     reorder_window_size: u32,
+    /// Weight given to each new gap in the loss-rate moving average
+    loss_ema_alpha: f64,
+    /// Called with every gap `process_packet` detects
+    gap_callback: Option<GapCallback>,
 }
 
 /// Concurrent flow tracker using DashMap for lock-free access
 #[cfg(feature = "async")]
 pub struct FlowTracker {
     flows: DashMap<FlowId, FlowState>,
+    /// MACsec AN history per SCI, for key rotation counting
+    an_history: DashMap<u64, AnHistory>,
     #[allow(dead_code)]
     reorder_window_size: u32,
+    /// Weight given to each new gap in the loss-rate moving average
+    loss_ema_alpha: f64,
+    /// Called with every gap `process_packet` detects
+    gap_callback: Option<GapCallback>,
+}
+
+/// Gap notification hook (see `FlowTracker::with_gap_callback`)
+#[cfg(not(feature = "async"))]
+type GapCallback = Box<dyn Fn(&SequenceGap) + Send>;
+
+/// Gap notification hook; `Sync` too, since the tracker is shared across tasks
+#[cfg(feature = "async")]
+type GapCallback = Box<dyn Fn(&SequenceGap) + Send + Sync>;
+
+/// Rolling window over which one-way delay samples are kept
+const OWD_WINDOW: Duration = Duration::from_secs(60);
+
+/// Default EWMA weight for new loss observations (10%)
+const DEFAULT_LOSS_EMA_ALPHA: f64 = 0.1;
+
+/// ECN codepoint for Congestion Experienced (RFC 3168)
+const ECN_CE: u8 = 0b11;
+
+/// Association Number history for one MACsec Secure Channel
+struct AnHistory {
+    last_an: u8,
+    rotations: u64,
+}
+
+impl AnHistory {
+    fn new(an: u8) -> Self {
+        Self {
+            last_an: an,
+            rotations: 0,
+        }
+    }
+
+    /// Record the AN of a packet on this channel
+    ///
+    /// A change of AN counts as a rotation, except a packet carrying the
+    /// previous AN (mod 4): that is a straggler from the retiring SA.
+    fn observe(&mut self, an: u8) {
+        if an != self.last_an && an != (self.last_an + 3) % 4 {
+            self.last_an = an;
+            self.rotations += 1;
+        }
+    }
 }
 
 /// Internal state for a single flow
@@ -30,22 +91,40 @@ struct FlowState {
     /// Expected next sequence number (for normal forward flow)
     expected_sequence: Option<u32>,
     packets_received: u64,
+    /// Sorted by timestamp (see `push_gap`)
     gaps: Vec<SequenceGap>,
+    /// Late packets received so far per gap, keyed by index into `gaps`
+    gap_fills: HashMap<usize, u32>,
+    /// Stale reorder-buffer entries pruned behind `expected_sequence`
+    duplicate_packets: u64,
+    /// EWMA of per-gap loss fraction, updated on each gap detection
+    loss_rate_ema: f64,
+    /// Loss fraction of the most recent gap
+    loss_rate_instantaneous: f64,
     first_sequence: Option<u32>,
     last_sequence: Option<u32>,
     min_gap: Option<u32>,
     max_gap: Option<u32>,
+    an_rotations: u64,
 
     // Enhanced statistics
     total_bytes: u64,
     first_timestamp: Option<SystemTime>,
     last_timestamp: Option<SystemTime>,
     previous_timestamp: Option<SystemTime>,  // For inter-arrival calculation
-    min_inter_arrival_us: Option<u64>,       // Microseconds
-    max_inter_arrival_us: Option<u64>,
-    total_inter_arrival_us: u64,             // For average calculation
+    min_inter_arrival_ns: Option<u64>,       // Nanoseconds (hardware timestamps resolve ~5ns)
+    max_inter_arrival_ns: Option<u64>,
+    total_inter_arrival_ns: u64,             // For average calculation
     inter_arrival_count: u64,                // Number of inter-arrival measurements
+    inter_arrival_mean_ns: f64,              // Welford running mean
+    inter_arrival_m2: f64,                   // Welford sum of squared deviations (ns²)
     protocol_distribution: HashMap<u8, u64>, // For GenericL3 flows
+    protocol_version_distribution: HashMap<u8, u64>,
+    dscp_distribution: HashMap<u8, u64>,
+    ecn_congestion_signals: u64,
+
+    // One-way delay estimation: (rx timestamp, OWD in microseconds)
+    owd_samples: VecDeque<(SystemTime, i64)>,
 }
 
 impl FlowState {
@@ -56,21 +135,442 @@ impl FlowState {
             expected_sequence: None,
             packets_received: 0,
             gaps: Vec::new(),
+            gap_fills: HashMap::new(),
+            duplicate_packets: 0,
+            loss_rate_ema: 0.0,
+            loss_rate_instantaneous: 0.0,
             first_sequence: None,
             last_sequence: None,
             min_gap: None,
             max_gap: None,
+            an_rotations: 0,
             // Enhanced statistics initialization
             total_bytes: 0,
             first_timestamp: None,
             last_timestamp: None,
             previous_timestamp: None,
-            min_inter_arrival_us: None,
-            max_inter_arrival_us: None,
-            total_inter_arrival_us: 0,
+            min_inter_arrival_ns: None,
+            max_inter_arrival_ns: None,
+            total_inter_arrival_ns: 0,
             inter_arrival_count: 0,
+            inter_arrival_mean_ns: 0.0,
+            inter_arrival_m2: 0.0,
             protocol_distribution: HashMap::new(),
+            protocol_version_distribution: HashMap::new(),
+            dscp_distribution: HashMap::new(),
+            ecn_congestion_signals: 0,
+            owd_samples: VecDeque::new(),
+        }
+    }
+
+    /// Record one inter-arrival measurement
+    ///
+    /// Updates min/max/total and the Welford running mean and M2, so the
+    /// variance is available without storing individual samples. Samples are
+    /// kept in nanoseconds so sub-microsecond gaps between hardware-timestamped
+    /// packets are not truncated to zero.
+    fn record_inter_arrival(&mut self, duration: Duration) {
+        let duration_ns = duration.as_nanos().min(u64::MAX as u128) as u64;
+        if self.min_inter_arrival_ns.is_none_or(|min| duration_ns < min) {
+            self.min_inter_arrival_ns = Some(duration_ns);
+        }
+        if self.max_inter_arrival_ns.is_none_or(|max| duration_ns > max) {
+            self.max_inter_arrival_ns = Some(duration_ns);
+        }
+
+        self.total_inter_arrival_ns = self.total_inter_arrival_ns.saturating_add(duration_ns);
+        self.inter_arrival_count += 1;
+
+        let sample = duration_ns as f64;
+        let delta = sample - self.inter_arrival_mean_ns;
+        self.inter_arrival_mean_ns += delta / self.inter_arrival_count as f64;
+        self.inter_arrival_m2 += delta * (sample - self.inter_arrival_mean_ns);
+    }
+
+    /// Population variance of inter-arrival times in microseconds squared
+    ///
+    /// Low for constant-bitrate flows, high for bursty traffic. Returns 0.0
+    /// until at least one inter-arrival time has been measured.
+    fn compute_throughput_variance(&self) -> f64 {
+        if self.inter_arrival_count == 0 {
+            return 0.0;
+        }
+        self.inter_arrival_m2 / self.inter_arrival_count as f64 / 1_000_000.0
+    }
+
+    /// Coefficient of variation (std dev / mean) of inter-arrival times
+    fn burstiness_index(&self) -> f64 {
+        if self.inter_arrival_mean_ns <= 0.0 {
+            return 0.0;
+        }
+        let variance_ns2 = self.inter_arrival_m2 / self.inter_arrival_count as f64;
+        variance_ns2.sqrt() / self.inter_arrival_mean_ns
+    }
+
+    /// Record a one-way delay sample for a sequenced packet (RFC 4656 style)
+    ///
+    /// The sender's transmit time is estimated from the packet number, assuming
+    /// a constant send rate equal to the average inter-arrival time:
+    /// `tx = first_timestamp + (seq - first_sequence) * avg_interval`.
+    ///
+    /// The result is only an absolute delay when sender and receiver clocks are
+    /// synchronized (PTP/GPS). Without synchronization it is relative to the
+    /// first packet of the flow, which is still useful for spotting queue build-up.
+    fn record_owd(&mut self, sequence: u32, rx_timestamp: SystemTime) {
+        let (Some(first_seq), Some(first_ts)) = (self.first_sequence, self.first_timestamp) else {
+            return;
+        };
+        if self.inter_arrival_count == 0 {
+            return;
+        }
+
+        let avg_interval_us = (self.total_inter_arrival_ns / self.inter_arrival_count / 1000) as i128;
+        let seq_distance = sequence.wrapping_sub(first_seq) as i128;
+        let tx_estimated_us = micros_since_epoch(first_ts) + seq_distance * avg_interval_us;
+        let owd_us = (micros_since_epoch(rx_timestamp) - tx_estimated_us)
+            .clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+
+        self.owd_samples.push_back((rx_timestamp, owd_us));
+
+        // Keep only samples within the rolling window of the newest one
+        while let Some(&(oldest, _)) = self.owd_samples.front() {
+            match rx_timestamp.duration_since(oldest) {
+                Ok(age) if age > OWD_WINDOW => {
+                    self.owd_samples.pop_front();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Population standard deviation of the OWD samples in the window
+    fn owd_std_dev_us(&self) -> Option<f64> {
+        if self.owd_samples.is_empty() {
+            return None;
+        }
+
+        let n = self.owd_samples.len() as f64;
+        let mean = self.owd_samples.iter().map(|&(_, owd)| owd as f64).sum::<f64>() / n;
+        let variance = self
+            .owd_samples
+            .iter()
+            .map(|&(_, owd)| (owd as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        Some(variance.sqrt())
+    }
+
+    /// Fold a newly detected gap into the loss-rate moving average
+    ///
+    /// A gap of `n` packets detected on arrival of the next packet means `n`
+    /// of `n + 1` were lost: `ema = alpha * n / (n + 1) + (1 - alpha) * ema`.
+    fn record_gap_loss(&mut self, gap_size: u32, alpha: f64) {
+        let loss = gap_size as f64 / (gap_size as f64 + 1.0);
+        self.loss_rate_instantaneous = loss;
+        self.loss_rate_ema = alpha * loss + (1.0 - alpha) * self.loss_rate_ema;
+    }
+
+    /// Count a late packet against the reported gap it falls into
+    ///
+    /// Once every missing sequence number of a gap has arrived, the gap's
+    /// `recovery_time` is set. Like the detection timestamp, the fill time is
+    /// taken from the local clock.
+    fn record_late_fill(&mut self, sequence: u32) {
+        // Recent gaps are the likeliest to be filled
+        let Some(index) = self
+            .gaps
+            .iter()
+            .rposition(|gap| sequence.wrapping_sub(gap.expected) < gap.gap_size)
+        else {
+            return;
+        };
+
+        let filled = self.gap_fills.entry(index).or_insert(0);
+        *filled += 1;
+        let gap = &mut self.gaps[index];
+        if *filled == gap.gap_size && gap.recovery_time.is_none() {
+            gap.recovery_time = Some(
+                SystemTime::now()
+                    .duration_since(gap.timestamp)
+                    .unwrap_or_default(),
+            );
+        }
+    }
+
+    /// Percentage of reported gaps fully filled by late packets
+    fn pct_gaps_recovered(&self) -> f64 {
+        if self.gaps.is_empty() {
+            return 0.0;
+        }
+        let recovered = self.gaps.iter().filter(|gap| gap.recovery_time.is_some()).count();
+        recovered as f64 * 100.0 / self.gaps.len() as f64
+    }
+
+    /// Append a gap, keeping `gaps` sorted by timestamp
+    ///
+    /// Gaps are stamped from the local clock, so this is a push unless the
+    /// clock stepped backwards.
+    fn push_gap(&mut self, gap: SequenceGap) {
+        let index = self.gaps.partition_point(|g| g.timestamp <= gap.timestamp);
+        if index < self.gaps.len() {
+            // Keep gap_fills pointing at the same gaps
+            self.gap_fills = self
+                .gap_fills
+                .drain()
+                .map(|(i, filled)| (if i >= index { i + 1 } else { i }, filled))
+                .collect();
+        }
+        self.gaps.insert(index, gap);
+    }
+
+    /// Count the packet's DSCP class and any ECN congestion mark
+    ///
+    /// Only CE counts as congestion; ECT(0) and ECT(1) just mark the
+    /// sender as ECN-capable.
+    fn record_qos(&mut self, dscp: Option<u8>, ecn: Option<u8>) {
+        if let Some(dscp) = dscp {
+            *self.dscp_distribution.entry(dscp).or_insert(0) += 1;
+        }
+        if ecn == Some(ECN_CE) {
+            self.ecn_congestion_signals += 1;
+        }
+    }
+
+    /// Gaps detected strictly after `since`, oldest first
+    fn gaps_since(&self, since: SystemTime) -> &[SequenceGap] {
+        let start = self.gaps.partition_point(|g| g.timestamp <= since);
+        &self.gaps[start..]
+    }
+
+    /// Remove reorder-buffer entries captured more than `max_age` before `now`
+    ///
+    /// Returns the number of entries removed.
+    fn prune_reorder_buffer(&mut self, now: SystemTime, max_age: Duration) -> u64 {
+        let expected = self.expected_sequence;
+        let before = self.reorder_buffer.len();
+        let mut duplicates = 0;
+
+        self.reorder_buffer.retain(|&seq, packet| {
+            let stale = now
+                .duration_since(packet.timestamp)
+                .is_ok_and(|age| age > max_age);
+            if stale && expected.is_some_and(|expected| seq < expected) {
+                duplicates += 1;
+            }
+            !stale
+        });
+
+        self.duplicate_packets += duplicates;
+        (before - self.reorder_buffer.len()) as u64
+    }
+
+    /// Fold in the state another tracker kept for the same flow
+    ///
+    /// Counters and distributions are summed, sequence and time ranges
+    /// widened, gap lists combined in timestamp order and the inter-arrival
+    /// statistics pooled. Each side only knows the gaps it saw itself: a
+    /// gap between the last packet of one and the first of the other is
+    /// not inferred.
+    fn merge(&mut self, other: FlowState) {
+        // Sequence tracking continues from whichever side got further
+        if other.highest_sequence > self.highest_sequence {
+            self.highest_sequence = other.highest_sequence;
+            self.expected_sequence = other.expected_sequence;
+        }
+        self.first_sequence = min_option(self.first_sequence, other.first_sequence);
+        self.last_sequence = self.last_sequence.max(other.last_sequence);
+        self.min_gap = min_option(self.min_gap, other.min_gap);
+        self.max_gap = self.max_gap.max(other.max_gap);
+        self.an_rotations = self.an_rotations.max(other.an_rotations);
+        for (seq, packet) in other.reorder_buffer {
+            self.reorder_buffer.entry(seq).or_insert(packet);
+        }
+
+        // Loss EMAs are weighted by the number of gaps behind each
+        let (own_gaps, other_gaps) = (self.gaps.len(), other.gaps.len());
+        if own_gaps + other_gaps > 0 {
+            self.loss_rate_ema = (self.loss_rate_ema * own_gaps as f64
+                + other.loss_rate_ema * other_gaps as f64)
+                / (own_gaps + other_gaps) as f64;
+        }
+        let other_latest = other.gaps.last().map(|gap| gap.timestamp);
+        if other_latest > self.gaps.last().map(|gap| gap.timestamp) {
+            self.loss_rate_instantaneous = other.loss_rate_instantaneous;
+        }
+
+        // Combine gap lists, keeping late-fill progress with its gap
+        let own = std::mem::take(&mut self.gaps).into_iter().enumerate();
+        let own: Vec<_> = own
+            .map(|(i, gap)| (gap, self.gap_fills.get(&i).copied()))
+            .collect();
+        let theirs = other
+            .gaps
+            .into_iter()
+            .enumerate()
+            .map(|(i, gap)| (gap, other.gap_fills.get(&i).copied()));
+        let mut combined: Vec<_> = own.into_iter().chain(theirs).collect();
+        combined.sort_by_key(|(gap, _)| gap.timestamp);
+        self.gap_fills.clear();
+        for (i, (gap, filled)) in combined.into_iter().enumerate() {
+            if let Some(filled) = filled {
+                self.gap_fills.insert(i, filled);
+            }
+            self.gaps.push(gap);
+        }
+
+        self.packets_received += other.packets_received;
+        self.duplicate_packets += other.duplicate_packets;
+        self.total_bytes += other.total_bytes;
+        self.ecn_congestion_signals += other.ecn_congestion_signals;
+        self.first_timestamp = min_option(self.first_timestamp, other.first_timestamp);
+        self.last_timestamp = self.last_timestamp.max(other.last_timestamp);
+        self.previous_timestamp = self.previous_timestamp.max(other.previous_timestamp);
+
+        // Pool the Welford statistics (Chan et al. parallel update)
+        self.min_inter_arrival_ns =
+            min_option(self.min_inter_arrival_ns, other.min_inter_arrival_ns);
+        self.max_inter_arrival_ns = self.max_inter_arrival_ns.max(other.max_inter_arrival_ns);
+        self.total_inter_arrival_ns = self
+            .total_inter_arrival_ns
+            .saturating_add(other.total_inter_arrival_ns);
+        let count = self.inter_arrival_count + other.inter_arrival_count;
+        if count > 0 {
+            let n_a = self.inter_arrival_count as f64;
+            let n_b = other.inter_arrival_count as f64;
+            let delta = other.inter_arrival_mean_ns - self.inter_arrival_mean_ns;
+            self.inter_arrival_mean_ns += delta * n_b / count as f64;
+            self.inter_arrival_m2 +=
+                other.inter_arrival_m2 + delta * delta * n_a * n_b / count as f64;
+        }
+        self.inter_arrival_count = count;
+
+        for (key, count) in other.protocol_distribution {
+            *self.protocol_distribution.entry(key).or_insert(0) += count;
+        }
+        for (key, count) in other.protocol_version_distribution {
+            *self.protocol_version_distribution.entry(key).or_insert(0) += count;
+        }
+        for (key, count) in other.dscp_distribution {
+            *self.dscp_distribution.entry(key).or_insert(0) += count;
+        }
+
+        // OWD samples in capture order, within the window of the newest one
+        let mut samples: Vec<_> = self
+            .owd_samples
+            .drain(..)
+            .chain(other.owd_samples)
+            .collect();
+        samples.sort_by_key(|&(rx, _)| rx);
+        if let Some(&(newest, _)) = samples.last() {
+            samples.retain(|&(rx, _)| newest.duration_since(rx).is_ok_and(|age| age <= OWD_WINDOW));
+        }
+        self.owd_samples = samples.into();
+    }
+
+    /// Write buffered packets that carry raw bytes, lowest sequence first
+    fn export_to_pcap<W: Write>(&self, writer: &mut PcapNgWriter<W>) -> Result<u64, CaptureError> {
+        let mut written = 0;
+        for packet in self.reorder_buffer.values() {
+            if let Some(data) = &packet.raw_data {
+                writer.write_packet(data, packet.timestamp)?;
+                written += 1;
+            }
         }
+        Ok(written)
+    }
+
+    /// Value of `metric` for this flow, same as `metric.value(&self.to_stats(..))`
+    ///
+    /// Reads the counters directly so ranking doesn't build a snapshot per flow.
+    fn metric_value(&self, metric: FlowMetric) -> f64 {
+        let total_lost: u64 = self.gaps.iter().map(|gap| gap.gap_size as u64).sum();
+        let duration_secs = self
+            .first_timestamp
+            .zip(self.last_timestamp)
+            .and_then(|(first, last)| last.duration_since(first).ok())
+            .map(|d| d.as_secs_f64());
+        metric.value_from_counters(
+            self.packets_received,
+            self.total_bytes,
+            self.gaps.len() as u64,
+            total_lost,
+            duration_secs,
+        )
+    }
+
+    /// Build the public statistics snapshot for this flow
+    fn to_stats(&self, flow_id: &FlowId) -> FlowStats {
+        let total_lost: u64 = self.gaps.iter().map(|gap| gap.gap_size as u64).sum();
+
+        // Calculate average inter-arrival time
+        let avg_inter_arrival = if self.inter_arrival_count > 0 {
+            Some(Duration::from_nanos(
+                self.total_inter_arrival_ns / self.inter_arrival_count,
+            ))
+        } else {
+            None
+        };
+
+        // Convert nanoseconds back to Duration for min/max
+        let min_inter_arrival = self.min_inter_arrival_ns.map(Duration::from_nanos);
+        let max_inter_arrival = self.max_inter_arrival_ns.map(Duration::from_nanos);
+
+        FlowStats {
+            flow_id: flow_id.clone(),
+            packets_received: self.packets_received,
+            gaps_detected: self.gaps.len() as u64,
+            total_lost_packets: total_lost,
+            first_sequence: self.first_sequence,
+            last_sequence: self.last_sequence,
+            min_gap: self.min_gap,
+            max_gap: self.max_gap,
+            an_rotations: self.an_rotations,
+            // Enhanced statistics
+            total_bytes: self.total_bytes,
+            first_timestamp: self.first_timestamp,
+            last_timestamp: self.last_timestamp,
+            min_inter_arrival,
+            max_inter_arrival,
+            avg_inter_arrival,
+            protocol_distribution: self.protocol_distribution.clone(),
+            protocol_version_distribution: self.protocol_version_distribution.clone(),
+            dscp_distribution: self.dscp_distribution.clone(),
+            ecn_congestion_signals: self.ecn_congestion_signals,
+            estimated_owd_us: self.owd_samples.back().map(|&(_, owd)| owd),
+            owd_std_dev_us: self.owd_std_dev_us(),
+            inter_arrival_variance_us2: self.compute_throughput_variance().round() as u64,
+            std_dev_us: self.compute_throughput_variance().sqrt().round() as u64,
+            burstiness_index: self.burstiness_index(),
+            pct_gaps_recovered: self.pct_gaps_recovered(),
+            duplicate_packets: self.duplicate_packets,
+            loss_rate_ema: self.loss_rate_ema,
+            loss_rate_instantaneous: self.loss_rate_instantaneous,
+        }
+    }
+}
+
+/// Keep a loss EMA weight in `(0, 1]`, falling back to the default for NaN
+fn clamp_alpha(alpha: f64) -> f64 {
+    if alpha.is_nan() {
+        DEFAULT_LOSS_EMA_ALPHA
+    } else {
+        alpha.clamp(f64::MIN_POSITIVE, 1.0)
+    }
+}
+
+/// The smaller of two optional values, ignoring `None`
+fn min_option<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Signed microseconds since the Unix epoch (negative before 1970)
+fn micros_since_epoch(t: SystemTime) -> i128 {
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_micros() as i128,
+        Err(e) => -(e.duration().as_micros() as i128),
     }
 }
 
@@ -84,13 +584,42 @@ impl FlowTracker {
     pub fn with_window_size(window_size: u32) -> Self {
         Self {
             flows: HashMap::new(),
+            an_history: HashMap::new(),
             reorder_window_size: window_size,
+            loss_ema_alpha: DEFAULT_LOSS_EMA_ALPHA,
+            gap_callback: None,
         }
     }
 
+    /// Set the weight of each new gap in the loss-rate moving average
+    ///
+    /// Clamped to `(0, 1]`; 1.0 makes the average track the last gap only.
+    pub fn with_loss_ema_alpha(mut self, alpha: f64) -> Self {
+        self.loss_ema_alpha = clamp_alpha(alpha);
+        self
+    }
+
+    /// Call `callback` with every gap detected by `process_packet`
+    ///
+    /// Runs synchronously before `process_packet` returns, so keep it cheap
+    /// (send on a channel, bump a counter). Replaces any earlier callback.
+    pub fn with_gap_callback<F: Fn(&SequenceGap) + Send + 'static>(mut self, callback: F) -> Self {
+        self.gap_callback = Some(Box::new(callback));
+        self
+    }
+
     /// Process a packet and detect gaps
     /// Returns Some(gap) if a gap is detected, None otherwise
     pub fn process_packet(&mut self, packet: AnalyzedPacket) -> Option<SequenceGap> {
+        let gap = self.track_packet(packet);
+        if let (Some(gap), Some(callback)) = (&gap, &self.gap_callback) {
+            callback(gap);
+        }
+        gap
+    }
+
+    /// Update flow state for one packet; the body of `process_packet`
+    fn track_packet(&mut self, packet: AnalyzedPacket) -> Option<SequenceGap> {
         let flow_id = packet.flow_id.clone();
 
         // Ensure flow exists
@@ -105,24 +634,29 @@ impl FlowTracker {
             let state = self.flows.get_mut(&flow_id).unwrap();
             state.packets_received += 1;
 
+            // Track MACsec key rotations (AN changes on the same SCI)
+            if let FlowId::MACsec { sci, an } = &flow_id {
+                let history = self
+                    .an_history
+                    .entry(*sci)
+                    .or_insert_with(|| AnHistory::new(*an));
+                history.observe(*an);
+                state.an_rotations = history.rotations;
+            }
+
             // Track bytes received
             state.total_bytes += packet.payload_length as u64;
 
+            // Track protocol versions
+            if let Some(version) = packet.proto_version {
+                *state.protocol_version_distribution.entry(version).or_insert(0) += 1;
+            }
+            state.record_qos(packet.dscp, packet.ecn);
+
             // Track inter-arrival times
             if let Some(previous) = state.last_timestamp {
                 if let Ok(duration) = packet.timestamp.duration_since(previous) {
-                    let duration_us = duration.as_micros() as u64;
-
-                    // Update min/max inter-arrival times
-                    if state.min_inter_arrival_us.is_none() || duration_us < state.min_inter_arrival_us.unwrap() {
-                        state.min_inter_arrival_us = Some(duration_us);
-                    }
-                    if state.max_inter_arrival_us.is_none() || duration_us > state.max_inter_arrival_us.unwrap() {
-                        state.max_inter_arrival_us = Some(duration_us);
-                    }
-
-                    state.total_inter_arrival_us += duration_us;
-                    state.inter_arrival_count += 1;
+                    state.record_inter_arrival(duration);
                 }
             }
 
@@ -156,6 +690,7 @@ impl FlowTracker {
             let current_seq = packet.sequence_number;
             let highest = state.highest_sequence.unwrap();
             state.last_sequence = Some(current_seq);
+            state.record_owd(current_seq, packet.timestamp);
 
             // Check if this is the next expected packet
             if let Some(expected) = state.expected_sequence {
@@ -190,6 +725,7 @@ impl FlowTracker {
                         received: current_seq,
                         gap_size,
                         timestamp: SystemTime::now(),
+                        recovery_time: None,
                     });
 
                     // Update expected to skip over the gap
@@ -208,6 +744,7 @@ impl FlowTracker {
                             state.expected_sequence = Some(expected.wrapping_add(1));
                         }
                     }
+                    state.record_late_fill(current_seq);
                     state.reorder_buffer.insert(current_seq, packet);
                 }
             }
@@ -293,6 +830,7 @@ impl FlowTracker {
                 received,
                 gap_size,
                 timestamp: SystemTime::now(),
+                recovery_time: None,
             };
 
             return Some(gap);
@@ -305,44 +843,75 @@ impl FlowTracker {
     pub fn get_stats(&self) -> Vec<FlowStats> {
         self.flows
             .iter()
-            .map(|(flow_id, state)| {
-                let mut total_lost = 0u64;
-                for gap in &state.gaps {
-                    total_lost += gap.gap_size as u64;
-                }
+            .map(|(flow_id, state)| state.to_stats(flow_id))
+            .collect()
+    }
 
-                // Calculate average inter-arrival time
-                let avg_inter_arrival = if state.inter_arrival_count > 0 {
-                    Some(Duration::from_micros(
-                        state.total_inter_arrival_us / state.inter_arrival_count,
-                    ))
-                } else {
-                    None
-                };
+    /// Get statistics for a single flow, or `None` if it is unknown
+    ///
+    /// Only this flow's state is cloned, unlike `get_stats()`.
+    pub fn get_flow_snapshot(&self, flow_id: &FlowId) -> Option<FlowStats> {
+        self.flows.get(flow_id).map(|state| state.to_stats(flow_id))
+    }
 
-                // Convert microseconds back to Duration for min/max
-                let min_inter_arrival = state.min_inter_arrival_us.map(Duration::from_micros);
-                let max_inter_arrival = state.max_inter_arrival_us.map(Duration::from_micros);
+    /// Get statistics for the given flows only, in the order requested
+    ///
+    /// Unknown flow IDs are skipped.
+    pub fn get_stats_for_flows(&self, ids: &[FlowId]) -> Vec<FlowStats> {
+        ids.iter()
+            .filter_map(|flow_id| self.get_flow_snapshot(flow_id))
+            .collect()
+    }
 
-                FlowStats {
-                    flow_id: flow_id.clone(),
-                    packets_received: state.packets_received,
-                    gaps_detected: state.gaps.len() as u64,
-                    total_lost_packets: total_lost,
-                    first_sequence: state.first_sequence,
-                    last_sequence: state.last_sequence,
-                    min_gap: state.min_gap,
-                    max_gap: state.max_gap,
-                    // Enhanced statistics
-                    total_bytes: state.total_bytes,
-                    first_timestamp: state.first_timestamp,
-                    last_timestamp: state.last_timestamp,
-                    min_inter_arrival,
-                    max_inter_arrival,
-                    avg_inter_arrival,
-                    protocol_distribution: state.protocol_distribution.clone(),
-                }
-            })
+    /// Host graph of all `GenericL3` flows, see `FlowGraph`
+    pub fn get_flow_graph(&self) -> FlowGraph {
+        FlowGraph::from_stats(&self.get_stats())
+    }
+
+    /// Whether any packet has been seen for `id`
+    pub fn contains_flow(&self, id: &FlowId) -> bool {
+        self.flows.contains_key(id)
+    }
+
+    /// Number of flows tracked
+    pub fn flow_count(&self) -> usize {
+        self.flows.len()
+    }
+
+    /// IDs of all tracked flows, in no particular order
+    pub fn flow_ids(&self) -> Vec<FlowId> {
+        self.flows.keys().cloned().collect()
+    }
+
+    /// Get the `n` flows ranked highest by `metric`, in descending order
+    ///
+    /// Flows are scored from their counters when called, keeping a heap of
+    /// the best `n`; only those get a `FlowStats`. There is no heap kept up
+    /// to date in `process_packet`: rates and loss change for every flow as
+    /// time passes, not just the one a packet belongs to, and `n` varies
+    /// per call, so such a heap would go stale.
+    pub fn top_n_flows(&self, n: usize, metric: FlowMetric) -> Vec<FlowStats> {
+        top_n_by_score(
+            self.flows
+                .iter()
+                .map(|(flow_id, state)| (state.metric_value(metric), flow_id, flow_id)),
+            n,
+        )
+        .into_iter()
+        .map(|flow_id| self.flows[flow_id].to_stats(flow_id))
+        .collect()
+    }
+
+    /// Get all flows whose `metric` value is strictly above `threshold`
+    ///
+    /// For example, `above_threshold(FlowMetric::LossPercentage, 1.0)` returns
+    /// every flow losing more than 1% of its packets. Only matching flows
+    /// get a `FlowStats`.
+    pub fn above_threshold(&self, metric: FlowMetric, threshold: f64) -> Vec<FlowStats> {
+        self.flows
+            .iter()
+            .filter(|(_, state)| state.metric_value(metric) > threshold)
+            .map(|(flow_id, state)| state.to_stats(flow_id))
             .collect()
     }
 
@@ -354,9 +923,104 @@ impl FlowTracker {
             .collect()
     }
 
+    /// Get gaps detected after `since`, oldest first
+    ///
+    /// Pass the previous call's `latest_gap_timestamp()` to poll for new gaps.
+    pub fn get_gaps_since(&self, since: SystemTime) -> Vec<SequenceGap> {
+        let mut gaps: Vec<SequenceGap> = self
+            .flows
+            .values()
+            .flat_map(|state| state.gaps_since(since).iter().cloned())
+            .collect();
+        gaps.sort_by_key(|gap| gap.timestamp);
+        gaps
+    }
+
+    /// Timestamp of the most recent gap in any flow
+    pub fn latest_gap_timestamp(&self) -> Option<SystemTime> {
+        self.flows
+            .values()
+            .filter_map(|state| state.gaps.last().map(|gap| gap.timestamp))
+            .max()
+    }
+
+    /// Write the reorder-buffered packets of `flow_id` to `writer`, in sequence order
+    ///
+    /// Only packets still held in the reorder buffer are available, and only
+    /// those captured with raw storage enabled (`with_raw_storage(true)`);
+    /// others are skipped. Returns the number of packets written.
+    pub fn export_to_pcap<W: Write>(
+        &self,
+        flow_id: &FlowId,
+        writer: &mut PcapNgWriter<W>,
+    ) -> Result<u64, CaptureError> {
+        match self.flows.get(flow_id) {
+            Some(state) => state.export_to_pcap(writer),
+            None => Ok(0),
+        }
+    }
+
+    /// Drop reorder-buffer entries older than `max_age` from every flow
+    ///
+    /// Age is measured from each packet's capture timestamp to now. Pruned
+    /// entries behind the flow's expected sequence number are counted as
+    /// duplicates; the rest were orphaned by a gap that was never filled.
+    /// Returns the total number of entries removed.
+    pub fn prune_reorder_buffers(&mut self, max_age: Duration) -> u64 {
+        let now = SystemTime::now();
+        self.flows
+            .values_mut()
+            .map(|state| state.prune_reorder_buffer(now, max_age))
+            .sum()
+    }
+
+    /// Forget all state for one flow, e.g. after a MACsec SA rekey
+    ///
+    /// The next packet for `flow_id` starts a fresh flow: it is taken as
+    /// the first sequence number, so the restart is not reported as a gap.
+    /// Its statistics and recorded gaps are dropped; other flows, and the
+    /// key rotation count of the flow's SCI, are untouched. Returns `true`
+    /// if the flow was being tracked.
+    pub fn reset_flow(&mut self, flow_id: &FlowId) -> bool {
+        self.flows.remove(flow_id).is_some()
+    }
+
+    /// Combine the flows tracked by `other`, e.g. from another capture thread
+    ///
+    /// Flows only `other` has are taken over as they are. Flows both track
+    /// are merged, not overwritten: packet, byte and loss counts add up,
+    /// gap lists are combined and sequence ranges widened. A gap that falls
+    /// between the two trackers' packets is not detected. MACsec key
+    /// rotation counts keep the higher of the two per SCI. This tracker's
+    /// gap callback, window size and loss EMA weight are kept.
+    pub fn merge(&mut self, other: FlowTracker) {
+        for (flow_id, state) in other.flows {
+            match self.flows.entry(flow_id) {
+                std::collections::hash_map::Entry::Occupied(mut entry) => {
+                    entry.get_mut().merge(state)
+                }
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert(state);
+                }
+            }
+        }
+        for (sci, history) in other.an_history {
+            let own = self
+                .an_history
+                .entry(sci)
+                .or_insert_with(|| AnHistory::new(history.last_an));
+            if history.rotations > own.rotations {
+                *own = history;
+            }
+        }
+    }
+
     /// Record a gap detection (called internally)
     fn record_gap(&mut self, flow_id: &FlowId, gap: SequenceGap) {
+        let alpha = self.loss_ema_alpha;
         if let Some(state) = self.flows.get_mut(flow_id) {
+            state.record_gap_loss(gap.gap_size, alpha);
+
             // Update min/max gap
             if state.min_gap.is_none() || gap.gap_size < state.min_gap.unwrap() {
                 state.min_gap = Some(gap.gap_size);
@@ -365,7 +1029,7 @@ impl FlowTracker {
                 state.max_gap = Some(gap.gap_size);
             }
 
-            state.gaps.push(gap);
+            state.push_gap(gap);
         }
     }
 }
@@ -381,12 +1045,46 @@ impl FlowTracker {
     pub fn with_window_size(window_size: u32) -> Self {
         Self {
             flows: DashMap::new(),
+            an_history: DashMap::new(),
             reorder_window_size: window_size,
+            loss_ema_alpha: DEFAULT_LOSS_EMA_ALPHA,
+            gap_callback: None,
         }
     }
 
+    /// Set the weight of each new gap in the loss-rate moving average
+    ///
+    /// Clamped to `(0, 1]`; 1.0 makes the average track the last gap only.
+    pub fn with_loss_ema_alpha(mut self, alpha: f64) -> Self {
+        self.loss_ema_alpha = clamp_alpha(alpha);
+        self
+    }
+
+    /// Call `callback` with every gap detected by `process_packet`
+    ///
+    /// Runs synchronously before `process_packet` returns, on whichever
+    /// task processed the packet, and with no flow locked. Replaces any
+    /// earlier callback.
+    pub fn with_gap_callback<F: Fn(&SequenceGap) + Send + Sync + 'static>(
+        mut self,
+        callback: F,
+    ) -> Self {
+        self.gap_callback = Some(Box::new(callback));
+        self
+    }
+
     /// Process packet concurrently (lock-free with DashMap)
     pub fn process_packet(&self, packet: AnalyzedPacket) -> Option<SequenceGap> {
+        let gap = self.track_packet(packet);
+        if let (Some(gap), Some(callback)) = (&gap, &self.gap_callback) {
+            callback(gap);
+        }
+        gap
+    }
+
+    /// Update flow state for one packet; the body of `process_packet`.
+    /// The flow's DashMap entry is released when this returns.
+    fn track_packet(&self, packet: AnalyzedPacket) -> Option<SequenceGap> {
         let flow_id = packet.flow_id.clone();
 
         // DashMap handles locking internally per flow
@@ -398,24 +1096,29 @@ impl FlowTracker {
 
         state.packets_received += 1;
 
+        // Track MACsec key rotations (AN changes on the same SCI)
+        if let FlowId::MACsec { sci, an } = &flow_id {
+            let mut history = self
+                .an_history
+                .entry(*sci)
+                .or_insert_with(|| AnHistory::new(*an));
+            history.observe(*an);
+            state.an_rotations = history.rotations;
+        }
+
         // Track bytes received
         state.total_bytes += packet.payload_length as u64;
 
+        // Track protocol versions
+        if let Some(version) = packet.proto_version {
+            *state.protocol_version_distribution.entry(version).or_insert(0) += 1;
+        }
+        state.record_qos(packet.dscp, packet.ecn);
+
         // Track inter-arrival times
         if let Some(previous) = state.last_timestamp {
             if let Ok(duration) = packet.timestamp.duration_since(previous) {
-                let duration_us = duration.as_micros() as u64;
-
-                // Update min/max inter-arrival times
-                if state.min_inter_arrival_us.is_none() || duration_us < state.min_inter_arrival_us.unwrap() {
-                    state.min_inter_arrival_us = Some(duration_us);
-                }
-                if state.max_inter_arrival_us.is_none() || duration_us > state.max_inter_arrival_us.unwrap() {
-                    state.max_inter_arrival_us = Some(duration_us);
-                }
-
-                state.total_inter_arrival_us += duration_us;
-                state.inter_arrival_count += 1;
+                state.record_inter_arrival(duration);
             }
         }
 
@@ -449,6 +1152,7 @@ impl FlowTracker {
         let current_seq = packet.sequence_number;
         let highest = state.highest_sequence.unwrap();
         state.last_sequence = Some(current_seq);
+        state.record_owd(current_seq, packet.timestamp);
 
         // Check if next expected
         if let Some(expected) = state.expected_sequence {
@@ -476,6 +1180,7 @@ impl FlowTracker {
                     received: current_seq,
                     gap_size,
                     timestamp: SystemTime::now(),
+                    recovery_time: None,
                 });
 
                 state.expected_sequence = Some(current_seq.wrapping_add(1));
@@ -490,12 +1195,15 @@ impl FlowTracker {
                         state.expected_sequence = Some(expected.wrapping_add(1));
                     }
                 }
+                state.record_late_fill(current_seq);
                 state.reorder_buffer.insert(current_seq, packet);
             }
         }
 
         // Record gap if detected
         if let Some(ref gap_info) = gap {
+            state.record_gap_loss(gap_info.gap_size, self.loss_ema_alpha);
+
             // Update min/max gap stats
             if state.min_gap.is_none() || gap_info.gap_size < state.min_gap.unwrap() {
                 state.min_gap = Some(gap_info.gap_size);
@@ -503,7 +1211,7 @@ impl FlowTracker {
             if state.max_gap.is_none() || gap_info.gap_size > state.max_gap.unwrap() {
                 state.max_gap = Some(gap_info.gap_size);
             }
-            state.gaps.push(gap_info.clone());
+            state.push_gap(gap_info.clone());
         }
 
         gap
@@ -513,47 +1221,80 @@ impl FlowTracker {
     pub fn get_stats(&self) -> Vec<FlowStats> {
         self.flows
             .iter()
-            .map(|entry| {
-                let flow_id = entry.key();
-                let state = entry.value();
+            .map(|entry| entry.value().to_stats(entry.key()))
+            .collect()
+    }
 
-                let mut total_lost = 0u64;
-                for gap in &state.gaps {
-                    total_lost += gap.gap_size as u64;
-                }
+    /// Get statistics for a single flow, or `None` if it is unknown (concurrent-safe)
+    ///
+    /// Locks only the flow's shard and clones only its state, unlike `get_stats()`.
+    pub fn get_flow_snapshot(&self, flow_id: &FlowId) -> Option<FlowStats> {
+        self.flows.get(flow_id).map(|state| state.to_stats(flow_id))
+    }
 
-                // Calculate average inter-arrival time
-                let avg_inter_arrival = if state.inter_arrival_count > 0 {
-                    Some(Duration::from_micros(
-                        state.total_inter_arrival_us / state.inter_arrival_count,
-                    ))
-                } else {
-                    None
-                };
+    /// Get statistics for the given flows only, in the order requested (concurrent-safe)
+    ///
+    /// Each lookup locks a single shard; unknown flow IDs are skipped.
+    pub fn get_stats_for_flows(&self, ids: &[FlowId]) -> Vec<FlowStats> {
+        ids.iter()
+            .filter_map(|flow_id| self.get_flow_snapshot(flow_id))
+            .collect()
+    }
 
-                // Convert microseconds back to Duration for min/max
-                let min_inter_arrival = state.min_inter_arrival_us.map(Duration::from_micros);
-                let max_inter_arrival = state.max_inter_arrival_us.map(Duration::from_micros);
+    /// Host graph of all `GenericL3` flows (concurrent-safe)
+    pub fn get_flow_graph(&self) -> FlowGraph {
+        FlowGraph::from_stats(&self.get_stats())
+    }
 
-                FlowStats {
-                    flow_id: flow_id.clone(),
-                    packets_received: state.packets_received,
-                    gaps_detected: state.gaps.len() as u64,
-                    total_lost_packets: total_lost,
-                    first_sequence: state.first_sequence,
-                    last_sequence: state.last_sequence,
-                    min_gap: state.min_gap,
-                    max_gap: state.max_gap,
-                    // Enhanced statistics
-                    total_bytes: state.total_bytes,
-                    first_timestamp: state.first_timestamp,
-                    last_timestamp: state.last_timestamp,
-                    min_inter_arrival,
-                    max_inter_arrival,
-                    avg_inter_arrival,
-                    protocol_distribution: state.protocol_distribution.clone(),
-                }
-            })
+    /// Whether any packet has been seen for `id` (concurrent-safe)
+    pub fn contains_flow(&self, id: &FlowId) -> bool {
+        self.flows.contains_key(id)
+    }
+
+    /// Number of flows tracked (concurrent-safe)
+    pub fn flow_count(&self) -> usize {
+        self.flows.len()
+    }
+
+    /// IDs of all tracked flows, in no particular order (concurrent-safe)
+    ///
+    /// Only the keys are cloned; flow state is never copied.
+    pub fn flow_ids(&self) -> Vec<FlowId> {
+        self.flows.iter().map(|entry| entry.key().clone()).collect()
+    }
+
+    /// Get the `n` flows ranked highest by `metric`, in descending order (concurrent-safe)
+    ///
+    /// Scored on demand like the sync tracker's `top_n_flows`; only the
+    /// best `n` get a `FlowStats`. A flow removed between ranking and
+    /// snapshotting is left out.
+    pub fn top_n_flows(&self, n: usize, metric: FlowMetric) -> Vec<FlowStats> {
+        top_n_by_score(
+            self.flows.iter().map(|entry| {
+                let flow_id = entry.key().clone();
+                (entry.value().metric_value(metric), flow_id.clone(), flow_id)
+            }),
+            n,
+        )
+        .into_iter()
+        .filter_map(|flow_id| {
+            self.flows
+                .get(&flow_id)
+                .map(|state| state.to_stats(&flow_id))
+        })
+        .collect()
+    }
+
+    /// Get all flows whose `metric` value is strictly above `threshold` (concurrent-safe)
+    ///
+    /// For example, `above_threshold(FlowMetric::LossPercentage, 1.0)` returns
+    /// every flow losing more than 1% of its packets. Only matching flows
+    /// get a `FlowStats`.
+    pub fn above_threshold(&self, metric: FlowMetric, threshold: f64) -> Vec<FlowStats> {
+        self.flows
+            .iter()
+            .filter(|entry| entry.value().metric_value(metric) > threshold)
+            .map(|entry| entry.value().to_stats(entry.key()))
             .collect()
     }
 
@@ -564,6 +1305,96 @@ impl FlowTracker {
             .flat_map(|entry| entry.value().gaps.clone())
             .collect()
     }
+
+    /// Get gaps detected after `since`, oldest first (concurrent-safe)
+    ///
+    /// Pass the previous call's `latest_gap_timestamp()` to poll for new gaps.
+    pub fn get_gaps_since(&self, since: SystemTime) -> Vec<SequenceGap> {
+        let mut gaps: Vec<SequenceGap> = self
+            .flows
+            .iter()
+            .flat_map(|entry| entry.value().gaps_since(since).to_vec())
+            .collect();
+        gaps.sort_by_key(|gap| gap.timestamp);
+        gaps
+    }
+
+    /// Timestamp of the most recent gap in any flow (concurrent-safe)
+    pub fn latest_gap_timestamp(&self) -> Option<SystemTime> {
+        self.flows
+            .iter()
+            .filter_map(|entry| entry.value().gaps.last().map(|gap| gap.timestamp))
+            .max()
+    }
+
+    /// Write the reorder-buffered packets of `flow_id` to `writer`, in sequence order (concurrent-safe)
+    ///
+    /// Only packets still held in the reorder buffer are available, and only
+    /// those captured with raw storage enabled (`with_raw_storage(true)`);
+    /// others are skipped. Returns the number of packets written.
+    pub fn export_to_pcap<W: Write>(
+        &self,
+        flow_id: &FlowId,
+        writer: &mut PcapNgWriter<W>,
+    ) -> Result<u64, CaptureError> {
+        match self.flows.get(flow_id) {
+            Some(state) => state.export_to_pcap(writer),
+            None => Ok(0),
+        }
+    }
+
+    /// Drop reorder-buffer entries older than `max_age` from every flow (concurrent-safe)
+    ///
+    /// Age is measured from each packet's capture timestamp to now. Pruned
+    /// entries behind the flow's expected sequence number are counted as
+    /// duplicates; the rest were orphaned by a gap that was never filled.
+    /// Returns the total number of entries removed.
+    pub fn prune_reorder_buffers(&self, max_age: Duration) -> u64 {
+        let now = SystemTime::now();
+        self.flows
+            .iter_mut()
+            .map(|mut entry| entry.value_mut().prune_reorder_buffer(now, max_age))
+            .sum()
+    }
+
+    /// Forget all state for one flow, e.g. after a MACsec SA rekey
+    ///
+    /// The next packet for `flow_id` starts a fresh flow: it is taken as
+    /// the first sequence number, so the restart is not reported as a gap.
+    /// Its statistics and recorded gaps are dropped; other flows, and the
+    /// key rotation count of the flow's SCI, are untouched. Returns `true`
+    /// if the flow was being tracked.
+    pub fn reset_flow(&self, flow_id: &FlowId) -> bool {
+        self.flows.remove(flow_id).is_some()
+    }
+
+    /// Combine the flows tracked by `other`, e.g. from another capture thread
+    ///
+    /// Flows only `other` has are taken over as they are. Flows both track
+    /// are merged, not overwritten: packet, byte and loss counts add up,
+    /// gap lists are combined and sequence ranges widened. A gap that falls
+    /// between the two trackers' packets is not detected. MACsec key
+    /// rotation counts keep the higher of the two per SCI. This tracker's
+    /// gap callback, window size and loss EMA weight are kept.
+    pub fn merge(&self, other: FlowTracker) {
+        for (flow_id, state) in other.flows {
+            match self.flows.entry(flow_id) {
+                dashmap::mapref::entry::Entry::Occupied(mut entry) => entry.get_mut().merge(state),
+                dashmap::mapref::entry::Entry::Vacant(entry) => {
+                    entry.insert(state);
+                }
+            }
+        }
+        for (sci, history) in other.an_history {
+            let mut own = self
+                .an_history
+                .entry(sci)
+                .or_insert_with(|| AnHistory::new(history.last_an));
+            if history.rotations > own.rotations {
+                *own = history;
+            }
+        }
+    }
 }
 
 #[cfg(not(feature = "async"))]
@@ -583,6 +1414,7 @@ impl Default for FlowTracker {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::top_n_by_metric;
 
     fn create_packet(seq: u32, flow_id: FlowId) -> AnalyzedPacket {
         AnalyzedPacket {
@@ -590,13 +1422,17 @@ mod tests {
             flow_id,
             timestamp: SystemTime::now(),
             payload_length: 100,
+            proto_version: None,
+            dscp: None,
+            ecn: None,
+            raw_data: None,
         }
     }
 
     #[test]
     fn test_sequential_packets_no_gap() {
         let mut tracker = FlowTracker::new();
-        let flow = FlowId::MACsec { sci: 0x1234 };
+        let flow = FlowId::MACsec { sci: 0x1234, an: 0 };
 
         // Process sequential packets
         let gap1 = tracker.process_packet(create_packet(1, flow.clone()));
@@ -616,7 +1452,7 @@ mod tests {
     #[test]
     fn test_gap_detection() {
         let mut tracker = FlowTracker::new();
-        let flow = FlowId::MACsec { sci: 0x1234 };
+        let flow = FlowId::MACsec { sci: 0x1234, an: 0 };
 
         // Process packets with gap
         tracker.process_packet(create_packet(1, flow.clone()));
@@ -630,11 +1466,300 @@ mod tests {
         assert_eq!(gap_info.gap_size, 1);
     }
 
+    #[test]
+    fn test_gap_callback_sees_each_gap() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut tracker = FlowTracker::new().with_gap_callback(move |gap: &SequenceGap| {
+            tx.send(gap.clone()).unwrap();
+        });
+        let flow = FlowId::MACsec { sci: 0x1234, an: 0 };
+
+        let mut returned = Vec::new();
+        for seq in [1, 2, 4, 5, 3, 9] {
+            returned.extend(tracker.process_packet(create_packet(seq, flow.clone())));
+        }
+
+        // Called once per returned gap, with the same gap, before returning
+        let notified: Vec<SequenceGap> = rx.try_iter().collect();
+        assert_eq!(notified.len(), 2);
+        assert_eq!(notified.len(), returned.len());
+        for (seen, gap) in notified.iter().zip(&returned) {
+            assert_eq!((seen.expected, seen.received), (gap.expected, gap.received));
+        }
+    }
+
+    #[test]
+    fn test_reset_flow_after_rekey() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x1234, an: 0 };
+        let other = FlowId::MACsec { sci: 0x9999, an: 0 };
+
+        for seq in 1000..1010 {
+            tracker.process_packet(create_packet(seq, flow.clone()));
+        }
+        tracker.process_packet(create_packet(1, other.clone()));
+
+        assert!(tracker.reset_flow(&flow));
+        assert!(!tracker.contains_flow(&flow));
+        assert!(!tracker.reset_flow(&flow));
+
+        // Sequence numbers restart at 0 after the rekey: no gap
+        for seq in 0..5 {
+            let gap = tracker.process_packet(create_packet(seq, flow.clone()));
+            assert!(gap.is_none());
+        }
+        let stats = tracker.get_stats_for_flows(std::slice::from_ref(&flow));
+        assert_eq!(stats[0].packets_received, 5);
+        assert_eq!(stats[0].gaps_detected, 0);
+
+        // Gap detection still works on the restarted flow
+        assert!(tracker.process_packet(create_packet(7, flow)).is_some());
+
+        // The other flow kept its state
+        let gap = tracker.process_packet(create_packet(2, other.clone()));
+        assert!(gap.is_none());
+        assert!(tracker.process_packet(create_packet(4, other)).is_some());
+    }
+
+    #[test]
+    fn test_merge_trackers_from_parallel_threads() {
+        let flow = FlowId::MACsec { sci: 0x1234, an: 0 };
+        let only_b = FlowId::MACsec { sci: 0x4321, an: 1 };
+
+        // Each tracker sees one half of the flow
+        let mut a = FlowTracker::new();
+        for seq in (1..=50).filter(|&seq| seq != 10) {
+            a.process_packet(create_packet(seq, flow.clone()));
+        }
+        let mut b = FlowTracker::new();
+        for seq in (51..=100).filter(|&seq| seq != 70 && seq != 71) {
+            b.process_packet(create_packet(seq, flow.clone()));
+        }
+        b.process_packet(create_packet(1, only_b.clone()));
+
+        a.merge(b);
+
+        let stats = a.get_stats_for_flows(std::slice::from_ref(&flow));
+        assert_eq!(stats[0].packets_received, 97);
+        assert_eq!(stats[0].gaps_detected, 2);
+        assert_eq!(stats[0].total_lost_packets, 3);
+        assert_eq!(stats[0].total_bytes, 9700);
+        assert_eq!(stats[0].first_sequence, Some(1));
+        assert_eq!(stats[0].last_sequence, Some(100));
+        assert!(a.contains_flow(&only_b));
+
+        // The merged flow continues from the higher half
+        assert!(a.process_packet(create_packet(101, flow)).is_none());
+    }
+
+    #[test]
+    fn test_gap_recovery_by_late_packets() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x5150, an: 0 };
+
+        tracker.process_packet(create_packet(1, flow.clone()));
+        tracker.process_packet(create_packet(2, flow.clone()));
+        tracker.process_packet(create_packet(5, flow.clone())); // Missing 3, 4
+        tracker.process_packet(create_packet(6, flow.clone()));
+        tracker.process_packet(create_packet(9, flow.clone())); // Missing 7, 8
+
+        // One late packet does not heal a two-packet gap
+        tracker.process_packet(create_packet(3, flow.clone()));
+        assert!(tracker.get_gaps().iter().all(|gap| gap.recovery_time.is_none()));
+
+        tracker.process_packet(create_packet(4, flow.clone()));
+        // Duplicates are not counted twice
+        tracker.process_packet(create_packet(7, flow.clone()));
+        tracker.process_packet(create_packet(7, flow.clone()));
+
+        let mut gaps = tracker.get_gaps();
+        gaps.sort_by_key(|gap| gap.expected);
+        assert_eq!(gaps.len(), 2);
+        assert!(gaps[0].recovery_time.is_some());
+        assert!(gaps[0].recovery_time.unwrap() < Duration::from_secs(1));
+        assert!(gaps[1].recovery_time.is_none());
+
+        let stats = tracker.get_stats();
+        assert_eq!(stats[0].pct_gaps_recovered, 50.0);
+
+        tracker.process_packet(create_packet(8, flow.clone()));
+        assert_eq!(tracker.get_stats()[0].pct_gaps_recovered, 100.0);
+    }
+
+    fn gap_at(expected: u32, timestamp: SystemTime) -> SequenceGap {
+        SequenceGap {
+            flow_id: FlowId::MACsec { sci: 0x1, an: 0 },
+            expected,
+            received: expected + 1,
+            gap_size: 1,
+            timestamp,
+            recovery_time: None,
+        }
+    }
+
+    #[test]
+    fn test_gaps_since_filters_by_timestamp() {
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut state = FlowState::new();
+        state.push_gap(gap_at(10, t0));
+        state.push_gap(gap_at(30, t0 + Duration::from_secs(2)));
+        state.gap_fills.insert(1, 1);
+        // Clock stepped back: inserted in the middle, fill count follows its gap
+        state.push_gap(gap_at(20, t0 + Duration::from_secs(1)));
+        assert_eq!(state.gap_fills.get(&2), Some(&1));
+
+        let expected = |gaps: &[SequenceGap]| gaps.iter().map(|g| g.expected).collect::<Vec<_>>();
+        assert_eq!(expected(state.gaps_since(t0 - Duration::from_secs(1))), vec![10, 20, 30]);
+        assert_eq!(expected(state.gaps_since(t0)), vec![20, 30]);
+        assert_eq!(expected(state.gaps_since(t0 + Duration::from_millis(1500))), vec![30]);
+        assert!(state.gaps_since(t0 + Duration::from_secs(2)).is_empty());
+    }
+
+    #[test]
+    fn test_get_gaps_since_bookmark() {
+        let mut tracker = FlowTracker::new();
+        let flow_a = FlowId::MACsec { sci: 0xA, an: 0 };
+        let flow_b = FlowId::MACsec { sci: 0xB, an: 0 };
+        assert_eq!(tracker.latest_gap_timestamp(), None);
+
+        tracker.process_packet(create_packet(1, flow_a.clone()));
+        tracker.process_packet(create_packet(3, flow_a.clone())); // Missing 2
+        let bookmark = tracker.latest_gap_timestamp().unwrap();
+        std::thread::sleep(Duration::from_millis(2));
+
+        tracker.process_packet(create_packet(1, flow_b.clone()));
+        tracker.process_packet(create_packet(5, flow_b.clone())); // Missing 2-4
+
+        let new_gaps = tracker.get_gaps_since(bookmark);
+        assert_eq!(new_gaps.len(), 1);
+        assert_eq!(new_gaps[0].flow_id, flow_b);
+        assert_eq!(new_gaps[0].gap_size, 3);
+        assert_eq!(tracker.latest_gap_timestamp(), Some(new_gaps[0].timestamp));
+        assert_eq!(tracker.get_gaps_since(SystemTime::UNIX_EPOCH).len(), 2);
+    }
+
+    #[test]
+    fn test_pct_gaps_recovered_without_gaps() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x5151, an: 0 };
+        tracker.process_packet(create_packet(1, flow.clone()));
+        tracker.process_packet(create_packet(2, flow));
+        assert_eq!(tracker.get_stats()[0].pct_gaps_recovered, 0.0);
+    }
+
+    #[test]
+    fn test_prune_reorder_buffers() {
+        let mut tracker = FlowTracker::new();
+        let flow_a = FlowId::MACsec { sci: 0x6160, an: 0 };
+        let flow_b = FlowId::MACsec { sci: 0x6161, an: 0 };
+        let now = SystemTime::now();
+        let delayed = now - Duration::from_secs(10);
+        let recent = now - Duration::from_secs(2);
+
+        // Flow A: 5 is buffered past the 3-4 gap, 9 past the 7-8 gap
+        for (seq, timestamp) in [(1u32, delayed), (2, delayed), (5, delayed), (9, recent)] {
+            let mut pkt = create_packet(seq, flow_a.clone());
+            pkt.timestamp = timestamp;
+            tracker.process_packet(pkt);
+        }
+        // Flow B: 4 is buffered past the 2-3 gap
+        for seq in [1u32, 4] {
+            let mut pkt = create_packet(seq, flow_b.clone());
+            pkt.timestamp = delayed;
+            tracker.process_packet(pkt);
+        }
+
+        // Only the entries delayed past max_age go
+        assert_eq!(tracker.prune_reorder_buffers(Duration::from_secs(5)), 2);
+        assert_eq!(tracker.prune_reorder_buffers(Duration::from_secs(5)), 0);
+        assert_eq!(tracker.prune_reorder_buffers(Duration::from_secs(1)), 1);
+    }
+
+    #[test]
+    fn test_export_to_pcap_in_sequence_order() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x6163, an: 0 };
+        let other = FlowId::MACsec { sci: 0x6164, an: 0 };
+
+        // 1 is in order; 6, 4 and 5 are buffered out of order
+        for seq in [1u32, 6, 4, 5] {
+            let mut pkt = create_packet(seq, flow.clone());
+            pkt.raw_data = Some(vec![seq as u8; 14]);
+            tracker.process_packet(pkt);
+        }
+        // Buffered without raw bytes: skipped
+        tracker.process_packet(create_packet(8, flow.clone()));
+
+        let mut pcap = PcapNgWriter::new(Vec::new()).unwrap();
+        assert_eq!(tracker.export_to_pcap(&flow, &mut pcap).unwrap(), 3);
+        assert_eq!(tracker.export_to_pcap(&other, &mut pcap).unwrap(), 0);
+
+        // Each block is 32 bytes of framing plus 16 bytes of padded data;
+        // the first data byte is the sequence number
+        let buf = pcap.into_inner().unwrap();
+        let firsts: Vec<u8> = buf[48..].chunks(48).map(|block| block[28]).collect();
+        assert_eq!(firsts, vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn test_prune_counts_late_arrivals_as_duplicates() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x6162, an: 0 };
+        let delayed = SystemTime::now() - Duration::from_secs(10);
+
+        tracker.process_packet(create_packet(1, flow.clone()));
+        tracker.process_packet(create_packet(4, flow.clone())); // Missing 2, 3
+
+        // A late packet sits in the buffer behind the expected sequence
+        let mut late = create_packet(2, flow.clone());
+        late.timestamp = delayed;
+        tracker.process_packet(late);
+
+        assert_eq!(tracker.get_stats()[0].duplicate_packets, 0);
+        assert_eq!(tracker.prune_reorder_buffers(Duration::from_secs(5)), 1);
+        assert_eq!(tracker.get_stats()[0].duplicate_packets, 1);
+
+        // The fresh out-of-order packet is kept
+        assert_eq!(tracker.prune_reorder_buffers(Duration::from_secs(5)), 0);
+    }
+
+    #[test]
+    fn test_loss_rate_ema_converges() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x7170, an: 0 };
+
+        // Every other packet lost: each gap is 1 of 2 packets, a true rate of 50%
+        for seq in (1..=201u32).step_by(2) {
+            tracker.process_packet(create_packet(seq, flow.clone()));
+        }
+
+        let stats = tracker.get_stats();
+        assert_eq!(stats[0].gaps_detected, 100);
+        assert_eq!(stats[0].loss_rate_instantaneous, 0.5);
+        // ema = 0.5 * (1 - 0.9^100)
+        assert!((stats[0].loss_rate_ema - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_loss_rate_ema_weights_recent_gaps() {
+        let mut tracker = FlowTracker::new().with_loss_ema_alpha(0.5);
+        let flow = FlowId::MACsec { sci: 0x7171, an: 0 };
+
+        tracker.process_packet(create_packet(1, flow.clone()));
+        tracker.process_packet(create_packet(5, flow.clone())); // 3 lost: 0.75
+        tracker.process_packet(create_packet(7, flow.clone())); // 1 lost: 0.5
+
+        let stats = tracker.get_stats();
+        assert_eq!(stats[0].loss_rate_instantaneous, 0.5);
+        // 0.5 * 0.5 + 0.5 * (0.5 * 0.75)
+        assert!((stats[0].loss_rate_ema - 0.4375).abs() < 1e-12);
+    }
+
     #[test]
     fn test_multiple_flows() {
         let mut tracker = FlowTracker::new();
-        let flow1 = FlowId::MACsec { sci: 0x1111 };
-        let flow2 = FlowId::MACsec { sci: 0x2222 };
+        let flow1 = FlowId::MACsec { sci: 0x1111, an: 0 };
+        let flow2 = FlowId::MACsec { sci: 0x2222, an: 0 };
 
         // Two independent flows
         tracker.process_packet(create_packet(1, flow1.clone()));
@@ -654,7 +1779,7 @@ mod tests {
     #[test]
     fn test_wraparound_detection() {
         let mut tracker = FlowTracker::new();
-        let flow = FlowId::MACsec { sci: 0x1234 };
+        let flow = FlowId::MACsec { sci: 0x1234, an: 0 };
 
         // Test sequence near wraparound
         tracker.process_packet(create_packet(u32::MAX, flow.clone()));
@@ -670,7 +1795,7 @@ mod tests {
     #[test]
     fn test_total_bytes_tracking() {
         let mut tracker = FlowTracker::new();
-        let flow = FlowId::MACsec { sci: 0x5678 };
+        let flow = FlowId::MACsec { sci: 0x5678, an: 0 };
 
         // Create packets with known payload lengths
         let mut pkt1 = create_packet(1, flow.clone());
@@ -692,7 +1817,7 @@ mod tests {
     #[test]
     fn test_timestamp_tracking() {
         let mut tracker = FlowTracker::new();
-        let flow = FlowId::MACsec { sci: 0x9abc };
+        let flow = FlowId::MACsec { sci: 0x9abc, an: 0 };
 
         let now = SystemTime::now();
         let mut pkt1 = create_packet(1, flow.clone());
@@ -712,7 +1837,7 @@ mod tests {
     #[test]
     fn test_inter_arrival_time_tracking() {
         let mut tracker = FlowTracker::new();
-        let flow = FlowId::MACsec { sci: 0xdef0 };
+        let flow = FlowId::MACsec { sci: 0xdef0, an: 0 };
 
         let base_time = SystemTime::UNIX_EPOCH;
 
@@ -742,7 +1867,7 @@ mod tests {
     #[test]
     fn test_single_packet_no_inter_arrival() {
         let mut tracker = FlowTracker::new();
-        let flow = FlowId::MACsec { sci: 0x1234 };
+        let flow = FlowId::MACsec { sci: 0x1234, an: 0 };
 
         tracker.process_packet(create_packet(1, flow.clone()));
 
@@ -788,8 +1913,8 @@ mod tests {
     #[test]
     fn test_multiple_flows_independent_statistics() {
         let mut tracker = FlowTracker::new();
-        let flow1 = FlowId::MACsec { sci: 0x1111 };
-        let flow2 = FlowId::MACsec { sci: 0x2222 };
+        let flow1 = FlowId::MACsec { sci: 0x1111, an: 0 };
+        let flow2 = FlowId::MACsec { sci: 0x2222, an: 0 };
 
         let base_time = SystemTime::UNIX_EPOCH;
 
@@ -827,7 +1952,7 @@ mod tests {
     #[test]
     fn test_combined_statistics_with_gaps() {
         let mut tracker = FlowTracker::new();
-        let flow = FlowId::MACsec { sci: 0xabcd };
+        let flow = FlowId::MACsec { sci: 0xabcd, an: 0 };
 
         let base_time = SystemTime::UNIX_EPOCH;
 
@@ -867,4 +1992,427 @@ mod tests {
         assert_eq!(stats[0].min_inter_arrival, Some(Duration::from_millis(1)));
         assert_eq!(stats[0].max_inter_arrival, Some(Duration::from_millis(2)));
     }
+
+    #[test]
+    fn test_top_n_flows_by_gap_count() {
+        let mut tracker = FlowTracker::new();
+        let quiet = FlowId::MACsec { sci: 0x1, an: 0 };
+        let lossy = FlowId::MACsec { sci: 0x2, an: 0 };
+        let lossier = FlowId::MACsec { sci: 0x3, an: 0 };
+
+        for seq in 1..=5 {
+            tracker.process_packet(create_packet(seq, quiet.clone()));
+        }
+        // One gap: missing 2
+        for seq in [1, 3, 4] {
+            tracker.process_packet(create_packet(seq, lossy.clone()));
+        }
+        // Two gaps: missing 2 and 4
+        for seq in [1, 3, 5] {
+            tracker.process_packet(create_packet(seq, lossier.clone()));
+        }
+
+        let top = tracker.top_n_flows(2, FlowMetric::GapCount);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].flow_id, lossier);
+        assert_eq!(top[1].flow_id, lossy);
+
+        // Asking for more than exist returns all flows
+        assert_eq!(tracker.top_n_flows(10, FlowMetric::GapCount).len(), 3);
+        assert!(tracker.top_n_flows(0, FlowMetric::GapCount).is_empty());
+    }
+
+    #[test]
+    fn test_top_n_flows_by_packet_rate() {
+        let mut tracker = FlowTracker::new();
+        let slow = FlowId::MACsec { sci: 0x10, an: 0 };
+        let fast = FlowId::MACsec { sci: 0x20, an: 0 };
+        let base_time = SystemTime::UNIX_EPOCH;
+
+        // slow: 3 packets over 2s, fast: 3 packets over 20ms
+        for i in 0..3u32 {
+            let mut pkt = create_packet(i + 1, slow.clone());
+            pkt.timestamp = base_time + Duration::from_secs(i as u64);
+            tracker.process_packet(pkt);
+
+            let mut pkt = create_packet(i + 1, fast.clone());
+            pkt.timestamp = base_time + Duration::from_millis(i as u64 * 10);
+            tracker.process_packet(pkt);
+        }
+
+        let top = tracker.top_n_flows(1, FlowMetric::PacketRate);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].flow_id, fast);
+    }
+
+    #[test]
+    fn test_above_threshold_loss_percentage() {
+        let mut tracker = FlowTracker::new();
+        let clean = FlowId::MACsec { sci: 0xa, an: 0 };
+        let lossy = FlowId::MACsec { sci: 0xb, an: 0 };
+
+        for seq in 1..=10 {
+            tracker.process_packet(create_packet(seq, clean.clone()));
+        }
+        // 9 received, 1 lost -> 10% loss
+        for seq in (1..=10).filter(|&s| s != 5) {
+            tracker.process_packet(create_packet(seq, lossy.clone()));
+        }
+
+        let flagged = tracker.above_threshold(FlowMetric::LossPercentage, 1.0);
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].flow_id, lossy);
+        assert!((FlowMetric::LossPercentage.value(&flagged[0]) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_top_n_flows_matches_ranking_snapshots() {
+        let mut tracker = FlowTracker::new();
+        let base_time = SystemTime::UNIX_EPOCH;
+        for sci in 0..6u64 {
+            let flow_id = FlowId::MACsec { sci, an: 0 };
+            for seq in (1..=12u32).filter(|s| s % (sci as u32 + 2) != 0) {
+                let mut pkt = create_packet(seq, flow_id.clone());
+                pkt.timestamp = base_time + Duration::from_millis(seq as u64 * (sci + 1) * 7);
+                pkt.payload_length = 64 * (sci as usize + 1);
+                tracker.process_packet(pkt);
+            }
+        }
+
+        for metric in [
+            FlowMetric::PacketRate,
+            FlowMetric::ByteRate,
+            FlowMetric::GapCount,
+            FlowMetric::LossPercentage,
+        ] {
+            assert_eq!(
+                tracker.top_n_flows(3, metric),
+                top_n_by_metric(tracker.get_stats(), 3, metric)
+            );
+            let mut expected: Vec<FlowStats> = tracker
+                .get_stats()
+                .into_iter()
+                .filter(|stats| metric.value(stats) > 1.0)
+                .collect();
+            let mut flagged = tracker.above_threshold(metric, 1.0);
+            expected.sort_by(|a, b| a.flow_id.cmp(&b.flow_id));
+            flagged.sort_by(|a, b| a.flow_id.cmp(&b.flow_id));
+            assert_eq!(flagged, expected);
+        }
+    }
+
+    #[test]
+    fn test_an_rotation_creates_new_flow() {
+        let mut tracker = FlowTracker::new();
+        let sa0 = FlowId::MACsec { sci: 0x5555, an: 0 };
+        let sa1 = FlowId::MACsec { sci: 0x5555, an: 1 };
+
+        for seq in 1..=3 {
+            tracker.process_packet(create_packet(seq, sa0.clone()));
+        }
+        // Key rotated: packet numbers restart under the new AN
+        for seq in 1..=3 {
+            assert!(tracker.process_packet(create_packet(seq, sa1.clone())).is_none());
+        }
+
+        let stats = tracker.get_stats();
+        assert_eq!(stats.len(), 2);
+        for stat in stats {
+            assert_eq!(stat.packets_received, 3);
+            assert_eq!(stat.gaps_detected, 0);
+            if stat.flow_id == sa0 {
+                assert_eq!(stat.an_rotations, 0);
+            } else {
+                assert_eq!(stat.flow_id, sa1);
+                assert_eq!(stat.an_rotations, 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_an_straggler_is_not_a_rotation() {
+        let mut tracker = FlowTracker::new();
+        let sa0 = FlowId::MACsec { sci: 0x6666, an: 0 };
+        let sa1 = FlowId::MACsec { sci: 0x6666, an: 1 };
+
+        tracker.process_packet(create_packet(1, sa0.clone()));
+        tracker.process_packet(create_packet(1, sa1.clone()));
+        // Late packet from the retiring SA
+        tracker.process_packet(create_packet(2, sa0.clone()));
+        tracker.process_packet(create_packet(2, sa1.clone()));
+
+        let stats = tracker.get_stats();
+        let sa1_stats = stats.iter().find(|s| s.flow_id == sa1).unwrap();
+        assert_eq!(sa1_stats.an_rotations, 1);
+    }
+
+    #[test]
+    fn test_owd_constant_rate_is_zero() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x7777, an: 0 };
+        let base_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+
+        for seq in 1..=5u32 {
+            let mut pkt = create_packet(seq, flow.clone());
+            pkt.timestamp = base_time + Duration::from_millis(seq as u64);
+            tracker.process_packet(pkt);
+        }
+
+        let stats = tracker.get_stats();
+        assert_eq!(stats[0].estimated_owd_us, Some(0));
+        assert_eq!(stats[0].owd_std_dev_us, Some(0.0));
+    }
+
+    #[test]
+    fn test_owd_detects_delayed_packet() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x8888, an: 0 };
+        let base_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+
+        // Sent every 1000us; the 4th packet arrives 2000us late
+        for (seq, rx_us) in [(1u32, 0u64), (2, 1000), (3, 2000), (4, 5000)] {
+            let mut pkt = create_packet(seq, flow.clone());
+            pkt.timestamp = base_time + Duration::from_micros(rx_us);
+            tracker.process_packet(pkt);
+        }
+
+        // avg interval = 5000 / 3 = 1666us, tx estimate = 3 * 1666 = 4998us
+        let stats = tracker.get_stats();
+        assert_eq!(stats[0].estimated_owd_us, Some(2));
+        assert!(stats[0].owd_std_dev_us.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_owd_single_packet_has_no_estimate() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x9999, an: 0 };
+        tracker.process_packet(create_packet(1, flow));
+
+        let stats = tracker.get_stats();
+        assert_eq!(stats[0].estimated_owd_us, None);
+        assert_eq!(stats[0].owd_std_dev_us, None);
+    }
+
+    #[test]
+    fn test_sub_microsecond_inter_arrival() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0xBBBB, an: 0 };
+        let base_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+
+        // Hardware-timestamped back-to-back frames: 200ns and 600ns apart
+        for (seq, rx_ns) in [(1u32, 0u64), (2, 200), (3, 800)] {
+            let mut pkt = create_packet(seq, flow.clone());
+            pkt.timestamp = base_time + Duration::from_nanos(rx_ns);
+            tracker.process_packet(pkt);
+        }
+
+        let stats = tracker.get_stats();
+        assert_eq!(stats[0].min_inter_arrival, Some(Duration::from_nanos(200)));
+        assert_eq!(stats[0].max_inter_arrival, Some(Duration::from_nanos(600)));
+        assert_eq!(stats[0].avg_inter_arrival, Some(Duration::from_nanos(400)));
+        assert!((stats[0].burstiness_index - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_inter_arrival_variance_exact() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0xAAAA, an: 0 };
+        let base_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+
+        // Inter-arrival times: 1000, 2000, 3000, 4000us
+        // mean = 2500, variance = (1500² + 500² + 500² + 1500²) / 4 = 1_250_000
+        for (seq, rx_us) in [(1u32, 0u64), (2, 1000), (3, 3000), (4, 6000), (5, 10_000)] {
+            let mut pkt = create_packet(seq, flow.clone());
+            pkt.timestamp = base_time + Duration::from_micros(rx_us);
+            tracker.process_packet(pkt);
+        }
+
+        let stats = tracker.get_stats();
+        assert_eq!(stats[0].inter_arrival_variance_us2, 1_250_000);
+        assert_eq!(stats[0].std_dev_us, 1118); // sqrt(1_250_000) = 1118.03
+        assert!((stats[0].burstiness_index - 1_250_000f64.sqrt() / 2500.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_inter_arrival_variance_constant_rate() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0xBBBB, an: 0 };
+        let base_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+
+        for seq in 1..=10u32 {
+            let mut pkt = create_packet(seq, flow.clone());
+            pkt.timestamp = base_time + Duration::from_millis(seq as u64);
+            tracker.process_packet(pkt);
+        }
+
+        let stats = tracker.get_stats();
+        assert_eq!(stats[0].inter_arrival_variance_us2, 0);
+        assert_eq!(stats[0].std_dev_us, 0);
+        assert_eq!(stats[0].burstiness_index, 0.0);
+    }
+
+    #[test]
+    fn test_welford_numerical_stability() {
+        // Large offset with small spread: the naive sum-of-squares formula
+        // loses all precision here, Welford must not.
+        let mut state = FlowState::new();
+        let offset = 1_000_000_000_000u64;
+        for _ in 0..1000 {
+            for delta in [4u64, 7, 13, 16] {
+                state.record_inter_arrival(Duration::from_micros(offset + delta));
+            }
+        }
+
+        // Population variance of {4, 7, 13, 16} = 22.5
+        assert!((state.compute_throughput_variance() - 22.5).abs() < 1e-3);
+        assert_eq!(state.inter_arrival_count, 4000);
+    }
+
+    #[test]
+    fn test_get_stats_for_flows() {
+        let mut tracker = FlowTracker::new();
+        let flows: Vec<FlowId> = (0..5).map(|sci| FlowId::MACsec { sci, an: 0 }).collect();
+        for (i, flow) in flows.iter().enumerate() {
+            for seq in 1..=(i as u32 + 1) {
+                tracker.process_packet(create_packet(seq, flow.clone()));
+            }
+        }
+
+        assert_eq!(tracker.flow_count(), 5);
+        assert!(tracker.contains_flow(&flows[2]));
+        let unknown = FlowId::MACsec { sci: 0x99, an: 0 };
+        assert!(!tracker.contains_flow(&unknown));
+
+        // Requested order is preserved, unknown IDs are skipped
+        let stats = tracker.get_stats_for_flows(&[flows[3].clone(), unknown, flows[1].clone()]);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].flow_id, flows[3]);
+        assert_eq!(stats[0].packets_received, 4);
+        assert_eq!(stats[1].flow_id, flows[1]);
+        assert_eq!(stats[1].packets_received, 2);
+
+        assert!(tracker.get_stats_for_flows(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_flow_ids() {
+        let mut tracker = FlowTracker::new();
+        assert!(tracker.flow_ids().is_empty());
+
+        let flows = [
+            FlowId::MACsec { sci: 0x1, an: 0 },
+            FlowId::MACsec { sci: 0x2, an: 1 },
+            FlowId::MACsec { sci: 0x3, an: 0 },
+        ];
+        for flow in &flows {
+            tracker.process_packet(create_packet(1, flow.clone()));
+            tracker.process_packet(create_packet(2, flow.clone()));
+        }
+
+        let mut ids = tracker.flow_ids();
+        ids.sort();
+        assert_eq!(ids, flows);
+    }
+
+    #[test]
+    fn test_get_flow_snapshot_matches_get_stats() {
+        let mut tracker = FlowTracker::new();
+        let flows: Vec<FlowId> = (0..20).map(|sci| FlowId::MACsec { sci, an: 0 }).collect();
+        for (i, flow) in flows.iter().enumerate() {
+            // Skip a sequence number on odd flows so gap stats differ too
+            for seq in (1..=(i as u32 + 3)).filter(|&seq| i.is_multiple_of(2) || seq != 2) {
+                tracker.process_packet(create_packet(seq, flow.clone()));
+            }
+        }
+
+        for stats in tracker.get_stats() {
+            let snapshot = tracker.get_flow_snapshot(&stats.flow_id).unwrap();
+            assert_eq!(snapshot, stats);
+        }
+        let snapshot = tracker.get_flow_snapshot(&flows[7]).unwrap();
+        assert_eq!(snapshot.packets_received, 9);
+        assert_eq!(snapshot.gaps_detected, 1);
+
+        let unknown = FlowId::MACsec { sci: 0x99, an: 0 };
+        assert!(tracker.get_flow_snapshot(&unknown).is_none());
+    }
+
+    #[test]
+    fn test_dscp_distribution_and_ecn_congestion() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x8180, an: 0 };
+
+        // EF x2 (one CE-marked), AF41 with ECT(1), BE, and one without QoS info
+        let marks = [
+            (Some(46), Some(0b10)),
+            (Some(46), Some(0b11)),
+            (Some(34), Some(0b01)),
+            (Some(0), Some(0b00)),
+            (None, None),
+        ];
+        for (seq, (dscp, ecn)) in (1u32..).zip(marks) {
+            let mut packet = create_packet(seq, flow.clone());
+            packet.dscp = dscp;
+            packet.ecn = ecn;
+            tracker.process_packet(packet);
+        }
+
+        let stats = &tracker.get_stats()[0];
+        assert_eq!(stats.dscp_distribution.get(&46), Some(&2));
+        assert_eq!(stats.dscp_distribution.get(&34), Some(&1));
+        assert_eq!(stats.dscp_distribution.get(&0), Some(&1));
+        assert_eq!(stats.dscp_distribution.len(), 3);
+        assert_eq!(stats.ecn_congestion_signals, 1);
+    }
+
+    #[test]
+    fn test_protocol_version_distribution() {
+        let mut tracker = FlowTracker::new();
+        let flow = FlowId::MACsec { sci: 0x77, an: 0 };
+        for (seq, version) in [(1, Some(0)), (2, Some(1)), (3, Some(0)), (4, None), (5, Some(0))] {
+            let mut packet = create_packet(seq, flow.clone());
+            packet.proto_version = version;
+            tracker.process_packet(packet);
+        }
+
+        let stats = &tracker.get_stats()[0];
+        assert_eq!(stats.protocol_version_distribution.get(&0), Some(&3));
+        assert_eq!(stats.protocol_version_distribution.get(&1), Some(&1));
+        assert_eq!(stats.protocol_version_distribution.len(), 2);
+        assert_eq!(stats.packets_received, 5);
+    }
+
+    #[test]
+    fn test_flow_graph_merges_ports_between_hosts() {
+        use std::net::{IpAddr, Ipv4Addr};
+
+        let mut tracker = FlowTracker::new();
+        let host = |last: u8| IpAddr::V4(Ipv4Addr::new(10, 0, 0, last));
+        let l3 = |src: u8, dst: u8, src_port: u16, dst_port: u16| FlowId::GenericL3 {
+            src_ip: host(src),
+            dst_ip: host(dst),
+            src_port,
+            dst_port,
+            protocol: 6,
+        };
+
+        // Two sessions from .1 to .2, one from .2 to .3, plus a MACsec flow
+        tracker.process_packet(create_packet(0, l3(1, 2, 40000, 80)));
+        tracker.process_packet(create_packet(0, l3(1, 2, 40001, 443)));
+        tracker.process_packet(create_packet(0, l3(2, 3, 50000, 22)));
+        tracker.process_packet(create_packet(1, FlowId::MACsec { sci: 0x42, an: 0 }));
+
+        let graph = tracker.get_flow_graph();
+        assert_eq!(graph.nodes, vec![host(1), host(2), host(3)]);
+        assert_eq!(
+            graph.edges,
+            vec![(host(1), host(2), 200), (host(2), host(3), 100)]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph flows {"));
+        assert!(dot.contains("\"10.0.0.1\" -> \"10.0.0.2\" [label=\"200 bytes\"];"));
+        assert!(dot.contains("\"10.0.0.2\" -> \"10.0.0.3\" [label=\"100 bytes\"];"));
+        assert!(!dot.contains("\"10.0.0.1\" -> \"10.0.0.3\""));
+    }
 }
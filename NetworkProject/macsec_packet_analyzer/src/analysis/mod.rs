@@ -1,16 +1,79 @@
 pub mod flow;
+pub mod preprocess;
+pub mod rate_limit;
+
+#[cfg(feature = "cli")]
+pub mod correlation;
 
 #[cfg(feature = "cli")]
 use crate::capture::PacketSource;
 #[cfg(feature = "cli")]
 use crate::error::AnalysisError;
 #[cfg(feature = "cli")]
-use crate::protocol::SequenceParser;
+use crate::protocol::{ProtocolHint, SequenceParser};
+#[cfg(feature = "cli")]
+use crate::persist::PersistenceManager;
+#[cfg(feature = "cli")]
+use crate::types::{AnalyzedPacket, AnalysisReport, ProtocolBreakdown, SequenceGap};
 #[cfg(feature = "cli")]
-use crate::types::{AnalyzedPacket, AnalysisReport};
+use std::collections::HashMap;
+#[cfg(feature = "cli")]
+use std::fmt;
+#[cfg(feature = "cli")]
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "cli")]
 use self::flow::FlowTracker;
+#[cfg(feature = "cli")]
+use self::preprocess::PacketPreprocessor;
+#[cfg(feature = "cli")]
+use self::rate_limit::{PacketPacer, TokenBucket};
+
+/// Persist after this many packets...
+#[cfg(feature = "cli")]
+const PERSIST_PACKET_THRESHOLD: u64 = 100_000;
+/// ...or after this long, whichever comes first
+#[cfg(feature = "cli")]
+const PERSIST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Throughput of a `PacketAnalyzer` run and its rate limiters
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnalyzerStats {
+    pub packets_processed: u64,
+    pub elapsed: Duration,
+    /// Measured packets per second
+    pub analysis_pps: f64,
+    /// Ceiling set with `with_analysis_rate_limit`
+    pub analysis_rate_limit: Option<u64>,
+    /// Periodic database writes performed
+    pub persist_calls: u64,
+    /// Periodic writes skipped because the persist bucket was empty
+    pub persist_skipped: u64,
+    /// Measured database writes per second
+    pub persist_rate: f64,
+    /// Ceiling set with `with_max_persist_rate`
+    pub max_persist_rate: Option<u64>,
+}
+
+#[cfg(feature = "cli")]
+impl fmt::Display for AnalyzerStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = |l: Option<u64>| l.map_or("unlimited".to_string(), |l| l.to_string());
+        write!(
+            f,
+            "[{:.1}s] Packets: {}, Rate: {:.0} pps (limit {}), Persists: {} ({} skipped), Persist rate: {:.2}/s (limit {})",
+            self.elapsed.as_secs_f64(),
+            self.packets_processed,
+            self.analysis_pps,
+            limit(self.analysis_rate_limit),
+            self.persist_calls,
+            self.persist_skipped,
+            self.persist_rate,
+            limit(self.max_persist_rate),
+        )
+    }
+}
 
 /// Generic packet analyzer that works with any combination of:
 /// - Capture sources (file, live interface)
@@ -19,7 +82,16 @@ use self::flow::FlowTracker;
 pub struct PacketAnalyzer<S: PacketSource, P: SequenceParser> {
     source: S,
     parser: P,
+    hint: ProtocolHint,
+    preprocessors: Vec<Box<dyn PacketPreprocessor>>,
     flow_tracker: FlowTracker,
+    persistence: Option<PersistenceManager>,
+    persist_bucket: Option<TokenBucket>,
+    persist_packet_threshold: u64,
+    pacer: Option<PacketPacer>,
+    store_raw: bool,
+    debug: bool,
+    stats: AnalyzerStats,
 }
 
 #[cfg(feature = "cli")]
@@ -28,48 +100,226 @@ impl<S: PacketSource, P: SequenceParser> PacketAnalyzer<S, P> {
         Self {
             source,
             parser,
+            hint: ProtocolHint::auto(),
+            preprocessors: Vec::new(),
             flow_tracker: FlowTracker::new(),
+            persistence: None,
+            persist_bucket: None,
+            persist_packet_threshold: PERSIST_PACKET_THRESHOLD,
+            pacer: None,
+            store_raw: false,
+            debug: false,
+            stats: AnalyzerStats::default(),
         }
     }
 
+    /// Write flow statistics and gaps to the database while analyzing
+    ///
+    /// Writes happen every 100,000 packets or 30 seconds, and once more at
+    /// the end of the run.
+    pub fn with_persistence(mut self, persistence: PersistenceManager) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    /// Cap periodic database writes at `max_writes_per_second`
+    ///
+    /// When the token bucket is empty the write is skipped; the tracker keeps
+    /// accumulating and the next allowed write carries everything. The final
+    /// write at the end of the run is never skipped.
+    pub fn with_max_persist_rate(mut self, max_writes_per_second: u64) -> Self {
+        self.persist_bucket = Some(TokenBucket::new(max_writes_per_second));
+        self.stats.max_persist_rate = Some(max_writes_per_second);
+        self
+    }
+
+    /// Sleep in the packet loop to stay under `max_pps` packets per second
+    ///
+    /// Useful for feeding dashboards at a watchable rate during testing.
+    pub fn with_analysis_rate_limit(mut self, max_pps: u64) -> Self {
+        self.pacer = Some(PacketPacer::new(max_pps));
+        self.stats.analysis_rate_limit = Some(max_pps);
+        self
+    }
+
+    /// Keep each packet's raw bytes in `AnalyzedPacket::raw_data`
+    ///
+    /// Required for `FlowTracker::export_to_pcap`. Off by default, since
+    /// buffered packets then hold a copy of the whole frame.
+    pub fn with_raw_storage(mut self, store_raw: bool) -> Self {
+        self.store_raw = store_raw;
+        self
+    }
+
+    /// Print `AnalyzerStats` at every periodic persist point
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Flow tracker holding the state of every analyzed flow
+    pub fn flow_tracker(&self) -> &FlowTracker {
+        &self.flow_tracker
+    }
+
+    /// Throughput and limiter counters from the latest `analyze()` or
+    /// `analyze_streaming()` run
+    pub fn stats(&self) -> &AnalyzerStats {
+        &self.stats
+    }
+
+    /// Force a specific parser for every packet, bypassing `parser`
+    ///
+    /// `ProtocolHint::auto()` restores the analyzer's own parser.
+    pub fn set_protocol_hint(&mut self, hint: ProtocolHint) {
+        self.hint = hint;
+    }
+
+    /// Add a hook that runs on every packet before parsing
+    ///
+    /// Preprocessors run in insertion order; if one returns `None` the packet
+    /// is dropped and the rest are skipped.
+    pub fn add_preprocessor(&mut self, pp: Box<dyn PacketPreprocessor>) {
+        self.preprocessors.push(pp);
+    }
+
     /// Run the analysis on all packets from the source
+    ///
+    /// Collects every gap into `AnalysisReport::gaps`; use
+    /// `analyze_streaming` when a capture may hold too many to keep in memory.
     pub fn analyze(&mut self) -> Result<AnalysisReport, AnalysisError> {
-        let mut total_packets = 0;
         let mut gaps = Vec::new();
+        let mut report = self.analyze_streaming(|gap| gaps.push(gap.clone()))?;
+        report.gaps = gaps;
+        Ok(report)
+    }
+
+    /// Run the analysis, passing each gap to `on_gap` as it is detected
+    ///
+    /// Gaps are not buffered, so `AnalysisReport::gaps` is always empty; the
+    /// per-flow totals in `flow_stats` still count them.
+    pub fn analyze_streaming<F: FnMut(&SequenceGap)>(
+        &mut self,
+        mut on_gap: F,
+    ) -> Result<AnalysisReport, AnalysisError> {
+        let mut total_packets = 0;
+        let mut protocol_breakdown: HashMap<String, ProtocolBreakdown> = HashMap::new();
+        let start = Instant::now();
+        let mut last_persist = start;
 
         // Process all packets from source
         while let Some(raw_packet) = self.source.next_packet()? {
             total_packets += 1;
 
+            if let Some(delay) = self.pacer.as_mut().and_then(PacketPacer::pace) {
+                std::thread::sleep(delay);
+            }
+
+            if total_packets.is_multiple_of(self.persist_packet_threshold)
+                || last_persist.elapsed() >= PERSIST_INTERVAL
+            {
+                self.periodic_persist(total_packets, start)?;
+                last_persist = Instant::now();
+            }
+
+            // Apply preprocessors in order; None drops the packet
+            let raw_packet = match self
+                .preprocessors
+                .iter()
+                .try_fold(raw_packet, |packet, pp| pp.process(packet))
+            {
+                Some(packet) => packet,
+                None => continue,
+            };
+
             // Try to parse the packet
-            if let Some(seq_info) = self.parser.parse_sequence(&raw_packet.data)? {
+            let seq_info = match &self.hint.forced_parser {
+                Some(forced) => forced.parse_sequence(&raw_packet.data)?,
+                None => self.parser.parse_sequence(&raw_packet.data)?,
+            };
+
+            if let Some(seq_info) = seq_info {
+                let breakdown = protocol_breakdown
+                    .entry(seq_info.flow_id.protocol_name())
+                    .or_default();
+                breakdown.packets += 1;
+                breakdown.bytes += seq_info.payload_length as u64;
+
                 // Create analyzed packet
                 let analyzed = AnalyzedPacket {
                     sequence_number: seq_info.sequence_number,
                     flow_id: seq_info.flow_id,
                     timestamp: raw_packet.timestamp,
                     payload_length: seq_info.payload_length,
+                    proto_version: seq_info.proto_version,
+                    dscp: seq_info.dscp,
+                    ecn: seq_info.ecn,
+                    raw_data: self.store_raw.then_some(raw_packet.data),
                 };
 
                 // Track the packet and detect gaps
                 if let Some(gap) = self.flow_tracker.process_packet(analyzed) {
-                    gaps.push(gap);
+                    on_gap(&gap);
                 }
             }
         }
 
+        if let Some(persistence) = &self.persistence {
+            persistence.persist_flows(&self.flow_tracker)?;
+            self.stats.persist_calls += 1;
+        }
+        self.update_rates(total_packets, start);
+
         // Get flow statistics
         let flow_stats = self.flow_tracker.get_stats();
+        for stats in &flow_stats {
+            protocol_breakdown
+                .entry(stats.flow_id.protocol_name())
+                .or_default()
+                .flows += 1;
+        }
 
         let report = AnalysisReport {
             total_packets,
-            protocol: self.parser.protocol_name().to_string(),
-            gaps,
+            protocol_breakdown,
+            gaps: Vec::new(),
             flow_stats,
+            capture_stats: Some(self.source.stats()),
         };
 
         Ok(report)
     }
+
+    /// Persist if the rate limiter allows it, then report progress in debug mode
+    fn periodic_persist(&mut self, total_packets: u64, start: Instant) -> Result<(), AnalysisError> {
+        if let Some(persistence) = &self.persistence {
+            let allowed = self
+                .persist_bucket
+                .as_mut()
+                .is_none_or(TokenBucket::try_acquire);
+            if allowed {
+                persistence.persist_flows(&self.flow_tracker)?;
+                self.stats.persist_calls += 1;
+            } else {
+                self.stats.persist_skipped += 1;
+            }
+        }
+
+        self.update_rates(total_packets, start);
+        if self.debug {
+            println!("{}", self.stats);
+        }
+        Ok(())
+    }
+
+    fn update_rates(&mut self, total_packets: u64, start: Instant) {
+        let elapsed = start.elapsed();
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        self.stats.packets_processed = total_packets;
+        self.stats.elapsed = elapsed;
+        self.stats.analysis_pps = total_packets as f64 / secs;
+        self.stats.persist_rate = self.stats.persist_calls as f64 / secs;
+    }
 }
 
 #[cfg(test)]
@@ -129,8 +379,12 @@ mod tests {
                 sequence_number: data[0] as u32,
                 flow_id: crate::types::FlowId::MACsec {
                     sci: data[1] as u64,
+                    an: 0,
                 },
                 payload_length: data.len() - 2,
+                proto_version: None,
+                dscp: None,
+                ecn: None,
             }))
         }
 
@@ -155,10 +409,15 @@ mod tests {
         let parser = MockParser;
         let mut analyzer = PacketAnalyzer::new(source, parser);
 
-        let report = analyzer.analyze().unwrap();
+        let mut gap_count = 0;
+        let report = analyzer.analyze_streaming(|_| gap_count += 1).unwrap();
         assert_eq!(report.total_packets, 3);
-        assert_eq!(report.gaps.len(), 0);
+        assert_eq!(gap_count, 0);
         assert_eq!(report.flow_stats.len(), 1);
+
+        let capture_stats = report.capture_stats.unwrap();
+        assert_eq!(capture_stats.packets_received, 3);
+        assert_eq!(capture_stats.packets_dropped, 0);
     }
 
     #[test]
@@ -173,10 +432,217 @@ mod tests {
         let parser = MockParser;
         let mut analyzer = PacketAnalyzer::new(source, parser);
 
-        let report = analyzer.analyze().unwrap();
+        let mut gaps = Vec::new();
+        let report = analyzer
+            .analyze_streaming(|gap| gaps.push((gap.expected, gap.received)))
+            .unwrap();
         assert_eq!(report.total_packets, 3);
-        assert_eq!(report.gaps.len(), 1);
-        assert_eq!(report.gaps[0].expected, 3);
-        assert_eq!(report.gaps[0].received, 4);
+        assert_eq!(gaps, vec![(3, 4)]);
+        assert!(report.gaps.is_empty());
+        assert_eq!(report.flow_stats[0].gaps_detected, 1);
+    }
+
+    #[test]
+    fn test_analyze_collects_streamed_gaps() {
+        let packets = vec![vec![1, 1], vec![3, 1], vec![1, 2], vec![5, 1], vec![4, 2]];
+
+        let mut analyzer = PacketAnalyzer::new(MockSource::new(packets), MockParser);
+        let report = analyzer.analyze().unwrap();
+        let gaps: Vec<_> = report
+            .gaps
+            .iter()
+            .map(|g| (g.expected, g.received))
+            .collect();
+        assert_eq!(gaps, vec![(2, 3), (4, 5), (2, 4)]);
+    }
+
+    #[test]
+    fn test_analyzer_protocol_hint_overrides_parser() {
+        let packets = vec![vec![1, 1], vec![2, 1]];
+
+        let source = MockSource::new(packets);
+        let mut analyzer = PacketAnalyzer::new(source, MockParser);
+        // MACsec parser rejects the mock packets, so nothing is tracked
+        analyzer.set_protocol_hint(ProtocolHint::from_name("macsec").unwrap());
+
+        let report = analyzer.analyze_streaming(|_| {}).unwrap();
+        assert_eq!(report.total_packets, 2);
+        assert!(report.protocol_breakdown.is_empty());
+        assert!(report.flow_stats.is_empty());
+    }
+
+    #[test]
+    fn test_analyzer_preprocessors_chain_in_order() {
+        /// Drops packets whose first byte (the mock sequence) is odd
+        struct DropOdd;
+        impl PacketPreprocessor for DropOdd {
+            fn process(&self, packet: RawPacket) -> Option<RawPacket> {
+                packet.data[0].is_multiple_of(2).then_some(packet)
+            }
+        }
+
+        /// Halves the mock sequence number
+        struct Halve;
+        impl PacketPreprocessor for Halve {
+            fn process(&self, mut packet: RawPacket) -> Option<RawPacket> {
+                packet.data[0] /= 2;
+                Some(packet)
+            }
+        }
+
+        let packets = (1..=6).map(|seq| vec![seq, 1]).collect();
+        let mut analyzer = PacketAnalyzer::new(MockSource::new(packets), MockParser);
+        analyzer.add_preprocessor(Box::new(DropOdd));
+        analyzer.add_preprocessor(Box::new(Halve));
+
+        // 2, 4, 6 survive and become 1, 2, 3: no gaps
+        let mut gap_count = 0;
+        let report = analyzer.analyze_streaming(|_| gap_count += 1).unwrap();
+        assert_eq!(report.total_packets, 6);
+        assert_eq!(gap_count, 0);
+        assert_eq!(report.flow_stats[0].packets_received, 3);
+    }
+
+    #[test]
+    fn test_analyzer_vlan_strip_macsec() {
+        use super::preprocess::VlanStripPreprocessor;
+        use crate::protocol::MACsecParser;
+
+        let packets = (1u32..=3)
+            .map(|pn| {
+                let mut packet = vec![0u8; 12];
+                packet.extend_from_slice(&[0x81, 0x00, 0x00, 0x64]); // VLAN 100
+                packet.extend_from_slice(&[0x88, 0xE5, 0x00, 0x00]);
+                packet.extend_from_slice(&pn.to_be_bytes());
+                packet.extend_from_slice(&0x1234u64.to_be_bytes());
+                packet.extend_from_slice(&[0u8; 32]);
+                packet
+            })
+            .collect();
+
+        let mut analyzer = PacketAnalyzer::new(MockSource::new(packets), MACsecParser);
+        analyzer.add_preprocessor(Box::new(VlanStripPreprocessor));
+
+        let report = analyzer.analyze_streaming(|_| {}).unwrap();
+        assert_eq!(report.flow_stats.len(), 1);
+        assert_eq!(report.flow_stats[0].packets_received, 3);
+    }
+
+    #[test]
+    fn test_analyzer_protocol_breakdown() {
+        use crate::types::FlowId;
+        use std::net::{IpAddr, Ipv4Addr};
+
+        /// First byte picks the protocol: 0 = MACsec, 1 = IPsec, 2 = TCP
+        struct MixedParser;
+        impl SequenceParser for MixedParser {
+            fn parse_sequence(&self, data: &[u8]) -> Result<Option<SequenceInfo>, crate::error::ParseError> {
+                let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, data[2]));
+                let flow_id = match data[0] {
+                    0 => FlowId::MACsec { sci: data[2] as u64, an: 0 },
+                    1 => FlowId::IPsec { spi: 0x100, dst_ip: ip, mode: None },
+                    2 => FlowId::GenericL3 {
+                        src_ip: ip,
+                        dst_ip: ip,
+                        src_port: 1000,
+                        dst_port: 80,
+                        protocol: 6,
+                    },
+                    _ => return Ok(None),
+                };
+                Ok(Some(SequenceInfo {
+                    sequence_number: data[1] as u32,
+                    flow_id,
+                    payload_length: 100,
+                    proto_version: None,
+                    dscp: None,
+                    ecn: None,
+                }))
+            }
+
+            fn matches(&self, _data: &[u8]) -> bool {
+                true
+            }
+
+            fn protocol_name(&self) -> &str {
+                "Mixed"
+            }
+        }
+
+        // 16 MACsec packets over 2 flows, 3 IPsec, 1 TCP, 5 unparseable
+        let mut packets = Vec::new();
+        for seq in 1..=8 {
+            packets.push(vec![0, seq, 1]);
+            packets.push(vec![0, seq, 2]);
+        }
+        for seq in 1..=3 {
+            packets.push(vec![1, seq, 1]);
+        }
+        packets.push(vec![2, 1, 1]);
+        for seq in 1..=5 {
+            packets.push(vec![9, seq, 1]);
+        }
+
+        let mut analyzer = PacketAnalyzer::new(MockSource::new(packets), MixedParser);
+        let report = analyzer.analyze_streaming(|_| {}).unwrap();
+        assert_eq!(report.total_packets, 25);
+
+        let macsec = report.protocol_breakdown["MACsec"];
+        assert_eq!(macsec, ProtocolBreakdown { packets: 16, flows: 2, bytes: 1_600 });
+        assert_eq!(report.protocol_breakdown["IPsec"].flows, 1);
+        assert_eq!(report.protocol_breakdown["TCP"].packets, 1);
+        assert_eq!(report.protocol_breakdown.len(), 3);
+
+        assert_eq!(report.packet_percentage("MACsec"), 80.0);
+        assert_eq!(report.packet_percentage("IPsec"), 15.0);
+        assert_eq!(report.packet_percentage("TCP"), 5.0);
+        assert_eq!(report.packet_percentage("UDP"), 0.0);
+    }
+
+    fn memory_persistence() -> (PersistenceManager, std::sync::Arc<std::sync::Mutex<crate::db::Database>>) {
+        use crate::db::{Database, DatabaseConfig};
+        let mut db = Database::open(&DatabaseConfig::sqlite(":memory:")).unwrap();
+        db.initialize().unwrap();
+        let db = std::sync::Arc::new(std::sync::Mutex::new(db));
+        (PersistenceManager::new(std::sync::Arc::clone(&db)), db)
+    }
+
+    #[test]
+    fn test_analyzer_max_persist_rate_skips_writes() {
+        let packets = (1..=100).map(|seq| vec![seq, 1]).collect();
+        let (persistence, db) = memory_persistence();
+        let mut analyzer = PacketAnalyzer::new(MockSource::new(packets), MockParser)
+            .with_persistence(persistence)
+            .with_max_persist_rate(2);
+        analyzer.persist_packet_threshold = 10;
+
+        analyzer.analyze_streaming(|_| {}).unwrap();
+
+        // 10 periodic checkpoints in well under a second: 2 allowed, 8 skipped,
+        // plus the unconditional final write
+        let stats = analyzer.stats();
+        assert_eq!(stats.packets_processed, 100);
+        assert_eq!(stats.persist_skipped, 8);
+        assert_eq!(stats.persist_calls, 3);
+        assert_eq!(stats.max_persist_rate, Some(2));
+
+        let flows = db.lock().unwrap().get_flows(None, None).unwrap();
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].packets_received, 100);
+    }
+
+    #[test]
+    fn test_analyzer_rate_limit_slows_loop() {
+        let packets = (1..=20).map(|seq| vec![seq, 1]).collect();
+        let mut analyzer = PacketAnalyzer::new(MockSource::new(packets), MockParser)
+            .with_analysis_rate_limit(200);
+
+        let start = Instant::now();
+        analyzer.analyze_streaming(|_| {}).unwrap();
+
+        // 20 packets at 200 pps take at least 100ms
+        assert!(start.elapsed() >= Duration::from_millis(95));
+        assert_eq!(analyzer.stats().analysis_rate_limit, Some(200));
+        assert!(analyzer.stats().analysis_pps <= 210.0);
     }
 }
@@ -0,0 +1,157 @@
+//! Packet pre-processing hooks
+//!
+//! Preprocessors run between capture and parsing. Each one receives the raw
+//! packet and returns a (possibly modified) packet, or `None` to drop it.
+//! Typical uses are de-encapsulation, decryption or header stripping so that
+//! the protocol parsers see the frame layout they expect.
+
+use crate::types::RawPacket;
+
+/// 802.1Q VLAN tag TPID
+const ETHERTYPE_VLAN: u16 = 0x8100;
+/// 802.1ad (QinQ) service tag TPID
+const ETHERTYPE_QINQ: u16 = 0x88A8;
+const ETHERTYPE_IPV4: u16 = 0x0800;
+
+const ETHERNET_HEADER_LEN: usize = 14;
+const VLAN_TAG_LEN: usize = 4;
+
+/// A transformation applied to each packet before parsing
+pub trait PacketPreprocessor: Send {
+    /// Return the packet to pass on, or `None` to drop it
+    fn process(&self, packet: RawPacket) -> Option<RawPacket>;
+}
+
+/// Removes 802.1Q (and stacked 802.1ad) VLAN tags from Ethernet frames
+///
+/// After stripping, bytes 12-13 hold the inner EtherType, so parsers that
+/// match on EtherType (e.g. MACsec 0x88E5) see an untagged frame.
+pub struct VlanStripPreprocessor;
+
+impl PacketPreprocessor for VlanStripPreprocessor {
+    fn process(&self, mut packet: RawPacket) -> Option<RawPacket> {
+        let mut removed = 0;
+        while packet.data.len() >= ETHERNET_HEADER_LEN + VLAN_TAG_LEN {
+            let ethertype = u16::from_be_bytes([packet.data[12], packet.data[13]]);
+            if ethertype != ETHERTYPE_VLAN && ethertype != ETHERTYPE_QINQ {
+                break;
+            }
+            // Tag occupies bytes 12-15 (TPID + TCI)
+            packet.data.drain(12..12 + VLAN_TAG_LEN);
+            removed += VLAN_TAG_LEN;
+        }
+
+        packet.length = packet.length.saturating_sub(removed);
+        Some(packet)
+    }
+}
+
+/// Removes trailing Ethernet padding from short frames
+///
+/// Frames below the 60-byte Ethernet minimum are padded with zeros, which
+/// inflates payload lengths. The real frame length is only known from the
+/// L3 header, so only IPv4 frames (using the IP total length) are trimmed;
+/// other frames pass through unchanged.
+pub struct EthernetPadStripPreprocessor;
+
+impl PacketPreprocessor for EthernetPadStripPreprocessor {
+    fn process(&self, mut packet: RawPacket) -> Option<RawPacket> {
+        if packet.data.len() < ETHERNET_HEADER_LEN + 4 {
+            return Some(packet);
+        }
+
+        let ethertype = u16::from_be_bytes([packet.data[12], packet.data[13]]);
+        if ethertype != ETHERTYPE_IPV4 {
+            return Some(packet);
+        }
+
+        // IPv4 total length at bytes 2-3 of the IP header
+        let ip_total_len = u16::from_be_bytes([packet.data[16], packet.data[17]]) as usize;
+        let frame_len = ETHERNET_HEADER_LEN + ip_total_len;
+        if ip_total_len >= 20 && frame_len < packet.data.len() {
+            let removed = packet.data.len() - frame_len;
+            packet.data.truncate(frame_len);
+            packet.length = packet.length.saturating_sub(removed);
+        }
+
+        Some(packet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{MACsecParser, SequenceParser};
+    use crate::types::FlowId;
+    use std::time::SystemTime;
+
+    fn raw(data: Vec<u8>) -> RawPacket {
+        let length = data.len();
+        RawPacket {
+            data,
+            timestamp: SystemTime::now(),
+            length,
+        }
+    }
+
+    /// MACsec frame (PN 7, SCI 0xAABB) with `tags` VLAN tags after the MACs
+    fn vlan_macsec_packet(tags: &[u16]) -> Vec<u8> {
+        let mut packet = vec![0u8; 12];
+        for &tpid in tags {
+            packet.extend_from_slice(&tpid.to_be_bytes());
+            packet.extend_from_slice(&[0x00, 0x64]); // VLAN 100
+        }
+        packet.extend_from_slice(&[0x88, 0xE5, 0x00, 0x00]);
+        packet.extend_from_slice(&7u32.to_be_bytes());
+        packet.extend_from_slice(&0xAABBu64.to_be_bytes());
+        packet.extend_from_slice(&[0u8; 32]);
+        packet
+    }
+
+    #[test]
+    fn test_vlan_strip_makes_macsec_parseable() {
+        let parser = MACsecParser;
+        let packet = raw(vlan_macsec_packet(&[ETHERTYPE_VLAN]));
+        assert!(parser.parse_sequence(&packet.data).unwrap().is_none());
+
+        let stripped = VlanStripPreprocessor.process(packet).unwrap();
+        assert_eq!(stripped.data, vlan_macsec_packet(&[]));
+        assert_eq!(stripped.length, stripped.data.len());
+
+        let seq_info = parser.parse_sequence(&stripped.data).unwrap().unwrap();
+        assert_eq!(seq_info.sequence_number, 7);
+        assert!(matches!(seq_info.flow_id, FlowId::MACsec { sci: 0xAABB, an: 0 }));
+    }
+
+    #[test]
+    fn test_vlan_strip_qinq_and_untagged() {
+        let stripped = VlanStripPreprocessor
+            .process(raw(vlan_macsec_packet(&[ETHERTYPE_QINQ, ETHERTYPE_VLAN])))
+            .unwrap();
+        assert_eq!(stripped.data, vlan_macsec_packet(&[]));
+
+        let untagged = vlan_macsec_packet(&[]);
+        let unchanged = VlanStripPreprocessor.process(raw(untagged.clone())).unwrap();
+        assert_eq!(unchanged.data, untagged);
+    }
+
+    #[test]
+    fn test_pad_strip_ipv4() {
+        // 14-byte Ethernet + 28-byte IPv4/UDP, padded to 60 bytes
+        let mut data = vec![0u8; 60];
+        data[12..14].copy_from_slice(&[0x08, 0x00]);
+        data[14] = 0x45;
+        data[16..18].copy_from_slice(&28u16.to_be_bytes());
+
+        let stripped = EthernetPadStripPreprocessor.process(raw(data)).unwrap();
+        assert_eq!(stripped.data.len(), 42);
+        assert_eq!(stripped.length, 42);
+    }
+
+    #[test]
+    fn test_pad_strip_ignores_non_ipv4() {
+        let data = vlan_macsec_packet(&[]);
+        let unchanged = EthernetPadStripPreprocessor.process(raw(data.clone())).unwrap();
+        assert_eq!(unchanged.data, data);
+    }
+}
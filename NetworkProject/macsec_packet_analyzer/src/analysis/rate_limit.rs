@@ -0,0 +1,138 @@
+//! Rate limiting for the analysis loop
+//!
+//! `TokenBucket` caps how often periodic database writes may run, and
+//! `PacketPacer` slows packet processing down to a fixed packets-per-second
+//! ceiling.
+
+use std::time::{Duration, Instant};
+
+/// Token bucket refilled at a fixed rate, holding at most one second of tokens
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate_per_sec: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Bucket allowing `rate_per_sec` acquisitions per second, starting full
+    ///
+    /// A rate of 0 is treated as 1.
+    pub fn new(rate_per_sec: u64) -> Self {
+        Self::new_at(rate_per_sec, Instant::now())
+    }
+
+    fn new_at(rate_per_sec: u64, now: Instant) -> Self {
+        let rate = rate_per_sec.max(1) as f64;
+        Self {
+            rate_per_sec: rate,
+            capacity: rate,
+            tokens: rate,
+            last_refill: now,
+        }
+    }
+
+    /// Configured refill rate in tokens per second
+    pub fn rate_per_sec(&self) -> f64 {
+        self.rate_per_sec
+    }
+
+    /// Take one token if available
+    pub fn try_acquire(&mut self) -> bool {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Keeps a packet loop at or below `max_pps`
+#[derive(Debug, Clone)]
+pub struct PacketPacer {
+    max_pps: u64,
+    start: Option<Instant>,
+    packets: u64,
+}
+
+/// Delays shorter than this are carried over to the next packet, so the loop
+/// sleeps in batches instead of once per packet
+const MIN_SLEEP: Duration = Duration::from_millis(1);
+
+impl PacketPacer {
+    /// Pacer allowing `max_pps` packets per second (0 is treated as 1)
+    pub fn new(max_pps: u64) -> Self {
+        Self {
+            max_pps: max_pps.max(1),
+            start: None,
+            packets: 0,
+        }
+    }
+
+    /// Configured packets-per-second ceiling
+    pub fn max_pps(&self) -> u64 {
+        self.max_pps
+    }
+
+    /// Record one packet and return how long to sleep before the next
+    pub fn pace(&mut self) -> Option<Duration> {
+        self.pace_at(Instant::now())
+    }
+
+    fn pace_at(&mut self, now: Instant) -> Option<Duration> {
+        let start = *self.start.get_or_insert(now);
+        self.packets += 1;
+
+        let due = Duration::from_secs_f64(self.packets as f64 / self.max_pps as f64);
+        let ahead = due.saturating_sub(now.saturating_duration_since(start));
+        (ahead >= MIN_SLEEP).then_some(ahead)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_refills_at_rate() {
+        let t0 = Instant::now();
+        let mut bucket = TokenBucket::new_at(2, t0);
+
+        assert!(bucket.try_acquire_at(t0));
+        assert!(bucket.try_acquire_at(t0));
+        assert!(!bucket.try_acquire_at(t0));
+
+        // Half a second at 2/s refills one token
+        let t1 = t0 + Duration::from_millis(500);
+        assert!(bucket.try_acquire_at(t1));
+        assert!(!bucket.try_acquire_at(t1));
+
+        // Idle time never banks more than one second of tokens
+        let t2 = t1 + Duration::from_secs(60);
+        assert!(bucket.try_acquire_at(t2));
+        assert!(bucket.try_acquire_at(t2));
+        assert!(!bucket.try_acquire_at(t2));
+    }
+
+    #[test]
+    fn test_pacer_sleeps_when_ahead() {
+        let t0 = Instant::now();
+        let mut pacer = PacketPacer::new(100);
+
+        // First packet at t0 is due at 10ms
+        assert_eq!(pacer.pace_at(t0), Some(Duration::from_millis(10)));
+
+        // Already behind schedule: no sleep
+        assert_eq!(pacer.pace_at(t0 + Duration::from_millis(50)), None);
+    }
+}
@@ -4,18 +4,21 @@
 //! Provides HTTP endpoints to retrieve flow statistics, gaps, and summary data
 //! stored in the SQLite database.
 
-use crate::db::{Database, DatabaseConfig};
-use crate::types::FlowId;
+use crate::db::{AnomalyThreshold, AnomalyType, Database, DatabaseConfig};
+use crate::types::{FlowId, FlowMetric, ProtocolBreakdown};
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
-    routing::get,
-    Json, Router,
+    routing::{get, post},
+    Extension, Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// API request/response models
 #[derive(Debug, Serialize, Deserialize)]
@@ -48,6 +51,24 @@ pub struct FlowResponse {
     pub avg_inter_arrival_ms: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub protocol_distribution: Option<Value>,
+    /// Packets per protocol version (MACsec V bit, IPv4 version)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol_version_distribution: Option<Value>,
+    /// Packets per DSCP class (plain IPv4 flows)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dscp_distribution: Option<Value>,
+    /// Packets marked ECN Congestion Experienced
+    #[serde(default)]
+    pub ecn_congestion_signals: u64,
+    /// Moving average of per-gap loss fraction (recent trend)
+    #[serde(default)]
+    pub loss_rate_ema: f64,
+    /// Loss fraction of the most recent gap
+    #[serde(default)]
+    pub loss_rate_instantaneous: f64,
+    /// Lost packets as a percentage of expected packets over the flow's lifetime
+    #[serde(default)]
+    pub packet_loss_percent: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -57,6 +78,9 @@ pub struct GapResponse {
     pub received_sequence: u32,
     pub gap_size: u32,
     pub timestamp: String,
+    /// Microseconds until late packets filled the gap, if they did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_time_us: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -72,6 +96,12 @@ pub struct SummaryResponse {
     pub total_bytes: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub avg_bandwidth_mbps: Option<f64>,
+
+    // Gap size distribution (bucket upper bound -> count)
+    pub gap_size_histogram: BTreeMap<u32, u64>,
+
+    // Packets, flows and bytes per protocol (MACsec, IPsec, TCP, ...)
+    pub protocol_breakdown: BTreeMap<String, ProtocolBreakdown>,
 }
 
 /// Query parameters for pagination
@@ -90,6 +120,70 @@ pub struct FlowQueryParams {
     pub max_bytes: Option<u64>,
     pub min_bandwidth_mbps: Option<f64>,
     pub max_bandwidth_mbps: Option<f64>,
+    /// Comma-separated flow IDs; looks up only these flows (limit/offset ignored)
+    pub flow_ids: Option<String>,
+    /// Full-text search on flow IDs, e.g. an IP or port (limit/offset ignored)
+    pub search: Option<String>,
+}
+
+/// Query parameters for ranking flows by a metric
+#[derive(Debug, Deserialize)]
+pub struct TopFlowsParams {
+    /// Ranking metric (default: gap_count)
+    pub metric: Option<FlowMetric>,
+    /// Number of flows to return (default: 10, max: 1000)
+    pub n: Option<usize>,
+}
+
+/// Query parameters for the gappiest flows
+#[derive(Debug, Deserialize)]
+pub struct TopGappyParams {
+    /// Number of flows to return (default: 10, max: 1000)
+    pub limit: Option<usize>,
+}
+
+/// Query parameters for high-loss flows
+#[derive(Debug, Deserialize)]
+pub struct HighLossParams {
+    /// Loss percentage a flow must exceed (default: 1.0)
+    pub threshold: Option<f64>,
+}
+
+/// Query parameters for flow time-series metrics
+#[derive(Debug, Deserialize)]
+pub struct TimeseriesParams {
+    /// Bucket width in seconds (default: 60)
+    pub interval_s: Option<u64>,
+}
+
+/// Query parameters for incremental gap polling
+#[derive(Debug, Deserialize)]
+pub struct GapsSinceParams {
+    /// RFC 3339 / ISO 8601 timestamp; omitted returns every gap
+    pub since: Option<String>,
+}
+
+/// Query parameters for anomaly detection; unset fields use `AnomalyThreshold::default()`
+#[derive(Debug, Deserialize)]
+pub struct AnomalyParams {
+    pub min_loss_pct: Option<f64>,
+    pub max_loss_pct: Option<f64>,
+    pub min_packets: Option<u64>,
+    pub min_bandwidth_mbps: Option<f64>,
+    pub max_bandwidth_mbps: Option<f64>,
+}
+
+impl From<AnomalyParams> for AnomalyThreshold {
+    fn from(params: AnomalyParams) -> Self {
+        let defaults = AnomalyThreshold::default();
+        AnomalyThreshold {
+            min_loss_pct: params.min_loss_pct.unwrap_or(defaults.min_loss_pct),
+            max_loss_pct: params.max_loss_pct.unwrap_or(defaults.max_loss_pct),
+            min_packets: params.min_packets.unwrap_or(defaults.min_packets),
+            min_bandwidth_mbps: params.min_bandwidth_mbps.unwrap_or(defaults.min_bandwidth_mbps),
+            max_bandwidth_mbps: params.max_bandwidth_mbps.unwrap_or(defaults.max_bandwidth_mbps),
+        }
+    }
 }
 
 /// Shared database connection wrapped in Arc<Mutex<>>
@@ -142,9 +236,19 @@ fn flow_stats_to_response(stats: &crate::types::FlowStats) -> FlowResponse {
     } else {
         serde_json::to_value(&stats.protocol_distribution).ok()
     };
+    let protocol_version_distribution = if stats.protocol_version_distribution.is_empty() {
+        None
+    } else {
+        serde_json::to_value(&stats.protocol_version_distribution).ok()
+    };
+    let dscp_distribution = if stats.dscp_distribution.is_empty() {
+        None
+    } else {
+        serde_json::to_value(&stats.dscp_distribution).ok()
+    };
 
     FlowResponse {
-        flow_id: stats.flow_id.to_string(),
+        flow_id: stats.flow_id.to_compact_string(),
         packets_received: stats.packets_received,
         gaps_detected: stats.gaps_detected,
         total_lost_packets: stats.total_lost_packets,
@@ -161,6 +265,12 @@ fn flow_stats_to_response(stats: &crate::types::FlowStats) -> FlowResponse {
         max_inter_arrival_ms,
         avg_inter_arrival_ms,
         protocol_distribution,
+        protocol_version_distribution,
+        dscp_distribution,
+        ecn_congestion_signals: stats.ecn_congestion_signals,
+        loss_rate_ema: stats.loss_rate_ema,
+        loss_rate_instantaneous: stats.loss_rate_instantaneous,
+        packet_loss_percent: stats.packet_loss_percent(),
     }
 }
 
@@ -180,37 +290,85 @@ fn flow_stats_to_response(stats: &crate::types::FlowStats) -> FlowResponse {
 /// # Ok(())
 /// # }
 /// ```
+///
+/// Admin endpoints are not served; see `start_server_with_admin_token`.
 pub async fn start_server(
     db_config: DatabaseConfig,
     listen_addr: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    start_server_with_admin_token(db_config, listen_addr, None).await
+}
+
+/// Create and start the REST API server, serving the admin endpoints
+/// (`/api/v1/admin/*`) when `admin_token` is set
+///
+/// Admin requests must send `Authorization: Bearer <admin_token>`; others
+/// get 401 Unauthorized. With no token the admin routes return 404.
+pub async fn start_server_with_admin_token(
+    db_config: DatabaseConfig,
+    listen_addr: &str,
+    admin_token: Option<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Initialize database
     let mut db = Database::open(&db_config)?;
     db.initialize()?;
 
     let db: SharedDb = Arc::new(Mutex::new(db));
+    let admin_enabled = admin_token.is_some();
 
     // Build router with all endpoints
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/health", get(health_check))
+        .route("/metrics", get(get_prometheus_metrics))
         .route("/api/v1/stats/summary", get(get_summary_stats))
+        .route("/api/v1/stats/top-gappy", get(get_top_gappy_flows))
         .route("/api/v1/flows", get(list_flows))
+        .route("/api/v1/flows/top", get(get_top_flows))
+        .route("/api/v1/flows/high-loss", get(get_high_loss_flows))
         .route("/api/v1/flows/:flow_id", get(get_flow_detail))
         .route("/api/v1/flows/:flow_id/gaps", get(get_flow_gaps))
-        .with_state(db);
+        .route("/api/v1/flows/:flow_id/metrics", get(get_flow_metrics))
+        .route("/api/v1/gaps", get(get_gaps_since))
+        .route("/api/v1/anomalies", get(get_anomalies))
+        .route("/api/v1/graph", get(get_flow_graph));
+    if let Some(token) = admin_token {
+        app = app.route(
+            "/api/v1/admin/vacuum",
+            post(vacuum_database).layer(Extension(AdminToken(token))),
+        );
+    }
+    let app = app.with_state(db);
 
     // Start server
     let listener = tokio::net::TcpListener::bind(listen_addr).await?;
     println!("REST API server listening on http://{}", listen_addr);
     println!("Available endpoints:");
     println!("  GET /health - Health check");
+    println!("  GET /metrics - Prometheus metrics (gap size histogram, anomaly counts)");
     println!("  GET /api/v1/stats/summary - Summary statistics with bandwidth metrics");
+    println!("  GET /api/v1/stats/top-gappy - Flows with the most sequence gaps");
+    println!("    Query params: limit (default 10)");
     println!("  GET /api/v1/flows - List all flows with enhanced statistics");
-    println!("    Query params: limit, offset, min_bytes, max_bytes, min_bandwidth_mbps, max_bandwidth_mbps");
+    println!("    Query params: limit, offset, min_bytes, max_bytes, min_bandwidth_mbps, max_bandwidth_mbps, flow_ids, search");
+    println!("  GET /api/v1/flows/top - Top N flows ranked by a metric");
+    println!("    Query params: metric (packet_rate|byte_rate|gap_count|loss_percentage), n");
+    println!("  GET /api/v1/flows/high-loss - Flows losing more than a percentage of packets");
+    println!("    Query params: threshold (percent, default 1.0)");
     println!("  GET /api/v1/flows/:flow_id - Get flow details with all metrics");
     println!("  GET /api/v1/flows/:flow_id/gaps - Get gaps for a flow");
     println!("    Note: Gap detection is only available for MACsec and IPsec flows");
     println!("          Generic L3 (TCP/UDP) flows will have 0 gaps detected");
+    println!("  GET /api/v1/flows/:flow_id/metrics - Packet/byte/gap rate time-series");
+    println!("    Query params: interval_s (default 60)");
+    println!("  GET /api/v1/gaps - Gaps across all flows detected after a timestamp");
+    println!("    Query params: since (ISO 8601)");
+    println!("  GET /api/v1/anomalies - Flows with high loss, unusual bandwidth or no traffic");
+    println!("    Query params: min_loss_pct, max_loss_pct, min_packets, min_bandwidth_mbps, max_bandwidth_mbps");
+    println!("  GET /api/v1/graph - Host graph of Generic L3 flows in Graphviz DOT format");
+    if admin_enabled {
+        println!("  POST /api/v1/admin/vacuum - Reclaim unused database space (admin)");
+        println!("    Requires header: Authorization: Bearer <admin_token>");
+    }
 
     axum::serve(listener, app).await?;
     Ok(())
@@ -230,44 +388,16 @@ async fn get_summary_stats(
 ) -> Result<Json<SummaryResponse>, ApiError> {
     let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
     let stats = db.get_summary_stats()?;
+    // Aggregated in SQL over every flow, not a page of get_flows()
+    let protocol_breakdown = db.get_protocol_breakdown()?;
+    let total_bytes = stats.total_bytes.max(0) as u64;
 
-    // Calculate aggregate statistics from all flows for bandwidth
-    let all_flows = db.get_flows(None, None)?;
-    let total_bytes: u64 = all_flows.iter().map(|f| f.total_bytes).sum();
-
-    // Calculate average bandwidth across all flows
-    // Use the overall time span from earliest first_timestamp to latest last_timestamp
-    let avg_bandwidth_mbps = if all_flows.is_empty() {
-        None
-    } else {
-        // Find the earliest first_timestamp and latest last_timestamp across all flows
-        let overall_first = all_flows
-            .iter()
-            .filter_map(|f| f.first_timestamp)
-            .min();
-        let overall_last = all_flows
-            .iter()
-            .filter_map(|f| f.last_timestamp)
-            .max();
-
-        let total_duration = overall_first
-            .zip(overall_last)
-            .and_then(|(first, last)| {
-                last.duration_since(first)
-                    .ok()
-                    .map(|d| d.as_secs_f64())
-            });
-
-        if let Some(duration) = total_duration {
-            if duration > 0.0 && total_bytes > 0 {
-                Some((total_bytes as f64 * 8.0) / duration / 1_000_000.0)
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    };
+    // Average bandwidth over the overall time span, from the earliest
+    // first_timestamp to the latest last_timestamp across all flows
+    let avg_bandwidth_mbps = stats
+        .duration_secs
+        .filter(|&duration| duration > 0.0 && total_bytes > 0)
+        .map(|duration| (total_bytes as f64 * 8.0) / duration / 1_000_000.0);
 
     Ok(Json(SummaryResponse {
         total_flows: stats.total_flows,
@@ -277,16 +407,67 @@ async fn get_summary_stats(
         max_gap_size: stats.max_gap_size,
         total_bytes: if total_bytes > 0 { Some(total_bytes) } else { None },
         avg_bandwidth_mbps,
+        gap_size_histogram: stats.gap_size_histogram,
+        protocol_breakdown,
     }))
 }
 
+/// Prometheus text exposition of the gap size histogram and anomaly counts
+///
+/// Buckets are cumulative as Prometheus expects:
+/// `macsec_gap_size_bucket{le="4"}` counts every gap of size <= 4.
+/// Anomalies use the default `AnomalyThreshold`.
+async fn get_prometheus_metrics(
+    State(db): State<SharedDb>,
+) -> Result<impl IntoResponse, ApiError> {
+    let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
+    let stats = db.get_summary_stats()?;
+
+    let mut body = String::new();
+    let _ = writeln!(body, "# HELP macsec_gap_size Size of detected sequence gaps in packets");
+    let _ = writeln!(body, "# TYPE macsec_gap_size histogram");
+
+    let mut cumulative = 0u64;
+    for (bound, count) in &stats.gap_size_histogram {
+        cumulative += count;
+        let _ = writeln!(body, "macsec_gap_size_bucket{{le=\"{}\"}} {}", bound, cumulative);
+    }
+    let _ = writeln!(body, "macsec_gap_size_bucket{{le=\"+Inf\"}} {}", cumulative);
+    // Every lost packet belongs to exactly one gap, so the sum of gap sizes is total loss
+    let _ = writeln!(body, "macsec_gap_size_sum {}", stats.total_lost_packets);
+    let _ = writeln!(body, "macsec_gap_size_count {}", cumulative);
+
+    let anomalies = db.get_flow_anomalies(AnomalyThreshold::default())?;
+    let _ = writeln!(body, "# HELP macsec_flow_anomalies Flows currently flagged by anomaly detection");
+    let _ = writeln!(body, "# TYPE macsec_flow_anomalies gauge");
+    for kind in ["high_loss", "low_bandwidth", "high_bandwidth", "no_traffic"] {
+        let count = anomalies.iter().filter(|a| a.anomaly_type.name() == kind).count();
+        let _ = writeln!(body, "macsec_flow_anomalies{{type=\"{}\"}} {}", kind, count);
+    }
+
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    ))
+}
+
 /// List all flows with pagination and optional filtering
 async fn list_flows(
     State(db): State<SharedDb>,
     Query(params): Query<FlowQueryParams>,
 ) -> Result<Json<Value>, ApiError> {
     let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
-    let flows = db.get_flows(params.limit, params.offset)?;
+    let flows = match (params.flow_ids.as_deref(), params.search.as_deref()) {
+        (Some(flow_ids), _) => {
+            let flow_ids = split_flow_ids(flow_ids)
+                .into_iter()
+                .map(parse_flow_id)
+                .collect::<Result<Vec<FlowId>, _>>()?;
+            db.get_flows_by_ids(&flow_ids)?
+        }
+        (None, Some(search)) => db.full_text_search_flows(search)?,
+        (None, None) => db.get_flows(params.limit, params.offset)?,
+    };
 
     let flow_responses: Vec<FlowResponse> = flows
         .into_iter()
@@ -326,13 +507,104 @@ async fn list_flows(
     })))
 }
 
+/// Parse a flow ID from a request, in compact or `Display` form
+fn parse_flow_id(s: &str) -> Result<FlowId, crate::error::ParseError> {
+    FlowId::from_compact_string(s).or_else(|_| s.parse())
+}
+
+/// Split a `flow_ids` list on top-level commas
+///
+/// Flow IDs contain commas inside their braces (e.g. `MACsec { sci: .., an: 0 }`),
+/// so only commas outside `{ }` separate entries.
+fn split_flow_ids(list: &str) -> Vec<&str> {
+    let mut ids = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in list.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                ids.push(list[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    ids.push(list[start..].trim());
+    ids.retain(|id| !id.is_empty());
+    ids
+}
+
+/// Get the top N flows ranked by a metric (e.g. `?metric=gap_count&n=10`)
+async fn get_top_flows(
+    State(db): State<SharedDb>,
+    Query(params): Query<TopFlowsParams>,
+) -> Result<Json<Value>, ApiError> {
+    let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
+    let metric = params.metric.unwrap_or(FlowMetric::GapCount);
+    let n = params.n.unwrap_or(10);
+
+    // Ranked in SQL across every stored flow
+    let flows = db.get_top_flows_by_metric(metric, n)?;
+    let flow_responses: Vec<FlowResponse> = flows.iter().map(flow_stats_to_response).collect();
+
+    Ok(Json(json!({
+        "metric": metric,
+        "count": flow_responses.len(),
+        "flows": flow_responses
+    })))
+}
+
+/// Get the flows with the most sequence gaps
+///
+/// Like `/api/v1/flows/top?metric=gap_count`, but ties are broken by total
+/// lost packets rather than flow ID.
+async fn get_top_gappy_flows(
+    State(db): State<SharedDb>,
+    Query(params): Query<TopGappyParams>,
+) -> Result<Json<Value>, ApiError> {
+    let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
+    let flows = db.get_top_flows_by_gap_count(params.limit.unwrap_or(10))?;
+    let flow_responses: Vec<FlowResponse> = flows.iter().map(flow_stats_to_response).collect();
+
+    Ok(Json(json!({
+        "count": flow_responses.len(),
+        "flows": flow_responses
+    })))
+}
+
+/// Get flows whose packet loss exceeds `threshold` percent, worst first
+async fn get_high_loss_flows(
+    State(db): State<SharedDb>,
+    Query(params): Query<HighLossParams>,
+) -> Result<Json<Value>, ApiError> {
+    let threshold = params.threshold.unwrap_or(1.0);
+    if !threshold.is_finite() {
+        return Err(ApiError::InvalidParameter(format!(
+            "threshold: expected a number, got {}",
+            threshold
+        )));
+    }
+
+    let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
+    let flows = db.get_flows_with_loss_above(threshold)?;
+    let flow_responses: Vec<FlowResponse> = flows.iter().map(flow_stats_to_response).collect();
+
+    Ok(Json(json!({
+        "count": flow_responses.len(),
+        "threshold": threshold,
+        "flows": flow_responses
+    })))
+}
+
 /// Get detailed statistics for a specific flow with enhanced metrics
 async fn get_flow_detail(
     State(db): State<SharedDb>,
     Path(flow_id): Path<String>,
 ) -> Result<Json<FlowResponse>, ApiError> {
     let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
-    let flow_id = FlowId::new(flow_id);
+    let flow_id = parse_flow_id(&flow_id)?;
     let stats = db
         .get_flow(&flow_id)?
         .ok_or(ApiError::FlowNotFound)?;
@@ -355,32 +627,192 @@ async fn get_flow_gaps(
     Query(params): Query<PaginationParams>,
 ) -> Result<Json<Value>, ApiError> {
     let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
-    let flow_id = FlowId::new(flow_id);
+    let flow_id = parse_flow_id(&flow_id)?;
     let gaps = db.get_flow_gaps(&flow_id, params.limit, params.offset)?;
 
-    let gap_responses: Vec<GapResponse> = gaps
+    let gap_responses: Vec<GapResponse> = gaps.into_iter().map(gap_to_response).collect();
+
+    Ok(Json(json!({
+        "count": gap_responses.len(),
+        "gaps": gap_responses
+    })))
+}
+
+/// Get gaps across all flows detected after `since` (e.g. `?since=2024-01-01T00:00:00Z`)
+///
+/// `latest` is the newest returned gap's timestamp, or `since` when there are
+/// none; pass it as the next `since` to poll incrementally.
+async fn get_gaps_since(
+    State(db): State<SharedDb>,
+    Query(params): Query<GapsSinceParams>,
+) -> Result<Json<Value>, ApiError> {
+    let since = match params.since.as_deref() {
+        Some(since) => chrono::DateTime::parse_from_rfc3339(since)
+            .map(|dt| std::time::SystemTime::from(dt.with_timezone(&chrono::Utc)))
+            .map_err(|e| ApiError::InvalidParameter(format!("since: {}", e)))?,
+        None => std::time::UNIX_EPOCH,
+    };
+
+    let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
+    let gaps = db.get_gaps_since(since)?;
+    let latest = gaps.last().map_or(since, |gap| gap.timestamp);
+    let gap_responses: Vec<GapResponse> = gaps.into_iter().map(gap_to_response).collect();
+
+    Ok(Json(json!({
+        "count": gap_responses.len(),
+        "latest": chrono::DateTime::<chrono::Utc>::from(latest).to_rfc3339(),
+        "gaps": gap_responses
+    })))
+}
+
+fn gap_to_response(g: crate::types::SequenceGap) -> GapResponse {
+    GapResponse {
+        flow_id: g.flow_id.to_compact_string(),
+        expected_sequence: g.expected,
+        received_sequence: g.received,
+        gap_size: g.gap_size,
+        timestamp: chrono::DateTime::<chrono::Utc>::from(g.timestamp).to_rfc3339(),
+        recovery_time_us: g.recovery_time.map(|d| d.as_micros() as u64),
+    }
+}
+
+/// Get packet, byte and gap rates for a flow over fixed intervals
+async fn get_flow_metrics(
+    State(db): State<SharedDb>,
+    Path(flow_id): Path<String>,
+    Query(params): Query<TimeseriesParams>,
+) -> Result<Json<Value>, ApiError> {
+    let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
+    let flow_id = parse_flow_id(&flow_id)?;
+    let interval_s = params.interval_s.unwrap_or(60).max(1);
+    let points = db.get_flow_metrics_timeseries(&flow_id, Duration::from_secs(interval_s))?;
+
+    let points: Vec<Value> = points
         .into_iter()
-        .map(|g| GapResponse {
-            flow_id: g.flow_id.to_string(),
-            expected_sequence: g.expected,
-            received_sequence: g.received,
-            gap_size: g.gap_size,
-            timestamp: chrono::DateTime::<chrono::Utc>::from(g.timestamp).to_rfc3339(),
+        .map(|p| {
+            json!({
+                "timestamp": chrono::DateTime::<chrono::Utc>::from(p.timestamp).to_rfc3339(),
+                "packet_rate_pps": p.packet_rate_pps,
+                "byte_rate_bps": p.byte_rate_bps,
+                "gap_rate": p.gap_rate,
+            })
         })
         .collect();
 
     Ok(Json(json!({
-        "count": gap_responses.len(),
-        "gaps": gap_responses
+        "flow_id": flow_id.to_compact_string(),
+        "interval_s": interval_s,
+        "count": points.len(),
+        "points": points
+    })))
+}
+
+/// Token the admin endpoints expect as `Authorization: Bearer <token>`
+#[derive(Clone)]
+struct AdminToken(String);
+
+/// Whether `headers` carry `Authorization: Bearer <expected>`
+///
+/// Compares in constant time so response timing doesn't leak the token.
+fn has_bearer_token(headers: &HeaderMap, expected: &str) -> bool {
+    let Some(provided) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Run VACUUM on the database and report the space reclaimed (admin)
+///
+/// VACUUM rewrites the whole file, so it runs on the blocking thread pool.
+async fn vacuum_database(
+    State(db): State<SharedDb>,
+    Extension(AdminToken(token)): Extension<AdminToken>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, ApiError> {
+    if !has_bearer_token(&headers, &token) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let (size_before, size_after) = tokio::task::spawn_blocking(move || {
+        let mut db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
+        let size_before = db.size_bytes()?;
+        db.vacuum()?;
+        Ok::<_, ApiError>((size_before, db.size_bytes()?))
+    })
+    .await
+    .map_err(|e| ApiError::DatabaseError(e.to_string()))??;
+
+    Ok(Json(json!({
+        "status": "ok",
+        "size_before_bytes": size_before,
+        "size_after_bytes": size_after,
+        "reclaimed_bytes": size_before.saturating_sub(size_after)
     })))
 }
 
+/// List flows that cross the anomaly thresholds (e.g. `?min_loss_pct=5.0`)
+async fn get_anomalies(
+    State(db): State<SharedDb>,
+    Query(params): Query<AnomalyParams>,
+) -> Result<Json<Value>, ApiError> {
+    let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
+    let anomalies = db.get_flow_anomalies(params.into())?;
+
+    let entries: Vec<Value> = anomalies
+        .iter()
+        .map(|anomaly| {
+            let idle_seconds = match anomaly.anomaly_type {
+                AnomalyType::NoTraffic(idle) => Some(idle.as_secs_f64()),
+                _ => None,
+            };
+            json!({
+                "anomaly_type": anomaly.anomaly_type.name(),
+                "idle_seconds": idle_seconds,
+                "flow": flow_stats_to_response(&anomaly.flow),
+            })
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "count": entries.len(),
+        "anomalies": entries
+    })))
+}
+
+/// Host-to-host traffic of Generic L3 flows as a Graphviz DOT document
+async fn get_flow_graph(State(db): State<SharedDb>) -> Result<impl IntoResponse, ApiError> {
+    let db = db.lock().map_err(|_| ApiError::DatabaseLocked)?;
+    // Every stored flow, not a page of get_flows
+    let graph = db.get_flow_graph()?;
+
+    Ok((
+        [(axum::http::header::CONTENT_TYPE, "text/vnd.graphviz")],
+        graph.to_dot(),
+    ))
+}
+
 /// API error types
 #[derive(Debug)]
 pub enum ApiError {
     DatabaseError(String),
     DatabaseLocked,
     FlowNotFound,
+    InvalidFlowId(String),
+    InvalidParameter(String),
+    Unauthorized,
+    CaptureFailed {
+        backend: &'static str,
+        message: String,
+    },
 }
 
 impl IntoResponse for ApiError {
@@ -407,6 +839,35 @@ impl IntoResponse for ApiError {
                     "message": "The requested flow was not found"
                 }),
             ),
+            ApiError::InvalidFlowId(msg) => (
+                StatusCode::BAD_REQUEST,
+                json!({
+                    "error": "invalid_flow_id",
+                    "message": msg
+                }),
+            ),
+            ApiError::InvalidParameter(msg) => (
+                StatusCode::BAD_REQUEST,
+                json!({
+                    "error": "invalid_parameter",
+                    "message": msg
+                }),
+            ),
+            ApiError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                json!({
+                    "error": "unauthorized",
+                    "message": "A valid admin bearer token is required"
+                }),
+            ),
+            ApiError::CaptureFailed { backend, message } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json!({
+                    "error": "capture_error",
+                    "backend": backend,
+                    "message": message
+                }),
+            ),
         };
 
         (status, Json(body)).into_response()
@@ -415,6 +876,107 @@ impl IntoResponse for ApiError {
 
 impl From<crate::error::CaptureError> for ApiError {
     fn from(err: crate::error::CaptureError) -> Self {
-        ApiError::DatabaseError(err.to_string())
+        match err {
+            crate::error::CaptureError::DatabaseError(_) => {
+                ApiError::DatabaseError(err.to_string())
+            }
+            _ => ApiError::CaptureFailed {
+                backend: err.backend(),
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+impl From<crate::error::ParseError> for ApiError {
+    fn from(err: crate::error::ParseError) -> Self {
+        ApiError::InvalidFlowId(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_flow_ids() {
+        let ids = split_flow_ids(
+            "MACsec { sci: 0x0000000000000001, an: 0 },IPsec { spi: 0x00001234, dst: 10.0.0.1 }, ",
+        );
+        assert_eq!(
+            ids,
+            vec![
+                "MACsec { sci: 0x0000000000000001, an: 0 }",
+                "IPsec { spi: 0x00001234, dst: 10.0.0.1 }",
+            ]
+        );
+        assert!(ids.iter().all(|id| id.parse::<FlowId>().is_ok()));
+        assert!(split_flow_ids("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_flow_id_accepts_both_forms() {
+        let flow_id = FlowId::MACsec {
+            sci: 0x0011_2233_4455_6677,
+            an: 1,
+        };
+        let compact = flow_id.to_compact_string();
+        assert_eq!(parse_flow_id(&compact).unwrap(), flow_id);
+        assert_eq!(parse_flow_id(&flow_id.to_string()).unwrap(), flow_id);
+        assert!(parse_flow_id("macsec-zz").is_err());
+    }
+
+    #[test]
+    fn test_responses_use_compact_flow_id() {
+        let flow_id = FlowId::IPsec {
+            spi: 0x1234,
+            dst_ip: "10.0.0.1".parse().unwrap(),
+            mode: None,
+        };
+        let response = gap_to_response(crate::types::SequenceGap {
+            flow_id: flow_id.clone(),
+            expected: 10,
+            received: 12,
+            gap_size: 2,
+            timestamp: std::time::SystemTime::UNIX_EPOCH,
+            recovery_time: None,
+        });
+
+        // Usable as-is in a /api/v1/flows/:flow_id path
+        assert_eq!(response.flow_id, "ipsec-00001234-10.0.0.1");
+        assert_eq!(parse_flow_id(&response.flow_id).unwrap(), flow_id);
+    }
+
+    #[test]
+    fn test_has_bearer_token() {
+        let with_auth = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+            headers
+        };
+
+        assert!(has_bearer_token(&with_auth("Bearer s3cret"), "s3cret"));
+        assert!(!has_bearer_token(&with_auth("Bearer s3cre"), "s3cret"));
+        assert!(!has_bearer_token(&with_auth("Bearer s3cres"), "s3cret"));
+        assert!(!has_bearer_token(&with_auth("s3cret"), "s3cret"));
+        assert!(!has_bearer_token(&HeaderMap::new(), "s3cret"));
+    }
+
+    #[test]
+    fn test_capture_error_keeps_backend() {
+        use crate::error::CaptureError;
+
+        match ApiError::from(CaptureError::NapatechError("port 300 out of range".into())) {
+            ApiError::CaptureFailed { backend, message } => {
+                assert_eq!(backend, "Napatech");
+                assert_eq!(message, "Napatech error: port 300 out of range");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert!(matches!(
+            ApiError::from(CaptureError::DatabaseError("disk full".into())),
+            ApiError::DatabaseError(_)
+        ));
     }
 }
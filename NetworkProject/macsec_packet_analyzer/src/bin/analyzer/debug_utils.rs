@@ -3,7 +3,7 @@
 
 use crate::analyzer::TimingStats;
 use macsec_packet_analyzer::analysis::flow::FlowTracker;
-use macsec_packet_analyzer::protocol::ProtocolRegistry;
+use macsec_packet_analyzer::protocol::{DetectionMetrics, ProtocolRegistry};
 use std::sync::Arc;
 use std::time::Instant;
 use std::time::Duration;
@@ -109,6 +109,13 @@ pub fn print_analysis_report(
         println!("Total (with overhead): {:.3}µs", total_avg);
         println!("Unaccounted overhead: {:.3}µs", total_avg - (detect_avg + track_avg));
 
+        let detections = timing_stats_obj.detection_samples();
+        if !detections.is_empty() {
+            println!();
+            println!("=== Detection Tier Timing (Debug Mode) ===");
+            DetectionMetrics::print_histogram(&detections);
+        }
+
         // Calculate I/O overhead
         let measured_us = detect_avg + track_avg;
         let total_us = 1_000_000.0 / pps.max(1.0);
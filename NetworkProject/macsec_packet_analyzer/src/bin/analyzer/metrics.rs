@@ -3,7 +3,7 @@
 
 use macsec_packet_analyzer::{
     analysis::flow::FlowTracker,
-    protocol::ProtocolRegistry,
+    protocol::{DetectionMetrics, ProtocolHint, ProtocolRegistry},
     types::{AnalyzedPacket, ProcessMetrics, RawPacket},
 };
 use std::sync::Arc;
@@ -16,8 +16,12 @@ pub struct TimingStats {
     total_track_us: Mutex<u128>,
     total_process_us: Mutex<u128>,  // Total time including overhead between operations
     packet_count: Mutex<u64>,
+    detections: Mutex<Vec<DetectionMetrics>>,
 }
 
+/// Registry timing samples kept for the histogram (about 32 MB)
+const MAX_DETECTION_SAMPLES: usize = 1_000_000;
+
 impl TimingStats {
     pub fn new() -> Self {
         Self {
@@ -25,6 +29,7 @@ impl TimingStats {
             total_track_us: Mutex::new(0),
             total_process_us: Mutex::new(0),
             packet_count: Mutex::new(0),
+            detections: Mutex::new(Vec::new()),
         }
     }
 
@@ -42,6 +47,18 @@ impl TimingStats {
         if let Ok(mut count) = self.packet_count.lock() {
             *count += 1;
         }
+        if let Some(detection) = metrics.detection {
+            if let Ok(mut detections) = self.detections.lock() {
+                if detections.len() < MAX_DETECTION_SAMPLES {
+                    detections.push(detection);
+                }
+            }
+        }
+    }
+
+    /// Registry timing of the first packets (up to one million)
+    pub fn detection_samples(&self) -> Vec<DetectionMetrics> {
+        self.detections.lock().map(|d| d.clone()).unwrap_or_default()
     }
 
     /// Get average timing (in microseconds)
@@ -68,6 +85,7 @@ impl TimingStats {
 pub fn process_single_packet(
     raw_packet: &RawPacket,
     registry: &Arc<ProtocolRegistry>,
+    hint: &ProtocolHint,
     flow_tracker: &Arc<FlowTracker>,
     debug: bool,
 ) -> Result<ProcessMetrics, Box<dyn std::error::Error>> {
@@ -75,7 +93,12 @@ pub fn process_single_packet(
     let total_start = if debug { Some(Instant::now()) } else { None };
 
     let detect_start = if debug { Some(Instant::now()) } else { None };
-    let seq_info = registry.detect_and_parse(&raw_packet.data)?;
+    let (seq_info, detection) = if debug && !hint.is_forced() {
+        let (result, detection) = registry.detect_and_parse_with_metrics(&raw_packet.data);
+        (result?, Some(detection))
+    } else {
+        (hint.parse(registry, &raw_packet.data)?, None)
+    };
     let detect_us = detect_start.map(|s| s.elapsed().as_micros()).unwrap_or(0);
 
     let mut metrics = ProcessMetrics {
@@ -84,6 +107,7 @@ pub fn process_single_packet(
         detect_us,
         track_us: 0,
         total_us: 0,
+        detection,
     };
 
     if let Some(seq_info) = seq_info {
@@ -92,6 +116,10 @@ pub fn process_single_packet(
             flow_id: seq_info.flow_id,
             timestamp: raw_packet.timestamp,
             payload_length: seq_info.payload_length,
+            proto_version: seq_info.proto_version,
+            dscp: seq_info.dscp,
+            ecn: seq_info.ecn,
+            raw_data: None,
         };
 
         let track_start = if debug { Some(Instant::now()) } else { None };
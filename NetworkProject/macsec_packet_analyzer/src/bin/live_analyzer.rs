@@ -24,6 +24,8 @@
 //!   # Live capture
 //!   ./target/release/live_analyzer eth0 live.db
 //!   ./target/release/live_analyzer eth0 live.db --debug
+//!   ./target/release/live_analyzer eth0 live.db --protocol macsec
+//!   ./target/release/live_analyzer eth0 live.db --retention-days 7
 //!
 //!   # PCAP replay with different timing modes
 //!   ./target/release/live_analyzer traffic.pcap test.db --replay --mode fast
@@ -48,7 +50,7 @@ use macsec_packet_analyzer::{
     db::{Database, DatabaseConfig},
     error::CaptureError,
     persist::PersistenceManager,
-    protocol::ProtocolRegistry,
+    protocol::{ProtocolHint, ProtocolRegistry},
 };
 
 use std::sync::{Arc, Mutex};
@@ -57,6 +59,12 @@ use tokio::signal;
 use tokio::task::spawn_blocking;
 use tokio::sync::mpsc;
 
+/// How often the retention cleanup runs when --retention-days is set
+const RETENTION_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Age after which buffered out-of-order packets are dropped; also the prune interval
+const REORDER_BUFFER_MAX_AGE: Duration = Duration::from_secs(30);
+
 // Feature-gated imports for different capture backends
 #[cfg(all(feature = "async", feature = "pcap"))]
 use macsec_packet_analyzer::capture::{PcapLiveCapture, ReplayCapture, ReplayMode};
@@ -83,12 +91,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Check for --debug flag
     let debug = args.iter().any(|arg| arg == "--debug");
 
+    // Check for --protocol <macsec|ipsec|generic|auto>
+    let hint = match args.iter().position(|arg| arg == "--protocol") {
+        Some(idx) => {
+            let name = args.get(idx + 1).ok_or("--protocol requires an argument")?;
+            ProtocolHint::from_name(name)?
+        }
+        None => ProtocolHint::auto(),
+    };
+
+    // Check for --retention-days <n>
+    let retention = match args.iter().position(|arg| arg == "--retention-days") {
+        Some(idx) => {
+            let days: u64 = args
+                .get(idx + 1)
+                .ok_or("--retention-days requires a number argument")?
+                .parse()?;
+            if days == 0 {
+                return Err("--retention-days must be > 0".into());
+            }
+            let secs = days
+                .checked_mul(24 * 60 * 60)
+                .ok_or("--retention-days is too large")?;
+            Some(Duration::from_secs(secs))
+        }
+        None => None,
+    };
+
     if is_replay {
         // PCAP replay mode
-        run_replay_capture(source, db_path, &args[3..], debug).await?;
+        run_replay_capture(source, db_path, &args[3..], debug, hint, retention).await?;
     } else {
         // Live capture mode (default, backward compatible)
-        run_with_compiled_backend(source, db_path, debug).await?;
+        run_with_compiled_backend(source, db_path, debug, hint, retention).await?;
     }
 
     Ok(())
@@ -99,17 +134,19 @@ async fn run_with_compiled_backend(
     interface: &str,
     db_path: &str,
     debug: bool,
+    hint: ProtocolHint,
+    retention: Option<Duration>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(all(feature = "async", feature = "pcap"))]
     {
         let mut capture = PcapLiveCapture::open(interface)?;
-        run_analyzer_impl(&mut capture, db_path, "PCAP", interface, debug).await
+        run_analyzer_impl(&mut capture, db_path, "PCAP", interface, debug, hint, retention).await
     }
 
     #[cfg(all(target_os = "linux", feature = "napatech"))]
     {
         let mut capture = NapatechCapture::open(0, 0)?; // Default port 0, stream 0
-        run_analyzer_impl(&mut capture, db_path, "Napatech", interface, debug).await
+        run_analyzer_impl(&mut capture, db_path, "Napatech", interface, debug, hint, retention).await
     }
 
     #[cfg(not(any(all(feature = "async", feature = "pcap"), all(target_os = "linux", feature = "napatech"))))]
@@ -127,6 +164,8 @@ async fn run_replay_capture(
     db_path: &str,
     options: &[String],
     debug: bool,
+    hint: ProtocolHint,
+    retention: Option<Duration>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Parse replay options
     let (replay_mode, enable_looping) = parse_replay_options(options)?;
@@ -135,7 +174,7 @@ async fn run_replay_capture(
     let mut capture = ReplayCapture::open(pcap_path, replay_mode, enable_looping)?;
 
     // Run generic analyzer (reuses existing infrastructure)
-    run_analyzer_impl(&mut capture, db_path, "PCAP Replay", pcap_path, debug).await?;
+    run_analyzer_impl(&mut capture, db_path, "PCAP Replay", pcap_path, debug, hint, retention).await?;
 
     // Report PCAP I/O statistics if in debug mode
     if debug {
@@ -152,6 +191,8 @@ async fn run_replay_capture(
     _db_path: &str,
     _options: &[String],
     _debug: bool,
+    _hint: ProtocolHint,
+    _retention: Option<Duration>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     eprintln!("Error: PCAP replay requires --features async,pcap");
     eprintln!("Build with: cargo build --bin live_analyzer");
@@ -221,6 +262,10 @@ fn parse_replay_options(options: &[String]) -> Result<(ReplayMode, bool), Box<dy
                 // Handled by main or in main args, skip it
                 i += 1;
             }
+            "--protocol" | "--retention-days" => {
+                // Handled by main, skip it and its value
+                i += 2;
+            }
             other => {
                 eprintln!("Warning: Unknown option: {}", other);
                 i += 1;
@@ -263,6 +308,8 @@ fn print_usage(program: &str) {
     eprintln!();
     eprintln!("Common Options:");
     eprintln!("  --debug         - Enable debug output (shows packet statistics)");
+    eprintln!("  --protocol <p>  - Skip detection: macsec|ipsec|generic|auto (default: auto)");
+    eprintln!("  --retention-days <n> - Delete flows/gaps older than n days (on start, then daily)");
     eprintln!();
     eprintln!("Replay-Specific Options:");
     eprintln!("  --mode <mode>   - Replay timing: fast|original|fixed|speed (default: fast)");
@@ -285,6 +332,7 @@ fn print_usage(program: &str) {
     eprintln!("LIVE CAPTURE:");
     eprintln!("  {} eth0 live.db", program);
     eprintln!("  {} eth0 live.db --debug", program);
+    eprintln!("  {} eth0 live.db --retention-days 7", program);
     eprintln!();
     eprintln!("PCAP REPLAY:");
     eprintln!("  {} traffic.pcap test.db --replay --mode fast", program);
@@ -316,6 +364,34 @@ async fn handle_write_queue(mut rx: mpsc::Receiver<WriteTask>, persistence: Pers
     }
 }
 
+/// Delete flows and gaps older than `age`, then reclaim the freed space
+fn apply_retention(db: &Mutex<Database>, age: Duration, debug: bool) {
+    let mut db = match db.lock() {
+        Ok(db) => db,
+        Err(_) => {
+            eprintln!("Warning: Retention cleanup skipped, database lock poisoned");
+            return;
+        }
+    };
+
+    let result = db.delete_gaps_older_than(age).and_then(|gaps| {
+        let flows = db.delete_flows_older_than(age)?;
+        if flows + gaps > 0 {
+            db.vacuum()?;
+        }
+        Ok((flows, gaps))
+    });
+
+    match result {
+        Ok((flows, gaps)) => {
+            if debug {
+                println!("Retention cleanup: removed {} flows, {} gaps", flows, gaps);
+            }
+        }
+        Err(e) => eprintln!("Warning: Retention cleanup failed: {}", e),
+    }
+}
+
 /// Generic implementation that works with any AsyncPacketSource
 async fn run_analyzer_impl<T: AsyncPacketSource>(
     capture: &mut T,
@@ -323,11 +399,17 @@ async fn run_analyzer_impl<T: AsyncPacketSource>(
     backend_name: &str,
     interface: &str,
     debug: bool,
+    hint: ProtocolHint,
+    retention: Option<Duration>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     if debug {
         println!("Starting async packet analyzer with automatic protocol detection");
         println!("  Interface: {}", interface);
-        println!("  Protocol: Auto-detect (MACsec, IPsec, Generic L3)");
+        if hint.is_forced() {
+            println!("  Protocol: {} (forced, detection skipped)", hint.protocol_name());
+        } else {
+            println!("  Protocol: Auto-detect (MACsec, IPsec, Generic L3)");
+        }
         println!("  Database: {}", db_path);
         println!("  Capture backend: {}", backend_name);
         println!();
@@ -342,6 +424,22 @@ async fn run_analyzer_impl<T: AsyncPacketSource>(
         db.initialize()?;
     }
 
+    // Spawn retention task: cleans up on startup, then every 24 hours.
+    // The deletes and VACUUM block on SQLite, so they run on the blocking pool.
+    let retention_task = retention.map(|age| {
+        let db = Arc::clone(&db);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                let db = Arc::clone(&db);
+                if let Err(e) = spawn_blocking(move || apply_retention(&db, age, debug)).await {
+                    eprintln!("Warning: Retention cleanup task failed: {}", e);
+                }
+            }
+        })
+    });
+
     // Create persistence manager
     let persistence = PersistenceManager::new(Arc::clone(&db));
 
@@ -365,11 +463,15 @@ async fn run_analyzer_impl<T: AsyncPacketSource>(
     };
 
     // Run the generic analyzer with any AsyncPacketSource
-    run_analyzer(capture, &registry, &flow_tracker, &persistence, debug, write_tx).await?;
+    run_analyzer(capture, &registry, &hint, &flow_tracker, &persistence, debug, write_tx).await?;
 
     // Wait for writer task to finish any remaining writes
     let _ = writer_task.await;
 
+    if let Some(task) = retention_task {
+        task.abort();
+    }
+
     Ok(())
 }
 
@@ -378,6 +480,7 @@ async fn run_analyzer_impl<T: AsyncPacketSource>(
 async fn run_analyzer<T: AsyncPacketSource>(
     capture: &mut T,
     registry: &Arc<ProtocolRegistry>,
+    hint: &ProtocolHint,
     flow_tracker: &Arc<FlowTracker>,
     persistence: &PersistenceManager,
     debug: bool,
@@ -394,6 +497,7 @@ async fn run_analyzer<T: AsyncPacketSource>(
     // This reduces lock contention on FlowTracker during high-speed replay
     let persist_interval = Duration::from_secs(30);
     let persist_packet_threshold = 100_000;
+    let mut prune_interval = tokio::time::interval(REORDER_BUFFER_MAX_AGE);
 
     // Track timing metrics in debug mode
     let timing_stats = if debug {
@@ -414,6 +518,7 @@ async fn run_analyzer<T: AsyncPacketSource>(
                         let metrics = analyzer::process_single_packet(
                             &raw_packet,
                             &registry,
+                            hint,
                             &flow_tracker,
                             debug,
                         )?;
@@ -513,6 +618,14 @@ async fn run_analyzer<T: AsyncPacketSource>(
                 }
             }
 
+            // Drop reorder-buffer entries that will never be delivered
+            _ = prune_interval.tick() => {
+                let pruned = flow_tracker.prune_reorder_buffers(REORDER_BUFFER_MAX_AGE);
+                if debug && pruned > 0 {
+                    println!("Pruned {} stale reorder-buffer entries", pruned);
+                }
+            }
+
             // Ctrl+C received
             _ = sigint.recv() => {
                 if debug {
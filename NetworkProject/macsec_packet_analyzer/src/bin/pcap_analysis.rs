@@ -66,6 +66,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         flow_id: seq_info.flow_id,
                         timestamp: raw_packet.timestamp,
                         payload_length: seq_info.payload_length,
+                        proto_version: seq_info.proto_version,
+                        dscp: seq_info.dscp,
+                        ecn: seq_info.ecn,
+                        raw_data: None,
                     };
 
                     // Process and detect gaps
@@ -128,6 +132,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let summary = db.get_summary_stats()?;
         println!("Total flows in DB: {}", summary.total_flows);
         println!("Total packets: {}", summary.total_packets_received);
+        println!("Total bytes: {}", summary.total_bytes);
         println!("Total gaps: {}", summary.total_gaps_detected);
         println!("Total lost packets: {}", summary.total_lost_packets);
         println!("Max gap size: {}", summary.max_gap_size);
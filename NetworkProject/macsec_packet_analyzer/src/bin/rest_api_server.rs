@@ -10,6 +10,10 @@
 //!     "server": {"host": "127.0.0.1", "port": 3000}
 //!   }
 //!
+//!   Admin endpoints (POST /api/v1/admin/vacuum) are only served when
+//!   "server" also sets "admin_token"; requests must then send
+//!   `Authorization: Bearer <admin_token>`.
+//!
 //! Usage:
 //!   cargo build --bin rest_api_server --release
 //!   ./target/release/rest_api_server
@@ -109,19 +113,34 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!();
     println!("Endpoints:");
     println!("  GET /health                       - Health check");
+    println!("  GET /metrics                      - Prometheus metrics");
     println!("  GET /api/v1/stats/summary         - Summary statistics across all flows");
+    println!("  GET /api/v1/stats/top-gappy       - Flows with the most sequence gaps");
+    println!("    ?limit=10");
     println!("  GET /api/v1/flows                 - List all flows (with pagination)");
     println!("    ?limit=10&offset=0");
+    println!("    ?search=192.168.1             - Full-text search on flow IDs");
+    println!("  GET /api/v1/flows/top             - Top N flows ranked by a metric");
+    println!("    ?metric=gap_count&n=10");
+    println!("  GET /api/v1/flows/high-loss       - Flows above a packet loss percentage");
+    println!("    ?threshold=1.5");
     println!("  GET /api/v1/flows/<flow_id>       - Get details for a specific flow");
     println!("  GET /api/v1/flows/<flow_id>/gaps  - Get all gaps for a specific flow");
     println!("    ?limit=10&offset=0");
+    println!("  GET /api/v1/flows/<flow_id>/metrics - Rate time-series for a flow");
+    println!("    ?interval_s=60");
+    if config.server.admin_token.is_some() {
+        println!("  POST /api/v1/admin/vacuum         - Reclaim unused database space (admin)");
+        println!("    Authorization: Bearer <server.admin_token>");
+    }
     println!();
 
     // Use configured database path
     let db_config = DatabaseConfig::sqlite(db_path);
 
-    // Start the REST API server
-    api::start_server(db_config, &listen_addr).await?;
+    // Start the REST API server; admin endpoints only with a configured token
+    api::start_server_with_admin_token(db_config, &listen_addr, config.server.admin_token.clone())
+        .await?;
 
     Ok(())
 }
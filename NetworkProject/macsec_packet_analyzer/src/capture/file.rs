@@ -1,5 +1,9 @@
 use pcap::Capture;
-use std::time::{Duration, UNIX_EPOCH};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::error::CaptureError;
 use crate::types::{CaptureStats, RawPacket};
@@ -7,26 +11,188 @@ use crate::types::{CaptureStats, RawPacket};
 use super::source::PacketSource;
 
 /// File-based packet capture from a pcap file
+///
+/// `open_multiple` chains several files; they are read in order as one
+/// stream.
 pub struct FileCapture {
     capture: Capture<pcap::Offline>,
+    path: PathBuf,
     packets_read: u64,
+    /// Files still to read once this one is exhausted
+    remaining: VecDeque<FileCapture>,
+    /// Position of the current file in the `open_multiple` list
+    file_index: usize,
 }
 
+/// Size of the legacy pcap global header
+const GLOBAL_HEADER_LEN: usize = 24;
+/// Size of each legacy pcap packet record header
+const RECORD_HEADER_LEN: usize = 16;
+
 impl FileCapture {
     /// Open a pcap file for reading
     pub fn open(path: &str) -> Result<Self, CaptureError> {
         let capture = Capture::from_file(path)
-            .map_err(|e| CaptureError::OpenFailed(format!("Failed to open {}: {}", path, e)))?;
+            .map_err(|e| CaptureError::PcapError(format!("Failed to open {}: {}", path, e)))?;
 
         Ok(Self {
             capture,
+            path: PathBuf::from(path),
             packets_read: 0,
+            remaining: VecDeque::new(),
+            file_index: 0,
         })
     }
+
+    /// Open several pcap files to be read back to back, e.g. hourly splits
+    ///
+    /// Every file is opened up front, so a missing one fails here rather than
+    /// halfway through the analysis. `next_packet()` returns `Ok(None)` only
+    /// once the last file is exhausted.
+    pub fn open_multiple(paths: &[&str]) -> Result<Self, CaptureError> {
+        let mut files = paths
+            .iter()
+            .map(|path| Self::open(path))
+            .collect::<Result<VecDeque<_>, _>>()?;
+
+        let mut first = files
+            .pop_front()
+            .ok_or_else(|| CaptureError::OpenFailed("No pcap files given".to_string()))?;
+        first.remaining = files;
+        Ok(first)
+    }
+
+    /// Index into the `open_multiple` paths of the file being read (0 for `open`)
+    pub fn current_file_index(&self) -> usize {
+        self.file_index
+    }
+
+    /// Path of the file being read
+    pub fn current_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Count packets by walking the record headers, without reading frames
+    ///
+    /// Exact for complete files; a record cut off by truncation is not
+    /// counted. Only the legacy pcap format is supported, not pcapng.
+    /// With `open_multiple`, this and the timestamp queries below describe
+    /// the current file only.
+    pub fn packet_count_estimate(&self) -> Result<u64, CaptureError> {
+        let mut scanner = HeaderScanner::open(&self.path)?;
+        let mut count = 0;
+        while scanner.next_record()?.is_some() {
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Capture time of the first packet, from its record header
+    pub fn first_packet_timestamp(&self) -> Result<SystemTime, CaptureError> {
+        HeaderScanner::open(&self.path)?
+            .next_record()?
+            .ok_or(CaptureError::NoMorePackets)
+    }
+
+    /// Capture time of the last packet, from its record header
+    ///
+    /// Legacy pcap has no index, so this seeks from header to header
+    /// through the file; frame data is skipped, never read.
+    pub fn last_packet_timestamp(&self) -> Result<SystemTime, CaptureError> {
+        let mut scanner = HeaderScanner::open(&self.path)?;
+        let mut last = None;
+        while let Some(timestamp) = scanner.next_record()? {
+            last = Some(timestamp);
+        }
+        last.ok_or(CaptureError::NoMorePackets)
+    }
 }
 
-impl PacketSource for FileCapture {
-    fn next_packet(&mut self) -> Result<Option<RawPacket>, CaptureError> {
+/// Reads legacy pcap record headers, seeking past each packet's data
+struct HeaderScanner {
+    reader: BufReader<File>,
+    big_endian: bool,
+    /// Fractional timestamps are nanoseconds (magic 0xa1b23c4d), not micros
+    nanos: bool,
+}
+
+impl HeaderScanner {
+    fn open(path: &Path) -> Result<Self, CaptureError> {
+        let file = File::open(path).map_err(|e| {
+            CaptureError::OpenFailed(format!("Failed to open {}: {}", path.display(), e))
+        })?;
+        let mut reader = BufReader::new(file);
+
+        let mut header = [0u8; GLOBAL_HEADER_LEN];
+        reader
+            .read_exact(&mut header)
+            .map_err(|e| CaptureError::PcapError(format!("Missing pcap header: {}", e)))?;
+
+        let magic = [header[0], header[1], header[2], header[3]];
+        let (big_endian, nanos) = match u32::from_le_bytes(magic) {
+            0xa1b2_c3d4 => (false, false),
+            0xa1b2_3c4d => (false, true),
+            0xd4c3_b2a1 => (true, false),
+            0x4d3c_b2a1 => (true, true),
+            other => {
+                return Err(CaptureError::PcapError(format!(
+                    "Not a legacy pcap file (magic {:#010x}); pcapng is not supported",
+                    other
+                )))
+            }
+        };
+
+        Ok(Self {
+            reader,
+            big_endian,
+            nanos,
+        })
+    }
+
+    /// Timestamp of the next record, or `None` at the end of the file
+    fn next_record(&mut self) -> Result<Option<SystemTime>, CaptureError> {
+        let mut header = [0u8; RECORD_HEADER_LEN];
+        match self.reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => {
+                return Err(CaptureError::ReadFailed(format!(
+                    "Error reading pcap header: {}",
+                    e
+                )))
+            }
+        }
+
+        let field = |i: usize| {
+            let bytes = [header[i], header[i + 1], header[i + 2], header[i + 3]];
+            if self.big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            }
+        };
+        let (ts_sec, ts_frac, incl_len) = (field(0), field(4), field(8));
+
+        // Stop at a record whose data runs past the end of the file
+        let mut data = (&mut self.reader).take(incl_len as u64);
+        let skipped = std::io::copy(&mut data, &mut std::io::sink())
+            .map_err(|e| CaptureError::ReadFailed(format!("Error skipping packet: {}", e)))?;
+        if skipped < incl_len as u64 {
+            return Ok(None);
+        }
+
+        let frac = if self.nanos {
+            Duration::from_nanos(ts_frac as u64)
+        } else {
+            Duration::from_micros(ts_frac as u64)
+        };
+        Ok(Some(UNIX_EPOCH + Duration::from_secs(ts_sec as u64) + frac))
+    }
+}
+
+impl FileCapture {
+    /// Next packet of the current file only
+    fn next_packet_in_file(&mut self) -> Result<Option<RawPacket>, CaptureError> {
         match self.capture.next() {
             Ok(packet) => {
                 self.packets_read += 1;
@@ -44,12 +210,30 @@ impl PacketSource for FileCapture {
                 }))
             }
             Err(pcap::Error::NoMorePackets) => Ok(None),
-            Err(e) => Err(CaptureError::ReadFailed(format!(
+            Err(e) => Err(CaptureError::PcapError(format!(
                 "Error reading pcap: {}",
                 e
             ))),
         }
     }
+}
+
+impl PacketSource for FileCapture {
+    fn next_packet(&mut self) -> Result<Option<RawPacket>, CaptureError> {
+        loop {
+            if let Some(packet) = self.next_packet_in_file()? {
+                return Ok(Some(packet));
+            }
+
+            // Current file exhausted: move on to the next one, if any
+            let Some(next) = self.remaining.pop_front() else {
+                return Ok(None);
+            };
+            self.capture = next.capture;
+            self.path = next.path;
+            self.file_index += 1;
+        }
+    }
 
     fn stats(&self) -> CaptureStats {
         CaptureStats {
@@ -58,3 +242,130 @@ impl PacketSource for FileCapture {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/macsec_traffic.pcap");
+
+    fn ts(secs: u64, micros: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_micros(micros)
+    }
+
+    #[test]
+    fn test_header_scan_matches_full_read() {
+        let mut capture = FileCapture::open(FIXTURE).unwrap();
+        assert_eq!(capture.packet_count_estimate().unwrap(), 195);
+        assert_eq!(
+            capture.first_packet_timestamp().unwrap(),
+            ts(1_767_642_370, 118_335)
+        );
+        assert_eq!(
+            capture.last_packet_timestamp().unwrap(),
+            ts(1_767_642_370, 129_297)
+        );
+
+        // Same answers as reading every packet through libpcap
+        let mut timestamps = Vec::new();
+        while let Some(packet) = capture.next_packet().unwrap() {
+            timestamps.push(packet.timestamp);
+        }
+        assert_eq!(timestamps.len(), 195);
+        assert_eq!(
+            timestamps.first(),
+            Some(&capture.first_packet_timestamp().unwrap())
+        );
+        assert_eq!(
+            timestamps.last(),
+            Some(&capture.last_packet_timestamp().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_header_scan_big_endian_nanos_truncated() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0xa1b2_3c4du32.to_be_bytes());
+        bytes.extend_from_slice(&[
+            0, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0, 0, 1,
+        ]);
+        for (secs, nanos, len) in [(10u32, 5u32, 60u32), (11, 7, 60), (12, 9, 60)] {
+            for field in [secs, nanos, len, len] {
+                bytes.extend_from_slice(&field.to_be_bytes());
+            }
+            bytes.resize(bytes.len() + len as usize, 0);
+        }
+        // Cut the third packet short
+        bytes.truncate(bytes.len() - 10);
+
+        let path = std::env::temp_dir().join(format!("header_scan_{}.pcap", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let capture = FileCapture::open(path.to_str().unwrap());
+        let result = capture.map(|c| {
+            (
+                c.packet_count_estimate().unwrap(),
+                c.first_packet_timestamp().unwrap(),
+                c.last_packet_timestamp().unwrap(),
+            )
+        });
+        std::fs::remove_file(&path).unwrap();
+
+        let (count, first, last) = result.unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(first, UNIX_EPOCH + Duration::new(10, 5));
+        assert_eq!(last, UNIX_EPOCH + Duration::new(11, 7));
+    }
+
+    /// Write a little-endian pcap with one 60-byte packet per id, each
+    /// filled with its id and stamped `id` seconds after the epoch
+    fn write_pcap(name: &str, ids: &[u8]) -> PathBuf {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+        bytes.extend_from_slice(&[
+            2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0, 1, 0, 0, 0,
+        ]);
+        for &id in ids {
+            for field in [id as u32, 0, 60, 60] {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+            bytes.extend_from_slice(&[id; 60]);
+        }
+
+        let path = std::env::temp_dir().join(format!("{}_{}.pcap", name, std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_open_multiple_reads_files_in_order() {
+        let first = write_pcap("open_multiple_0", &[1, 2, 3]);
+        let second = write_pcap("open_multiple_1", &[4, 5]);
+        let paths = [first.to_str().unwrap(), second.to_str().unwrap()];
+
+        let mut capture = FileCapture::open_multiple(&paths).unwrap();
+        let mut read = Vec::new();
+        while let Some(packet) = capture.next_packet().unwrap() {
+            read.push((capture.current_file_index(), packet.data[0]));
+            assert_eq!(packet.timestamp, ts(packet.data[0] as u64, 0));
+        }
+        assert_eq!(read, vec![(0, 1), (0, 2), (0, 3), (1, 4), (1, 5)]);
+        assert_eq!(capture.current_path(), second.as_path());
+        assert_eq!(capture.stats().packets_received, 5);
+        assert!(capture.next_packet().unwrap().is_none());
+
+        std::fs::remove_file(&first).unwrap();
+        std::fs::remove_file(&second).unwrap();
+    }
+
+    #[test]
+    fn test_open_multiple_errors() {
+        assert!(matches!(
+            FileCapture::open_multiple(&[]),
+            Err(CaptureError::OpenFailed(_))
+        ));
+        assert!(matches!(
+            FileCapture::open_multiple(&[FIXTURE, "/nonexistent/capture.pcap"]),
+            Err(CaptureError::PcapError(_))
+        ));
+    }
+}
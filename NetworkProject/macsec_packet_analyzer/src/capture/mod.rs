@@ -1,3 +1,4 @@
+pub mod pcapng;
 pub mod source;
 
 #[cfg(feature = "cli")]
@@ -6,6 +7,12 @@ pub mod file;
 #[cfg(all(feature = "async", feature = "pcap"))]
 pub mod pcap_live;
 
+#[cfg(feature = "async")]
+pub mod channel;
+
+#[cfg(feature = "async")]
+pub mod udp;
+
 #[cfg(all(target_os = "linux", feature = "async"))]
 pub mod af_packet;
 
@@ -18,6 +25,7 @@ pub mod napatech;
 #[cfg(all(feature = "async", feature = "pcap"))]
 pub mod replay;
 
+pub use pcapng::PcapNgWriter;
 pub use source::PacketSource;
 
 #[cfg(feature = "async")]
@@ -27,16 +35,22 @@ pub use source::AsyncPacketSource;
 pub use file::FileCapture;
 
 #[cfg(all(feature = "async", feature = "pcap"))]
-pub use pcap_live::PcapLiveCapture;
+pub use pcap_live::{PcapLiveCapture, PcapLiveCaptureBuilder};
+
+#[cfg(feature = "async")]
+pub use channel::ChannelPacketSource;
+
+#[cfg(feature = "async")]
+pub use udp::UdpPacketSource;
 
 #[cfg(all(target_os = "linux", feature = "async"))]
-pub use af_packet::AfPacketCapture;
+pub use af_packet::{AfPacketCapture, FanoutMode, FanoutStats};
 
 #[cfg(all(target_os = "linux", feature = "async"))]
 pub use xdp::XdpCapture;
 
 #[cfg(all(target_os = "linux", feature = "napatech"))]
-pub use napatech::{NapatechCapture, NapatechConfig, NapatechCaptureMode, NapatechStats};
+pub use napatech::{NapatechCapture, NapatechConfig, NapatechCaptureMode, NapatechStats, TimestampMode};
 
 #[cfg(all(feature = "async", feature = "pcap"))]
 pub use replay::{ReplayCapture, ReplayMode};
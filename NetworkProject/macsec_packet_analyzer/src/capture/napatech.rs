@@ -20,7 +20,7 @@
 use crate::capture::source::AsyncPacketSource;
 use crate::error::CaptureError;
 use crate::types::{CaptureStats, RawPacket};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::ptr;
 use std::ffi::CString;
 
@@ -140,6 +140,36 @@ extern "C" {
     pub fn NT_ExplainError(status: i32) -> *const u8;
 }
 
+/// Source of the timestamp attached to each captured packet
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimestampMode {
+    /// FPGA timestamp from the packet descriptor (~5ns precision)
+    #[default]
+    Hardware,
+    /// Host clock read when the packet is pulled from the stream
+    Software,
+}
+
+impl TimestampMode {
+    /// Approximate timestamp resolution in nanoseconds
+    pub fn resolution_ns(self) -> u64 {
+        match self {
+            TimestampMode::Hardware => 5,
+            TimestampMode::Software => 1000,
+        }
+    }
+
+    /// Timestamp for a packet with the given descriptor timestamp
+    ///
+    /// The FPGA counter is nanoseconds since the Unix epoch.
+    fn packet_timestamp(self, descriptor_ns: u64) -> SystemTime {
+        match self {
+            TimestampMode::Hardware => UNIX_EPOCH + Duration::from_nanos(descriptor_ns),
+            TimestampMode::Software => SystemTime::now(),
+        }
+    }
+}
+
 /// Safe wrapper around Napatech NTAPI
 pub struct NapatechCapture {
     net_rx_handle: i32,
@@ -147,6 +177,7 @@ pub struct NapatechCapture {
     stream_id: u32,
     packets_read: u64,
     initialized: bool,
+    timestamp_mode: TimestampMode,
 }
 
 impl NapatechCapture {
@@ -173,13 +204,13 @@ impl NapatechCapture {
     pub fn open(port_id: u32, stream_id: u32) -> Result<Self, CaptureError> {
         // Validate parameters
         if port_id > 255 {
-            return Err(CaptureError::OpenFailed(
+            return Err(CaptureError::NapatechError(
                 format!("Invalid Napatech port ID: {}", port_id),
             ));
         }
 
         if stream_id > 63 {
-            return Err(CaptureError::OpenFailed(
+            return Err(CaptureError::NapatechError(
                 format!("Invalid Napatech stream ID: {} (must be 0-63)", stream_id),
             ));
         }
@@ -188,7 +219,7 @@ impl NapatechCapture {
             // Initialize NTAPI library
             let init_status = NT_Init(0);
             if init_status != 0 {
-                return Err(CaptureError::OpenFailed(
+                return Err(CaptureError::NapatechError(
                     format!("NT_Init failed with status {}", init_status),
                 ));
             }
@@ -197,7 +228,7 @@ impl NapatechCapture {
             let net_rx_handle = NT_NetRxOpen(stream_id, port_id);
             if net_rx_handle < 0 {
                 NT_Release();
-                return Err(CaptureError::OpenFailed(
+                return Err(CaptureError::NapatechError(
                     format!(
                         "NT_NetRxOpen failed for port {} stream {} with status {}",
                         port_id, stream_id, net_rx_handle
@@ -211,6 +242,7 @@ impl NapatechCapture {
                 stream_id,
                 packets_read: 0,
                 initialized: true,
+                timestamp_mode: TimestampMode::default(),
             })
         }
     }
@@ -231,20 +263,20 @@ impl NapatechCapture {
     /// ```
     pub fn set_attribute(&mut self, attribute: &str) -> Result<(), CaptureError> {
         if !self.initialized {
-            return Err(CaptureError::OpenFailed(
+            return Err(CaptureError::NapatechError(
                 "Capture not initialized".to_string(),
             ));
         }
 
         // Convert Rust string to C string
         let c_attr = CString::new(attribute).map_err(|_| {
-            CaptureError::OpenFailed("Attribute string contains null byte".to_string())
+            CaptureError::NapatechError("Attribute string contains null byte".to_string())
         })?;
 
         unsafe {
             let status = NT_NetRxSetAttribute(self.net_rx_handle, c_attr.as_ptr() as *const u8);
             if status != 0 {
-                return Err(CaptureError::OpenFailed(format!(
+                return Err(CaptureError::NapatechError(format!(
                     "NT_NetRxSetAttribute failed with status {}",
                     status
                 )));
@@ -268,13 +300,31 @@ impl NapatechCapture {
     pub fn packets_read(&self) -> u64 {
         self.packets_read
     }
+
+    /// Select hardware (FPGA) or software (host clock) packet timestamps
+    ///
+    /// Hardware mode is the default. Software mode is only useful when the
+    /// adapter's clock is not synchronized to the host.
+    pub fn set_timestamp_mode(&mut self, mode: TimestampMode) {
+        self.timestamp_mode = mode;
+    }
+
+    /// Get the current timestamp mode
+    pub fn timestamp_mode(&self) -> TimestampMode {
+        self.timestamp_mode
+    }
+
+    /// Approximate resolution of packet timestamps in nanoseconds
+    pub fn timestamp_resolution_ns(&self) -> u64 {
+        self.timestamp_mode.resolution_ns()
+    }
 }
 
 #[cfg(feature = "napatech")]
 impl AsyncPacketSource for NapatechCapture {
     async fn next_packet(&mut self) -> Result<Option<RawPacket>, CaptureError> {
         if !self.initialized {
-            return Err(CaptureError::OpenFailed(
+            return Err(CaptureError::NapatechError(
                 "Capture not initialized".to_string(),
             ));
         }
@@ -293,7 +343,7 @@ impl AsyncPacketSource for NapatechCapture {
 
                     if packet_ptr.is_null() || descriptor.is_null() {
                         NT_NetRxRelease(self.net_rx_handle, &mut net_buf);
-                        return Err(CaptureError::ReadFailed(
+                        return Err(CaptureError::NapatechError(
                             "Failed to get packet data or descriptor".to_string(),
                         ));
                     }
@@ -305,9 +355,7 @@ impl AsyncPacketSource for NapatechCapture {
                     )
                     .to_vec();
 
-                    // Convert Napatech timestamp (typically FPGA counter in nanoseconds)
-                    // to SystemTime for compatibility
-                    let timestamp = SystemTime::now();
+                    let timestamp = self.timestamp_mode.packet_timestamp(desc.timestamp);
 
                     self.packets_read += 1;
 
@@ -330,7 +378,7 @@ impl AsyncPacketSource for NapatechCapture {
                 }
                 _ => {
                     // Error
-                    Err(CaptureError::ReadFailed(format!(
+                    Err(CaptureError::NapatechError(format!(
                         "NT_NetRxRead failed with status {}",
                         status
                     )))
@@ -408,6 +456,8 @@ pub struct NapatechConfig {
     pub capture_crc: bool,
     /// Packet capture mode
     pub capture_mode: NapatechCaptureMode,
+    /// Packet timestamp source
+    pub timestamp_mode: TimestampMode,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -438,6 +488,7 @@ impl Default for NapatechConfig {
             stream_id: 0,
             capture_crc: false,
             capture_mode: NapatechCaptureMode::AllPackets,
+            timestamp_mode: TimestampMode::Hardware,
         }
     }
 }
@@ -471,6 +522,12 @@ impl NapatechConfig {
         self.capture_mode = mode;
         self
     }
+
+    /// Set the packet timestamp source
+    pub fn with_timestamp_mode(mut self, mode: TimestampMode) -> Self {
+        self.timestamp_mode = mode;
+        self
+    }
 }
 
 /// Performance characteristics of Napatech SmartNICs
@@ -502,3 +559,38 @@ impl NapatechConfig {
 /// # Ok(())
 /// # }
 /// ```
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hardware_timestamp_uses_descriptor() {
+        let ts = TimestampMode::Hardware.packet_timestamp(1_700_000_000_123_456_789);
+        let since_epoch = ts.duration_since(UNIX_EPOCH).unwrap();
+        assert_eq!(since_epoch.as_secs(), 1_700_000_000);
+        assert_eq!(since_epoch.subsec_nanos(), 123_456_789);
+    }
+
+    #[test]
+    fn test_software_timestamp_ignores_descriptor() {
+        let before = SystemTime::now();
+        let ts = TimestampMode::Software.packet_timestamp(42);
+        assert!(ts >= before);
+    }
+
+    #[test]
+    fn test_timestamp_resolution() {
+        assert_eq!(TimestampMode::Hardware.resolution_ns(), 5);
+        assert_eq!(TimestampMode::Software.resolution_ns(), 1000);
+        assert_eq!(NapatechConfig::new().timestamp_mode, TimestampMode::Hardware);
+    }
+
+    #[test]
+    fn test_invalid_port_is_napatech_error() {
+        // Parameter validation runs before the NTAPI is touched
+        let err = NapatechCapture::open(300, 0).err().unwrap();
+        assert!(matches!(err, CaptureError::NapatechError(_)));
+        assert_eq!(err.backend(), "Napatech");
+    }
+}
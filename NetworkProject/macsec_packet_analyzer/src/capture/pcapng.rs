@@ -0,0 +1,148 @@
+//! Minimal PCAPNG writer for exporting packets to external tools
+//!
+//! Writes one section with a single Ethernet interface and one Enhanced
+//! Packet Block per packet, with microsecond timestamps (the PCAPNG
+//! default). Output opens in Wireshark, tshark and tcpdump.
+
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::CaptureError;
+
+const SHB_TYPE: u32 = 0x0A0D_0D0A;
+const IDB_TYPE: u32 = 0x0000_0001;
+const EPB_TYPE: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+const LINKTYPE_ETHERNET: u16 = 1;
+
+/// Streams packets into a PCAPNG capture
+///
+/// The section and interface headers are written by `new`, so an export
+/// with no packets is still a valid, empty capture.
+pub struct PcapNgWriter<W: Write> {
+    writer: W,
+    packets_written: u64,
+}
+
+impl<W: Write> PcapNgWriter<W> {
+    /// Write the PCAPNG headers to `writer` and return a writer for packets
+    pub fn new(writer: W) -> Result<Self, CaptureError> {
+        let mut pcap = Self {
+            writer,
+            packets_written: 0,
+        };
+
+        // Section Header Block: version 1.0, section length unknown (-1)
+        let mut shb = Vec::with_capacity(16);
+        shb.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        shb.extend_from_slice(&1u16.to_le_bytes());
+        shb.extend_from_slice(&0u16.to_le_bytes());
+        shb.extend_from_slice(&(-1i64).to_le_bytes());
+        pcap.write_block(SHB_TYPE, &shb)?;
+
+        // Interface Description Block: Ethernet, no snap length limit
+        let mut idb = Vec::with_capacity(8);
+        idb.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+        idb.extend_from_slice(&0u16.to_le_bytes());
+        idb.extend_from_slice(&0u32.to_le_bytes());
+        pcap.write_block(IDB_TYPE, &idb)?;
+
+        Ok(pcap)
+    }
+
+    /// Append one packet captured at `timestamp`
+    pub fn write_packet(&mut self, data: &[u8], timestamp: SystemTime) -> Result<(), CaptureError> {
+        let micros = timestamp
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or(0);
+        let len = u32::try_from(data.len()).map_err(|_| {
+            CaptureError::PcapError(format!("packet too large for PCAPNG: {} bytes", data.len()))
+        })?;
+
+        let mut epb = Vec::with_capacity(20 + padded_len(data.len()));
+        epb.extend_from_slice(&0u32.to_le_bytes()); // interface ID
+        epb.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+        epb.extend_from_slice(&(micros as u32).to_le_bytes());
+        epb.extend_from_slice(&len.to_le_bytes()); // captured length
+        epb.extend_from_slice(&len.to_le_bytes()); // original length
+        epb.extend_from_slice(data);
+        epb.resize(20 + padded_len(data.len()), 0);
+        self.write_block(EPB_TYPE, &epb)?;
+
+        self.packets_written += 1;
+        Ok(())
+    }
+
+    /// Packets written so far
+    pub fn packets_written(&self) -> u64 {
+        self.packets_written
+    }
+
+    /// Flush and return the underlying writer
+    pub fn into_inner(mut self) -> Result<W, CaptureError> {
+        self.writer.flush().map_err(write_error)?;
+        Ok(self.writer)
+    }
+
+    /// Write a block: type, total length, body, total length again
+    fn write_block(&mut self, block_type: u32, body: &[u8]) -> Result<(), CaptureError> {
+        let total_len = (body.len() + 12) as u32;
+        let mut block = Vec::with_capacity(body.len() + 12);
+        block.extend_from_slice(&block_type.to_le_bytes());
+        block.extend_from_slice(&total_len.to_le_bytes());
+        block.extend_from_slice(body);
+        block.extend_from_slice(&total_len.to_le_bytes());
+        self.writer.write_all(&block).map_err(write_error)
+    }
+}
+
+/// Packet data is padded to a 32-bit boundary
+fn padded_len(len: usize) -> usize {
+    len.div_ceil(4) * 4
+}
+
+fn write_error(err: std::io::Error) -> CaptureError {
+    CaptureError::PcapError(format!("PCAPNG write failed: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn u32_at(buf: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_empty_capture_has_headers() {
+        let buf = PcapNgWriter::new(Vec::new()).unwrap().into_inner().unwrap();
+
+        assert_eq!(buf.len(), 28 + 20);
+        assert_eq!(u32_at(&buf, 0), SHB_TYPE);
+        assert_eq!(u32_at(&buf, 8), BYTE_ORDER_MAGIC);
+        assert_eq!(u32_at(&buf, 28), IDB_TYPE);
+    }
+
+    #[test]
+    fn test_packet_block_layout() {
+        let mut pcap = PcapNgWriter::new(Vec::new()).unwrap();
+        let ts = UNIX_EPOCH + Duration::from_micros(0x1_0000_0002);
+        pcap.write_packet(&[0xAA; 5], ts).unwrap();
+        assert_eq!(pcap.packets_written(), 1);
+
+        let buf = pcap.into_inner().unwrap();
+        let epb = &buf[48..];
+        // 12 bytes framing + 20 bytes fields + 5 bytes data padded to 8
+        assert_eq!(epb.len(), 40);
+        assert_eq!(u32_at(epb, 0), EPB_TYPE);
+        assert_eq!(u32_at(epb, 4), 40);
+        assert_eq!(u32_at(epb, 12), 1); // timestamp high
+        assert_eq!(u32_at(epb, 16), 2); // timestamp low
+        assert_eq!(u32_at(epb, 20), 5);
+        assert_eq!(&epb[28..33], &[0xAA; 5]);
+        assert_eq!(&epb[33..36], &[0; 3]);
+        assert_eq!(u32_at(epb, 36), 40);
+    }
+}
@@ -9,6 +9,11 @@
 //! Supports optional infinite looping for sustained stress testing.
 //! When looping, returns `Ok(None)` to signal loop reset, allowing the analyzer
 //! to persist data and reset flow tracking state to avoid artificial gaps.
+//!
+//! By default replayed packets are stamped with the time they are delivered.
+//! With a time shift (`with_time_shift()`, `shift_to_now()`) they keep their
+//! original PCAP spacing, moved by a fixed offset, so old captures show up in
+//! real-time dashboards as if they happened now.
 
 use crate::capture::source::AsyncPacketSource;
 use crate::error::CaptureError;
@@ -49,6 +54,13 @@ impl std::fmt::Display for ReplayMode {
     }
 }
 
+/// Offset applied to original PCAP timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeShift {
+    Forward(Duration),
+    Backward(Duration),
+}
+
 /// PCAP-based replay capture for stress testing
 ///
 /// Loads a PCAP file into memory and replays packets with configurable timing.
@@ -70,12 +82,19 @@ pub struct ReplayCapture {
     first_packet_time: Option<SystemTime>,
     replay_start_time: Option<Instant>,
 
+    // Timestamp rewriting (None = stamp with delivery time)
+    time_shift: Option<TimeShift>,
+
     // Loop reset handling
     pending_loop_reset: bool,
 
+    // Gap injection: skip every Nth packet of each pass (None = disabled)
+    drop_every: Option<u64>,
+
     // Statistics
     packets_replayed: u64,
     loops_completed: u64,
+    injected_drops: u64,
 
     // I/O timing (for performance analysis in debug mode)
     io_timing: Mutex<IoTiming>,
@@ -108,20 +127,7 @@ impl ReplayCapture {
         replay_mode: ReplayMode,
         enable_looping: bool,
     ) -> Result<Self, CaptureError> {
-        // Validate replay mode configuration
-        match replay_mode {
-            ReplayMode::FixedRate(pps) if pps == 0 => {
-                return Err(CaptureError::OpenFailed(
-                    "FixedRate: packets per second must be > 0".to_string(),
-                ));
-            }
-            ReplayMode::SpeedMultiplier(m) if !(m > 0.0) => {
-                return Err(CaptureError::OpenFailed(
-                    "SpeedMultiplier: multiplier must be > 0.0".to_string(),
-                ));
-            }
-            _ => {}
-        }
+        validate_replay_mode(replay_mode)?;
 
         // Open PCAP file
         let mut capture = Capture::from_file(path).map_err(|e| {
@@ -184,7 +190,42 @@ impl ReplayCapture {
             replay_mode
         );
 
-        Ok(Self {
+        Ok(Self::from_packets(packets, first_packet_time, replay_mode, enable_looping))
+    }
+
+    /// Replay packets built in memory instead of loaded from a PCAP file
+    ///
+    /// Packets are replayed in the given order; their timestamps drive the
+    /// `OriginalTiming` and `SpeedMultiplier` pacing just like PCAP timestamps.
+    ///
+    /// # Errors
+    /// - `CaptureError::OpenFailed` if `packets` is empty
+    /// - `CaptureError::OpenFailed` if replay mode configuration is invalid
+    pub fn from_raw_packets(
+        packets: Vec<RawPacket>,
+        replay_mode: ReplayMode,
+        enable_looping: bool,
+    ) -> Result<Self, CaptureError> {
+        validate_replay_mode(replay_mode)?;
+
+        let Some(first) = packets.first() else {
+            return Err(CaptureError::OpenFailed(
+                "No packets to replay".to_string(),
+            ));
+        };
+        let first_packet_time = Some(first.timestamp);
+
+        Ok(Self::from_packets(packets, first_packet_time, replay_mode, enable_looping))
+    }
+
+    /// Build a replay source from already-loaded packets
+    fn from_packets(
+        packets: Vec<RawPacket>,
+        first_packet_time: Option<SystemTime>,
+        replay_mode: ReplayMode,
+        enable_looping: bool,
+    ) -> Self {
+        Self {
             packets,
             current_index: 0,
             loop_count: 0,
@@ -192,29 +233,196 @@ impl ReplayCapture {
             enable_looping,
             first_packet_time,
             replay_start_time: None,
+            time_shift: None,
             pending_loop_reset: false,
+            drop_every: None,
             packets_replayed: 0,
             loops_completed: 0,
+            injected_drops: 0,
             io_timing: Mutex::new(IoTiming {
                 total_io_us: 0,
                 io_call_count: 0,
                 min_io_us: u128::MAX,
                 max_io_us: 0,
             }),
-        })
+        }
+    }
+
+    /// Replace each packet's timestamp with the matching entry of `timestamps`
+    ///
+    /// The custom timestamps are used for `OriginalTiming`/`SpeedMultiplier`
+    /// pacing and are delivered unchanged (unless a time shift is set), so
+    /// conformance tests can feed packets exactly N nanoseconds apart.
+    ///
+    /// # Errors
+    /// `CaptureError::OpenFailed` if there is not exactly one timestamp per packet
+    pub fn with_custom_timestamps(mut self, timestamps: Vec<SystemTime>) -> Result<Self, CaptureError> {
+        if timestamps.len() != self.packets.len() {
+            return Err(CaptureError::OpenFailed(format!(
+                "Got {} custom timestamps for {} packets",
+                timestamps.len(),
+                self.packets.len()
+            )));
+        }
+
+        for (packet, timestamp) in self.packets.iter_mut().zip(&timestamps) {
+            packet.timestamp = *timestamp;
+        }
+        self.first_packet_time = timestamps.first().copied();
+        self.time_shift.get_or_insert(TimeShift::Forward(Duration::ZERO));
+        Ok(self)
+    }
+
+    /// Deliver packets with their original timestamps moved forward by `shift`
+    ///
+    /// Pacing in `OriginalTiming`/`SpeedMultiplier` mode still uses the
+    /// unshifted PCAP timestamps.
+    pub fn with_time_shift(mut self, shift: Duration) -> Self {
+        self.time_shift = Some(TimeShift::Forward(shift));
+        self
+    }
+
+    /// Shift timestamps so the first packet appears at `start`
+    ///
+    /// `start` may be earlier than the capture, giving a negative shift.
+    pub fn with_time_shift_to(mut self, start: SystemTime) -> Self {
+        let Some(first) = self.first_packet_time else {
+            return self;
+        };
+
+        self.time_shift = Some(match start.duration_since(first) {
+            Ok(forward) => TimeShift::Forward(forward),
+            Err(e) => TimeShift::Backward(e.duration()),
+        });
+        self
+    }
+
+    /// Shift timestamps so the first packet appears to arrive now
+    pub fn shift_to_now(self) -> Self {
+        self.with_time_shift_to(SystemTime::now())
+    }
+
+    /// Inject sequence gaps by skipping every `every`-th packet of each pass
+    ///
+    /// Skipped packets are reported as dropped in `stats()`. Values below 2
+    /// disable injection (1 would drop everything).
+    pub fn with_gap_injection(mut self, every: u64) -> Self {
+        self.drop_every = (every >= 2).then_some(every);
+        self
+    }
+
+    /// Whether the packet at `index` is dropped by gap injection
+    fn is_injected_drop(&self, index: usize) -> bool {
+        self.drop_every
+            .is_some_and(|every| (index as u64 + 1).is_multiple_of(every))
+    }
+
+    /// True once every packet has been delivered and looping is disabled
+    pub fn is_complete(&self) -> bool {
+        self.current_index >= self.packets.len() && !self.enable_looping
+    }
+
+    /// Rewind to the first packet without re-reading the file
+    ///
+    /// Clears the replay start time and the per-run counters (packets
+    /// replayed, loops completed, injected drops), so a replay that ended
+    /// with `NoMorePackets` can be run again. Configuration and I/O timing
+    /// statistics are kept.
+    pub fn reset(&mut self) {
+        self.current_index = 0;
+        self.loop_count = 0;
+        self.first_packet_time = self.packets.first().map(|p| p.timestamp);
+        self.replay_start_time = None;
+        self.pending_loop_reset = false;
+        self.packets_replayed = 0;
+        self.loops_completed = 0;
+        self.injected_drops = 0;
+    }
+
+    /// Continue the replay from the packet at `index`
+    ///
+    /// Pacing restarts at that packet, as if the capture began there; after
+    /// a loop the replay starts again from the first packet.
+    ///
+    /// # Errors
+    /// `CaptureError::OpenFailed` if `index` is past the last packet
+    pub fn seek_to_packet(&mut self, index: usize) -> Result<(), CaptureError> {
+        let Some(packet) = self.packets.get(index) else {
+            return Err(CaptureError::OpenFailed(format!(
+                "Cannot seek to packet {}: capture has {} packets",
+                index,
+                self.packets.len()
+            )));
+        };
+
+        self.first_packet_time = Some(packet.timestamp);
+        self.current_index = index;
+        self.replay_start_time = None;
+        self.pending_loop_reset = false;
+        Ok(())
+    }
+
+    /// Number of packets loaded for replay
+    pub fn packet_count(&self) -> usize {
+        self.packets.len()
+    }
+
+    /// Timestamp to report for `original`, honouring the time shift
+    ///
+    /// When looping, each pass is moved past the previous one by the capture's
+    /// span so shifted timestamps keep increasing.
+    fn shifted_timestamp(&self, original: SystemTime) -> SystemTime {
+        let Some(shift) = self.time_shift else {
+            return SystemTime::now();
+        };
+
+        let span = match (self.first_packet_time, self.packets.last()) {
+            (Some(first), Some(last)) => last.timestamp.duration_since(first).unwrap_or_default(),
+            _ => Duration::ZERO,
+        };
+        let loop_offset = span.saturating_mul(self.loops_completed.min(u32::MAX as u64) as u32);
+
+        let shifted = match shift {
+            TimeShift::Forward(d) => original.checked_add(d),
+            TimeShift::Backward(d) => original.checked_sub(d),
+        };
+        shifted
+            .and_then(|t| t.checked_add(loop_offset))
+            .unwrap_or(original)
     }
 
     /// Get current replay statistics
     pub fn replay_stats(&self) -> ReplayStats {
+        let replay_progress_pct = if self.packets.is_empty() {
+            100.0
+        } else {
+            (self.current_index.min(self.packets.len()) * 100) as f64 / self.packets.len() as f64
+        };
+
         ReplayStats {
             packets_replayed: self.packets_replayed,
             loops_completed: self.loops_completed,
             current_loop: self.loop_count,
             total_packets: self.packets.len() as u64,
+            injected_drops: self.injected_drops,
+            replay_progress_pct,
         }
     }
 }
 
+/// Reject replay modes that cannot pace packets
+fn validate_replay_mode(replay_mode: ReplayMode) -> Result<(), CaptureError> {
+    match replay_mode {
+        ReplayMode::FixedRate(0) => Err(CaptureError::OpenFailed(
+            "FixedRate: packets per second must be > 0".to_string(),
+        )),
+        ReplayMode::SpeedMultiplier(m) if !(m > 0.0) => Err(CaptureError::OpenFailed(
+            "SpeedMultiplier: multiplier must be > 0.0".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// Statistics about replay progress
 #[derive(Debug, Clone)]
 pub struct ReplayStats {
@@ -222,6 +430,10 @@ pub struct ReplayStats {
     pub loops_completed: u64,
     pub current_loop: u64,
     pub total_packets: u64,
+    /// Packets skipped by gap injection (see `with_gap_injection`)
+    pub injected_drops: u64,
+    /// Position in the current pass through the file, 0-100
+    pub replay_progress_pct: f64,
 }
 
 impl AsyncPacketSource for ReplayCapture {
@@ -232,10 +444,18 @@ impl AsyncPacketSource for ReplayCapture {
         if self.pending_loop_reset {
             self.pending_loop_reset = false;
             self.current_index = 0;
+            // Undo any seek_to_packet(): the next pass starts at the first packet
+            self.first_packet_time = self.packets.first().map(|p| p.timestamp);
             self.replay_start_time = Some(Instant::now());
             // Continue to next packet from start of file
         }
 
+        // Skip packets dropped by gap injection
+        while self.current_index < self.packets.len() && self.is_injected_drop(self.current_index) {
+            self.current_index += 1;
+            self.injected_drops += 1;
+        }
+
         // Handle loop wraparound
         if self.current_index >= self.packets.len() {
             if !self.enable_looping {
@@ -273,8 +493,9 @@ impl AsyncPacketSource for ReplayCapture {
         self.current_index += 1;
         self.packets_replayed += 1;
 
-        // Update timestamp to current time (for gap detection timestamps)
-        result.timestamp = SystemTime::now();
+        // Update timestamp to current time (for gap detection timestamps),
+        // or to the shifted original timestamp if a time shift is set
+        result.timestamp = self.shifted_timestamp(result.timestamp);
 
         self.record_io_time(io_start);
         Ok(Some(result))
@@ -283,7 +504,7 @@ impl AsyncPacketSource for ReplayCapture {
     fn stats(&self) -> CaptureStats {
         CaptureStats {
             packets_received: self.packets_replayed,
-            packets_dropped: 0, // Replay never drops packets
+            packets_dropped: self.injected_drops, // Only gap injection drops packets
         }
     }
 }
@@ -450,4 +671,250 @@ mod tests {
         assert!(format!("{}", ReplayMode::FixedRate(1000)).contains("fixed"));
         assert!(format!("{}", ReplayMode::SpeedMultiplier(2.0)).contains("speed"));
     }
+
+    /// Three packets, 10ms apart, captured in early 2022
+    fn old_packets() -> (Vec<RawPacket>, SystemTime) {
+        let first = UNIX_EPOCH + Duration::from_secs(1_640_995_200);
+        let packets = (0..3u64)
+            .map(|i| RawPacket {
+                data: vec![0u8; 60],
+                timestamp: first + Duration::from_millis(i * 10),
+                length: 60,
+            })
+            .collect();
+        (packets, first)
+    }
+
+    #[tokio::test]
+    async fn test_shift_to_now() {
+        let (packets, first) = old_packets();
+        let mut capture =
+            ReplayCapture::from_packets(packets, Some(first), ReplayMode::Fast, false).shift_to_now();
+
+        let packet = capture.next_packet().await.unwrap().unwrap();
+        let now = SystemTime::now();
+        let diff = match now.duration_since(packet.timestamp) {
+            Ok(d) => d,
+            Err(e) => e.duration(),
+        };
+        assert!(diff < Duration::from_secs(1));
+
+        // Original spacing is preserved
+        let second = capture.next_packet().await.unwrap().unwrap();
+        assert_eq!(
+            second.timestamp.duration_since(packet.timestamp).unwrap(),
+            Duration::from_millis(10)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_time_shift_forward_and_backward() {
+        let (packets, first) = old_packets();
+        let mut forward = ReplayCapture::from_packets(packets.clone(), Some(first), ReplayMode::Fast, false)
+            .with_time_shift(Duration::from_secs(3600));
+        let packet = forward.next_packet().await.unwrap().unwrap();
+        assert_eq!(packet.timestamp, first + Duration::from_secs(3600));
+
+        let earlier = first - Duration::from_secs(60);
+        let mut backward = ReplayCapture::from_packets(packets, Some(first), ReplayMode::Fast, false)
+            .with_time_shift_to(earlier);
+        let packet = backward.next_packet().await.unwrap().unwrap();
+        assert_eq!(packet.timestamp, earlier);
+    }
+
+    #[tokio::test]
+    async fn test_time_shift_keeps_increasing_across_loops() {
+        let (packets, first) = old_packets();
+        let mut capture = ReplayCapture::from_packets(packets, Some(first), ReplayMode::Fast, true)
+            .with_time_shift(Duration::ZERO);
+
+        let mut timestamps = Vec::new();
+        while timestamps.len() < 4 {
+            if let Some(packet) = capture.next_packet().await.unwrap() {
+                timestamps.push(packet.timestamp);
+            }
+        }
+
+        // Second pass starts one capture span (20ms) after the first pass
+        assert_eq!(timestamps[3], first + Duration::from_millis(20));
+        assert!(timestamps.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[tokio::test]
+    async fn test_gap_injection_counts_as_dropped() {
+        let packets: Vec<RawPacket> = (0..10u64)
+            .map(|i| RawPacket {
+                data: vec![i as u8; 60],
+                timestamp: UNIX_EPOCH + Duration::from_millis(i),
+                length: 60,
+            })
+            .collect();
+        let mut capture = ReplayCapture::from_packets(packets, Some(UNIX_EPOCH), ReplayMode::Fast, false)
+            .with_gap_injection(3);
+
+        let mut delivered = Vec::new();
+        while let Ok(Some(packet)) = capture.next_packet().await {
+            delivered.push(packet.data[0]);
+        }
+
+        // Packets 3, 6 and 9 (1-based) are skipped
+        assert_eq!(delivered, vec![0, 1, 3, 4, 6, 7, 9]);
+        let stats = capture.stats();
+        assert_eq!(stats.packets_received, 7);
+        assert_eq!(stats.packets_dropped, 3);
+        assert_eq!(capture.replay_stats().injected_drops, 3);
+    }
+
+    #[tokio::test]
+    async fn test_replay_progress_and_completion() {
+        let (packets, first) = old_packets();
+        let mut capture = ReplayCapture::from_packets(packets, Some(first), ReplayMode::Fast, false);
+        assert_eq!(capture.replay_stats().replay_progress_pct, 0.0);
+        assert!(!capture.is_complete());
+
+        capture.next_packet().await.unwrap();
+        let progress = capture.replay_stats().replay_progress_pct;
+        assert!((progress - 100.0 / 3.0).abs() < 1e-9);
+
+        capture.next_packet().await.unwrap();
+        capture.next_packet().await.unwrap();
+        assert_eq!(capture.replay_stats().replay_progress_pct, 100.0);
+        assert!(capture.is_complete());
+        assert_eq!(capture.stats().packets_dropped, 0);
+
+        // A looping replay is never complete
+        let (packets, first) = old_packets();
+        let mut looping = ReplayCapture::from_packets(packets, Some(first), ReplayMode::Fast, true);
+        for _ in 0..3 {
+            looping.next_packet().await.unwrap();
+        }
+        assert!(!looping.is_complete());
+    }
+
+    #[tokio::test]
+    async fn test_reset_replays_again() {
+        let (packets, first) = old_packets();
+        let mut capture =
+            ReplayCapture::from_packets(packets, Some(first), ReplayMode::Fast, false)
+                .with_gap_injection(3);
+
+        let mut first_run = Vec::new();
+        while let Ok(Some(packet)) = capture.next_packet().await {
+            first_run.push(packet.data);
+        }
+        assert!(capture.is_complete());
+        assert!(matches!(
+            capture.next_packet().await,
+            Err(CaptureError::NoMorePackets)
+        ));
+
+        capture.reset();
+        assert!(!capture.is_complete());
+        let stats = capture.replay_stats();
+        assert_eq!(stats.packets_replayed, 0);
+        assert_eq!(stats.loops_completed, 0);
+        assert_eq!(stats.injected_drops, 0);
+        assert_eq!(stats.replay_progress_pct, 0.0);
+
+        let mut second_run = Vec::new();
+        while let Ok(Some(packet)) = capture.next_packet().await {
+            second_run.push(packet.data);
+        }
+        assert_eq!(second_run, first_run);
+        assert_eq!(capture.replay_stats().packets_replayed, 2);
+        assert_eq!(capture.stats().packets_dropped, 1);
+    }
+
+    /// Ten packets, 1ms apart, each carrying its sequence number in byte 0
+    fn numbered_packets() -> Vec<RawPacket> {
+        (0..10u64)
+            .map(|i| RawPacket {
+                data: vec![i as u8; 60],
+                timestamp: UNIX_EPOCH + Duration::from_millis(i),
+                length: 60,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_seek_to_packet() {
+        let mut capture =
+            ReplayCapture::from_raw_packets(numbered_packets(), ReplayMode::Fast, false).unwrap();
+        assert_eq!(capture.packet_count(), 10);
+
+        capture.seek_to_packet(5).unwrap();
+        let mut delivered = Vec::new();
+        while let Ok(Some(packet)) = capture.next_packet().await {
+            delivered.push(packet.data[0]);
+        }
+        assert_eq!(delivered, vec![5, 6, 7, 8, 9]);
+
+        assert!(matches!(
+            capture.seek_to_packet(10),
+            Err(CaptureError::OpenFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_seek_then_loop_with_original_timing() {
+        let mut capture =
+            ReplayCapture::from_raw_packets(numbered_packets(), ReplayMode::OriginalTiming, true)
+                .unwrap();
+        capture.seek_to_packet(8).unwrap();
+
+        // Pacing starts at packet 8, so the first packet is not delayed by 8ms
+        let replay_start = Instant::now();
+        let mut delivered = Vec::new();
+        while delivered.len() < 4 {
+            if let Some(packet) = capture.next_packet().await.unwrap() {
+                delivered.push(packet.data[0]);
+            }
+        }
+        assert_eq!(delivered, vec![8, 9, 0, 1]);
+        assert!(replay_start.elapsed() < Duration::from_millis(8));
+    }
+
+    #[test]
+    fn test_from_raw_packets_rejects_empty() {
+        let result = ReplayCapture::from_raw_packets(Vec::new(), ReplayMode::Fast, false);
+        assert!(matches!(result, Err(CaptureError::OpenFailed(_))));
+
+        let (packets, _) = old_packets();
+        let result = ReplayCapture::from_raw_packets(packets, ReplayMode::FixedRate(0), false);
+        assert!(matches!(result, Err(CaptureError::OpenFailed(_))));
+    }
+
+    #[test]
+    fn test_custom_timestamps_length_mismatch() {
+        let (packets, first) = old_packets();
+        let capture = ReplayCapture::from_raw_packets(packets, ReplayMode::Fast, false).unwrap();
+        let result = capture.with_custom_timestamps(vec![first, first]);
+        assert!(matches!(result, Err(CaptureError::OpenFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_custom_timestamps_drive_original_timing() {
+        let (packets, _) = old_packets();
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let timestamps: Vec<SystemTime> = (0..3u64)
+            .map(|i| start + Duration::from_nanos(i * 1_000_000))
+            .collect();
+
+        let mut capture = ReplayCapture::from_raw_packets(packets, ReplayMode::OriginalTiming, false)
+            .unwrap()
+            .with_custom_timestamps(timestamps.clone())
+            .unwrap();
+
+        let replay_start = Instant::now();
+        let mut delivered = Vec::new();
+        while let Ok(Some(packet)) = capture.next_packet().await {
+            delivered.push(packet.timestamp);
+        }
+
+        // Delivered exactly as given, paced by the 1ms spacing rather than the PCAP's 10ms
+        assert_eq!(delivered, timestamps);
+        let elapsed = replay_start.elapsed();
+        assert!(elapsed >= Duration::from_millis(2));
+        assert!(elapsed < Duration::from_millis(20));
+    }
 }
@@ -26,7 +26,7 @@ impl XdpCapture {
         // 5. Set up RX/TX rings
         // 6. Bind socket to interface queue
 
-        Err(CaptureError::XdpNotAvailable(
+        Err(CaptureError::XdpError(
             "XDP support requires kernel 4.18+, libbpf, and XDP-capable NIC driver"
                 .to_string(),
         ))
@@ -45,3 +45,16 @@ impl XdpCapture {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_open_failure_is_xdp_error() {
+        let err = XdpCapture::open("eth0").err().unwrap();
+        assert!(matches!(err, CaptureError::XdpError(_)));
+        assert_eq!(err.backend(), "XDP");
+    }
+}
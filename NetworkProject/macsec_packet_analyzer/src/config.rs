@@ -38,6 +38,11 @@ pub struct ServerConfig {
     /// Host to bind to
     #[serde(default = "default_host")]
     pub host: String,
+
+    /// Bearer token required by the admin endpoints (`/api/v1/admin/*`);
+    /// they are not served at all when unset
+    #[serde(default)]
+    pub admin_token: Option<String>,
 }
 
 fn default_db_path() -> String {
@@ -65,6 +70,7 @@ impl Default for ServerConfig {
         Self {
             port: default_port(),
             host: default_host(),
+            admin_token: None,
         }
     }
 }
@@ -173,6 +179,7 @@ mod tests {
         assert_eq!(config.database.path, "analysis.db");
         assert_eq!(config.server.port, 3000);
         assert_eq!(config.server.host, "127.0.0.1");
+        assert_eq!(config.server.admin_token, None);
     }
 
     #[test]
@@ -201,7 +208,8 @@ mod tests {
             },
             "server": {
                 "port": 8080,
-                "host": "0.0.0.0"
+                "host": "0.0.0.0",
+                "admin_token": "s3cret"
             }
         }"#;
 
@@ -209,6 +217,7 @@ mod tests {
         assert_eq!(config.database.path, "mydata.db");
         assert_eq!(config.server.port, 8080);
         assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.server.admin_token.as_deref(), Some("s3cret"));
     }
 
     #[test]
@@ -5,10 +5,34 @@
 //! Current implementation uses rusqlite for SQLite with chrono for timestamps.
 
 use crate::error::CaptureError;
-use crate::types::{FlowId, FlowStats, SequenceGap};
+use crate::types::{FlowGraph, FlowId, FlowMetric, FlowStats, ProtocolBreakdown, SequenceGap};
 use chrono::{DateTime, Utc};
 use rusqlite::OptionalExtension;
-use std::time::SystemTime;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// `sequence_gaps.detected_at` format (UTC); sorts correctly as a string
+const GAP_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// Upsert of one `flows` row, shared by `insert_flow` and `insert_flow_batch`
+///
+/// Updates an existing row in place: `INSERT OR REPLACE` would delete it
+/// first, and with foreign keys enforced that deletion cascades to the
+/// flow's statistics and time-series samples.
+const INSERT_FLOW_SQL: &str = "INSERT INTO flows (
+        id, label, first_sequence, last_sequence, packets_received,
+        gaps_detected, total_lost_packets, min_gap, max_gap, updated_at
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, CURRENT_TIMESTAMP)
+    ON CONFLICT(id) DO UPDATE SET
+        label = excluded.label,
+        first_sequence = excluded.first_sequence,
+        last_sequence = excluded.last_sequence,
+        packets_received = excluded.packets_received,
+        gaps_detected = excluded.gaps_detected,
+        total_lost_packets = excluded.total_lost_packets,
+        min_gap = excluded.min_gap,
+        max_gap = excluded.max_gap,
+        updated_at = excluded.updated_at";
 
 /// Database configuration supporting multiple backends
 #[derive(Clone, Debug)]
@@ -85,6 +109,7 @@ impl Database {
         let schema_sql = "
             CREATE TABLE IF NOT EXISTS flows (
                 id TEXT PRIMARY KEY,
+                label TEXT,
                 first_sequence INTEGER,
                 last_sequence INTEGER,
                 packets_received INTEGER NOT NULL DEFAULT 0,
@@ -103,6 +128,7 @@ impl Database {
                 received_sequence INTEGER NOT NULL,
                 gap_size INTEGER NOT NULL,
                 detected_at DATETIME NOT NULL,
+                recovery_time_us INTEGER,
                 created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
                 FOREIGN KEY(flow_id) REFERENCES flows(id)
             );
@@ -117,61 +143,298 @@ impl Database {
                 max_inter_arrival_us INTEGER,
                 avg_inter_arrival_us INTEGER,
                 protocol_distribution TEXT,
+                protocol_version_distribution TEXT,
+                loss_rate_ema REAL,
+                loss_rate_instantaneous REAL,
+                dscp_distribution TEXT,
+                ecn_congestion_signals INTEGER,
                 updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
                 FOREIGN KEY(flow_id) REFERENCES flows(id) ON DELETE CASCADE
             );
 
+            CREATE TABLE IF NOT EXISTS flow_timeseries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                flow_id TEXT NOT NULL,
+                sampled_at DATETIME NOT NULL,
+                packets INTEGER NOT NULL,
+                bytes INTEGER NOT NULL,
+                gaps INTEGER NOT NULL,
+                FOREIGN KEY(flow_id) REFERENCES flows(id) ON DELETE CASCADE
+            );
+
             CREATE INDEX IF NOT EXISTS idx_flows_created_at ON flows(created_at);
             CREATE INDEX IF NOT EXISTS idx_gaps_flow_id ON sequence_gaps(flow_id);
             CREATE INDEX IF NOT EXISTS idx_gaps_detected_at ON sequence_gaps(detected_at);
             CREATE INDEX IF NOT EXISTS idx_stats_flow_id ON flow_statistics(flow_id);
+            CREATE INDEX IF NOT EXISTS idx_timeseries_flow_sampled ON flow_timeseries(flow_id, sampled_at);
         ";
 
         self.conn
             .execute_batch(schema_sql)
             .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
 
+        // Columns added after the initial schema, for databases created earlier
+        self.add_column_if_missing("flow_statistics", "protocol_version_distribution", "TEXT")?;
+        self.add_column_if_missing("sequence_gaps", "recovery_time_us", "INTEGER")?;
+        self.add_column_if_missing("flow_statistics", "loss_rate_ema", "REAL")?;
+        self.add_column_if_missing("flow_statistics", "loss_rate_instantaneous", "REAL")?;
+        self.add_column_if_missing("flow_statistics", "dscp_distribution", "TEXT")?;
+        self.add_column_if_missing("flow_statistics", "ecn_congestion_signals", "INTEGER")?;
+        self.add_column_if_missing("flows", "label", "TEXT")?;
+        self.migrate_flow_keys()?;
+        self.create_flows_fts()?;
+
         Ok(())
     }
 
-    /// Store flow statistics
-    pub fn insert_flow(&mut self, stats: &FlowStats) -> Result<(), CaptureError> {
-        let flow_id = stats.flow_id.to_string();
+    /// Rewrite flow IDs stored in `Display` form as compact keys
+    ///
+    /// Databases written before compact keys (see `FlowId::to_compact_string`)
+    /// used the `Display` form, which always contains a space; compact keys
+    /// never do. Both the current form and the older one (see
+    /// `parse_legacy_flow_id`) are converted. If any ID can't be converted
+    /// nothing is rewritten and an error names the rows left. Flows without
+    /// a label get one, so the search index covers them.
+    fn migrate_flow_keys(&mut self) -> Result<(), CaptureError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        // Parent and child keys are rewritten one table at a time; check the
+        // foreign keys once at commit, when they agree again
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON")
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+
+        let mut unconverted = Vec::new();
+        for (table, column) in [
+            ("flows", "id"),
+            ("sequence_gaps", "flow_id"),
+            ("flow_statistics", "flow_id"),
+            ("flow_timeseries", "flow_id"),
+        ] {
+            let legacy_ids = {
+                let mut stmt = tx
+                    .prepare(&format!(
+                        "SELECT DISTINCT {} FROM {} WHERE {} LIKE '% %'",
+                        column, table, column
+                    ))
+                    .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+                let ids = stmt
+                    .query_map([], |row| row.get::<_, String>(0))
+                    .map_err(|e| CaptureError::DatabaseError(e.to_string()))?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+                ids
+            };
+
+            for legacy_id in legacy_ids {
+                let flow_id = match parse_legacy_flow_id(&legacy_id) {
+                    Some(flow_id) => flow_id,
+                    None => {
+                        unconverted.push(format!("{}.{} = {:?}", table, column, legacy_id));
+                        continue;
+                    }
+                };
+                tx.execute(
+                    &format!("UPDATE {} SET {} = ?1 WHERE {} = ?2", table, column, column),
+                    rusqlite::params![flow_id.to_compact_string(), legacy_id],
+                )
+                .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+            }
+        }
+
+        if !unconverted.is_empty() {
+            // Dropping the transaction rolls back the keys already rewritten
+            return Err(CaptureError::DatabaseError(format!(
+                "{} flow IDs can't be converted to compact keys: {}",
+                unconverted.len(),
+                unconverted.join(", ")
+            )));
+        }
+
+        let unlabeled = {
+            let mut stmt = tx
+                .prepare("SELECT id FROM flows WHERE label IS NULL")
+                .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+            let ids = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(|e| CaptureError::DatabaseError(e.to_string()))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+            ids
+        };
+        for id in unlabeled {
+            // An unparseable ID is most likely already a human-readable label
+            let label = FlowId::from_compact_string(&id)
+                .map(|flow_id| flow_id.to_string())
+                .unwrap_or_else(|_| id.clone());
+            tx.execute("UPDATE flows SET label = ?1 WHERE id = ?2", [&label, &id])
+                .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))
+    }
+
+    /// Create the FTS5 index over flow labels and the triggers that maintain it
+    ///
+    /// Labels are the `Display` form of the flow ID, so searches match IPs
+    /// and ports as written rather than the hex of the compact keys. On
+    /// databases created before the index existed, or with the older index
+    /// over `id`, it is (re)built from the current contents of `flows`.
+    fn create_flows_fts(&mut self) -> Result<(), CaptureError> {
+        let exists: bool = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'flows_fts'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        let indexes_label: bool = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('flows_fts') WHERE name = 'label'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+
+        if exists && !indexes_label {
+            self.conn
+                .execute_batch(
+                    "DROP TRIGGER IF EXISTS flows_fts_insert;
+                     DROP TRIGGER IF EXISTS flows_fts_delete;
+                     DROP TRIGGER IF EXISTS flows_fts_update;
+                     DROP TABLE flows_fts;",
+                )
+                .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        }
+
+        // insert_flow updates rows in place, but a REPLACE conflict's implicit
+        // delete only fires the delete trigger with recursive_triggers on
+        let fts_sql = "
+            PRAGMA recursive_triggers = ON;
+
+            CREATE VIRTUAL TABLE IF NOT EXISTS flows_fts
+                USING fts5(label, content='flows', content_rowid='rowid');
+
+            CREATE TRIGGER IF NOT EXISTS flows_fts_insert AFTER INSERT ON flows BEGIN
+                INSERT INTO flows_fts(rowid, label) VALUES (new.rowid, new.label);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS flows_fts_delete AFTER DELETE ON flows BEGIN
+                INSERT INTO flows_fts(flows_fts, rowid, label)
+                    VALUES ('delete', old.rowid, old.label);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS flows_fts_update AFTER UPDATE OF id, label ON flows BEGIN
+                INSERT INTO flows_fts(flows_fts, rowid, label)
+                    VALUES ('delete', old.rowid, old.label);
+                INSERT INTO flows_fts(rowid, label) VALUES (new.rowid, new.label);
+            END;
+        ";
 
         self.conn
-            .execute(
-                "INSERT OR REPLACE INTO flows (
-                    id, first_sequence, last_sequence, packets_received,
-                    gaps_detected, total_lost_packets, min_gap, max_gap, updated_at
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, CURRENT_TIMESTAMP)",
-                rusqlite::params![
-                    &flow_id,
-                    stats.first_sequence,
-                    stats.last_sequence,
-                    stats.packets_received,
-                    stats.gaps_detected,
-                    stats.total_lost_packets,
-                    stats.min_gap,
-                    stats.max_gap,
-                ],
+            .execute_batch(fts_sql)
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+
+        if !exists || !indexes_label {
+            self.conn
+                .execute("INSERT INTO flows_fts(flows_fts) VALUES ('rebuild')", [])
+                .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Add `column` to `table` unless it already exists
+    fn add_column_if_missing(
+        &mut self,
+        table: &str,
+        column: &str,
+        column_type: &str,
+    ) -> Result<(), CaptureError> {
+        let exists: bool = self
+            .conn
+            .query_row(
+                &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{}') WHERE name = ?1", table),
+                [column],
+                |row| row.get(0),
             )
             .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
 
+        if !exists {
+            self.conn
+                .execute(
+                    &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, column_type),
+                    [],
+                )
+                .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Store flow statistics
+    pub fn insert_flow(&mut self, stats: &FlowStats) -> Result<(), CaptureError> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(INSERT_FLOW_SQL)
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        execute_insert_flow(&mut stmt, stats)
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+
         Ok(())
     }
 
+    /// Store statistics for many flows in a single transaction
+    ///
+    /// Much faster than calling `insert_flow` per flow, which commits (and
+    /// syncs to disk) once per row. Either every row is written or, on
+    /// error, none are.
+    ///
+    /// Returns the number of rows inserted or updated.
+    pub fn insert_flow_batch(&mut self, stats: &[FlowStats]) -> Result<u64, CaptureError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+
+        let mut inserted = 0;
+        {
+            let mut stmt = tx
+                .prepare_cached(INSERT_FLOW_SQL)
+                .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+            for flow in stats {
+                inserted += execute_insert_flow(&mut stmt, flow)
+                    .map_err(|e| CaptureError::DatabaseError(e.to_string()))?
+                    as u64;
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        Ok(inserted)
+    }
+
     /// Store sequence gap detection
     pub fn insert_gap(&mut self, gap: &SequenceGap) -> Result<(), CaptureError> {
-        let flow_id = gap.flow_id.to_string();
+        let flow_id = gap.flow_id.to_compact_string();
         let detected_at = DateTime::<Utc>::from(gap.timestamp)
-            .format("%Y-%m-%d %H:%M:%S%.3f")
+            .format(GAP_TIMESTAMP_FORMAT)
             .to_string();
 
         self.conn
             .execute(
-                "INSERT INTO sequence_gaps (flow_id, expected_sequence, received_sequence, gap_size, detected_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
-                rusqlite::params![&flow_id, gap.expected, gap.received, gap.gap_size, &detected_at],
+                "INSERT INTO sequence_gaps (flow_id, expected_sequence, received_sequence, gap_size, detected_at, recovery_time_us)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    &flow_id,
+                    gap.expected,
+                    gap.received,
+                    gap.gap_size,
+                    &detected_at,
+                    gap.recovery_time.map(|d| d.as_micros() as i64),
+                ],
             )
             .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
 
@@ -180,7 +443,7 @@ impl Database {
 
     /// Store enhanced statistics for a flow
     pub fn insert_statistics(&mut self, stats: &FlowStats) -> Result<(), CaptureError> {
-        let flow_id = stats.flow_id.to_string();
+        let flow_id = stats.flow_id.to_compact_string();
 
         // Format timestamps as ISO 8601
         let first_timestamp = stats.first_timestamp.map(|t| {
@@ -204,14 +467,26 @@ impl Database {
                 Err(_) => None,
             }
         };
+        let protocol_version_distribution = if stats.protocol_version_distribution.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&stats.protocol_version_distribution).ok()
+        };
+        let dscp_distribution = if stats.dscp_distribution.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&stats.dscp_distribution).ok()
+        };
 
         self.conn
             .execute(
                 "INSERT OR REPLACE INTO flow_statistics (
                     flow_id, total_bytes, first_timestamp, last_timestamp,
                     min_inter_arrival_us, max_inter_arrival_us, avg_inter_arrival_us,
-                    protocol_distribution, updated_at
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, CURRENT_TIMESTAMP)",
+                    protocol_distribution, protocol_version_distribution,
+                    loss_rate_ema, loss_rate_instantaneous,
+                    dscp_distribution, ecn_congestion_signals, updated_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, CURRENT_TIMESTAMP)",
                 rusqlite::params![
                     &flow_id,
                     stats.total_bytes as i64,
@@ -221,6 +496,42 @@ impl Database {
                     max_inter_arrival_us,
                     avg_inter_arrival_us,
                     protocol_distribution,
+                    protocol_version_distribution,
+                    stats.loss_rate_ema,
+                    stats.loss_rate_instantaneous,
+                    dscp_distribution,
+                    stats.ecn_congestion_signals as i64,
+                ],
+            )
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Store a snapshot of a flow's cumulative counters for time-series queries
+    ///
+    /// Call once per persistence cycle; rates are derived from the difference
+    /// between adjacent samples.
+    pub fn insert_timeseries_sample(
+        &mut self,
+        stats: &FlowStats,
+        sampled_at: SystemTime,
+    ) -> Result<(), CaptureError> {
+        let flow_id = stats.flow_id.to_compact_string();
+        let sampled_at = DateTime::<Utc>::from(sampled_at)
+            .format("%Y-%m-%d %H:%M:%S%.3f")
+            .to_string();
+
+        self.conn
+            .execute(
+                "INSERT INTO flow_timeseries (flow_id, sampled_at, packets, bytes, gaps)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    &flow_id,
+                    &sampled_at,
+                    stats.packets_received as i64,
+                    stats.total_bytes as i64,
+                    stats.gaps_detected as i64,
                 ],
             )
             .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
@@ -228,9 +539,71 @@ impl Database {
         Ok(())
     }
 
+    /// Get per-interval packet, byte and gap rates for a flow
+    ///
+    /// Samples are grouped into `interval`-sized buckets (at least 1 second)
+    /// and the last counter value of each bucket is compared with the previous
+    /// bucket. The first bucket only serves as a baseline, so N buckets yield
+    /// N-1 points. A counter that goes backwards (tracker restart) is treated
+    /// as starting from zero.
+    pub fn get_flow_metrics_timeseries(
+        &self,
+        flow_id: &FlowId,
+        interval: Duration,
+    ) -> Result<Vec<TimeseriesPoint>, CaptureError> {
+        let flow_id_str = flow_id.to_compact_string();
+        let interval_s = interval.as_secs().max(1) as i64;
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT CAST(strftime('%s', sampled_at) AS INTEGER) / ?2 * ?2 AS bucket_start,
+                        MAX(packets), MAX(bytes), MAX(gaps)
+                 FROM flow_timeseries
+                 WHERE flow_id = ?1
+                 GROUP BY bucket_start
+                 ORDER BY bucket_start ASC",
+            )
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        let buckets = stmt
+            .query_map(rusqlite::params![&flow_id_str, interval_s], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        let points = buckets
+            .windows(2)
+            .map(|pair| {
+                let (prev_start, prev_packets, prev_bytes, prev_gaps) = pair[0];
+                let (start, packets, bytes, gaps) = pair[1];
+                let elapsed = (start - prev_start).max(1) as f64;
+                let delta = |current: i64, previous: i64| {
+                    if current >= previous { current - previous } else { current }
+                };
+
+                TimeseriesPoint {
+                    timestamp: UNIX_EPOCH + Duration::from_secs(start.max(0) as u64),
+                    packet_rate_pps: delta(packets, prev_packets) as f64 / elapsed,
+                    byte_rate_bps: (delta(bytes, prev_bytes) as f64 / elapsed) as u64,
+                    gap_rate: delta(gaps, prev_gaps) as f64 / elapsed,
+                }
+            })
+            .collect();
+
+        Ok(points)
+    }
+
     /// Get enhanced statistics for a specific flow
     pub fn get_statistics(&self, flow_id: &FlowId) -> Result<Option<FlowStatisticsRecord>, CaptureError> {
-        let flow_id_str = flow_id.to_string();
+        let flow_id_str = flow_id.to_compact_string();
         let mut stmt = self
             .conn
             .prepare(
@@ -262,7 +635,7 @@ impl Database {
 
     /// Get flow statistics by ID
     pub fn get_flow(&self, flow_id: &FlowId) -> Result<Option<FlowStats>, CaptureError> {
-        let flow_id_str = flow_id.to_string();
+        let flow_id_str = flow_id.to_compact_string();
         let mut stmt = self
             .conn
             .prepare(
@@ -270,7 +643,9 @@ impl Database {
                         f.gaps_detected, f.total_lost_packets, f.min_gap, f.max_gap,
                         s.total_bytes, s.first_timestamp, s.last_timestamp,
                         s.min_inter_arrival_us, s.max_inter_arrival_us, s.avg_inter_arrival_us,
-                        s.protocol_distribution
+                        s.protocol_distribution, s.protocol_version_distribution,
+                        s.loss_rate_ema, s.loss_rate_instantaneous,
+                        s.dscp_distribution, s.ecn_congestion_signals
                  FROM flows f
                  LEFT JOIN flow_statistics s ON f.id = s.flow_id
                  WHERE f.id = ?1",
@@ -278,49 +653,57 @@ impl Database {
             .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
 
         let result = stmt
-            .query_row(rusqlite::params![&flow_id_str], |row| {
-                let total_bytes = row.get::<_, Option<i64>>(8)?.unwrap_or(0) as u64;
-                let first_timestamp = row.get::<_, Option<String>>(9)?
-                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| SystemTime::from(dt.with_timezone(&Utc)));
-                let last_timestamp = row.get::<_, Option<String>>(10)?
-                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| SystemTime::from(dt.with_timezone(&Utc)));
-                let min_inter_arrival = row.get::<_, Option<i64>>(11)?
-                    .map(|v| std::time::Duration::from_micros(v as u64));
-                let max_inter_arrival = row.get::<_, Option<i64>>(12)?
-                    .map(|v| std::time::Duration::from_micros(v as u64));
-                let avg_inter_arrival = row.get::<_, Option<i64>>(13)?
-                    .map(|v| std::time::Duration::from_micros(v as u64));
-                let protocol_distribution_str = row.get::<_, Option<String>>(14)?;
-                let protocol_distribution = protocol_distribution_str
-                    .and_then(|s| serde_json::from_str(&s).ok())
-                    .unwrap_or_default();
-
-                Ok(FlowStats {
-                    flow_id: FlowId::new(row.get::<_, String>(0)?),
-                    first_sequence: row.get(1)?,
-                    last_sequence: row.get(2)?,
-                    packets_received: row.get(3)?,
-                    gaps_detected: row.get(4)?,
-                    total_lost_packets: row.get(5)?,
-                    min_gap: row.get(6)?,
-                    max_gap: row.get(7)?,
-                    total_bytes,
-                    first_timestamp,
-                    last_timestamp,
-                    min_inter_arrival,
-                    max_inter_arrival,
-                    avg_inter_arrival,
-                    protocol_distribution,
-                })
-            })
+            .query_row(rusqlite::params![&flow_id_str], flow_stats_from_row)
             .optional()
             .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
 
         Ok(result)
     }
 
+    /// Get statistics for specific flows by primary key, in the order requested
+    ///
+    /// Unknown flow IDs are skipped. Avoids a full scan when only a handful
+    /// of flows are needed.
+    pub fn get_flows_by_ids(&self, flow_ids: &[FlowId]) -> Result<Vec<FlowStats>, CaptureError> {
+        let mut flows = Vec::with_capacity(flow_ids.len());
+        for flow_id in flow_ids {
+            if let Some(flow) = self.get_flow(flow_id)? {
+                flows.push(flow);
+            }
+        }
+        Ok(flows)
+    }
+
+    /// Find flows whose ID contains `query`, best matches first
+    ///
+    /// Searches the `Display` form of the IDs, tokenized on punctuation, so `192.168.1` matches
+    /// `192.168.1.5:443` but not `192.168.10.2`, and `443` matches the port.
+    /// The query is searched as a phrase; FTS5 operators are not interpreted.
+    pub fn full_text_search_flows(&self, query: &str) -> Result<Vec<FlowStats>, CaptureError> {
+        if query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let phrase = format!("\"{}\"", query.replace('"', "\"\""));
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT f.id FROM flows_fts
+                 JOIN flows f ON f.rowid = flows_fts.rowid
+                 WHERE flows_fts MATCH ?1
+                 ORDER BY flows_fts.rank",
+            )
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        let flow_ids = stmt
+            .query_map([phrase], |row| row.get::<_, String>(0))
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?
+            .map(|id| id.map(flow_id_from_key))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+
+        self.get_flows_by_ids(&flow_ids)
+    }
+
     /// Get all flow statistics with optional filtering
     pub fn get_flows(
         &self,
@@ -337,7 +720,9 @@ impl Database {
                         f.gaps_detected, f.total_lost_packets, f.min_gap, f.max_gap,
                         s.total_bytes, s.first_timestamp, s.last_timestamp,
                         s.min_inter_arrival_us, s.max_inter_arrival_us, s.avg_inter_arrival_us,
-                        s.protocol_distribution
+                        s.protocol_distribution, s.protocol_version_distribution,
+                        s.loss_rate_ema, s.loss_rate_instantaneous,
+                        s.dscp_distribution, s.ecn_congestion_signals
                  FROM flows f
                  LEFT JOIN flow_statistics s ON f.id = s.flow_id
                  ORDER BY f.updated_at DESC
@@ -346,43 +731,140 @@ impl Database {
             .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
 
         let flows = stmt
-            .query_map(rusqlite::params![limit, offset], |row| {
-                let total_bytes = row.get::<_, Option<i64>>(8)?.unwrap_or(0) as u64;
-                let first_timestamp = row.get::<_, Option<String>>(9)?
-                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| SystemTime::from(dt.with_timezone(&Utc)));
-                let last_timestamp = row.get::<_, Option<String>>(10)?
-                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
-                    .map(|dt| SystemTime::from(dt.with_timezone(&Utc)));
-                let min_inter_arrival = row.get::<_, Option<i64>>(11)?
-                    .map(|v| std::time::Duration::from_micros(v as u64));
-                let max_inter_arrival = row.get::<_, Option<i64>>(12)?
-                    .map(|v| std::time::Duration::from_micros(v as u64));
-                let avg_inter_arrival = row.get::<_, Option<i64>>(13)?
-                    .map(|v| std::time::Duration::from_micros(v as u64));
-                let protocol_distribution_str = row.get::<_, Option<String>>(14)?;
-                let protocol_distribution = protocol_distribution_str
-                    .and_then(|s| serde_json::from_str(&s).ok())
-                    .unwrap_or_default();
-
-                Ok(FlowStats {
-                    flow_id: FlowId::new(row.get::<_, String>(0)?),
-                    first_sequence: row.get(1)?,
-                    last_sequence: row.get(2)?,
-                    packets_received: row.get(3)?,
-                    gaps_detected: row.get(4)?,
-                    total_lost_packets: row.get(5)?,
-                    min_gap: row.get(6)?,
-                    max_gap: row.get(7)?,
-                    total_bytes,
-                    first_timestamp,
-                    last_timestamp,
-                    min_inter_arrival,
-                    max_inter_arrival,
-                    avg_inter_arrival,
-                    protocol_distribution,
-                })
-            })
+            .query_map(rusqlite::params![limit, offset], flow_stats_from_row)
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        Ok(flows)
+    }
+
+    /// Get the flows with the most sequence gaps, worst first
+    ///
+    /// Ties are broken by total lost packets. `limit` is capped at 1000 like
+    /// [`Database::get_flows`].
+    pub fn get_top_flows_by_gap_count(&self, limit: usize) -> Result<Vec<FlowStats>, CaptureError> {
+        let limit = limit.min(1000) as i64;
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT f.id, f.first_sequence, f.last_sequence, f.packets_received,
+                        f.gaps_detected, f.total_lost_packets, f.min_gap, f.max_gap,
+                        s.total_bytes, s.first_timestamp, s.last_timestamp,
+                        s.min_inter_arrival_us, s.max_inter_arrival_us, s.avg_inter_arrival_us,
+                        s.protocol_distribution, s.protocol_version_distribution,
+                        s.loss_rate_ema, s.loss_rate_instantaneous,
+                        s.dscp_distribution, s.ecn_congestion_signals
+                 FROM flows f
+                 LEFT JOIN flow_statistics s ON f.id = s.flow_id
+                 ORDER BY f.gaps_detected DESC, f.total_lost_packets DESC, f.id
+                 LIMIT ?1",
+            )
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        let flows = stmt
+            .query_map([limit], flow_stats_from_row)
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        Ok(flows)
+    }
+
+    /// Get the `n` flows with the highest value of `metric`, best first
+    ///
+    /// Ranking happens in SQL, so every stored flow is considered. Scores
+    /// follow `FlowMetric::value`: rates are per second over the flow's
+    /// lifetime and 0 without two distinct timestamps. Ties are broken by
+    /// flow ID. `n` is capped at 1000 like [`Database::get_flows`].
+    pub fn get_top_flows_by_metric(
+        &self,
+        metric: FlowMetric,
+        n: usize,
+    ) -> Result<Vec<FlowStats>, CaptureError> {
+        let limit = n.min(1000) as i64;
+        let score = match metric {
+            FlowMetric::PacketRate => {
+                "CASE WHEN julianday(s.last_timestamp) > julianday(s.first_timestamp)
+                      THEN f.packets_received
+                           / ((julianday(s.last_timestamp) - julianday(s.first_timestamp)) * 86400.0)
+                      ELSE 0.0
+                 END"
+            }
+            FlowMetric::ByteRate => {
+                "CASE WHEN julianday(s.last_timestamp) > julianday(s.first_timestamp)
+                      THEN s.total_bytes
+                           / ((julianday(s.last_timestamp) - julianday(s.first_timestamp)) * 86400.0)
+                      ELSE 0.0
+                 END"
+            }
+            FlowMetric::GapCount => "f.gaps_detected",
+            FlowMetric::LossPercentage => {
+                "CASE WHEN f.packets_received + f.total_lost_packets > 0
+                      THEN f.total_lost_packets * 100.0 / (f.packets_received + f.total_lost_packets)
+                      ELSE 0.0
+                 END"
+            }
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT f.id, f.first_sequence, f.last_sequence, f.packets_received,
+                        f.gaps_detected, f.total_lost_packets, f.min_gap, f.max_gap,
+                        s.total_bytes, s.first_timestamp, s.last_timestamp,
+                        s.min_inter_arrival_us, s.max_inter_arrival_us, s.avg_inter_arrival_us,
+                        s.protocol_distribution, s.protocol_version_distribution,
+                        s.loss_rate_ema, s.loss_rate_instantaneous,
+                        s.dscp_distribution, s.ecn_congestion_signals,
+                        {} AS score
+                 FROM flows f
+                 LEFT JOIN flow_statistics s ON f.id = s.flow_id
+                 ORDER BY score DESC, f.id
+                 LIMIT ?1",
+                score
+            ))
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        let flows = stmt
+            .query_map([limit], flow_stats_from_row)
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        Ok(flows)
+    }
+
+    /// Get flows losing more than `threshold_percent` of their packets, worst first
+    ///
+    /// Loss is lost packets over received plus lost, as in
+    /// `FlowStats::packet_loss_percent`; flows with neither are skipped.
+    pub fn get_flows_with_loss_above(
+        &self,
+        threshold_percent: f64,
+    ) -> Result<Vec<FlowStats>, CaptureError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT f.id, f.first_sequence, f.last_sequence, f.packets_received,
+                        f.gaps_detected, f.total_lost_packets, f.min_gap, f.max_gap,
+                        s.total_bytes, s.first_timestamp, s.last_timestamp,
+                        s.min_inter_arrival_us, s.max_inter_arrival_us, s.avg_inter_arrival_us,
+                        s.protocol_distribution, s.protocol_version_distribution,
+                        s.loss_rate_ema, s.loss_rate_instantaneous,
+                        s.dscp_distribution, s.ecn_congestion_signals
+                 FROM flows f
+                 LEFT JOIN flow_statistics s ON f.id = s.flow_id
+                 WHERE CAST(f.total_lost_packets AS REAL)
+                       / NULLIF(f.packets_received + f.total_lost_packets, 0) > ?1
+                 ORDER BY CAST(f.total_lost_packets AS REAL)
+                          / (f.packets_received + f.total_lost_packets) DESC, f.id",
+            )
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        let flows = stmt
+            .query_map([threshold_percent / 100.0], flow_stats_from_row)
             .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
@@ -390,6 +872,103 @@ impl Database {
         Ok(flows)
     }
 
+    /// Find flows that cross any of the given thresholds
+    ///
+    /// Loss percentage, bandwidth and idle time are computed in SQL and the
+    /// anomaly flags evaluated in the same query. A flow crossing several
+    /// thresholds is reported once per anomaly type, ordered by flow ID.
+    pub fn get_flow_anomalies(
+        &self,
+        threshold: AnomalyThreshold,
+    ) -> Result<Vec<FlowAnomaly>, CaptureError> {
+        let max_bandwidth_mbps = threshold
+            .max_bandwidth_mbps
+            .is_finite()
+            .then_some(threshold.max_bandwidth_mbps);
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT * FROM (
+                    SELECT m.*,
+                           m.packets_received < ?3 AS no_traffic,
+                           m.packets_received >= ?3 AND m.loss_pct >= ?1 AND m.loss_pct <= ?2
+                               AS high_loss,
+                           m.packets_received >= ?3 AND m.bandwidth_mbps < ?4 AS low_bandwidth,
+                           m.packets_received >= ?3 AND ?5 IS NOT NULL AND m.bandwidth_mbps > ?5
+                               AS high_bandwidth
+                    FROM (
+                        SELECT f.id, f.first_sequence, f.last_sequence, f.packets_received,
+                               f.gaps_detected, f.total_lost_packets, f.min_gap, f.max_gap,
+                               s.total_bytes, s.first_timestamp, s.last_timestamp,
+                               s.min_inter_arrival_us, s.max_inter_arrival_us, s.avg_inter_arrival_us,
+                               s.protocol_distribution, s.protocol_version_distribution,
+                               s.loss_rate_ema, s.loss_rate_instantaneous,
+                               s.dscp_distribution, s.ecn_congestion_signals,
+                               CASE WHEN f.packets_received + f.total_lost_packets > 0
+                                    THEN f.total_lost_packets * 100.0
+                                         / (f.packets_received + f.total_lost_packets)
+                               END AS loss_pct,
+                               CASE WHEN julianday(s.last_timestamp) > julianday(s.first_timestamp)
+                                    THEN s.total_bytes * 8.0
+                                         / ((julianday(s.last_timestamp) - julianday(s.first_timestamp)) * 86400.0)
+                                         / 1000000.0
+                               END AS bandwidth_mbps,
+                               (julianday('now') - julianday(s.last_timestamp)) * 86400.0
+                                   AS idle_seconds
+                        FROM flows f
+                        LEFT JOIN flow_statistics s ON f.id = s.flow_id
+                    ) m
+                 )
+                 WHERE no_traffic OR high_loss OR low_bandwidth OR high_bandwidth
+                 ORDER BY id",
+            )
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(
+                rusqlite::params![
+                    threshold.min_loss_pct,
+                    threshold.max_loss_pct,
+                    threshold.min_packets as i64,
+                    threshold.min_bandwidth_mbps,
+                    max_bandwidth_mbps,
+                ],
+                |row| {
+                    let flow = flow_stats_from_row(row)?;
+                    let idle_seconds = row.get::<_, Option<f64>>(22)?.unwrap_or(0.0).max(0.0);
+                    let flag = |idx: usize| row.get::<_, Option<bool>>(idx).map(|b| b.unwrap_or(false));
+                    let mut types = Vec::new();
+                    if flag(23)? {
+                        types.push(AnomalyType::NoTraffic(Duration::from_secs_f64(idle_seconds)));
+                    }
+                    if flag(24)? {
+                        types.push(AnomalyType::HighLoss);
+                    }
+                    if flag(25)? {
+                        types.push(AnomalyType::LowBandwidth);
+                    }
+                    if flag(26)? {
+                        types.push(AnomalyType::HighBandwidth);
+                    }
+                    Ok((flow, types))
+                },
+            )
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .flat_map(|(flow, types)| {
+                types.into_iter().map(move |anomaly_type| FlowAnomaly {
+                    flow: flow.clone(),
+                    anomaly_type,
+                })
+            })
+            .collect())
+    }
+
     /// Get gaps for a specific flow
     pub fn get_flow_gaps(
         &self,
@@ -397,14 +976,15 @@ impl Database {
         limit: Option<i64>,
         offset: Option<i64>,
     ) -> Result<Vec<SequenceGap>, CaptureError> {
-        let flow_id_str = flow_id.to_string();
+        let flow_id_str = flow_id.to_compact_string();
         let limit = limit.unwrap_or(100).min(1000);
         let offset = offset.unwrap_or(0).max(0);
 
         let mut stmt = self
             .conn
             .prepare(
-                "SELECT flow_id, expected_sequence, received_sequence, gap_size, detected_at
+                "SELECT flow_id, expected_sequence, received_sequence, gap_size, detected_at,
+                        recovery_time_us
                  FROM sequence_gaps
                  WHERE flow_id = ?1
                  ORDER BY detected_at DESC
@@ -413,22 +993,36 @@ impl Database {
             .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
 
         let gaps = stmt
-            .query_map(rusqlite::params![&flow_id_str, limit, offset], |row| {
-                let detected_at_str: String = row.get(4)?;
-                // Parse ISO 8601 format back to SystemTime
-                let dt = chrono::DateTime::parse_from_rfc3339(&detected_at_str)
-                    .ok()
-                    .map(|dt| SystemTime::from(dt.with_timezone(&Utc)))
-                    .unwrap_or(SystemTime::now());
-
-                Ok(SequenceGap {
-                    flow_id: FlowId::new(row.get::<_, String>(0)?),
-                    expected: row.get(1)?,
-                    received: row.get(2)?,
-                    gap_size: row.get(3)?,
-                    timestamp: dt,
-                })
-            })
+            .query_map(rusqlite::params![&flow_id_str, limit, offset], gap_from_row)
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        Ok(gaps)
+    }
+
+    /// Get gaps detected after `since` across all flows, oldest first
+    ///
+    /// `detected_at` is stored with millisecond precision, so `since` is
+    /// truncated to the millisecond before comparing.
+    pub fn get_gaps_since(&self, since: SystemTime) -> Result<Vec<SequenceGap>, CaptureError> {
+        let since = DateTime::<Utc>::from(since)
+            .format(GAP_TIMESTAMP_FORMAT)
+            .to_string();
+
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT flow_id, expected_sequence, received_sequence, gap_size, detected_at,
+                        recovery_time_us
+                 FROM sequence_gaps
+                 WHERE detected_at > ?1
+                 ORDER BY detected_at ASC, id ASC",
+            )
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        let gaps = stmt
+            .query_map([&since], gap_from_row)
             .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
@@ -445,27 +1039,237 @@ impl Database {
                         COALESCE(SUM(f.packets_received), 0) as total_packets,
                         COALESCE(SUM(f.gaps_detected), 0) as total_gaps,
                         COALESCE(SUM(f.total_lost_packets), 0) as total_lost,
-                        COALESCE(MAX(f.max_gap), 0) as max_gap_size
+                        COALESCE(MAX(f.max_gap), 0) as max_gap_size,
+                        COALESCE(SUM(s.total_bytes), 0) as total_bytes,
+                        (MAX(julianday(s.last_timestamp)) - MIN(julianday(s.first_timestamp)))
+                            * 86400.0 as duration_secs
                  FROM flows f
                  LEFT JOIN flow_statistics s ON f.id = s.flow_id",
             )
             .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
 
-        stmt.query_row([], |row| {
-            Ok(SummaryStats {
-                total_flows: row.get(0)?,
-                total_packets_received: row.get(1)?,
-                total_gaps_detected: row.get(2)?,
-                total_lost_packets: row.get(3)?,
-                max_gap_size: row.get(4)?,
+        let mut summary = stmt
+            .query_row([], |row| {
+                Ok(SummaryStats {
+                    total_flows: row.get(0)?,
+                    total_packets_received: row.get(1)?,
+                    total_gaps_detected: row.get(2)?,
+                    total_lost_packets: row.get(3)?,
+                    max_gap_size: row.get(4)?,
+                    total_bytes: row.get(5)?,
+                    duration_secs: row.get(6)?,
+                    gap_size_histogram: SummaryStats::empty_histogram(),
+                })
             })
-        })
-        .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        // Gap size distribution, folded into logarithmic buckets
+        let mut stmt = self
+            .conn
+            .prepare("SELECT gap_size, COUNT(*) FROM sequence_gaps GROUP BY gap_size")
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, u64>(1)?)))
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        for row in rows {
+            let (gap_size, count) =
+                row.map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+            *summary
+                .gap_size_histogram
+                .entry(SummaryStats::bucket_for(gap_size))
+                .or_insert(0) += count;
+        }
+
+        Ok(summary)
+    }
+
+    /// Packets, flows and bytes per protocol across all flows
+    ///
+    /// Aggregated in SQL, grouped on the protocol prefix of the compact key
+    /// (`macsec`, `ipsec`, or `l3-` plus the IP protocol number). Keys are
+    /// `FlowId::protocol_name()`.
+    pub fn get_protocol_breakdown(
+        &self,
+    ) -> Result<BTreeMap<String, ProtocolBreakdown>, CaptureError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT MIN(f.id), COUNT(*),
+                        COALESCE(SUM(f.packets_received), 0),
+                        COALESCE(SUM(s.total_bytes), 0)
+                 FROM flows f
+                 LEFT JOIN flow_statistics s ON f.id = s.flow_id
+                 GROUP BY CASE WHEN f.id LIKE 'l3-%' THEN substr(f.id, 1, 5)
+                               ELSE substr(f.id, 1, instr(f.id, '-') - 1)
+                          END",
+            )
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        // Every key in a group shares its protocol, so one key names the group
+        let mut breakdown: BTreeMap<String, ProtocolBreakdown> = BTreeMap::new();
+        for row in rows {
+            let (key, flows, packets, bytes) =
+                row.map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+            let entry = breakdown
+                .entry(flow_id_from_key(key).protocol_name())
+                .or_default();
+            entry.flows += flows.max(0) as u64;
+            entry.packets += packets.max(0) as u64;
+            entry.bytes += bytes.max(0) as u64;
+        }
+
+        Ok(breakdown)
+    }
+
+    /// Host graph of every stored `GenericL3` flow, see `FlowGraph`
+    ///
+    /// Streams each flow's ID and byte count into the graph, so no flow is
+    /// left out and no `FlowStats` is built.
+    pub fn get_flow_graph(&self) -> Result<FlowGraph, CaptureError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT f.id, COALESCE(s.total_bytes, 0)
+                 FROM flows f
+                 LEFT JOIN flow_statistics s ON f.id = s.flow_id
+                 WHERE f.id LIKE 'l3-%'",
+            )
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    flow_id_from_key(row.get(0)?),
+                    row.get::<_, i64>(1)?.max(0) as u64,
+                ))
+            })
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e: rusqlite::Error| CaptureError::DatabaseError(e.to_string()))?;
+
+        Ok(FlowGraph::from_byte_counts(rows))
+    }
+
+    /// Rebuild the database file to reclaim space left by deleted rows
+    pub fn vacuum(&mut self) -> Result<(), CaptureError> {
+        self.conn
+            .execute_batch("VACUUM")
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))
+    }
+
+    /// Current database size in bytes (page_count * page_size)
+    pub fn size_bytes(&self) -> Result<u64, CaptureError> {
+        let page_count: i64 = self
+            .conn
+            .query_row("PRAGMA page_count", [], |row| row.get(0))
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        let page_size: i64 = self
+            .conn
+            .query_row("PRAGMA page_size", [], |row| row.get(0))
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        Ok((page_count.max(0) as u64) * (page_size.max(0) as u64))
+    }
+
+    /// Run VACUUM only if the database has grown past `max_size_bytes`
+    ///
+    /// Returns whether VACUUM was run.
+    pub fn auto_vacuum_threshold(&mut self, max_size_bytes: u64) -> Result<bool, CaptureError> {
+        if self.size_bytes()? <= max_size_bytes {
+            return Ok(false);
+        }
+        self.vacuum()?;
+        Ok(true)
+    }
+
+    /// Delete flows not updated within `age`, along with their gaps,
+    /// statistics and time-series samples
+    ///
+    /// Returns the number of flows deleted.
+    pub fn delete_flows_older_than(&mut self, age: Duration) -> Result<u64, CaptureError> {
+        // flows.updated_at is written by SQLite's CURRENT_TIMESTAMP (UTC, second precision)
+        let cutoff = retention_cutoff(age)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+
+        // The bundled SQLite enforces foreign keys. sequence_gaps has no
+        // ON DELETE CASCADE, so its rows must go before the flows they
+        // reference; statistics and time-series samples would cascade, but
+        // are removed the same way for databases opened with enforcement off.
+        for table in ["flow_timeseries", "flow_statistics", "sequence_gaps"] {
+            tx.execute(
+                &format!(
+                    "DELETE FROM {} WHERE flow_id IN (SELECT id FROM flows WHERE updated_at < ?1)",
+                    table
+                ),
+                [&cutoff],
+            )
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        }
+        let deleted = tx
+            .execute("DELETE FROM flows WHERE updated_at < ?1", [&cutoff])
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        Ok(deleted as u64)
+    }
+
+    /// Delete flows not updated within `older_than` and reclaim their space
+    ///
+    /// For long-running deployments: `delete_flows_older_than` (one
+    /// transaction, gaps, statistics and time-series samples included)
+    /// followed by `VACUUM`, which cannot run inside a transaction. The file
+    /// only shrinks after the vacuum.
+    ///
+    /// Returns the number of flows deleted.
+    pub fn vacuum_old_flows(&mut self, older_than: Duration) -> Result<u64, CaptureError> {
+        let deleted = self.delete_flows_older_than(older_than)?;
+        if deleted > 0 {
+            self.vacuum()?;
+        }
+        Ok(deleted)
+    }
+
+    /// Delete gaps detected more than `age` ago
+    ///
+    /// Returns the number of gaps deleted.
+    pub fn delete_gaps_older_than(&mut self, age: Duration) -> Result<u64, CaptureError> {
+        // Same format as insert_gap, so string comparison orders correctly
+        let cutoff = retention_cutoff(age)
+            .format(GAP_TIMESTAMP_FORMAT)
+            .to_string();
+
+        let deleted = self
+            .conn
+            .execute("DELETE FROM sequence_gaps WHERE detected_at < ?1", [&cutoff])
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
+        Ok(deleted as u64)
     }
 
     /// Clear all data (useful for testing)
     #[allow(dead_code)]
     pub fn clear_all(&mut self) -> Result<(), CaptureError> {
+        self.conn
+            .execute("DELETE FROM flow_timeseries", [])
+            .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
         self.conn
             .execute("DELETE FROM flow_statistics", [])
             .map_err(|e| CaptureError::DatabaseError(e.to_string()))?;
@@ -479,6 +1283,160 @@ impl Database {
     }
 }
 
+/// Run a statement prepared from `INSERT_FLOW_SQL` for one flow
+fn execute_insert_flow(
+    stmt: &mut rusqlite::CachedStatement<'_>,
+    stats: &FlowStats,
+) -> rusqlite::Result<usize> {
+    stmt.execute(rusqlite::params![
+        stats.flow_id.to_compact_string(),
+        stats.flow_id.to_string(),
+        stats.first_sequence,
+        stats.last_sequence,
+        stats.packets_received,
+        stats.gaps_detected,
+        stats.total_lost_packets,
+        stats.min_gap,
+        stats.max_gap,
+    ])
+}
+
+/// Cutoff for deleting rows older than `age`, clamped to the Unix epoch
+///
+/// No row predates 1970, so an `age` reaching back further deletes nothing
+/// instead of overflowing `SystemTime`.
+fn retention_cutoff(age: Duration) -> DateTime<Utc> {
+    let cutoff = SystemTime::now()
+        .checked_sub(age)
+        .map_or(UNIX_EPOCH, |cutoff| cutoff.max(UNIX_EPOCH));
+    DateTime::<Utc>::from(cutoff)
+}
+
+/// Protocol number given to `Unknown { … }` L3 flows from old databases
+///
+/// That form dropped the protocol number; 255 is reserved by IANA, so it
+/// can't collide with a real protocol.
+const LEGACY_UNKNOWN_PROTOCOL: u8 = 255;
+
+/// Parse a flow ID stored in any `Display` form this crate has written
+///
+/// Besides what `FlowId::from_str` accepts, older versions wrote
+/// `MACsec { sci: 0x… }` without an AN (taken as AN 0) and
+/// `Unknown { src:port -> dst:port }` for L3 protocols other than TCP and
+/// UDP (see `LEGACY_UNKNOWN_PROTOCOL`).
+fn parse_legacy_flow_id(id: &str) -> Option<FlowId> {
+    if let Ok(flow_id) = id.parse() {
+        return Some(flow_id);
+    }
+    if let Some(hex) = id
+        .strip_prefix("MACsec { sci: 0x")
+        .and_then(|rest| rest.strip_suffix(" }"))
+    {
+        let sci = u64::from_str_radix(hex, 16).ok()?;
+        return Some(FlowId::MACsec { sci, an: 0 });
+    }
+    let body = id.strip_prefix("Unknown ")?;
+    format!("IP/{} {}", LEGACY_UNKNOWN_PROTOCOL, body).parse().ok()
+}
+
+/// Turn a stored flow key back into a `FlowId`
+///
+/// `Database::initialize` migrates every legacy ID, so keys are compact
+/// strings; anything else was written by hand and is parsed as best it
+/// can be.
+fn flow_id_from_key(key: String) -> FlowId {
+    FlowId::from_compact_string(&key).unwrap_or_else(|_| FlowId::new(key))
+}
+
+/// Build a `SequenceGap` from a sequence_gaps row
+///
+/// Expects flow_id, expected_sequence, received_sequence, gap_size,
+/// detected_at, recovery_time_us.
+fn gap_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SequenceGap> {
+    let detected_at: String = row.get(4)?;
+    let timestamp = chrono::NaiveDateTime::parse_from_str(&detected_at, GAP_TIMESTAMP_FORMAT)
+        .map(|dt| SystemTime::from(dt.and_utc()))
+        .or_else(|_| {
+            chrono::DateTime::parse_from_rfc3339(&detected_at)
+                .map(|dt| SystemTime::from(dt.with_timezone(&Utc)))
+        })
+        .unwrap_or(UNIX_EPOCH);
+
+    Ok(SequenceGap {
+        flow_id: flow_id_from_key(row.get(0)?),
+        expected: row.get(1)?,
+        received: row.get(2)?,
+        gap_size: row.get(3)?,
+        timestamp,
+        recovery_time: row
+            .get::<_, Option<i64>>(5)?
+            .map(|us| std::time::Duration::from_micros(us as u64)),
+    })
+}
+
+/// Build a `FlowStats` from the flows/flow_statistics join
+///
+/// Expects columns 0-19 in the order selected by `get_flow` and `get_flows`.
+fn flow_stats_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<FlowStats> {
+    let total_bytes = row.get::<_, Option<i64>>(8)?.unwrap_or(0) as u64;
+    let first_timestamp = row.get::<_, Option<String>>(9)?
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+        .map(|dt| SystemTime::from(dt.with_timezone(&Utc)));
+    let last_timestamp = row.get::<_, Option<String>>(10)?
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+        .map(|dt| SystemTime::from(dt.with_timezone(&Utc)));
+    let min_inter_arrival = row.get::<_, Option<i64>>(11)?
+        .map(|v| std::time::Duration::from_micros(v as u64));
+    let max_inter_arrival = row.get::<_, Option<i64>>(12)?
+        .map(|v| std::time::Duration::from_micros(v as u64));
+    let avg_inter_arrival = row.get::<_, Option<i64>>(13)?
+        .map(|v| std::time::Duration::from_micros(v as u64));
+    let protocol_distribution_str = row.get::<_, Option<String>>(14)?;
+    let protocol_distribution = protocol_distribution_str
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    let protocol_version_distribution = row.get::<_, Option<String>>(15)?
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    let loss_rate_ema = row.get::<_, Option<f64>>(16)?.unwrap_or(0.0);
+    let loss_rate_instantaneous = row.get::<_, Option<f64>>(17)?.unwrap_or(0.0);
+    let dscp_distribution = row.get::<_, Option<String>>(18)?
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    let ecn_congestion_signals = row.get::<_, Option<i64>>(19)?.unwrap_or(0) as u64;
+
+    Ok(FlowStats {
+        flow_id: flow_id_from_key(row.get(0)?),
+        first_sequence: row.get(1)?,
+        last_sequence: row.get(2)?,
+        packets_received: row.get(3)?,
+        gaps_detected: row.get(4)?,
+        total_lost_packets: row.get(5)?,
+        min_gap: row.get(6)?,
+        max_gap: row.get(7)?,
+        an_rotations: 0, // Not persisted
+        total_bytes,
+        first_timestamp,
+        last_timestamp,
+        min_inter_arrival,
+        max_inter_arrival,
+        avg_inter_arrival,
+        protocol_distribution,
+        protocol_version_distribution,
+        dscp_distribution,
+        ecn_congestion_signals,
+        estimated_owd_us: None, // Not persisted
+        owd_std_dev_us: None,
+        inter_arrival_variance_us2: 0, // Not persisted
+        std_dev_us: 0,
+        burstiness_index: 0.0,
+        pct_gaps_recovered: 0.0, // Not persisted
+        duplicate_packets: 0,    // Not persisted
+        loss_rate_ema,
+        loss_rate_instantaneous,
+    })
+}
+
 /// Summary statistics across all flows
 #[derive(Debug, Clone, serde::Serialize)]
 #[cfg_attr(feature = "rest-api", serde(crate = "serde"))]
@@ -488,6 +1446,133 @@ pub struct SummaryStats {
     pub total_gaps_detected: i64,
     pub total_lost_packets: i64,
     pub max_gap_size: i64,
+    pub total_bytes: i64,
+    /// Seconds from the earliest first packet to the latest last packet
+    /// across all flows; `None` when no flow has timestamps
+    pub duration_secs: Option<f64>,
+    /// Gap count per logarithmic bucket, keyed by the bucket's upper bound.
+    /// Bucket 2^k holds gaps with 2^(k-1) < size <= 2^k.
+    pub gap_size_histogram: BTreeMap<u32, u64>,
+}
+
+impl SummaryStats {
+    /// Largest bucket that is always present in the histogram
+    const MAX_STANDARD_BUCKET: u32 = 65536;
+
+    /// Histogram with all standard buckets (1, 2, 4, ..., 65536) set to zero
+    pub fn empty_histogram() -> BTreeMap<u32, u64> {
+        let mut histogram = BTreeMap::new();
+        let mut bound = 1u32;
+        while bound <= Self::MAX_STANDARD_BUCKET {
+            histogram.insert(bound, 0);
+            bound *= 2;
+        }
+        histogram
+    }
+
+    /// Upper bound of the logarithmic bucket holding `gap_size`
+    ///
+    /// Gaps larger than 2^31 share the `u32::MAX` bucket.
+    pub fn bucket_for(gap_size: u32) -> u32 {
+        gap_size
+            .max(1)
+            .checked_next_power_of_two()
+            .unwrap_or(u32::MAX)
+    }
+
+    /// Estimate the gap size at percentile `pct` (0-100) from the histogram
+    ///
+    /// Returns the upper bound of the bucket containing the percentile, so the
+    /// true value is at most this and greater than half of it. Returns 0 when
+    /// no gaps were recorded.
+    pub fn histogram_percentile(&self, pct: f64) -> u32 {
+        let total: u64 = self.gap_size_histogram.values().sum();
+        if total == 0 {
+            return 0;
+        }
+
+        let rank = ((pct.clamp(0.0, 100.0) / 100.0) * total as f64).ceil().max(1.0) as u64;
+        let mut cumulative = 0u64;
+        for (&bound, &count) in &self.gap_size_histogram {
+            cumulative += count;
+            if cumulative >= rank {
+                return bound;
+            }
+        }
+
+        // Unreachable with a consistent histogram; fall back to the largest bucket
+        self.gap_size_histogram.keys().next_back().copied().unwrap_or(0)
+    }
+}
+
+/// Thresholds for `Database::get_flow_anomalies`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnomalyThreshold {
+    /// Loss at or above this percentage is `HighLoss`
+    pub min_loss_pct: f64,
+    /// Loss above this percentage is not reported as `HighLoss`
+    pub max_loss_pct: f64,
+    /// Flows with fewer packets are `NoTraffic` and skip the other checks
+    pub min_packets: u64,
+    /// Bandwidth below this is `LowBandwidth`
+    pub min_bandwidth_mbps: f64,
+    /// Bandwidth above this is `HighBandwidth` (infinite disables the check)
+    pub max_bandwidth_mbps: f64,
+}
+
+impl Default for AnomalyThreshold {
+    /// 1-100% loss, at least one packet, no bandwidth limits
+    fn default() -> Self {
+        Self {
+            min_loss_pct: 1.0,
+            max_loss_pct: 100.0,
+            min_packets: 1,
+            min_bandwidth_mbps: 0.0,
+            max_bandwidth_mbps: f64::INFINITY,
+        }
+    }
+}
+
+/// Why a flow was reported by `Database::get_flow_anomalies`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnomalyType {
+    HighLoss,
+    LowBandwidth,
+    HighBandwidth,
+    /// Fewer than `min_packets` received; holds the time since the last packet
+    NoTraffic(Duration),
+}
+
+impl AnomalyType {
+    /// snake_case name used in API responses and metric labels
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnomalyType::HighLoss => "high_loss",
+            AnomalyType::LowBandwidth => "low_bandwidth",
+            AnomalyType::HighBandwidth => "high_bandwidth",
+            AnomalyType::NoTraffic(_) => "no_traffic",
+        }
+    }
+}
+
+/// A flow that crossed an `AnomalyThreshold`
+#[derive(Debug, Clone)]
+pub struct FlowAnomaly {
+    pub flow: FlowStats,
+    pub anomaly_type: AnomalyType,
+}
+
+/// Flow rates over one time-series interval
+#[derive(Debug, Clone)]
+pub struct TimeseriesPoint {
+    /// Start of the interval
+    pub timestamp: SystemTime,
+    /// Packets per second
+    pub packet_rate_pps: f64,
+    /// Bytes per second
+    pub byte_rate_bps: u64,
+    /// Gaps detected per second
+    pub gap_rate: f64,
 }
 
 /// Enhanced statistics for a single flow
@@ -503,3 +1588,924 @@ pub struct FlowStatisticsRecord {
     pub avg_inter_arrival_us: Option<i64>,
     pub protocol_distribution: Option<String>, // JSON string
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FlowId;
+
+    fn open_memory_db() -> Database {
+        let mut db = Database::open(&DatabaseConfig::sqlite(":memory:")).unwrap();
+        db.initialize().unwrap();
+        db
+    }
+
+    /// Insert the `flows` row that gaps from `gap()` belong to
+    fn insert_gap_flow(db: &Database) {
+        db.conn
+            .execute("INSERT INTO flows (id) VALUES (?1)", [gap(1).flow_id.to_compact_string()])
+            .unwrap();
+    }
+
+    fn gap(gap_size: u32) -> SequenceGap {
+        SequenceGap {
+            flow_id: FlowId::MACsec { sci: 0x1, an: 0 },
+            expected: 1,
+            received: 1 + gap_size,
+            gap_size,
+            timestamp: SystemTime::now(),
+            recovery_time: None,
+        }
+    }
+
+    #[test]
+    fn test_bucket_for() {
+        assert_eq!(SummaryStats::bucket_for(1), 1);
+        assert_eq!(SummaryStats::bucket_for(2), 2);
+        assert_eq!(SummaryStats::bucket_for(3), 4);
+        assert_eq!(SummaryStats::bucket_for(4), 4);
+        assert_eq!(SummaryStats::bucket_for(5), 8);
+        assert_eq!(SummaryStats::bucket_for(65536), 65536);
+        assert_eq!(SummaryStats::bucket_for(65537), 131072);
+        assert_eq!(SummaryStats::bucket_for(u32::MAX), u32::MAX);
+    }
+
+    #[test]
+    fn test_summary_gap_size_histogram() {
+        let mut db = open_memory_db();
+        insert_gap_flow(&db);
+        for size in [1, 1, 3, 4, 100] {
+            db.insert_gap(&gap(size)).unwrap();
+        }
+
+        let summary = db.get_summary_stats().unwrap();
+        assert_eq!(summary.gap_size_histogram.get(&1), Some(&2));
+        assert_eq!(summary.gap_size_histogram.get(&2), Some(&0));
+        assert_eq!(summary.gap_size_histogram.get(&4), Some(&2));
+        assert_eq!(summary.gap_size_histogram.get(&128), Some(&1));
+        assert_eq!(summary.gap_size_histogram.get(&65536), Some(&0));
+        assert_eq!(summary.gap_size_histogram.values().sum::<u64>(), 5);
+    }
+
+    #[test]
+    fn test_summary_total_bytes() {
+        let mut db = open_memory_db();
+        assert_eq!(db.get_summary_stats().unwrap().total_bytes, 0);
+
+        let flows: Vec<FlowStats> = (0..150)
+            .map(|sci| {
+                let mut stats = flow_stats(10, 1500, 0);
+                stats.flow_id = FlowId::MACsec { sci, an: 0 };
+                stats
+            })
+            .collect();
+        db.insert_flow_batch(&flows).unwrap();
+        // Byte counts live in flow_statistics; a flow without a row counts as 0
+        for stats in &flows[..149] {
+            db.insert_statistics(stats).unwrap();
+        }
+
+        let summary = db.get_summary_stats().unwrap();
+        assert_eq!(summary.total_flows, 150);
+        assert_eq!(summary.total_bytes, 149 * 1500);
+    }
+
+    #[test]
+    fn test_summary_duration_secs() {
+        let mut db = open_memory_db();
+        assert_eq!(db.get_summary_stats().unwrap().duration_secs, None);
+
+        let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let flows: Vec<FlowStats> = [(0, 10), (5, 30), (20, 25)]
+            .into_iter()
+            .zip(0..)
+            .map(|((first, last), sci)| {
+                let mut stats = flow_stats(10, 1500, 0);
+                stats.flow_id = FlowId::MACsec { sci, an: 0 };
+                stats.first_timestamp = Some(t0 + Duration::from_secs(first));
+                stats.last_timestamp = Some(t0 + Duration::from_secs(last));
+                stats
+            })
+            .collect();
+        db.insert_flow_batch(&flows).unwrap();
+        for stats in &flows {
+            db.insert_statistics(stats).unwrap();
+        }
+
+        // Earliest first packet (t0) to latest last packet (t0 + 30s)
+        let secs = db.get_summary_stats().unwrap().duration_secs.unwrap();
+        assert!((secs - 30.0).abs() < 0.01, "{}", secs);
+    }
+
+    #[test]
+    fn test_protocol_breakdown() {
+        let mut db = open_memory_db();
+        assert!(db.get_protocol_breakdown().unwrap().is_empty());
+
+        let l3 = |protocol: u8, src_port: u16| FlowId::GenericL3 {
+            src_ip: "10.0.0.1".parse().unwrap(),
+            dst_ip: "10.0.0.2".parse().unwrap(),
+            src_port,
+            dst_port: 80,
+            protocol,
+        };
+        let flow_ids: Vec<FlowId> = (0..150)
+            .map(|sci| FlowId::MACsec { sci, an: 0 })
+            .chain([
+                FlowId::IPsec {
+                    spi: 0x1234,
+                    dst_ip: "10.0.0.9".parse().unwrap(),
+                    mode: None,
+                },
+                l3(6, 1000),
+                l3(6, 1001),
+                l3(17, 1000),
+                l3(47, 1000),
+            ])
+            .collect();
+        let flows: Vec<FlowStats> = flow_ids
+            .into_iter()
+            .map(|flow_id| {
+                let mut stats = flow_stats(10, 100, 0);
+                stats.flow_id = flow_id;
+                stats
+            })
+            .collect();
+        db.insert_flow_batch(&flows).unwrap();
+        for stats in &flows {
+            db.insert_statistics(stats).unwrap();
+        }
+
+        // Covers every flow, not just one page of get_flows
+        let breakdown = db.get_protocol_breakdown().unwrap();
+        let counts = |name: &str| breakdown[name];
+        assert_eq!(
+            counts("MACsec"),
+            ProtocolBreakdown {
+                packets: 1500,
+                flows: 150,
+                bytes: 15_000
+            }
+        );
+        assert_eq!(counts("IPsec").flows, 1);
+        assert_eq!(counts("TCP").flows, 2);
+        assert_eq!(counts("TCP").packets, 20);
+        assert_eq!(counts("UDP").flows, 1);
+        assert_eq!(counts("IP/47").flows, 1);
+        assert_eq!(breakdown.len(), 5);
+    }
+
+    #[test]
+    fn test_histogram_percentile() {
+        let mut db = open_memory_db();
+        let empty = db.get_summary_stats().unwrap();
+        assert_eq!(empty.histogram_percentile(50.0), 0);
+
+        insert_gap_flow(&db);
+        for size in [1, 1, 3, 4, 100] {
+            db.insert_gap(&gap(size)).unwrap();
+        }
+        let summary = db.get_summary_stats().unwrap();
+        assert_eq!(summary.histogram_percentile(0.0), 1);
+        assert_eq!(summary.histogram_percentile(40.0), 1);
+        assert_eq!(summary.histogram_percentile(50.0), 4);
+        assert_eq!(summary.histogram_percentile(80.0), 4);
+        assert_eq!(summary.histogram_percentile(99.0), 128);
+        assert_eq!(summary.histogram_percentile(100.0), 128);
+    }
+
+    fn flow_stats(packets: u64, bytes: u64, gaps: u64) -> FlowStats {
+        FlowStats {
+            flow_id: FlowId::MACsec { sci: 0x1, an: 0 },
+            packets_received: packets,
+            gaps_detected: gaps,
+            total_lost_packets: 0,
+            first_sequence: None,
+            last_sequence: None,
+            min_gap: None,
+            max_gap: None,
+            an_rotations: 0,
+            total_bytes: bytes,
+            first_timestamp: None,
+            last_timestamp: None,
+            min_inter_arrival: None,
+            max_inter_arrival: None,
+            avg_inter_arrival: None,
+            protocol_distribution: Default::default(),
+            protocol_version_distribution: Default::default(),
+            dscp_distribution: Default::default(),
+            ecn_congestion_signals: 0,
+            estimated_owd_us: None,
+            owd_std_dev_us: None,
+            inter_arrival_variance_us2: 0,
+            std_dev_us: 0,
+            burstiness_index: 0.0,
+            pct_gaps_recovered: 0.0,
+            duplicate_packets: 0,
+            loss_rate_ema: 0.0,
+            loss_rate_instantaneous: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_flow_metrics_timeseries() {
+        let mut db = open_memory_db();
+        let flow = FlowId::MACsec { sci: 0x1, an: 0 };
+        // Aligned to a 60s boundary
+        let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_040);
+        db.insert_flow(&flow_stats(0, 0, 0)).unwrap();
+
+        // Two samples in the first bucket: only the last one counts
+        db.insert_timeseries_sample(&flow_stats(100, 10_000, 0), t0).unwrap();
+        db.insert_timeseries_sample(&flow_stats(300, 30_000, 1), t0 + Duration::from_secs(30))
+            .unwrap();
+        db.insert_timeseries_sample(&flow_stats(900, 90_000, 4), t0 + Duration::from_secs(60))
+            .unwrap();
+        db.insert_timeseries_sample(&flow_stats(2_100, 210_000, 4), t0 + Duration::from_secs(120))
+            .unwrap();
+
+        let points = db
+            .get_flow_metrics_timeseries(&flow, Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(points.len(), 2);
+
+        assert_eq!(points[0].timestamp, t0 + Duration::from_secs(60));
+        assert_eq!(points[0].packet_rate_pps, 10.0);
+        assert_eq!(points[0].byte_rate_bps, 1_000);
+        assert_eq!(points[0].gap_rate, 0.05);
+
+        assert_eq!(points[1].packet_rate_pps, 20.0);
+        assert_eq!(points[1].byte_rate_bps, 2_000);
+        assert_eq!(points[1].gap_rate, 0.0);
+    }
+
+    #[test]
+    fn test_flow_metrics_timeseries_unknown_flow() {
+        let db = open_memory_db();
+        let flow = FlowId::MACsec { sci: 0x42, an: 0 };
+        let points = db
+            .get_flow_metrics_timeseries(&flow, Duration::from_secs(60))
+            .unwrap();
+        assert!(points.is_empty());
+    }
+
+    #[test]
+    fn test_get_flow_anomalies() {
+        let mut db = open_memory_db();
+        let now = SystemTime::now();
+        let insert = |db: &mut Database, sci: u64, packets: u64, lost: u64, bytes: u64, span_s: u64, idle_s: u64| {
+            let mut stats = flow_stats(packets, bytes, 0);
+            stats.flow_id = FlowId::MACsec { sci, an: 0 };
+            stats.total_lost_packets = lost;
+            stats.last_timestamp = Some(now - Duration::from_secs(idle_s));
+            stats.first_timestamp = Some(now - Duration::from_secs(idle_s + span_s));
+            db.insert_flow(&stats).unwrap();
+            db.insert_statistics(&stats).unwrap();
+        };
+
+        insert(&mut db, 0x1, 900, 100, 1_250_000, 10, 0); // 10% loss, 1 Mbps
+        insert(&mut db, 0x2, 1_000, 0, 1_250, 10, 0); // 0.001 Mbps
+        insert(&mut db, 0x3, 1_000, 0, 1_250_000_000, 10, 0); // 1000 Mbps
+        insert(&mut db, 0x4, 2, 0, 200, 1, 600); // Idle for 10 minutes
+        insert(&mut db, 0x5, 1_000, 0, 1_250_000, 10, 0); // Healthy
+
+        let threshold = AnomalyThreshold {
+            min_loss_pct: 5.0,
+            max_loss_pct: 100.0,
+            min_packets: 10,
+            min_bandwidth_mbps: 0.01,
+            max_bandwidth_mbps: 100.0,
+        };
+        let anomalies = db.get_flow_anomalies(threshold).unwrap();
+        let found: Vec<(FlowId, &str)> = anomalies
+            .iter()
+            .map(|a| (a.flow.flow_id.clone(), a.anomaly_type.name()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (FlowId::MACsec { sci: 0x1, an: 0 }, "high_loss"),
+                (FlowId::MACsec { sci: 0x2, an: 0 }, "low_bandwidth"),
+                (FlowId::MACsec { sci: 0x3, an: 0 }, "high_bandwidth"),
+                (FlowId::MACsec { sci: 0x4, an: 0 }, "no_traffic"),
+            ]
+        );
+
+        match anomalies[3].anomaly_type {
+            AnomalyType::NoTraffic(idle) => {
+                assert!(idle >= Duration::from_secs(590) && idle <= Duration::from_secs(610))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(anomalies[0].flow.total_lost_packets, 100);
+
+        // Default thresholds only flag the lossy flow
+        let defaults = db.get_flow_anomalies(AnomalyThreshold::default()).unwrap();
+        assert_eq!(defaults.len(), 1);
+        assert_eq!(defaults[0].anomaly_type, AnomalyType::HighLoss);
+    }
+
+    #[test]
+    fn test_get_gaps_since() {
+        let mut db = open_memory_db();
+        insert_gap_flow(&db);
+        let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for (secs, size) in [(0, 1), (10, 2), (20, 3)] {
+            let mut g = gap(size);
+            g.timestamp = t0 + Duration::from_secs(secs);
+            db.insert_gap(&g).unwrap();
+        }
+
+        let sizes = |since| {
+            db.get_gaps_since(since)
+                .unwrap()
+                .iter()
+                .map(|g| g.gap_size)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sizes(UNIX_EPOCH), vec![1, 2, 3]);
+        assert_eq!(sizes(t0), vec![2, 3]); // Strictly after
+        assert_eq!(sizes(t0 + Duration::from_secs(15)), vec![3]);
+        assert!(sizes(t0 + Duration::from_secs(20)).is_empty());
+
+        // Timestamps round-trip through the stored format
+        let gaps = db.get_gaps_since(UNIX_EPOCH).unwrap();
+        assert_eq!(gaps[1].timestamp, t0 + Duration::from_secs(10));
+        let flow_gaps = db
+            .get_flow_gaps(&FlowId::MACsec { sci: 0x1, an: 0 }, None, None)
+            .unwrap();
+        assert_eq!(flow_gaps[0].timestamp, t0 + Duration::from_secs(20));
+    }
+
+    fn count_rows(db: &Database, table: &str) -> i64 {
+        db.conn
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_insert_flow_batch() {
+        let mut db = open_memory_db();
+        let flows: Vec<FlowStats> = (0..100)
+            .map(|sci| {
+                let mut stats = flow_stats(sci + 1, 100 * (sci + 1), 0);
+                stats.flow_id = FlowId::MACsec { sci, an: 0 };
+                stats
+            })
+            .collect();
+
+        assert_eq!(db.insert_flow_batch(&flows).unwrap(), 100);
+        assert_eq!(count_rows(&db, "flows"), 100);
+        let stored = db.get_flow(&FlowId::MACsec { sci: 42, an: 0 }).unwrap();
+        assert_eq!(stored.unwrap().packets_received, 43);
+
+        // Existing flows are replaced, not duplicated
+        assert_eq!(db.insert_flow_batch(&flows[..10]).unwrap(), 10);
+        assert_eq!(count_rows(&db, "flows"), 100);
+        assert_eq!(db.insert_flow_batch(&[]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_get_top_flows_by_metric() {
+        let mut db = open_memory_db();
+        assert!(db
+            .get_top_flows_by_metric(FlowMetric::PacketRate, 10)
+            .unwrap()
+            .is_empty());
+
+        let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        // (packets, bytes, gaps, lost, duration in seconds)
+        let flows: Vec<FlowStats> = (0..)
+            .zip([
+                (100, 1000, 3, 3, 10),
+                (50, 9000, 1, 40, 1),
+                (10, 500, 0, 0, 0),
+            ])
+            .map(|(sci, (packets, bytes, gaps, lost, secs))| {
+                let mut stats = flow_stats(packets, bytes, gaps);
+                stats.flow_id = FlowId::MACsec { sci, an: 0 };
+                stats.total_lost_packets = lost;
+                stats.first_timestamp = Some(t0);
+                stats.last_timestamp = Some(t0 + Duration::from_secs(secs));
+                stats
+            })
+            .collect();
+        db.insert_flow_batch(&flows).unwrap();
+        for stats in &flows {
+            db.insert_statistics(stats).unwrap();
+        }
+
+        let ranked = |metric| -> Vec<u64> {
+            db.get_top_flows_by_metric(metric, 10)
+                .unwrap()
+                .iter()
+                .map(|f| match f.flow_id {
+                    FlowId::MACsec { sci, .. } => sci,
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+        // Packet rate: 10/s, 50/s, 0 (single timestamp)
+        assert_eq!(ranked(FlowMetric::PacketRate), vec![1, 0, 2]);
+        // Byte rate: 100/s, 9000/s, 0
+        assert_eq!(ranked(FlowMetric::ByteRate), vec![1, 0, 2]);
+        assert_eq!(ranked(FlowMetric::GapCount), vec![0, 1, 2]);
+        // Loss: 2.9%, 44.4%, 0%
+        assert_eq!(ranked(FlowMetric::LossPercentage), vec![1, 0, 2]);
+
+        // Same order as ranking the snapshots in memory
+        for metric in [FlowMetric::PacketRate, FlowMetric::GapCount] {
+            let expected: Vec<FlowId> = crate::types::top_n_by_metric(flows.clone(), 2, metric)
+                .into_iter()
+                .map(|f| f.flow_id)
+                .collect();
+            let top: Vec<FlowId> = db
+                .get_top_flows_by_metric(metric, 2)
+                .unwrap()
+                .into_iter()
+                .map(|f| f.flow_id)
+                .collect();
+            assert_eq!(top, expected);
+        }
+        assert!(db
+            .get_top_flows_by_metric(FlowMetric::GapCount, 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_get_top_flows_by_gap_count() {
+        let mut db = open_memory_db();
+        assert!(db.get_top_flows_by_gap_count(10).unwrap().is_empty());
+
+        let flows: Vec<FlowStats> = (0..)
+            .zip([3, 0, 7, 1, 5])
+            .map(|(sci, gaps)| {
+                let mut stats = flow_stats(100, 1000, gaps);
+                stats.flow_id = FlowId::MACsec { sci, an: 0 };
+                stats
+            })
+            .collect();
+        db.insert_flow_batch(&flows).unwrap();
+
+        let top = db.get_top_flows_by_gap_count(3).unwrap();
+        let gaps: Vec<u64> = top.iter().map(|f| f.gaps_detected).collect();
+        assert_eq!(gaps, vec![7, 5, 3]);
+        assert_eq!(top[0].flow_id, FlowId::MACsec { sci: 2, an: 0 });
+        assert_eq!(db.get_top_flows_by_gap_count(100).unwrap().len(), 5);
+        assert!(db.get_top_flows_by_gap_count(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_flows_with_loss_above() {
+        let mut db = open_memory_db();
+        // (received, lost): 0%, 1%, 2%, 50%, and a flow with no packets
+        let flows: Vec<FlowStats> = (0..)
+            .zip([(100, 0), (99, 1), (98, 2), (50, 50), (0, 0)])
+            .map(|(sci, (received, lost))| {
+                let mut stats = flow_stats(received, 1000, lost.min(1));
+                stats.flow_id = FlowId::MACsec { sci, an: 0 };
+                stats.total_lost_packets = lost;
+                stats
+            })
+            .collect();
+        db.insert_flow_batch(&flows).unwrap();
+
+        let ids = |threshold: f64| -> Vec<FlowId> {
+            db.get_flows_with_loss_above(threshold)
+                .unwrap()
+                .into_iter()
+                .map(|f| f.flow_id)
+                .collect()
+        };
+        let macsec = |sci| FlowId::MACsec { sci, an: 0 };
+        assert_eq!(ids(1.5), vec![macsec(3), macsec(2)]);
+        // Strictly above: the 1% flow is excluded at 1%
+        assert_eq!(ids(1.0), vec![macsec(3), macsec(2)]);
+        assert_eq!(ids(0.0), vec![macsec(3), macsec(2), macsec(1)]);
+        assert!(ids(50.0).is_empty());
+
+        let worst = &db.get_flows_with_loss_above(10.0).unwrap()[0];
+        assert_eq!(worst.packet_loss_percent(), 50.0);
+    }
+
+    #[test]
+    fn test_delete_gaps_older_than() {
+        let mut db = open_memory_db();
+        insert_gap_flow(&db);
+        let now = SystemTime::now();
+        for hours_ago in [0, 1, 25, 48] {
+            let mut g = gap(1);
+            g.timestamp = now - Duration::from_secs(hours_ago * 3600);
+            db.insert_gap(&g).unwrap();
+        }
+
+        let deleted = db.delete_gaps_older_than(Duration::from_secs(24 * 3600)).unwrap();
+        assert_eq!(deleted, 2);
+        assert_eq!(count_rows(&db, "sequence_gaps"), 2);
+
+        // Nothing left to delete at the same cutoff
+        assert_eq!(db.delete_gaps_older_than(Duration::from_secs(24 * 3600)).unwrap(), 0);
+
+        // An age reaching past the epoch deletes nothing instead of panicking
+        assert_eq!(db.delete_gaps_older_than(Duration::MAX).unwrap(), 0);
+        assert_eq!(count_rows(&db, "sequence_gaps"), 2);
+    }
+
+    #[test]
+    fn test_delete_flows_older_than() {
+        let mut db = open_memory_db();
+        let mut old = flow_stats(10, 1_000, 1);
+        old.flow_id = FlowId::MACsec { sci: 0x1, an: 0 };
+        let mut recent = flow_stats(20, 2_000, 0);
+        recent.flow_id = FlowId::MACsec { sci: 0x2, an: 0 };
+
+        for stats in [&old, &recent] {
+            db.insert_flow(stats).unwrap();
+            db.insert_statistics(stats).unwrap();
+            db.insert_timeseries_sample(stats, SystemTime::now()).unwrap();
+        }
+        db.insert_gap(&gap(3)).unwrap(); // Belongs to the old flow
+
+        // Backdate the first flow by two days
+        db.conn
+            .execute(
+                "UPDATE flows SET updated_at = datetime('now', '-2 days') WHERE id = ?1",
+                [old.flow_id.to_compact_string()],
+            )
+            .unwrap();
+
+        assert_eq!(db.delete_flows_older_than(Duration::MAX).unwrap(), 0);
+
+        let deleted = db.delete_flows_older_than(Duration::from_secs(24 * 3600)).unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(count_rows(&db, "flows"), 1);
+        assert_eq!(count_rows(&db, "flow_statistics"), 1);
+        assert_eq!(count_rows(&db, "flow_timeseries"), 1);
+        assert_eq!(count_rows(&db, "sequence_gaps"), 0);
+        assert!(db.get_flow(&recent.flow_id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_vacuum_old_flows() {
+        let mut db = open_memory_db();
+        let flows: Vec<FlowStats> = (0..50)
+            .map(|sci| {
+                let mut stats = flow_stats(10, 1_000, 0);
+                stats.flow_id = FlowId::MACsec { sci, an: 0 };
+                stats
+            })
+            .collect();
+        db.insert_flow_batch(&flows).unwrap();
+        for stats in &flows {
+            db.insert_statistics(stats).unwrap();
+        }
+        db.insert_gap(&gap(2)).unwrap(); // Flow sci 0x1
+
+        // The first half was last updated a week ago
+        for stats in &flows[..25] {
+            db.conn
+                .execute(
+                    "UPDATE flows SET updated_at = datetime('now', '-7 days') WHERE id = ?1",
+                    [stats.flow_id.to_compact_string()],
+                )
+                .unwrap();
+        }
+
+        let day = Duration::from_secs(24 * 3600);
+        assert_eq!(db.vacuum_old_flows(day).unwrap(), 25);
+        assert_eq!(count_rows(&db, "flows"), 25);
+        assert_eq!(count_rows(&db, "flow_statistics"), 25);
+        assert_eq!(count_rows(&db, "sequence_gaps"), 0);
+        assert!(db.get_flow(&flows[25].flow_id).unwrap().is_some());
+
+        // Nothing old is left
+        assert_eq!(db.vacuum_old_flows(day).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_vacuum_and_threshold() {
+        let mut db = open_memory_db();
+        db.vacuum().unwrap();
+
+        let size = db.size_bytes().unwrap();
+        assert!(size > 0);
+        assert!(!db.auto_vacuum_threshold(u64::MAX).unwrap());
+        assert!(db.auto_vacuum_threshold(0).unwrap());
+    }
+
+    #[test]
+    fn test_get_flows_by_ids() {
+        let mut db = open_memory_db();
+        let ids: Vec<FlowId> = (1..=3).map(|sci| FlowId::MACsec { sci, an: 0 }).collect();
+        for (i, id) in ids.iter().enumerate() {
+            let mut stats = flow_stats(10 * (i as u64 + 1), 1_000, 0);
+            stats.flow_id = id.clone();
+            db.insert_flow(&stats).unwrap();
+        }
+
+        let unknown = FlowId::MACsec { sci: 0x42, an: 0 };
+        let flows = db
+            .get_flows_by_ids(&[ids[2].clone(), unknown, ids[0].clone()])
+            .unwrap();
+        assert_eq!(flows.len(), 2);
+        assert_eq!(flows[0].flow_id, ids[2]);
+        assert_eq!(flows[0].packets_received, 30);
+        assert_eq!(flows[1].flow_id, ids[0]);
+    }
+
+    #[test]
+    fn test_dscp_and_ecn_roundtrip() {
+        let mut db = open_memory_db();
+        let mut stats = flow_stats(6, 600, 0);
+        stats.dscp_distribution.insert(46, 500);
+        stats.dscp_distribution.insert(0, 100);
+        stats.ecn_congestion_signals = 7;
+        db.insert_flow(&stats).unwrap();
+        db.insert_statistics(&stats).unwrap();
+
+        let loaded = db.get_flow(&stats.flow_id).unwrap().unwrap();
+        assert_eq!(loaded.dscp_distribution, stats.dscp_distribution);
+        assert_eq!(loaded.ecn_congestion_signals, 7);
+    }
+
+    #[test]
+    fn test_protocol_version_distribution_roundtrip() {
+        let mut db = open_memory_db();
+        let mut stats = flow_stats(5, 500, 0);
+        stats.protocol_version_distribution.insert(0, 4);
+        stats.protocol_version_distribution.insert(1, 1);
+        db.insert_flow(&stats).unwrap();
+        db.insert_statistics(&stats).unwrap();
+
+        let loaded = db.get_flow(&stats.flow_id).unwrap().unwrap();
+        assert_eq!(loaded.protocol_version_distribution, stats.protocol_version_distribution);
+
+        // Re-running initialize on an existing database is a no-op
+        db.initialize().unwrap();
+        assert_eq!(db.get_flows(None, None).unwrap()[0].protocol_version_distribution.len(), 2);
+    }
+
+    #[test]
+    fn test_loss_rate_roundtrip() {
+        let mut db = open_memory_db();
+        let mut stats = flow_stats(5, 500, 2);
+        stats.loss_rate_ema = 0.125;
+        stats.loss_rate_instantaneous = 0.5;
+        db.insert_flow(&stats).unwrap();
+        db.insert_statistics(&stats).unwrap();
+
+        let loaded = db.get_flow(&stats.flow_id).unwrap().unwrap();
+        assert_eq!(loaded.loss_rate_ema, 0.125);
+        assert_eq!(loaded.loss_rate_instantaneous, 0.5);
+    }
+
+    #[test]
+    fn test_get_flow_graph_includes_every_flow() {
+        let mut db = open_memory_db();
+        assert_eq!(db.get_flow_graph().unwrap(), FlowGraph::default());
+
+        // More flows than one get_flows page, one MACsec flow that is skipped
+        let flows: Vec<FlowStats> = (0..1500u16)
+            .map(|port| l3_flow([10, 0, 0, (port % 3) as u8], port, [10, 0, 1, 1], 443, 6))
+            .chain([FlowId::MACsec { sci: 0x1, an: 0 }])
+            .map(|flow_id| {
+                let mut stats = flow_stats(1, 10, 0);
+                stats.flow_id = flow_id;
+                stats
+            })
+            .collect();
+        db.insert_flow_batch(&flows).unwrap();
+        for stats in &flows {
+            db.insert_statistics(stats).unwrap();
+        }
+
+        let graph = db.get_flow_graph().unwrap();
+        assert_eq!(graph, FlowGraph::from_stats(&flows));
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(graph.edges.iter().map(|e| e.2).sum::<u64>(), 15_000);
+    }
+
+    fn l3_flow(src: [u8; 4], src_port: u16, dst: [u8; 4], dst_port: u16, protocol: u8) -> FlowId {
+        FlowId::GenericL3 {
+            src_ip: std::net::IpAddr::from(src),
+            dst_ip: std::net::IpAddr::from(dst),
+            src_port,
+            dst_port,
+            protocol,
+        }
+    }
+
+    fn search_ids(db: &Database, query: &str) -> Vec<FlowId> {
+        let mut ids: Vec<FlowId> = db
+            .full_text_search_flows(query)
+            .unwrap()
+            .into_iter()
+            .map(|f| f.flow_id)
+            .collect();
+        ids.sort_by_key(|id| id.to_string());
+        ids
+    }
+
+    #[test]
+    fn test_full_text_search_flows() {
+        let mut db = open_memory_db();
+        let web = l3_flow([192, 168, 1, 5], 443, [10, 0, 0, 1], 50000, 6);
+        let dns = l3_flow([192, 168, 10, 2], 53, [10, 0, 0, 1], 53, 17);
+        let macsec = FlowId::MACsec { sci: 0x0011223344556677, an: 1 };
+        for id in [&web, &dns, &macsec] {
+            let mut stats = flow_stats(10, 1_000, 0);
+            stats.flow_id = id.clone();
+            db.insert_flow(&stats).unwrap();
+            // Re-inserting (upsert) must not leave stale index entries
+            db.insert_flow(&stats).unwrap();
+        }
+
+        assert_eq!(search_ids(&db, "192.168.1"), vec![web.clone()]);
+        assert_eq!(search_ids(&db, "443"), vec![web.clone()]);
+        assert_eq!(search_ids(&db, "10.0.0.1"), vec![web.clone(), dns.clone()]);
+        assert_eq!(search_ids(&db, "0x0011223344556677"), vec![macsec.clone()]);
+        assert_eq!(search_ids(&db, "udp"), vec![dns.clone()]);
+        assert!(search_ids(&db, "172.16").is_empty());
+        assert!(search_ids(&db, "  ").is_empty());
+        // FTS5 syntax in the query is searched literally, not parsed
+        assert!(search_ids(&db, "\"443 OR (").is_empty());
+
+        let full = db.full_text_search_flows("443").unwrap();
+        assert_eq!(full[0].packets_received, 10);
+
+        db.clear_all().unwrap();
+        assert!(search_ids(&db, "10.0.0.1").is_empty());
+    }
+
+    #[test]
+    fn test_full_text_search_migrates_existing_flows() {
+        let mut db = open_memory_db();
+        let mut stats = flow_stats(3, 300, 0);
+        stats.flow_id = l3_flow([172, 16, 0, 9], 8080, [10, 1, 1, 1], 9000, 6);
+        db.insert_flow(&stats).unwrap();
+
+        // Simulate a database created before the FTS index existed
+        db.conn
+            .execute_batch(
+                "DROP TRIGGER flows_fts_insert;
+                 DROP TRIGGER flows_fts_delete;
+                 DROP TRIGGER flows_fts_update;
+                 DROP TABLE flows_fts;",
+            )
+            .unwrap();
+
+        db.initialize().unwrap();
+        assert_eq!(search_ids(&db, "172.16.0.9"), vec![stats.flow_id.clone()]);
+    }
+
+    #[test]
+    fn test_initialize_migrates_display_keys() {
+        let mut db = open_memory_db();
+        // Each flow with its ID as older versions of `Display` wrote it
+        let flows = [
+            (
+                l3_flow([172, 16, 0, 9], 8080, [10, 1, 1, 1], 9000, 6),
+                "TCP { 172.16.0.9:8080 -> 10.1.1.1:9000 }",
+            ),
+            (
+                FlowId::MACsec {
+                    sci: 0x0011223344556677,
+                    an: 0,
+                },
+                "MACsec { sci: 0x0011223344556677 }",
+            ),
+            (
+                FlowId::IPsec {
+                    spi: 0x2a,
+                    dst_ip: "10.0.0.1".parse().unwrap(),
+                    mode: None,
+                },
+                "IPsec { spi: 0x0000002a, dst: 10.0.0.1 }",
+            ),
+            (
+                l3_flow(
+                    [192, 168, 1, 5],
+                    0,
+                    [192, 168, 1, 6],
+                    0,
+                    LEGACY_UNKNOWN_PROTOCOL,
+                ),
+                "Unknown { 192.168.1.5:0 -> 192.168.1.6:0 }",
+            ),
+        ];
+        for (flow_id, _) in &flows {
+            let mut stats = flow_stats(3, 300, 0);
+            stats.flow_id = flow_id.clone();
+            db.insert_flow(&stats).unwrap();
+            db.insert_statistics(&stats).unwrap();
+            db.insert_timeseries_sample(&stats, SystemTime::now())
+                .unwrap();
+            let mut lost = gap(2);
+            lost.flow_id = flow_id.clone();
+            db.insert_gap(&lost).unwrap();
+        }
+
+        // Simulate a database written with Display keys and the FTS index over id
+        db.conn
+            .execute_batch(
+                "DROP TRIGGER flows_fts_insert;
+                 DROP TRIGGER flows_fts_delete;
+                 DROP TRIGGER flows_fts_update;
+                 DROP TABLE flows_fts;
+                 CREATE VIRTUAL TABLE flows_fts
+                     USING fts5(id, content='flows', content_rowid='rowid');",
+            )
+            .unwrap();
+        let tx = db.conn.transaction().unwrap();
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON").unwrap();
+        for (flow_id, display) in &flows {
+            let compact = flow_id.to_compact_string();
+            tx.execute(
+                "UPDATE flows SET id = ?1, label = NULL WHERE id = ?2",
+                [display, &compact.as_str()],
+            )
+            .unwrap();
+            for table in ["sequence_gaps", "flow_statistics", "flow_timeseries"] {
+                tx.execute(
+                    &format!("UPDATE {} SET flow_id = ?1 WHERE flow_id = ?2", table),
+                    [display, &compact.as_str()],
+                )
+                .unwrap();
+            }
+        }
+        tx.commit().unwrap();
+
+        db.initialize().unwrap();
+        for (flow_id, _) in &flows {
+            let compact = flow_id.to_compact_string();
+            assert!(db.get_flow(flow_id).unwrap().is_some(), "{}", compact);
+            let gaps = db.get_flow_gaps(flow_id, None, None).unwrap();
+            assert_eq!(gaps.len(), 1);
+            assert!(db.get_statistics(flow_id).unwrap().is_some());
+            let samples: i64 = db
+                .conn
+                .query_row(
+                    "SELECT COUNT(*) FROM flow_timeseries WHERE flow_id = ?1",
+                    [&compact],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(samples, 1);
+        }
+        let legacy: i64 = db
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM flows WHERE id LIKE '% %'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(legacy, 0);
+        assert_eq!(search_ids(&db, "172.16.0.9"), vec![flows[0].0.clone()]);
+    }
+
+    #[test]
+    fn test_initialize_rejects_unconvertible_display_keys() {
+        let mut db = open_memory_db();
+        let mut stats = flow_stats(3, 300, 0);
+        stats.flow_id = l3_flow([172, 16, 0, 9], 8080, [10, 1, 1, 1], 9000, 6);
+        db.insert_flow(&stats).unwrap();
+        db.conn
+            .execute_batch(
+                "UPDATE flows SET id = 'TCP { 172.16.0.9:8080 -> 10.1.1.1:9000 }';
+                 INSERT INTO flows (id) VALUES ('not a flow');",
+            )
+            .unwrap();
+
+        let err = db.initialize().unwrap_err().to_string();
+        assert!(err.contains("1 flow IDs"), "{}", err);
+        assert!(err.contains("not a flow"), "{}", err);
+
+        // The convertible ID was rolled back along with the rest
+        let legacy: i64 = db
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM flows WHERE id LIKE '% %'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(legacy, 2);
+    }
+
+    #[test]
+    fn test_gap_recovery_time_roundtrip() {
+        let mut db = open_memory_db();
+        insert_gap_flow(&db);
+        let mut recovered = gap(2);
+        recovered.recovery_time = Some(Duration::from_micros(1_500));
+        db.insert_gap(&recovered).unwrap();
+        db.insert_gap(&gap(4)).unwrap();
+
+        let mut gaps = db.get_flow_gaps(&recovered.flow_id, None, None).unwrap();
+        gaps.sort_by_key(|g| g.gap_size);
+        assert_eq!(gaps[0].recovery_time, Some(Duration::from_micros(1_500)));
+        assert_eq!(gaps[1].recovery_time, None);
+    }
+}
@@ -20,10 +20,37 @@ pub enum CaptureError {
     #[error("AF_PACKET error: {0}")]
     AfPacketError(String),
 
+    #[error("XDP error: {0}")]
+    XdpError(String),
+
+    #[error("Napatech error: {0}")]
+    NapatechError(String),
+
+    #[error("pcap error: {0}")]
+    PcapError(String),
+
     #[error("Database error: {0}")]
     DatabaseError(String),
 }
 
+impl CaptureError {
+    /// Capture backend the error came from: `AF_PACKET`, `XDP`, `Napatech`,
+    /// `pcap`, `database`, or `generic` for backend-neutral errors
+    pub fn backend(&self) -> &'static str {
+        match self {
+            CaptureError::AfPacketError(_) => "AF_PACKET",
+            CaptureError::XdpError(_) | CaptureError::XdpNotAvailable(_) => "XDP",
+            CaptureError::NapatechError(_) => "Napatech",
+            CaptureError::PcapError(_) => "pcap",
+            CaptureError::DatabaseError(_) => "database",
+            CaptureError::OpenFailed(_)
+            | CaptureError::ReadFailed(_)
+            | CaptureError::NoMorePackets
+            | CaptureError::UnsupportedOperation(_) => "generic",
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ParseError {
     #[error("Packet too short for protocol")]
@@ -41,3 +68,25 @@ pub enum AnalysisError {
     #[error("Parse error: {0}")]
     Parse(#[from] ParseError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_error_backend() {
+        let cases = [
+            (CaptureError::AfPacketError("socket".into()), "AF_PACKET", "AF_PACKET error: socket"),
+            (CaptureError::XdpError("no driver".into()), "XDP", "XDP error: no driver"),
+            (CaptureError::NapatechError("NT_Init".into()), "Napatech", "Napatech error: NT_Init"),
+            (CaptureError::PcapError("eth9".into()), "pcap", "pcap error: eth9"),
+            (CaptureError::OpenFailed("x".into()), "generic", "Failed to open capture: x"),
+        ];
+
+        for (err, backend, display) in cases {
+            assert_eq!(err.backend(), backend);
+            assert_eq!(err.to_string(), display);
+        }
+        assert_eq!(CaptureError::XdpNotAvailable("macOS".into()).backend(), "XDP");
+    }
+}
@@ -28,32 +28,45 @@ pub mod config;
 
 // Re-export commonly used public API
 #[cfg(feature = "cli")]
-pub use analysis::PacketAnalyzer;
+pub use analysis::{AnalyzerStats, PacketAnalyzer};
+
+#[cfg(feature = "cli")]
+pub use analysis::correlation::{CorrelatedGap, GapCause, MultiFileAnalyzer};
 
 #[cfg(feature = "async")]
 pub use analysis::flow::FlowTracker;
 
+#[cfg(any(feature = "cli", feature = "async"))]
+pub use analysis::preprocess::{EthernetPadStripPreprocessor, PacketPreprocessor, VlanStripPreprocessor};
+
 #[cfg(feature = "cli")]
 pub use capture::FileCapture;
 
 #[cfg(any(feature = "cli", feature = "async"))]
-pub use capture::PacketSource;
+pub use capture::{PacketSource, PcapNgWriter};
 
 #[cfg(feature = "async")]
-pub use capture::AsyncPacketSource;
+pub use capture::{AsyncPacketSource, ChannelPacketSource, UdpPacketSource};
 
 #[cfg(all(feature = "async", feature = "pcap"))]
-pub use capture::PcapLiveCapture;
+pub use capture::{PcapLiveCapture, PcapLiveCaptureBuilder};
 
 #[cfg(all(target_os = "linux", feature = "async"))]
-pub use capture::{AfPacketCapture, XdpCapture};
+pub use capture::{AfPacketCapture, FanoutMode, FanoutStats, XdpCapture};
 
 #[cfg(all(target_os = "linux", feature = "napatech"))]
-pub use capture::{NapatechCapture, NapatechConfig, NapatechCaptureMode, NapatechStats};
+pub use capture::{NapatechCapture, NapatechConfig, NapatechCaptureMode, NapatechStats, TimestampMode};
 
 #[cfg(all(feature = "async", feature = "pcap"))]
 pub use capture::{ReplayCapture, ReplayMode};
 
 pub use error::{AnalysisError, CaptureError, ParseError};
-pub use protocol::{MACsecParser, SequenceParser, ProtocolRegistry, RegistryStats};
-pub use types::{AnalyzedPacket, AnalysisReport, FlowId, FlowStats, SequenceGap};
+pub use protocol::{
+    DetectionMetrics, DetectionTier, MACsecParser, ParserOrdering, ProtocolHint, SequenceParser,
+    ProtocolRegistry, RegistryStats,
+};
+pub use types::{
+    AnalyzedPacket, AnalysisReport, FlowGraph, FlowId, FlowMetric, FlowSortMetric, FlowStats,
+    FlowStatsComparator, FlowStatsSortExt, IpsecMode, ProtocolBreakdown, SequenceGap,
+    StatisticalSummary,
+};
@@ -11,14 +11,46 @@ use macsec_packet_analyzer::{
     analysis::PacketAnalyzer, capture::FileCapture, protocol::MACsecParser,
 };
 
+/// Output format selected with --format
+#[cfg(all(feature = "cli", not(feature = "async")))]
+#[derive(Clone, Copy, PartialEq)]
+enum OutputFormat {
+    /// Per-flow statistics and full gap list (default)
+    Text,
+    /// Expected vs received table, see AnalysisReport::expected_vs_actual_table
+    Table,
+    Json,
+    Csv,
+}
+
 #[cfg(all(feature = "cli", not(feature = "async")))]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Usage: macsec_packet_analyzer [pcap_file] [--format text|table|json|csv] [--summary]
+    let args: Vec<String> = env::args().skip(1).collect();
+    let summary_only = args.iter().any(|arg| arg == "--summary");
+    let format = match args.iter().position(|arg| arg == "--format") {
+        Some(idx) => match args.get(idx + 1).map(String::as_str) {
+            Some("text") => OutputFormat::Text,
+            Some("table") => OutputFormat::Table,
+            Some("json") => OutputFormat::Json,
+            Some("csv") => OutputFormat::Csv,
+            Some(other) => return Err(format!("Unknown format: {} (expected text|table|json|csv)", other).into()),
+            None => return Err("--format requires an argument".into()),
+        },
+        None => OutputFormat::Text,
+    };
     // Get pcap file path from command line or use default
-    let pcap_file = env::args()
-        .nth(1)
+    let pcap_file = args
+        .iter()
+        .enumerate()
+        .find(|(i, arg)| !arg.starts_with("--") && (*i == 0 || args[i - 1] != "--format"))
+        .map(|(_, arg)| arg.clone())
         .unwrap_or_else(|| "macsec_traffic.pcap".to_string());
 
-    println!("Analyzing MACsec packets from: {}\n", pcap_file);
+    // Machine-readable formats keep stdout clean
+    if !summary_only && (format == OutputFormat::Text || format == OutputFormat::Table) {
+        println!("Analyzing MACsec packets from: {}\n", pcap_file);
+    }
 
     // Create capture and parser
     let source = FileCapture::open(&pcap_file)?;
@@ -28,12 +60,102 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut analyzer = PacketAnalyzer::new(source, parser);
     let report = analyzer.analyze()?;
 
+    if summary_only {
+        println!("{}", report.statistical_summary());
+        return Ok(());
+    }
+
+    match format {
+        OutputFormat::Text => {}
+        OutputFormat::Table => {
+            print!("{}", report.expected_vs_actual_table());
+            return Ok(());
+        }
+        OutputFormat::Csv => {
+            print!("{}", report.gaps_csv());
+            return Ok(());
+        }
+        OutputFormat::Json => {
+            let flows: Vec<_> = report
+                .flow_stats
+                .iter()
+                .map(|s| {
+                    serde_json::json!({
+                        "flow_id": s.flow_id.to_string(),
+                        "packets_received": s.packets_received,
+                        "gaps_detected": s.gaps_detected,
+                        "total_lost_packets": s.total_lost_packets,
+                        "first_sequence": s.first_sequence,
+                        "last_sequence": s.last_sequence,
+                        "min_gap": s.min_gap,
+                        "max_gap": s.max_gap,
+                    })
+                })
+                .collect();
+            let gaps: Vec<_> = report
+                .gaps
+                .iter()
+                .map(|g| {
+                    serde_json::json!({
+                        "flow_id": g.flow_id.to_string(),
+                        "expected": g.expected,
+                        "received": g.received,
+                        "gap_size": g.gap_size,
+                        "timestamp": g
+                            .timestamp
+                            .duration_since(SystemTime::UNIX_EPOCH)
+                            .map(|d| d.as_secs_f64())
+                            .unwrap_or(0.0),
+                    })
+                })
+                .collect();
+            let protocols: serde_json::Map<String, serde_json::Value> = report
+                .protocol_breakdown
+                .iter()
+                .map(|(name, b)| {
+                    (
+                        name.clone(),
+                        serde_json::json!({
+                            "packets": b.packets,
+                            "flows": b.flows,
+                            "bytes": b.bytes,
+                            "percentage": report.packet_percentage(name),
+                        }),
+                    )
+                })
+                .collect();
+            let json = serde_json::json!({
+                "total_packets": report.total_packets,
+                "protocol_breakdown": protocols,
+                "flows": flows,
+                "gaps": gaps,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+            return Ok(());
+        }
+    }
+
     // Print analysis results
     println!("Analysis Report:");
     println!("================");
     println!("Total packets processed: {}", report.total_packets);
-    println!("Protocol: {}", report.protocol);
-    println!("Flows detected: {}\n", report.flow_stats.len());
+    println!("Flows detected: {}", report.flow_stats.len());
+    if let Some(capture) = &report.capture_stats {
+        println!("Packets dropped by capture: {}", capture.packets_dropped);
+    }
+    let mut protocols: Vec<_> = report.protocol_breakdown.iter().collect();
+    protocols.sort_by(|a, b| b.1.packets.cmp(&a.1.packets));
+    for (name, breakdown) in protocols {
+        println!(
+            "  {}: {} packets ({:.1}%), {} flows, {} bytes",
+            name,
+            breakdown.packets,
+            report.packet_percentage(name),
+            breakdown.flows,
+            breakdown.bytes
+        );
+    }
+    println!();
 
     // Print per-flow statistics
     for flow_stat in &report.flow_stats {
@@ -96,7 +218,9 @@ use crossbeam::channel::bounded;
 
 #[cfg(all(feature = "cli", feature = "async"))]
 use macsec_packet_analyzer::{
-    analysis::flow::FlowTracker, protocol::{MACsecParser, SequenceParser},
+    analysis::flow::FlowTracker,
+    capture::{AsyncPacketSource, ChannelPacketSource},
+    protocol::{MACsecParser, SequenceParser},
     types::{AnalyzedPacket, RawPacket},
 };
 
@@ -110,7 +234,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Starting continuous packet analysis from: {}\n", pcap_file);
 
     // Three-stage pipeline with bounded channels (backpressure)
-    let (_raw_tx, raw_rx) = bounded::<RawPacket>(10000); // Capture → Parser
+    let (raw_tx, raw_rx) = bounded::<RawPacket>(10000); // Capture → Parser
     let (analyzed_tx, analyzed_rx) = bounded::<AnalyzedPacket>(10000); // Parser → Analyzer
 
     // Shared flow tracker (concurrent access via DashMap)
@@ -122,20 +246,34 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // For now, placeholder
         println!("Capture thread started (placeholder)");
         // Future: while let Some(packet) = source.next_packet().await { raw_tx.send(packet); }
+
+        // Dropping the sender ends the parser stage once the channel drains
+        drop(raw_tx);
     });
 
     // Stage 2: Parser thread (packet transformer)
     let parser_handle = tokio::spawn(async move {
         let parser = MACsecParser;
+        let mut source = ChannelPacketSource::new(raw_rx);
         let mut parsed_count = 0u64;
 
-        while let Ok(raw_packet) = raw_rx.recv() {
+        loop {
+            let raw_packet = match source.next_packet().await {
+                Ok(Some(packet)) => packet,
+                Ok(None) => continue, // Nothing yet, poll again
+                Err(_) => break,      // Capture stage finished
+            };
+
             if let Ok(Some(seq_info)) = parser.parse_sequence(&raw_packet.data) {
                 let analyzed = AnalyzedPacket {
                     sequence_number: seq_info.sequence_number,
                     flow_id: seq_info.flow_id,
                     timestamp: raw_packet.timestamp,
                     payload_length: seq_info.payload_length,
+                    proto_version: seq_info.proto_version,
+                    dscp: seq_info.dscp,
+                    ecn: seq_info.ecn,
+                    raw_data: None,
                 };
 
                 if analyzed_tx.send(analyzed).is_err() {
@@ -9,6 +9,7 @@ use crate::db::Database;
 use crate::error::CaptureError;
 use crate::types::{FlowStats, SequenceGap};
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 /// Persistence manager for syncing analysis results to database
 #[derive(Clone)]
@@ -31,10 +32,12 @@ impl PersistenceManager {
 
         // Get all flow stats and persist them
         let stats = tracker.get_stats();
+        let sampled_at = SystemTime::now();
         for flow_stat in stats {
             db.insert_flow(&flow_stat)?;
             // Also persist enhanced statistics
             db.insert_statistics(&flow_stat)?;
+            db.insert_timeseries_sample(&flow_stat, sampled_at)?;
         }
 
         // Get all gaps and persist them
@@ -83,10 +86,12 @@ impl PersistenceManager {
         })?;
 
         // Persist all flow statistics
+        let sampled_at = SystemTime::now();
         for flow_stat in stats {
             db.insert_flow(&flow_stat)?;
             // Also persist enhanced statistics
             db.insert_statistics(&flow_stat)?;
+            db.insert_timeseries_sample(&flow_stat, sampled_at)?;
         }
 
         // Persist all gaps
@@ -102,6 +107,8 @@ impl PersistenceManager {
 mod tests {
     use super::*;
     use crate::db::DatabaseConfig;
+    use crate::types::{AnalyzedPacket, FlowId};
+    use std::time::Duration;
 
     #[test]
     fn test_create_persistence_manager() -> Result<(), CaptureError> {
@@ -110,4 +117,45 @@ mod tests {
         let _manager = PersistenceManager::new(db);
         Ok(())
     }
+
+    fn packet(seq: u32, flow_id: FlowId) -> AnalyzedPacket {
+        AnalyzedPacket {
+            sequence_number: seq,
+            flow_id,
+            timestamp: SystemTime::now(),
+            payload_length: 100,
+            proto_version: None,
+            dscp: None,
+            ecn: None,
+            raw_data: None,
+        }
+    }
+
+    #[test]
+    #[allow(unused_mut)] // process_packet takes &mut self without the async feature
+    fn test_persist_flows_twice_keeps_history() -> Result<(), CaptureError> {
+        let mut db = Database::open(&DatabaseConfig::sqlite(":memory:"))?;
+        db.initialize()?;
+        let db = Arc::new(Mutex::new(db));
+        let manager = PersistenceManager::new(Arc::clone(&db));
+
+        let flow = FlowId::MACsec { sci: 0x1, an: 0 };
+        let mut tracker = FlowTracker::new();
+        tracker.process_packet(packet(1, flow.clone()));
+        tracker.process_packet(packet(3, flow.clone())); // One lost packet
+        manager.persist_flows(&tracker)?;
+
+        // Samples are bucketed by whole seconds
+        std::thread::sleep(Duration::from_millis(1100));
+        tracker.process_packet(packet(4, flow.clone()));
+        manager.persist_flows(&tracker)?;
+
+        let db = db.lock().unwrap();
+        let points = db.get_flow_metrics_timeseries(&flow, Duration::from_secs(1))?;
+        assert!(!points.is_empty());
+        assert!(db.get_statistics(&flow)?.is_some());
+        assert!(!db.get_flow_gaps(&flow, None, None)?.is_empty());
+        assert_eq!(db.get_flow(&flow)?.unwrap().packets_received, 3);
+        Ok(())
+    }
 }
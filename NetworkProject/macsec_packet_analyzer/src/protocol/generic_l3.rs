@@ -22,14 +22,21 @@ use super::parser::SequenceParser;
 ///
 /// Packet structure:
 /// - Ethernet (14 bytes)
-/// - IPv4 header (20+ bytes)
+/// - IPv4 header (20+ bytes, longer when IP options are present)
 /// - TCP/UDP header
+///
+/// IP fragments are skipped (`Ok(None)`): only the first fragment carries the
+/// transport header, so ports can't be extracted reliably.
 pub struct GenericL3Parser;
 
 // IP protocol numbers
 const IP_PROTOCOL_TCP: u8 = 6;
 const IP_PROTOCOL_UDP: u8 = 17;
 
+// IPv4 flags/fragment offset field (bytes 6-7 of the IP header)
+const IP_FLAG_MORE_FRAGMENTS: u8 = 0x20;
+const IP_FRAGMENT_OFFSET_MASK: u16 = 0x1FFF;
+
 impl SequenceParser for GenericL3Parser {
     fn parse_sequence(&self, data: &[u8]) -> Result<Option<SequenceInfo>, ParseError> {
         // Generic L3 flows: Extract 5-tuple for flow identification
@@ -52,11 +59,26 @@ impl SequenceParser for GenericL3Parser {
         let ihl = (data[14] & 0x0f) as usize * 4;
         let ip_header_end = 14 + ihl;
 
+        // IHL below 5 words is not a valid IPv4 header
+        if ihl < 20 {
+            return Err(ParseError::InvalidFormat(format!(
+                "IPv4 IHL too small: {} bytes",
+                ihl
+            )));
+        }
+
         // Check we have enough data for the IP header
         if data.len() < ip_header_end {
             return Err(ParseError::PacketTooShort);
         }
 
+        // Skip fragments: MF flag set, or non-zero fragment offset
+        let ip_flags = data[20];
+        let fragment_offset = u16::from_be_bytes([data[20], data[21]]) & IP_FRAGMENT_OFFSET_MASK;
+        if ip_flags & IP_FLAG_MORE_FRAGMENTS != 0 || fragment_offset > 0 {
+            return Ok(None);
+        }
+
         // Extract IP protocol type
         let protocol = data[23];
 
@@ -116,6 +138,9 @@ impl SequenceParser for GenericL3Parser {
                 protocol,
             },
             payload_length,
+            proto_version: Some(data[14] >> 4), // IPv4 version field
+            dscp: Some(data[15] >> 2),          // ToS bits 7-2
+            ecn: Some(data[15] & 0x03),         // ToS bits 1-0
         }))
     }
 
@@ -136,6 +161,11 @@ impl SequenceParser for GenericL3Parser {
         protocol == IP_PROTOCOL_TCP || protocol == IP_PROTOCOL_UDP
     }
 
+    fn matches_cost(&self, _data: &[u8]) -> u32 {
+        // EtherType + IP protocol byte
+        2
+    }
+
     fn protocol_name(&self) -> &str {
         "Generic-L3"
     }
@@ -330,4 +360,90 @@ mod tests {
 
         assert!(!parser.matches(&packet));
     }
+
+    /// Rewrite a standard packet's IPv4 header to carry `options` (multiple of 4 bytes)
+    fn with_ip_options(packet: &[u8], options: &[u8]) -> Vec<u8> {
+        assert_eq!(options.len() % 4, 0);
+        let mut result = packet[..34].to_vec();
+        result[14] = 0x40 | (5 + options.len() / 4) as u8; // Version 4, larger IHL
+        result.extend_from_slice(options);
+        result.extend_from_slice(&packet[34..]);
+        result
+    }
+
+    #[test]
+    fn test_generic_l3_parser_ipv4_options() {
+        let parser = GenericL3Parser;
+        let base = create_udp_packet([192, 168, 1, 10], [10, 0, 0, 1], 5000, 6000);
+        // Router Alert option (4 bytes) + 4 NOPs: IHL = 7
+        let packet = with_ip_options(&base, &[0x94, 0x04, 0x00, 0x00, 0x01, 0x01, 0x01, 0x01]);
+
+        let seq_info = parser.parse_sequence(&packet).unwrap().unwrap();
+        match seq_info.flow_id {
+            FlowId::GenericL3 { src_ip, dst_ip, src_port, dst_port, protocol } => {
+                assert_eq!(src_ip, IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10)));
+                assert_eq!(dst_ip, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+                assert_eq!(src_port, 5000);
+                assert_eq!(dst_port, 6000);
+                assert_eq!(protocol, IP_PROTOCOL_UDP);
+            }
+            _ => panic!("Expected GenericL3 flow"),
+        }
+        assert_eq!(seq_info.payload_length, 10);
+    }
+
+    #[test]
+    fn test_generic_l3_parser_skips_first_fragment() {
+        let parser = GenericL3Parser;
+        let mut packet = create_udp_packet([192, 168, 1, 10], [10, 0, 0, 1], 5000, 6000);
+        packet[20] = 0x20; // MF set, offset 0
+
+        assert!(parser.parse_sequence(&packet).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_generic_l3_parser_skips_later_fragment() {
+        let parser = GenericL3Parser;
+        let mut packet = create_tcp_packet([192, 168, 1, 10], [10, 0, 0, 1], 12345, 80, 1000);
+        packet[20] = 0x00;
+        packet[21] = 0xB9; // Last fragment, offset 185 (1480 bytes)
+
+        assert!(parser.parse_sequence(&packet).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_generic_l3_parser_dont_fragment_is_parsed() {
+        let parser = GenericL3Parser;
+        let mut packet = create_tcp_packet([192, 168, 1, 10], [10, 0, 0, 1], 12345, 80, 1000);
+        packet[20] = 0x40; // DF set
+
+        let seq_info = parser.parse_sequence(&packet).unwrap().unwrap();
+        assert_eq!(seq_info.proto_version, Some(4));
+    }
+
+    #[test]
+    fn test_generic_l3_parser_dscp_ecn() {
+        let parser = GenericL3Parser;
+        // (ToS byte, DSCP, ECN): EF with CE, AF41 with ECT(0), BE not ECN-capable
+        for (tos, dscp, ecn) in [(0xBB, 46, 0b11), (0x8A, 34, 0b10), (0x00, 0, 0b00)] {
+            let mut packet = create_tcp_packet([192, 168, 1, 10], [10, 0, 0, 1], 12345, 80, 1000);
+            packet[15] = tos;
+
+            let seq_info = parser.parse_sequence(&packet).unwrap().unwrap();
+            assert_eq!(seq_info.dscp, Some(dscp));
+            assert_eq!(seq_info.ecn, Some(ecn));
+        }
+    }
+
+    #[test]
+    fn test_generic_l3_parser_invalid_ihl() {
+        let parser = GenericL3Parser;
+        let mut packet = create_tcp_packet([192, 168, 1, 10], [10, 0, 0, 1], 12345, 80, 1000);
+        packet[14] = 0x44; // IHL 4 (16 bytes) is invalid
+
+        assert!(matches!(
+            parser.parse_sequence(&packet),
+            Err(ParseError::InvalidFormat(_))
+        ));
+    }
 }
@@ -0,0 +1,112 @@
+//! Protocol hints for captures with a known protocol
+//!
+//! When a capture is known to carry a single protocol, running the
+//! `ProtocolRegistry` detection path (EtherType check, cache lookup, parser
+//! loop) on every packet is wasted work. A `ProtocolHint` with a forced parser
+//! skips the registry and calls that parser directly.
+
+use crate::error::ParseError;
+use crate::protocol::{GenericL3Parser, IPsecParser, MACsecParser, ProtocolRegistry, SequenceParser};
+use crate::types::SequenceInfo;
+
+/// Optional forced parser that bypasses automatic protocol detection
+#[derive(Default)]
+pub struct ProtocolHint {
+    pub forced_parser: Option<Box<dyn SequenceParser + Send + Sync>>,
+}
+
+impl ProtocolHint {
+    /// No hint: use automatic detection
+    pub fn auto() -> Self {
+        Self { forced_parser: None }
+    }
+
+    /// Always parse with `parser`
+    pub fn forced(parser: Box<dyn SequenceParser + Send + Sync>) -> Self {
+        Self {
+            forced_parser: Some(parser),
+        }
+    }
+
+    /// Build a hint from a CLI protocol name: `macsec`, `ipsec`, `generic` or `auto`
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "auto" => Ok(Self::auto()),
+            "macsec" => Ok(Self::forced(Box::new(MACsecParser))),
+            "ipsec" => Ok(Self::forced(Box::new(IPsecParser::new()))),
+            "generic" => Ok(Self::forced(Box::new(GenericL3Parser))),
+            other => Err(format!(
+                "Unknown protocol '{}' (expected macsec|ipsec|generic|auto)",
+                other
+            )),
+        }
+    }
+
+    /// Whether a parser is forced
+    pub fn is_forced(&self) -> bool {
+        self.forced_parser.is_some()
+    }
+
+    /// Protocol name of the forced parser, or "auto"
+    pub fn protocol_name(&self) -> &str {
+        self.forced_parser
+            .as_ref()
+            .map(|p| p.protocol_name())
+            .unwrap_or("auto")
+    }
+
+    /// Parse with the forced parser, or fall back to registry detection
+    pub fn parse(
+        &self,
+        registry: &ProtocolRegistry,
+        data: &[u8],
+    ) -> Result<Option<SequenceInfo>, ParseError> {
+        match &self.forced_parser {
+            Some(parser) => parser.parse_sequence(data),
+            None => registry.detect_and_parse(data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert!(!ProtocolHint::from_name("auto").unwrap().is_forced());
+        assert_eq!(ProtocolHint::from_name("macsec").unwrap().protocol_name(), "MACsec");
+        assert_eq!(ProtocolHint::from_name("IPsec").unwrap().protocol_name(), "IPsec-ESP");
+        assert_eq!(ProtocolHint::from_name("generic").unwrap().protocol_name(), "Generic-L3");
+        assert!(ProtocolHint::from_name("quic").is_err());
+    }
+
+    #[test]
+    fn test_forced_parser_bypasses_registry() {
+        let registry = ProtocolRegistry::new();
+        let hint = ProtocolHint::from_name("macsec").unwrap();
+
+        let mut packet = vec![0u8; 30];
+        packet[12] = 0x88;
+        packet[13] = 0xE5;
+        let _ = hint.parse(&registry, &packet);
+
+        // The registry never saw the packet
+        let stats = registry.get_stats();
+        assert_eq!(stats.ethertype_fast_path, 0);
+        assert_eq!(stats.cache_misses, 0);
+    }
+
+    #[test]
+    fn test_auto_uses_registry() {
+        let registry = ProtocolRegistry::new();
+        let hint = ProtocolHint::auto();
+
+        let mut packet = vec![0u8; 30];
+        packet[12] = 0x88;
+        packet[13] = 0xE5;
+        let _ = hint.parse(&registry, &packet);
+
+        assert_eq!(registry.get_stats().ethertype_fast_path, 1);
+    }
+}
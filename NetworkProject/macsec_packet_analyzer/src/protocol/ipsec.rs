@@ -1,8 +1,13 @@
 use std::net::IpAddr;
+use std::sync::Arc;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 
 use crate::error::ParseError;
 use crate::types::{FlowId, SequenceInfo};
 use super::parser::SequenceParser;
+use super::sad::{IpsecSaDatabase, ICV_LEN, IP_PROTOCOL_ESP};
 
 /// IPsec ESP (Encapsulating Security Payload) packet parser
 /// Extracts sequence numbers from ESP header
@@ -16,10 +21,44 @@ use super::parser::SequenceParser;
 ///   - Encrypted payload
 ///   - ESP trailer (variable)
 ///   - ICV (Integrity Check Value, 12-32 bytes)
-pub struct IPsecParser;
+///
+/// Without an SA database every ESP packet is parsed and the flow's mode is
+/// unknown. With one (`with_sad`), packets for unknown SPIs are skipped, the
+/// flow carries the SA's mode, and the ICV is checked as HMAC-SHA-256-128
+/// when the SA has a key.
+#[derive(Clone, Default)]
+pub struct IPsecParser {
+    sad: Option<Arc<IpsecSaDatabase>>,
+}
+
+impl IPsecParser {
+    /// Parser without SA lookups
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// SA-aware parser backed by `sad`
+    pub fn with_sad(sad: Arc<IpsecSaDatabase>) -> Self {
+        Self { sad: Some(sad) }
+    }
+}
+
+/// Check the trailing HMAC-SHA-256-128 ICV of an ESP packet
+///
+/// `esp` runs from the SPI to the end of the ICV; the MAC covers everything
+/// before the ICV.
+fn icv_is_valid(esp: &[u8], key: &[u8; 32]) -> bool {
+    if esp.len() < 8 + ICV_LEN {
+        return false;
+    }
+    let (authenticated, icv) = esp.split_at(esp.len() - ICV_LEN);
 
-// ESP protocol number in IP header
-const IP_PROTOCOL_ESP: u8 = 50;
+    let Ok(mut mac) = <Hmac<Sha256> as Mac>::new_from_slice(key) else {
+        return false;
+    };
+    mac.update(authenticated);
+    mac.verify_truncated_left(icv).is_ok()
+}
 
 impl SequenceParser for IPsecParser {
     fn parse_sequence(&self, data: &[u8]) -> Result<Option<SequenceInfo>, ParseError> {
@@ -77,14 +116,37 @@ impl SequenceParser for IPsecParser {
             esp_payload[7],
         ]);
 
+        // SA lookup: skip traffic for unknown SAs, verify the ICV if keyed
+        let mut mode = None;
+        if let Some(sad) = &self.sad {
+            let Some(sa) = sad.get(spi) else {
+                return Ok(None);
+            };
+            if sa.dst_ip != dst_ip || sa.protocol != IP_PROTOCOL_ESP {
+                return Ok(None);
+            }
+            if let Some(key) = &sa.key {
+                if !icv_is_valid(esp_payload, key) {
+                    return Err(ParseError::InvalidFormat(format!(
+                        "ESP ICV mismatch for SPI 0x{:08x}",
+                        spi
+                    )));
+                }
+            }
+            mode = Some(sa.mode);
+        }
+
         // Calculate payload length (encrypted portion + trailer + ICV)
         // This is everything after the 8-byte ESP header
         let payload_length = esp_payload.len() - 8;
 
         Ok(Some(SequenceInfo {
             sequence_number,
-            flow_id: FlowId::IPsec { spi, dst_ip },
+            flow_id: FlowId::IPsec { spi, dst_ip, mode },
             payload_length,
+            proto_version: None, // ESP has no version field
+            dscp: None,          // Outer header QoS is not tracked for ESP
+            ecn: None,
         }))
     }
 
@@ -108,6 +170,11 @@ impl SequenceParser for IPsecParser {
         data[23] == IP_PROTOCOL_ESP
     }
 
+    fn matches_cost(&self, _data: &[u8]) -> u32 {
+        // EtherType + IP protocol + minimum size check
+        3
+    }
+
     fn protocol_name(&self) -> &str {
         "IPsec-ESP"
     }
@@ -116,6 +183,7 @@ impl SequenceParser for IPsecParser {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::IpsecMode;
     use std::net::Ipv4Addr;
 
     /// Helper to create minimal valid ESP packet
@@ -152,7 +220,7 @@ mod tests {
 
     #[test]
     fn test_ipsec_parser_valid_packet() {
-        let parser = IPsecParser;
+        let parser = IPsecParser::new();
         let packet = create_esp_packet(0x12345678, 42, [10, 0, 0, 1]);
 
         let result = parser.parse_sequence(&packet).unwrap();
@@ -162,9 +230,10 @@ mod tests {
         assert_eq!(seq_info.sequence_number, 42);
 
         match seq_info.flow_id {
-            FlowId::IPsec { spi, dst_ip } => {
+            FlowId::IPsec { spi, dst_ip, mode } => {
                 assert_eq!(spi, 0x12345678);
                 assert_eq!(dst_ip, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+                assert_eq!(mode, None);
             }
             _ => panic!("Expected IPsec flow ID"),
         }
@@ -172,7 +241,7 @@ mod tests {
 
     #[test]
     fn test_ipsec_parser_wrong_protocol() {
-        let parser = IPsecParser;
+        let parser = IPsecParser::new();
         let mut packet = create_esp_packet(0x12345678, 42, [10, 0, 0, 1]);
 
         // Change IP protocol from ESP (50) to TCP (6)
@@ -184,7 +253,7 @@ mod tests {
 
     #[test]
     fn test_ipsec_parser_too_short() {
-        let parser = IPsecParser;
+        let parser = IPsecParser::new();
         let packet = vec![0u8; 20]; // Too short
 
         let result = parser.parse_sequence(&packet).unwrap();
@@ -193,7 +262,7 @@ mod tests {
 
     #[test]
     fn test_ipsec_matches() {
-        let parser = IPsecParser;
+        let parser = IPsecParser::new();
         let packet = create_esp_packet(0x12345678, 42, [10, 0, 0, 1]);
 
         assert!(parser.matches(&packet));
@@ -201,7 +270,7 @@ mod tests {
 
     #[test]
     fn test_ipsec_sequence_wraparound() {
-        let parser = IPsecParser;
+        let parser = IPsecParser::new();
         let packet = create_esp_packet(0xAABBCCDD, u32::MAX, [172, 16, 0, 1]);
 
         let result = parser.parse_sequence(&packet).unwrap();
@@ -213,7 +282,7 @@ mod tests {
 
     #[test]
     fn test_ipsec_parser_multiple_flows() {
-        let parser = IPsecParser;
+        let parser = IPsecParser::new();
 
         // Create two packets with different SPIs
         let packet1 = create_esp_packet(0x11111111, 100, [10, 0, 0, 1]);
@@ -236,7 +305,7 @@ mod tests {
 
     #[test]
     fn test_ipsec_payload_length() {
-        let parser = IPsecParser;
+        let parser = IPsecParser::new();
         let packet = create_esp_packet(0x12345678, 42, [10, 0, 0, 1]);
 
         let result = parser.parse_sequence(&packet).unwrap().unwrap();
@@ -246,7 +315,7 @@ mod tests {
 
     #[test]
     fn test_ipsec_wrong_ethertype() {
-        let parser = IPsecParser;
+        let parser = IPsecParser::new();
         let mut packet = create_esp_packet(0x12345678, 42, [10, 0, 0, 1]);
 
         // Change EtherType to IPv6 (0x86DD)
@@ -255,4 +324,73 @@ mod tests {
 
         assert!(!parser.matches(&packet));
     }
+
+    fn sa(spi: u32, dst_ip: [u8; 4], key: Option<[u8; 32]>) -> crate::protocol::SecurityAssociation {
+        crate::protocol::SecurityAssociation {
+            spi,
+            dst_ip: IpAddr::V4(Ipv4Addr::from(dst_ip)),
+            protocol: IP_PROTOCOL_ESP,
+            mode: IpsecMode::Tunnel,
+            key,
+            replay_window: 64,
+        }
+    }
+
+    /// Overwrite the trailing 16 bytes with a valid HMAC-SHA-256-128 ICV
+    fn sign(mut packet: Vec<u8>, key: &[u8; 32]) -> Vec<u8> {
+        let icv_start = packet.len() - ICV_LEN;
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).unwrap();
+        mac.update(&packet[34..icv_start]); // ESP header + payload
+        let tag = mac.finalize().into_bytes();
+        packet[icv_start..].copy_from_slice(&tag[..ICV_LEN]);
+        packet
+    }
+
+    #[test]
+    fn test_ipsec_sad_unknown_spi_skipped() {
+        let mut sad = IpsecSaDatabase::new();
+        sad.insert(sa(0x1000, [10, 0, 0, 1], None));
+        let parser = IPsecParser::with_sad(Arc::new(sad));
+
+        let known = create_esp_packet(0x1000, 1, [10, 0, 0, 1]);
+        let seq_info = parser.parse_sequence(&known).unwrap().unwrap();
+        match seq_info.flow_id {
+            FlowId::IPsec { spi, mode, .. } => {
+                assert_eq!(spi, 0x1000);
+                assert_eq!(mode, Some(IpsecMode::Tunnel));
+            }
+            _ => panic!("Expected IPsec flow ID"),
+        }
+
+        let unknown = create_esp_packet(0x2000, 1, [10, 0, 0, 1]);
+        assert!(parser.parse_sequence(&unknown).unwrap().is_none());
+
+        // Right SPI, wrong tunnel endpoint
+        let wrong_dst = create_esp_packet(0x1000, 1, [10, 0, 0, 2]);
+        assert!(parser.parse_sequence(&wrong_dst).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_ipsec_sad_validates_icv() {
+        let key = [0x5A; 32];
+        let mut sad = IpsecSaDatabase::new();
+        sad.insert(sa(0x1000, [10, 0, 0, 1], Some(key)));
+        let parser = IPsecParser::with_sad(Arc::new(sad));
+
+        let signed = sign(create_esp_packet(0x1000, 7, [10, 0, 0, 1]), &key);
+        let seq_info = parser.parse_sequence(&signed).unwrap().unwrap();
+        assert_eq!(seq_info.sequence_number, 7);
+
+        // Tampered sequence number breaks the ICV
+        let mut tampered = signed.clone();
+        tampered[41] ^= 0x01;
+        assert!(matches!(
+            parser.parse_sequence(&tampered),
+            Err(ParseError::InvalidFormat(_))
+        ));
+
+        // Signed with a different key
+        let wrong_key = sign(create_esp_packet(0x1000, 7, [10, 0, 0, 1]), &[0xA5; 32]);
+        assert!(parser.parse_sequence(&wrong_key).is_err());
+    }
 }
@@ -26,13 +26,19 @@ impl SequenceParser for MACsecParser {
         // Bytes 0-5:     Destination MAC
         // Bytes 6-11:    Source MAC
         // Bytes 12-13:   EtherType (0x88E5 for MACsec)
-        // Bytes 14:      TCI/AN flags
+        // Bytes 14:      TCI/AN flags (AN = bits 0-1, changes on key rotation)
         // Bytes 15:      Short Length
         // Bytes 16-19:   Packet Number (4 bytes, big-endian) ← TARGET FIELD
         // Bytes 20-27:   SCI (8 bytes, big-endian) ← FLOW IDENTIFIER
         // Bytes 28+:     Encrypted Payload
         // Last 16:       ICV (Integrity Check Value)
 
+        // Extract Association Number from the low 2 bits of the TCI/AN byte
+        let an = data[14] & 0x03;
+
+        // Version (V) is the top bit of the TCI/AN byte; 0 in current MACsec
+        let version = data[14] >> 7;
+
         // Extract packet number at offset 16-19 (4 bytes, big-endian)
         let packet_number = BigEndian::read_u32(&data[16..20]);
 
@@ -49,8 +55,11 @@ impl SequenceParser for MACsecParser {
 
         Ok(Some(SequenceInfo {
             sequence_number: packet_number,
-            flow_id: FlowId::MACsec { sci },
+            flow_id: FlowId::MACsec { sci, an },
             payload_length,
+            proto_version: Some(version),
+            dscp: None,
+            ecn: None,
         }))
     }
 
@@ -64,6 +73,11 @@ impl SequenceParser for MACsecParser {
         data[12] == 0x88 && data[13] == 0xE5
     }
 
+    fn matches_cost(&self, _data: &[u8]) -> u32 {
+        // EtherType only (bytes 12-13)
+        1
+    }
+
     fn protocol_name(&self) -> &str {
         "MACsec"
     }
@@ -102,7 +116,14 @@ mod tests {
         assert!(result.is_some());
         let seq_info = result.unwrap();
         assert_eq!(seq_info.sequence_number, 123);
-        assert!(matches!(seq_info.flow_id, FlowId::MACsec { sci: 0x001122334455AABB }));
+        assert!(matches!(seq_info.flow_id, FlowId::MACsec { sci: 0x001122334455AABB, an: 0 }));
+        assert_eq!(seq_info.proto_version, Some(0));
+
+        // V bit set, AN 2: version and AN are read independently
+        packet[14] = 0x82;
+        let seq_info = parser.parse_sequence(&packet).unwrap().unwrap();
+        assert_eq!(seq_info.proto_version, Some(1));
+        assert!(matches!(seq_info.flow_id, FlowId::MACsec { an: 2, .. }));
     }
 
     #[test]
@@ -146,4 +167,25 @@ mod tests {
         let seq_info = result.unwrap();
         assert_eq!(seq_info.sequence_number, 42);
     }
+
+    #[test]
+    fn test_macsec_parser_extracts_association_number() {
+        let mut packet = vec![0u8; 45];
+        packet[12] = 0x88;
+        packet[13] = 0xE5;
+        BigEndian::write_u64(&mut packet[20..28], 0x1122334455660001);
+
+        let parser = MACsecParser;
+
+        // TCI bits set alongside AN=0 must not leak into the AN
+        packet[14] = 0x2C;
+        let an0 = parser.parse_sequence(&packet).unwrap().unwrap();
+
+        packet[14] = 0x2D; // same TCI, AN=1
+        let an1 = parser.parse_sequence(&packet).unwrap().unwrap();
+
+        assert_eq!(an0.flow_id, FlowId::MACsec { sci: 0x1122334455660001, an: 0 });
+        assert_eq!(an1.flow_id, FlowId::MACsec { sci: 0x1122334455660001, an: 1 });
+        assert_ne!(an0.flow_id, an1.flow_id);
+    }
 }
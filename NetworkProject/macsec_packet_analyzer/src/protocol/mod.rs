@@ -1,11 +1,17 @@
 pub mod parser;
 pub mod macsec;
 pub mod ipsec;
+pub mod sad;
 pub mod generic_l3;
 pub mod registry;
+pub mod hint;
 
 pub use parser::SequenceParser;
 pub use macsec::MACsecParser;
 pub use ipsec::IPsecParser;
+pub use sad::{IpsecSaDatabase, SecurityAssociation};
 pub use generic_l3::GenericL3Parser;
-pub use registry::{ProtocolRegistry, RegistryStats};
+pub use registry::{
+    DetectionMetrics, DetectionTier, ParserOrdering, ProtocolRegistry, RegistryStats,
+};
+pub use hint::ProtocolHint;
@@ -12,6 +12,15 @@ pub trait SequenceParser {
     /// Check if packet matches this protocol (quick check before full parsing)
     fn matches(&self, data: &[u8]) -> bool;
 
+    /// Relative cost of calling `matches()` (number of header fields inspected)
+    ///
+    /// Used by `ProtocolRegistry` to try cheap, high-priority parsers first.
+    /// The registry calls this once at construction with an empty slice, so
+    /// implementations should return a static estimate rather than inspect `data`.
+    fn matches_cost(&self, _data: &[u8]) -> u32 {
+        1
+    }
+
     /// Get the name of this protocol for reporting
     fn protocol_name(&self) -> &str;
 }
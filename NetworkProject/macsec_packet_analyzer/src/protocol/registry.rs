@@ -10,14 +10,16 @@
 use crate::error::ParseError;
 use crate::protocol::SequenceParser;
 use crate::types::{FlowId, SequenceInfo};
+use std::fmt;
+use std::fmt::Write as _;
 use std::net::IpAddr;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 #[cfg(feature = "async")]
 use dashmap::DashMap;
 
-#[cfg(not(feature = "async"))]
 use std::collections::HashMap;
 
 #[cfg(not(feature = "async"))]
@@ -27,13 +29,32 @@ use std::sync::Mutex;
 struct ParserEntry {
     parser: Box<dyn SequenceParser + Send + Sync>,
     priority: u8,
+    cost: u32,
     name: String,
+    /// Set by `remove_parser()`; the entry is skipped until the next
+    /// `register_parser()` drops it from the list
+    removed: AtomicBool,
+}
+
+impl ParserEntry {
+    fn is_removed(&self) -> bool {
+        self.removed.load(Ordering::Relaxed)
+    }
+}
+
+/// How the registry orders parsers for full detection
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParserOrdering {
+    /// Descending priority only
+    Priority,
+    /// Descending `priority / matches_cost` ratio (default)
+    PriorityPerCost,
 }
 
 /// Protocol registry with automatic detection and flow-level caching
 ///
 /// Detects protocols by trying parsers in priority order and caches results per-flow.
-/// Supports extensibility via `add_parser()` for custom protocols.
+/// Supports extensibility via `register_parser()` for custom protocols.
 ///
 /// # Performance
 ///
@@ -51,8 +72,16 @@ struct ParserEntry {
 /// }
 /// ```
 pub struct ProtocolRegistry {
-    /// Parsers sorted by priority (highest first)
+    /// Parsers sorted by `ordering` (best first)
     parsers: Vec<ParserEntry>,
+    ordering: ParserOrdering,
+
+    /// Index of the MACsec parser used by the EtherType fast path
+    macsec_idx: Option<usize>,
+
+    /// Set once a parser is added with `register_parser()`; lets frames
+    /// with EtherTypes other than IPv4 reach full detection
+    has_custom_parsers: bool,
 
     /// Flow-level cache: FlowId -> parser index
     /// Maps detected flows to the parser that worked for them
@@ -79,13 +108,131 @@ pub struct RegistryStats {
     pub cache_size: usize,
 }
 
+/// Detection tier that produced a `detect_and_parse` result
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DetectionTier {
+    /// Decided by EtherType alone: MACsec, or rejected as unsupported
+    EtherTypeFastPath,
+    /// Parsed with the parser cached for the flow
+    FlowCacheHit,
+    /// Every parser tried in order
+    FullDetection,
+}
+
+impl DetectionTier {
+    const ALL: [DetectionTier; 3] = [
+        DetectionTier::EtherTypeFastPath,
+        DetectionTier::FlowCacheHit,
+        DetectionTier::FullDetection,
+    ];
+}
+
+impl fmt::Display for DetectionTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DetectionTier::EtherTypeFastPath => write!(f, "EtherType fast path"),
+            DetectionTier::FlowCacheHit => write!(f, "Flow cache hit"),
+            DetectionTier::FullDetection => write!(f, "Full detection"),
+        }
+    }
+}
+
+/// Timing of one `detect_and_parse_with_metrics` call
+///
+/// `parse_time_ns` is the final parser call, the one whose result is
+/// returned; `detection_time_ns` is everything else, including parsers
+/// that full detection tried and rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DetectionMetrics {
+    pub tier_used: DetectionTier,
+    pub detection_time_ns: u64,
+    pub parse_time_ns: u64,
+    pub total_time_ns: u64,
+}
+
+impl DetectionMetrics {
+    /// Detection-time percentiles per tier, one row per tier seen
+    pub fn histogram(stats: &[DetectionMetrics]) -> String {
+        let mut out = format!("Detection time (ns) over {} packets\n", stats.len());
+        let _ = writeln!(
+            out,
+            "{:<20} {:>10} {:>8} {:>8} {:>8} {:>8}",
+            "Tier", "Count", "p50", "p90", "p99", "max"
+        );
+
+        let rows = DetectionTier::ALL
+            .iter()
+            .map(|&tier| (tier.to_string(), Self::detection_times(stats, Some(tier))))
+            .chain(std::iter::once((
+                "All".to_string(),
+                Self::detection_times(stats, None),
+            )));
+
+        for (label, times) in rows {
+            if times.is_empty() {
+                continue;
+            }
+            let _ = writeln!(
+                out,
+                "{:<20} {:>10} {:>8} {:>8} {:>8} {:>8}",
+                label,
+                times.len(),
+                percentile(&times, 50.0),
+                percentile(&times, 90.0),
+                percentile(&times, 99.0),
+                times[times.len() - 1],
+            );
+        }
+        out
+    }
+
+    /// Print `histogram()` to stdout
+    pub fn print_histogram(stats: &[DetectionMetrics]) {
+        print!("{}", Self::histogram(stats));
+    }
+
+    /// Sorted detection times for `tier`, or for every tier when `None`
+    fn detection_times(stats: &[DetectionMetrics], tier: Option<DetectionTier>) -> Vec<u64> {
+        let mut times: Vec<u64> = stats
+            .iter()
+            .filter(|m| tier.is_none_or(|tier| m.tier_used == tier))
+            .map(|m| m.detection_time_ns)
+            .collect();
+        times.sort_unstable();
+        times
+    }
+}
+
+/// Nearest-rank percentile of a non-empty sorted slice
+fn percentile(sorted: &[u64], pct: f64) -> u64 {
+    let rank = (pct / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Tier and parse time collected while `detect_and_parse_with_metrics` runs
+struct DetectionTimer {
+    tier: DetectionTier,
+    parse_ns: u64,
+}
+
 impl ProtocolRegistry {
     /// Create new registry with default parsers (MACsec, IPsec, GenericL3)
+    ///
+    /// Parsers are ordered by `priority / matches_cost` so cheap, likely
+    /// checks run first during full detection.
     pub fn new() -> Self {
+        Self::with_ordering(ParserOrdering::PriorityPerCost)
+    }
+
+    /// Create new registry with default parsers and an explicit ordering strategy
+    pub fn with_ordering(ordering: ParserOrdering) -> Self {
         use crate::protocol::{GenericL3Parser, IPsecParser, MACsecParser};
 
         let mut registry = Self {
             parsers: Vec::new(),
+            ordering,
+            macsec_idx: None,
+            has_custom_parsers: false,
             #[cfg(feature = "async")]
             flow_cache: Arc::new(DashMap::new()),
             #[cfg(not(feature = "async"))]
@@ -97,35 +244,114 @@ impl ProtocolRegistry {
         };
 
         // Add parsers in priority order
-        registry.add_parser(Box::new(MACsecParser), 30, "MACsec");
-        registry.add_parser(Box::new(IPsecParser), 20, "IPsec-ESP");
-        registry.add_parser(Box::new(GenericL3Parser), 10, "Generic-L3");
+        registry.insert_parser(Box::new(MACsecParser), 30, "MACsec");
+        registry.insert_parser(Box::new(IPsecParser::new()), 20, "IPsec-ESP");
+        registry.insert_parser(Box::new(GenericL3Parser), 10, "Generic-L3");
 
         registry
     }
 
-    /// Add custom parser with priority
+    /// Register a custom parser with priority
     ///
-    /// Higher priority = checked first. Parsers are tried in descending priority order.
+    /// Higher priority = checked first. With `ParserOrdering::PriorityPerCost` the
+    /// priority is divided by the parser's `matches_cost()` before sorting.
+    ///
+    /// Once a custom parser is registered, frames with EtherTypes other than
+    /// IPv4 go through full detection instead of being rejected, so parsers
+    /// for proprietary encapsulations (e.g. EtherType 0x9000) are tried too.
+    /// The flow cache is cleared, since cached parser positions may shift.
     ///
     /// # Arguments
     /// * `parser` - Parser implementing SequenceParser trait
     /// * `priority` - Priority value (higher checked first)
-    /// * `name` - Human-readable name for metrics
-    fn add_parser(
+    /// * `name` - Human-readable name for metrics and `preload_known_flows()`
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut registry = ProtocolRegistry::new();
+    /// registry.register_parser(Box::new(MyProtocolParser), 25, "MyProtocol");
+    /// ```
+    pub fn register_parser(
+        &mut self,
+        parser: Box<dyn SequenceParser + Send + Sync>,
+        priority: u8,
+        name: &str,
+    ) {
+        self.insert_parser(parser, priority, name);
+        self.has_custom_parsers = true;
+        self.clear_cache();
+    }
+
+    /// Add a parser and re-sort the list by `ordering`
+    ///
+    /// Entries marked by `remove_parser()` are dropped here, where `&mut self`
+    /// guarantees no detection is running.
+    fn insert_parser(
         &mut self,
         parser: Box<dyn SequenceParser + Send + Sync>,
         priority: u8,
         name: &str,
     ) {
+        self.parsers.retain(|e| !e.is_removed());
+
+        // Cost is a static property of the parser, so probe with no data
+        let cost = parser.matches_cost(&[]).max(1);
+
         self.parsers.push(ParserEntry {
             parser,
             priority,
+            cost,
             name: name.to_string(),
+            removed: AtomicBool::new(false),
         });
 
-        // Sort by priority (highest first)
-        self.parsers.sort_by(|a, b| b.priority.cmp(&a.priority));
+        match self.ordering {
+            // Sort by priority (highest first)
+            ParserOrdering::Priority => {
+                self.parsers.sort_by(|a, b| b.priority.cmp(&a.priority));
+            }
+            // Sort by priority/cost (highest first), cross-multiplied to stay in integers
+            ParserOrdering::PriorityPerCost => {
+                self.parsers.sort_by(|a, b| {
+                    let lhs = b.priority as u64 * a.cost as u64;
+                    let rhs = a.priority as u64 * b.cost as u64;
+                    lhs.cmp(&rhs).then_with(|| b.priority.cmp(&a.priority))
+                });
+            }
+        }
+
+        self.macsec_idx = self.parsers.iter().position(|e| e.name == "MACsec");
+    }
+
+    /// Remove a parser by name, e.g. to stop IPsec detection at runtime
+    ///
+    /// Takes `&self` so a registry shared between capture threads can be
+    /// reconfigured in place. Flows cached with the removed parser are
+    /// evicted and go through full detection again. Built-in parsers can be
+    /// removed too; without MACsec, MACsec frames count as unknown protocol.
+    ///
+    /// Returns `true` if a parser with that name was registered.
+    pub fn remove_parser(&self, name: &str) -> bool {
+        let Some(idx) = self
+            .parsers
+            .iter()
+            .position(|e| e.name == name && !e.removed.swap(true, Ordering::Relaxed))
+        else {
+            return false;
+        };
+
+        self.evict_parser(idx as u8);
+        true
+    }
+
+    /// Parser names in the order full detection tries them
+    pub fn parser_order(&self) -> Vec<&str> {
+        self.parsers
+            .iter()
+            .filter(|e| !e.is_removed())
+            .map(|e| e.name.as_str())
+            .collect()
     }
 
     /// Detect protocol and parse packet using 3-tier strategy
@@ -144,6 +370,43 @@ impl ProtocolRegistry {
     pub fn detect_and_parse(
         &self,
         data: &[u8],
+    ) -> Result<Option<SequenceInfo>, ParseError> {
+        self.detect_and_parse_timed(data, None)
+    }
+
+    /// `detect_and_parse` plus timing of the tiers it went through
+    ///
+    /// For profiling; costs a few clock reads per packet. Callers that only
+    /// want the result can ignore the metrics:
+    ///
+    /// ```ignore
+    /// let (result, _) = registry.detect_and_parse_with_metrics(&packet_data);
+    /// ```
+    pub fn detect_and_parse_with_metrics(
+        &self,
+        data: &[u8],
+    ) -> (Result<Option<SequenceInfo>, ParseError>, DetectionMetrics) {
+        let start = Instant::now();
+        let mut timer = DetectionTimer {
+            tier: DetectionTier::EtherTypeFastPath,
+            parse_ns: 0,
+        };
+        let result = self.detect_and_parse_timed(data, Some(&mut timer));
+
+        let total_time_ns = start.elapsed().as_nanos() as u64;
+        let metrics = DetectionMetrics {
+            tier_used: timer.tier,
+            detection_time_ns: total_time_ns.saturating_sub(timer.parse_ns),
+            parse_time_ns: timer.parse_ns,
+            total_time_ns,
+        };
+        (result, metrics)
+    }
+
+    fn detect_and_parse_timed(
+        &self,
+        data: &[u8],
+        mut timer: Option<&mut DetectionTimer>,
     ) -> Result<Option<SequenceInfo>, ParseError> {
         // Minimum size for Ethernet frame with EtherType
         if data.len() < 14 {
@@ -155,25 +418,39 @@ impl ProtocolRegistry {
 
         // Fast path: MACsec (0x88E5) goes directly to MACsec parser
         if ethertype == 0x88E5 {
-            self.ethertype_fast_path.fetch_add(1, Ordering::Relaxed);
-            return self.parsers[0].parser.parse_sequence(data);
+            let macsec_idx = self
+                .macsec_idx
+                .filter(|&idx| !self.parsers[idx].is_removed());
+            if let Some(idx) = macsec_idx {
+                self.ethertype_fast_path.fetch_add(1, Ordering::Relaxed);
+                return self.run_parser(idx, data, timer);
+            }
         }
 
-        // Only IPv4 (0x0800) and other ethertypes might be supported
-        if ethertype != 0x0800 {
+        // Only IPv4 (0x0800) is supported by the built-in parsers; other
+        // EtherTypes are left to custom parsers, if any are registered
+        if ethertype != 0x0800 && !self.has_custom_parsers {
             self.unknown_protocol.fetch_add(1, Ordering::Relaxed);
             return Ok(None);
         }
 
         // TIER 2: Flow cache lookup (10-15 ns on hit)
-        if let Some(flow_id) = self.extract_provisional_flow_id(data) {
+        // The provisional flow ID reads IPv4 header fields, so only IPv4 is cached
+        let provisional_flow_id = if ethertype == 0x0800 {
+            self.extract_provisional_flow_id(data)
+        } else {
+            None
+        };
+        if let Some(flow_id) = provisional_flow_id {
             if let Some(parser_idx) = self.lookup_cache(&flow_id) {
                 self.cache_hits.fetch_add(1, Ordering::Relaxed);
+                if let Some(timer) = timer.as_deref_mut() {
+                    timer.tier = DetectionTier::FlowCacheHit;
+                }
 
                 // Use cached parser
-                if let Some(seq_info) = self.parsers[parser_idx as usize]
-                    .parser
-                    .parse_sequence(data)?
+                if let Some(seq_info) =
+                    self.run_parser(parser_idx as usize, data, timer.as_deref_mut())?
                 {
                     return Ok(Some(seq_info));
                 }
@@ -185,21 +462,120 @@ impl ProtocolRegistry {
 
         // TIER 3: Full detection (150-200 ns on miss)
         self.cache_misses.fetch_add(1, Ordering::Relaxed);
+        if let Some(timer) = timer.as_deref_mut() {
+            timer.tier = DetectionTier::FullDetection;
+        }
+
+        if let Some(seq_info) = self.detect_and_cache(data, timer)? {
+            return Ok(Some(seq_info));
+        }
+
+        // No parser matched
+        self.unknown_protocol.fetch_add(1, Ordering::Relaxed);
+        Ok(None)
+    }
 
-        // Try all parsers in priority order
-        for (idx, entry) in self.parsers.iter().enumerate() {
-            if let Some(seq_info) = entry.parser.parse_sequence(data)? {
+    /// Try all parsers in priority order and cache the first that matches
+    fn detect_and_cache(
+        &self,
+        data: &[u8],
+        mut timer: Option<&mut DetectionTimer>,
+    ) -> Result<Option<SequenceInfo>, ParseError> {
+        for idx in 0..self.parsers.len() {
+            if let Some(seq_info) = self.run_parser(idx, data, timer.as_deref_mut())? {
                 // Found matching parser - cache the result
                 self.cache_flow(&seq_info.flow_id, idx as u8);
                 return Ok(Some(seq_info));
             }
         }
-
-        // No parser matched
-        self.unknown_protocol.fetch_add(1, Ordering::Relaxed);
         Ok(None)
     }
 
+    /// Run one parser, recording its duration as the parse time when timed
+    ///
+    /// Each call overwrites the previous parse time, so after full detection
+    /// it holds the parser whose result was returned. A removed parser
+    /// matches nothing, which also turns stale cache entries into misses.
+    fn run_parser(
+        &self,
+        idx: usize,
+        data: &[u8],
+        timer: Option<&mut DetectionTimer>,
+    ) -> Result<Option<SequenceInfo>, ParseError> {
+        let entry = &self.parsers[idx];
+        if entry.is_removed() {
+            return Ok(None);
+        }
+        let parser = &entry.parser;
+        match timer {
+            None => parser.parse_sequence(data),
+            Some(timer) => {
+                let start = Instant::now();
+                let result = parser.parse_sequence(data);
+                timer.parse_ns = start.elapsed().as_nanos() as u64;
+                result
+            }
+        }
+    }
+
+    /// Pre-populate the flow cache from a pilot batch of packets
+    ///
+    /// Runs full detection on each IPv4 packet whose flow is not cached yet,
+    /// so the first full-rate packets of those flows hit the cache. Hit, miss
+    /// and unknown-protocol counters are left untouched, and malformed packets
+    /// are skipped. MACsec packets take the EtherType fast path and never need
+    /// warming.
+    ///
+    /// Returns the number of flows added to the cache.
+    pub fn warm_cache<'a>(&self, packets: impl IntoIterator<Item = &'a [u8]>) -> usize {
+        let mut warmed = 0;
+        for data in packets {
+            if data.len() < 14 || u16::from_be_bytes([data[12], data[13]]) != 0x0800 {
+                continue;
+            }
+            let Some(flow_id) = self.extract_provisional_flow_id(data) else {
+                continue;
+            };
+            if self.lookup_cache(&flow_id).is_some() {
+                continue;
+            }
+            if let Ok(Some(_)) = self.detect_and_cache(data, None) {
+                warmed += 1;
+            }
+        }
+        warmed
+    }
+
+    /// Insert `(flow, parser name)` pairs into the flow cache without parsing
+    ///
+    /// For flows known in advance. Parser names are those returned by
+    /// `parser_order()`. Nothing is inserted if any name is unknown.
+    ///
+    /// Returns the number of flows inserted.
+    pub fn preload_known_flows(&self, flows: &[(FlowId, &str)]) -> Result<usize, String> {
+        let resolved = flows
+            .iter()
+            .map(|(flow_id, name)| {
+                self.parsers
+                    .iter()
+                    .position(|entry| entry.name == *name && !entry.is_removed())
+                    .map(|idx| (flow_id, idx as u8))
+                    .ok_or_else(|| {
+                        format!(
+                            "Unknown parser '{}' (expected one of {})",
+                            name,
+                            self.parser_order().join(", ")
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (flow_id, idx) in &resolved {
+            self.cache_flow(flow_id, *idx);
+        }
+        Ok(resolved.len())
+    }
+
     /// Extract provisional FlowId for cache lookup (lightweight, doesn't validate)
     ///
     /// Returns `None` if packet structure is invalid or unsupported.
@@ -239,7 +615,8 @@ impl ProtocolRegistry {
                     esp_payload[3],
                 ]);
 
-                Some(FlowId::IPsec { spi, dst_ip })
+                // Mode needs an SA lookup; the fast path only identifies the flow
+                Some(FlowId::IPsec { spi, dst_ip, mode: None })
             }
             6 | 17 => {
                 // TCP (6) or UDP (17)
@@ -296,6 +673,34 @@ impl ProtocolRegistry {
         }
     }
 
+    /// Name of the parser cached for each flow
+    ///
+    /// A point-in-time copy for debugging auto-detection; flows cached with
+    /// a removed parser are left out.
+    pub fn snapshot_cache(&self) -> HashMap<FlowId, String> {
+        #[cfg(feature = "async")]
+        let entries: Vec<(FlowId, u8)> = self
+            .flow_cache
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+
+        #[cfg(not(feature = "async"))]
+        let entries: Vec<(FlowId, u8)> = self
+            .flow_cache
+            .lock()
+            .map(|cache| cache.iter().map(|(k, v)| (k.clone(), *v)).collect())
+            .unwrap_or_default();
+
+        entries
+            .into_iter()
+            .filter_map(|(flow_id, idx)| {
+                let entry = self.parsers.get(idx as usize)?;
+                (!entry.is_removed()).then(|| (flow_id, entry.name.clone()))
+            })
+            .collect()
+    }
+
     /// Clear flow cache (useful for testing or memory management)
     pub fn clear_cache(&self) {
         #[cfg(feature = "async")]
@@ -343,6 +748,19 @@ impl ProtocolRegistry {
             cache.remove(flow_id);
         }
     }
+
+    /// Evict every flow cached with one parser
+    #[cfg(feature = "async")]
+    fn evict_parser(&self, parser_idx: u8) {
+        self.flow_cache.retain(|_, idx| *idx != parser_idx);
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn evict_parser(&self, parser_idx: u8) {
+        if let Ok(mut cache) = self.flow_cache.lock() {
+            cache.retain(|_, idx| *idx != parser_idx);
+        }
+    }
 }
 
 impl Default for ProtocolRegistry {
@@ -513,6 +931,69 @@ mod tests {
         assert_eq!(stats.cache_hits, 1);
     }
 
+    #[test]
+    fn test_detection_metrics_tiers() {
+        let registry = ProtocolRegistry::new();
+        let tcp = create_ipv4_tcp_packet();
+
+        let (first, metrics) = registry.detect_and_parse_with_metrics(&tcp);
+        assert!(first.unwrap().is_some());
+        assert_eq!(metrics.tier_used, DetectionTier::FullDetection);
+        assert_eq!(
+            metrics.detection_time_ns + metrics.parse_time_ns,
+            metrics.total_time_ns
+        );
+
+        let (_, metrics) = registry.detect_and_parse_with_metrics(&tcp);
+        assert_eq!(metrics.tier_used, DetectionTier::FlowCacheHit);
+
+        let (_, metrics) = registry.detect_and_parse_with_metrics(&create_macsec_packet());
+        assert_eq!(metrics.tier_used, DetectionTier::EtherTypeFastPath);
+
+        // Rejected by the EtherType pre-filter without running a parser
+        let mut arp = vec![0u8; 20];
+        arp[12] = 0x08;
+        arp[13] = 0x06;
+        let (result, metrics) = registry.detect_and_parse_with_metrics(&arp);
+        assert!(result.unwrap().is_none());
+        assert_eq!(metrics.tier_used, DetectionTier::EtherTypeFastPath);
+        assert_eq!(metrics.parse_time_ns, 0);
+
+        // Same counters as the untimed path
+        let stats = registry.get_stats();
+        assert_eq!((stats.cache_misses, stats.cache_hits), (1, 1));
+    }
+
+    #[test]
+    fn test_detection_histogram_percentiles() {
+        let metrics = |tier, ns| DetectionMetrics {
+            tier_used: tier,
+            detection_time_ns: ns,
+            parse_time_ns: 0,
+            total_time_ns: ns,
+        };
+        let mut stats: Vec<DetectionMetrics> = (1..=100)
+            .map(|ns| metrics(DetectionTier::FlowCacheHit, ns))
+            .collect();
+        stats.push(metrics(DetectionTier::FullDetection, 500));
+
+        let histogram = DetectionMetrics::histogram(&stats);
+        let row = |label: &str| -> Vec<String> {
+            let line = histogram.lines().find(|l| l.starts_with(label)).unwrap();
+            line[label.len()..]
+                .split_whitespace()
+                .map(String::from)
+                .collect()
+        };
+
+        assert!(histogram.starts_with("Detection time (ns) over 101 packets"));
+        assert_eq!(row("Flow cache hit"), ["100", "50", "90", "99", "100"]);
+        assert_eq!(row("Full detection"), ["1", "500", "500", "500", "500"]);
+        assert_eq!(row("All"), ["101", "51", "91", "100", "500"]);
+        // Tiers with no samples are left out
+        assert!(!histogram.contains("EtherType fast path"));
+    }
+
     #[test]
     fn test_unknown_ethertype() {
         let registry = ProtocolRegistry::new();
@@ -568,4 +1049,249 @@ mod tests {
         let stats2 = registry2.get_stats();
         assert_eq!(stats2.ethertype_fast_path, 0);
     }
+
+    #[test]
+    fn test_matches_cost_defaults() {
+        assert_eq!(MACsecParser.matches_cost(&[]), 1);
+        assert_eq!(GenericL3Parser.matches_cost(&[]), 2);
+        assert_eq!(IPsecParser::new().matches_cost(&[]), 3);
+    }
+
+    #[test]
+    fn test_default_parser_order() {
+        // 30/1 > 20/3 > 10/2
+        let registry = ProtocolRegistry::new();
+        assert_eq!(
+            registry.parser_order(),
+            vec!["MACsec", "IPsec-ESP", "Generic-L3"]
+        );
+    }
+
+    struct ExpensiveParser;
+
+    impl SequenceParser for ExpensiveParser {
+        fn parse_sequence(&self, _data: &[u8]) -> Result<Option<SequenceInfo>, ParseError> {
+            Ok(None)
+        }
+
+        fn matches(&self, _data: &[u8]) -> bool {
+            false
+        }
+
+        fn matches_cost(&self, _data: &[u8]) -> u32 {
+            100
+        }
+
+        fn protocol_name(&self) -> &str {
+            "Expensive"
+        }
+    }
+
+    #[test]
+    fn test_expensive_parser_sorted_after_cheap_ones() {
+        let mut by_cost = ProtocolRegistry::with_ordering(ParserOrdering::PriorityPerCost);
+        by_cost.register_parser(Box::new(ExpensiveParser), 50, "Expensive");
+        assert_eq!(by_cost.parser_order().last(), Some(&"Expensive"));
+
+        let mut by_priority = ProtocolRegistry::with_ordering(ParserOrdering::Priority);
+        by_priority.register_parser(Box::new(ExpensiveParser), 50, "Expensive");
+        assert_eq!(by_priority.parser_order().first(), Some(&"Expensive"));
+
+        // MACsec fast path still finds its parser after reordering
+        let packet = create_macsec_packet();
+        let _ = by_priority.detect_and_parse(&packet);
+        assert_eq!(by_priority.get_stats().ethertype_fast_path, 1);
+    }
+
+    /// Proprietary encapsulation: EtherType 0x9000, then a 4-byte sequence number
+    struct MockParser;
+
+    impl SequenceParser for MockParser {
+        fn parse_sequence(&self, data: &[u8]) -> Result<Option<SequenceInfo>, ParseError> {
+            if !self.matches(data) {
+                return Ok(None);
+            }
+            Ok(Some(SequenceInfo {
+                sequence_number: u32::from_be_bytes([data[14], data[15], data[16], data[17]]),
+                flow_id: FlowId::MACsec { sci: 0x9000, an: 0 },
+                payload_length: data.len() - 18,
+                proto_version: None,
+                dscp: None,
+                ecn: None,
+            }))
+        }
+
+        fn matches(&self, data: &[u8]) -> bool {
+            data.len() >= 18 && data[12] == 0x90 && data[13] == 0x00
+        }
+
+        fn protocol_name(&self) -> &str {
+            "Mock"
+        }
+    }
+
+    fn create_mock_packet(seq: u32) -> Vec<u8> {
+        let mut packet = vec![0u8; 30];
+        packet[12] = 0x90; // EtherType 0x9000
+        packet[13] = 0x00;
+        packet[14..18].copy_from_slice(&seq.to_be_bytes());
+        packet
+    }
+
+    #[test]
+    fn test_register_custom_parser() {
+        let mut registry = ProtocolRegistry::new();
+        let packet = create_mock_packet(42);
+
+        // Built-in parsers reject the EtherType
+        assert!(registry.detect_and_parse(&packet).unwrap().is_none());
+        assert_eq!(registry.get_stats().unknown_protocol, 1);
+
+        registry.register_parser(Box::new(MockParser), 25, "Mock");
+        assert!(registry.parser_order().contains(&"Mock"));
+
+        let seq_info = registry.detect_and_parse(&packet).unwrap().unwrap();
+        assert_eq!(seq_info.sequence_number, 42);
+        assert_eq!(seq_info.flow_id, FlowId::MACsec { sci: 0x9000, an: 0 });
+        assert_eq!(seq_info.payload_length, 12);
+
+        // Other EtherTypes are still unknown, built-in protocols still detected
+        let mut arp = vec![0u8; 20];
+        arp[12] = 0x08;
+        arp[13] = 0x06;
+        assert!(registry.detect_and_parse(&arp).unwrap().is_none());
+        assert_eq!(registry.get_stats().unknown_protocol, 2);
+
+        let tcp = create_ipv4_tcp_packet();
+        assert!(registry.detect_and_parse(&tcp).unwrap().is_some());
+        assert!(registry.detect_and_parse(&tcp).unwrap().is_some());
+        assert_eq!(registry.get_stats().cache_hits, 1);
+    }
+
+    #[test]
+    fn test_register_parser_clears_flow_cache() {
+        let mut registry = ProtocolRegistry::new();
+        let tcp = create_ipv4_tcp_packet();
+        registry.detect_and_parse(&tcp).unwrap();
+        assert_eq!(registry.get_stats().cache_size, 1);
+
+        // A high priority parser moves the others down the list
+        registry.register_parser(Box::new(MockParser), 200, "Mock");
+        assert_eq!(registry.get_stats().cache_size, 0);
+        assert_eq!(registry.parser_order().first(), Some(&"Mock"));
+
+        let seq_info = registry.detect_and_parse(&tcp).unwrap().unwrap();
+        assert!(matches!(seq_info.flow_id, FlowId::GenericL3 { .. }));
+    }
+
+    #[test]
+    fn test_remove_macsec_parser() {
+        let registry = ProtocolRegistry::new();
+        let packet = create_macsec_packet();
+
+        assert!(registry.remove_parser("MACsec"));
+        assert!(!registry.remove_parser("MACsec"));
+        assert!(!registry.remove_parser("Bogus"));
+        assert_eq!(registry.parser_order(), vec!["IPsec-ESP", "Generic-L3"]);
+
+        assert!(registry.detect_and_parse(&packet).unwrap().is_none());
+        let stats = registry.get_stats();
+        assert_eq!(stats.ethertype_fast_path, 0);
+        assert_eq!(stats.unknown_protocol, 1);
+    }
+
+    #[test]
+    fn test_remove_parser_evicts_its_flows() {
+        let mut registry = ProtocolRegistry::new();
+        let tcp = create_ipv4_tcp_packet();
+        let udp = create_ipv4_udp_packet();
+        let flow_id = registry.extract_provisional_flow_id(&udp).unwrap();
+        registry.detect_and_parse(&tcp).unwrap();
+        registry
+            .preload_known_flows(&[(flow_id, "IPsec-ESP")])
+            .unwrap();
+        assert_eq!(registry.get_stats().cache_size, 2);
+
+        // Only the flow cached with IPsec-ESP goes
+        assert!(registry.remove_parser("IPsec-ESP"));
+        assert_eq!(registry.get_stats().cache_size, 1);
+        assert!(registry.detect_and_parse(&tcp).unwrap().is_some());
+        assert_eq!(registry.get_stats().cache_hits, 1);
+
+        // A removed parser can no longer be preloaded
+        let flow_id = registry.extract_provisional_flow_id(&tcp).unwrap();
+        let result = registry.preload_known_flows(&[(flow_id, "IPsec-ESP")]);
+        assert!(result.is_err());
+
+        // Registering again drops the removed entry for good
+        registry.register_parser(Box::new(IPsecParser::new()), 20, "IPsec-ESP");
+        assert_eq!(registry.parsers.len(), 3);
+        assert_eq!(
+            registry.parser_order(),
+            vec!["MACsec", "IPsec-ESP", "Generic-L3"]
+        );
+    }
+
+    #[test]
+    fn test_snapshot_cache() {
+        let registry = ProtocolRegistry::new();
+        let tcp = create_ipv4_tcp_packet();
+        assert!(registry.snapshot_cache().is_empty());
+
+        let flow_id = registry.detect_and_parse(&tcp).unwrap().unwrap().flow_id;
+        let snapshot = registry.snapshot_cache();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[&flow_id], "Generic-L3");
+
+        registry.remove_parser("Generic-L3");
+        assert!(registry.snapshot_cache().is_empty());
+    }
+
+    fn cache_hit_rate(stats: &RegistryStats) -> f64 {
+        stats.cache_hits as f64 / (stats.cache_hits + stats.cache_misses) as f64
+    }
+
+    #[test]
+    fn test_warm_cache() {
+        let registry = ProtocolRegistry::new();
+        let tcp = create_ipv4_tcp_packet();
+        let udp = create_ipv4_udp_packet();
+        let macsec = create_macsec_packet();
+
+        let pilot = [tcp.as_slice(), udp.as_slice(), tcp.as_slice(), macsec.as_slice()];
+        assert_eq!(registry.warm_cache(pilot), 2);
+
+        // Warming leaves the detection counters alone
+        let stats = registry.get_stats();
+        assert_eq!(stats.cache_size, 2);
+        assert_eq!(stats.cache_hits + stats.cache_misses, 0);
+        assert_eq!(stats.ethertype_fast_path, 0);
+
+        for _ in 0..5 {
+            registry.detect_and_parse(&tcp).unwrap();
+            registry.detect_and_parse(&udp).unwrap();
+        }
+        assert_eq!(cache_hit_rate(&registry.get_stats()), 1.0);
+    }
+
+    #[test]
+    fn test_preload_known_flows() {
+        let registry = ProtocolRegistry::new();
+        let tcp = create_ipv4_tcp_packet();
+        let flow_id = registry.extract_provisional_flow_id(&tcp).unwrap();
+
+        assert_eq!(registry.preload_known_flows(&[(flow_id.clone(), "Generic-L3")]), Ok(1));
+        registry.detect_and_parse(&tcp).unwrap();
+        registry.detect_and_parse(&tcp).unwrap();
+        assert_eq!(cache_hit_rate(&registry.get_stats()), 1.0);
+
+        // An unknown name rejects the whole batch
+        registry.clear_cache();
+        let result = registry.preload_known_flows(&[
+            (flow_id, "Generic-L3"),
+            (FlowId::new("unknown"), "Bogus"),
+        ]);
+        assert!(result.unwrap_err().contains("Bogus"));
+        assert_eq!(registry.get_stats().cache_size, 0);
+    }
 }
@@ -0,0 +1,168 @@
+//! IPsec Security Association Database (SAD)
+//!
+//! Maps SPIs to the parameters negotiated for each Security Association, so
+//! `IPsecParser::with_sad` can ignore traffic for unknown SAs, tag flows with
+//! their mode and check the ESP ICV when the integrity key is known.
+
+use std::collections::HashMap;
+#[cfg(feature = "serde_json")]
+use std::io;
+use std::net::IpAddr;
+
+use crate::types::IpsecMode;
+
+/// IP protocol number of ESP
+pub const IP_PROTOCOL_ESP: u8 = 50;
+
+/// Length of the HMAC-SHA-256-128 ICV (RFC 4868)
+pub const ICV_LEN: usize = 16;
+
+/// One Security Association
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub struct SecurityAssociation {
+    pub spi: u32,
+    pub dst_ip: IpAddr,
+    /// IP protocol carrying the SA: 50 (ESP) or 51 (AH)
+    pub protocol: u8,
+    pub mode: IpsecMode,
+    /// HMAC-SHA-256 integrity key; in JSON a 64-character hex string
+    #[cfg_attr(feature = "serde", serde(default, deserialize_with = "deserialize_hex_key"))]
+    pub key: Option<[u8; 32]>,
+    /// Anti-replay window size in packets
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub replay_window: u64,
+}
+
+/// SPI -> Security Association lookup table
+#[derive(Debug, Clone, Default)]
+pub struct IpsecSaDatabase {
+    entries: HashMap<u32, SecurityAssociation>,
+}
+
+impl IpsecSaDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the SA for `sa.spi`
+    pub fn insert(&mut self, sa: SecurityAssociation) {
+        self.entries.insert(sa.spi, sa);
+    }
+
+    /// SA for `spi`, if one is configured
+    pub fn get(&self, spi: u32) -> Option<&SecurityAssociation> {
+        self.entries.get(&spi)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Load SAs from a JSON file holding an array of SA objects:
+    ///
+    /// ```json
+    /// [
+    ///   {"spi": 4096, "dst_ip": "10.0.0.1", "protocol": 50, "mode": "tunnel",
+    ///    "key": "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff",
+    ///    "replay_window": 64}
+    /// ]
+    /// ```
+    ///
+    /// `key` and `replay_window` are optional. Malformed JSON is reported as
+    /// `io::ErrorKind::InvalidData`.
+    #[cfg(feature = "serde_json")]
+    pub fn load_from_file(path: &str) -> Result<Self, io::Error> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::from_json(&contents)
+    }
+
+    /// Parse the JSON format described in `load_from_file`
+    #[cfg(feature = "serde_json")]
+    pub fn from_json(json: &str) -> Result<Self, io::Error> {
+        let entries: Vec<SecurityAssociation> = serde_json::from_str(json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut sad = Self::new();
+        for sa in entries {
+            sad.insert(sa);
+        }
+        Ok(sad)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_hex_key<'de, D>(deserializer: D) -> Result<Option<[u8; 32]>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+    use serde::Deserialize;
+
+    let Some(hex) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(D::Error::custom("key must be 64 hex characters (32 bytes)"));
+    }
+
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+            .map_err(|_| D::Error::custom("key must be 64 hex characters (32 bytes)"))?;
+    }
+    Ok(Some(key))
+}
+
+#[cfg(all(test, feature = "serde_json"))]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_from_json() {
+        let sad = IpsecSaDatabase::from_json(
+            r#"[
+                {"spi": 4096, "dst_ip": "10.0.0.1", "protocol": 50, "mode": "tunnel",
+                 "key": "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+                 "replay_window": 64},
+                {"spi": 8192, "dst_ip": "10.0.0.2", "protocol": 50, "mode": "transport"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(sad.len(), 2);
+        let tunnel = sad.get(4096).unwrap();
+        assert_eq!(tunnel.dst_ip, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        assert_eq!(tunnel.mode, IpsecMode::Tunnel);
+        assert_eq!(tunnel.replay_window, 64);
+        let key = tunnel.key.unwrap();
+        assert_eq!(key[0], 0x00);
+        assert_eq!(key[31], 0x1f);
+
+        let transport = sad.get(8192).unwrap();
+        assert_eq!(transport.mode, IpsecMode::Transport);
+        assert_eq!(transport.key, None);
+        assert_eq!(transport.replay_window, 0);
+        assert!(sad.get(1).is_none());
+    }
+
+    #[test]
+    fn test_from_json_rejects_bad_key() {
+        let err = IpsecSaDatabase::from_json(
+            r#"[{"spi": 1, "dst_ip": "10.0.0.1", "protocol": 50, "mode": "tunnel", "key": "abcd"}]"#,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_load_from_file_missing() {
+        let err = IpsecSaDatabase::load_from_file("/nonexistent/sad.json").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -1,7 +1,9 @@
 use std::fmt;
+use std::str::FromStr;
 use std::time::SystemTime;
 use std::net::IpAddr;
-use std::collections::HashMap;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
 use std::time::Duration;
 
 #[cfg(feature = "rest-api")]
@@ -21,6 +23,13 @@ pub struct SequenceInfo {
     pub sequence_number: u32,
     pub flow_id: FlowId,
     pub payload_length: usize,
+    /// Protocol version carried in the header, if the protocol has one
+    /// (MACsec SecTAG V bit, IPv4 version; None for ESP)
+    pub proto_version: Option<u8>,
+    /// DSCP class from the IPv4 ToS byte (upper 6 bits), plain IPv4 only
+    pub dscp: Option<u8>,
+    /// ECN field from the IPv4 ToS byte (lower 2 bits), plain IPv4 only
+    pub ecn: Option<u8>,
 }
 
 /// Packet analyzed with sequence and flow information
@@ -30,21 +39,72 @@ pub struct AnalyzedPacket {
     pub flow_id: FlowId,
     pub timestamp: SystemTime,
     pub payload_length: usize,
+    /// Copied from `SequenceInfo::proto_version`
+    pub proto_version: Option<u8>,
+    /// Copied from `SequenceInfo::dscp`
+    pub dscp: Option<u8>,
+    /// Copied from `SequenceInfo::ecn`
+    pub ecn: Option<u8>,
+    /// Captured frame bytes, kept only when the analyzer is configured with
+    /// `with_raw_storage(true)` (see `FlowTracker::export_to_pcap`)
+    pub raw_data: Option<Vec<u8>>,
+}
+
+/// IPsec encapsulation mode, from the Security Association
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum IpsecMode {
+    /// Protects the payload of the original IP packet (host to host)
+    Transport,
+    /// Encapsulates the whole original IP packet (gateway to gateway)
+    Tunnel,
+}
+
+impl fmt::Display for IpsecMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpsecMode::Transport => write!(f, "transport"),
+            IpsecMode::Tunnel => write!(f, "tunnel"),
+        }
+    }
+}
+
+impl FromStr for IpsecMode {
+    type Err = crate::error::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "transport" => Ok(IpsecMode::Transport),
+            "tunnel" => Ok(IpsecMode::Tunnel),
+            other => Err(crate::error::ParseError::InvalidFormat(format!(
+                "invalid IPsec mode: {:?}",
+                other
+            ))),
+        }
+    }
 }
 
 /// Flow identifier - protocol-specific
+///
+/// Every variant is plain inline data (`IpAddr` included), so cloning a
+/// `FlowId` out of a parsed packet or the registry cache never allocates.
 #[derive(Debug, Clone, Hash, Eq, PartialEq, PartialOrd, Ord)]
 #[cfg_attr(feature = "rest-api", derive(Serialize, Deserialize))]
 pub enum FlowId {
     /// MACsec flow identified by Secure Channel Identifier (8 bytes)
-    MACsec { sci: u64 },
+    /// and Association Number (2 bits from the TCI/AN byte)
+    /// A key rotation changes the AN, so each Secure Association is its own flow
+    MACsec { sci: u64, an: u8 },
 
     /// IPsec ESP flow identified by SPI and destination IP
     /// SPI (Security Parameter Index) is the primary flow identifier
     /// dst_ip disambiguates when same SPI is used for multiple tunnels
+    /// mode is known only when the parser has an SA database (see `IpsecSaDatabase`)
     IPsec {
         spi: u32,
         dst_ip: IpAddr,
+        mode: Option<IpsecMode>,
     },
 
     /// Generic L3 flow identified by 5-tuple
@@ -58,24 +118,42 @@ pub enum FlowId {
     },
 }
 
+// Keep FlowId free of heap-owning fields; see the type docs
+const _: () = assert!(!std::mem::needs_drop::<FlowId>());
+
 impl FlowId {
     /// Create a FlowId from a string representation
+    ///
+    /// Parses the `Display` format exactly when possible (see `FromStr`) and
+    /// otherwise falls back to best-effort extraction with zeroed fields.
     pub fn new(s: impl Into<String>) -> Self {
         let s = s.into();
+        if let Ok(flow_id) = s.parse() {
+            return flow_id;
+        }
+
         if s.starts_with("MACsec") {
-            // Parse "MACsec { sci: 0x... }"
-            if let Some(hex_str) = s.split("0x").nth(1) {
-                if let Ok(sci) = u64::from_str_radix(hex_str.trim_end_matches(" }"), 16) {
-                    return FlowId::MACsec { sci };
-                }
-            }
-            FlowId::MACsec { sci: 0 }
+            // Parse "MACsec { sci: 0x..., an: N }"
+            let sci = s
+                .split("0x")
+                .nth(1)
+                .and_then(|rest| rest.split(|c: char| !c.is_ascii_hexdigit()).next())
+                .and_then(|hex_str| u64::from_str_radix(hex_str, 16).ok())
+                .unwrap_or(0);
+            let an = s
+                .split("an: ")
+                .nth(1)
+                .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+                .and_then(|digits| digits.parse().ok())
+                .unwrap_or(0);
+            FlowId::MACsec { sci, an }
         } else if s.starts_with("IPsec") {
             // Parse "IPsec { spi: 0x..., dst: ... }"
             // Simple parsing for now, can enhance later
             FlowId::IPsec {
                 spi: 0,
                 dst_ip: IpAddr::V4(std::net::Ipv4Addr::new(0, 0, 0, 0)),
+                mode: None,
             }
         } else if s.starts_with("TCP") || s.starts_with("UDP") {
             // Parse "TCP { ip:port -> ip:port }"
@@ -88,42 +166,294 @@ impl FlowId {
                 protocol: 6,
             }
         } else {
-            FlowId::MACsec { sci: 0 }
+            FlowId::MACsec { sci: 0, an: 0 }
+        }
+    }
+
+    /// Generic L3 flow from its 5-tuple, in the order returned by `five_tuple()`
+    pub const fn from_five_tuple(
+        protocol: u8,
+        src_ip: IpAddr,
+        dst_ip: IpAddr,
+        src_port: u16,
+        dst_port: u16,
+    ) -> Self {
+        FlowId::GenericL3 {
+            src_ip,
+            dst_ip,
+            src_port,
+            dst_port,
+            protocol,
+        }
+    }
+
+    /// MACsec flow for a Secure Channel, on Association Number 0
+    pub const fn from_macsec_sci(sci: u64) -> Self {
+        FlowId::MACsec { sci, an: 0 }
+    }
+
+    /// IPsec flow with an unknown encapsulation mode
+    pub const fn from_ipsec(spi: u32, dst_ip: IpAddr) -> Self {
+        FlowId::IPsec {
+            spi,
+            dst_ip,
+            mode: None,
+        }
+    }
+
+    /// `(protocol, src_ip, dst_ip, src_port, dst_port)` for Generic L3 flows
+    pub const fn five_tuple(&self) -> Option<(u8, IpAddr, IpAddr, u16, u16)> {
+        match *self {
+            FlowId::GenericL3 {
+                src_ip,
+                dst_ip,
+                src_port,
+                dst_port,
+                protocol,
+            } => Some((protocol, src_ip, dst_ip, src_port, dst_port)),
+            _ => None,
         }
     }
+
+    /// Variant name: `MACsec`, `IPsec` or `GenericL3`
+    ///
+    /// Unlike `protocol_name()`, Generic L3 flows are not split by transport.
+    pub const fn protocol_type(&self) -> &'static str {
+        match self {
+            FlowId::MACsec { .. } => "MACsec",
+            FlowId::IPsec { .. } => "IPsec",
+            FlowId::GenericL3 { .. } => "GenericL3",
+        }
+    }
+
+    /// Protocol label used in reports: `MACsec`, `IPsec`, `TCP`, `UDP` or `IP/<n>`
+    pub fn protocol_name(&self) -> String {
+        match self {
+            FlowId::MACsec { .. } => "MACsec".to_string(),
+            FlowId::IPsec { .. } => "IPsec".to_string(),
+            FlowId::GenericL3 { protocol, .. } => match *protocol {
+                6 => "TCP".to_string(),
+                17 => "UDP".to_string(),
+                other => format!("IP/{}", other),
+            },
+        }
+    }
+
+    /// Lowercase, URL-safe form for path segments and database keys
+    ///
+    /// Unlike `Display`, contains no spaces, braces or `0x` prefixes:
+    /// - `macsec-0000000000001234-0` (SCI, then AN)
+    /// - `ipsec-00000001-192.168.1.1`, with `-tunnel` or `-transport` when
+    ///   the mode is known
+    /// - `l3-06-c0a80101-0a000001-3039-0050` (protocol, source and
+    ///   destination IP as hex, source and destination port)
+    ///
+    /// Parsed back by `from_compact_string`.
+    pub fn to_compact_string(&self) -> String {
+        let ip_hex = |ip: &IpAddr| match ip {
+            IpAddr::V4(v4) => format!("{:08x}", u32::from(*v4)),
+            IpAddr::V6(v6) => format!("{:032x}", u128::from(*v6)),
+        };
+
+        match self {
+            FlowId::MACsec { sci, an } => format!("macsec-{:016x}-{}", sci, an),
+            FlowId::IPsec { spi, dst_ip, mode } => match mode {
+                Some(mode) => format!("ipsec-{:08x}-{}-{}", spi, dst_ip, mode),
+                None => format!("ipsec-{:08x}-{}", spi, dst_ip),
+            },
+            FlowId::GenericL3 {
+                src_ip,
+                dst_ip,
+                src_port,
+                dst_port,
+                protocol,
+            } => format!(
+                "l3-{:02x}-{}-{}-{:04x}-{:04x}",
+                protocol,
+                ip_hex(src_ip),
+                ip_hex(dst_ip),
+                src_port,
+                dst_port
+            ),
+        }
+    }
+
+    /// Parses the output of `to_compact_string`
+    ///
+    /// Hex fields must have their full width in lowercase, so every flow has
+    /// exactly one compact form.
+    pub fn from_compact_string(s: &str) -> Result<FlowId, crate::error::ParseError> {
+        let invalid =
+            || crate::error::ParseError::InvalidFormat(format!("invalid compact flow id: {:?}", s));
+
+        // Fixed-width lowercase hex field
+        let hex = |field: &str, digits: usize| -> Option<u128> {
+            let lower_hex = field
+                .bytes()
+                .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+            if field.len() != digits || !lower_hex {
+                return None;
+            }
+            u128::from_str_radix(field, 16).ok()
+        };
+        let ip = |field: &str| -> Option<IpAddr> {
+            match field.len() {
+                8 => hex(field, 8).map(|v| IpAddr::V4(std::net::Ipv4Addr::from(v as u32))),
+                32 => hex(field, 32).map(|v| IpAddr::V6(std::net::Ipv6Addr::from(v))),
+                _ => None,
+            }
+        };
+
+        let fields: Vec<&str> = s.split('-').collect();
+        let flow_id = match fields.as_slice() {
+            ["macsec", sci, an] => FlowId::MACsec {
+                sci: hex(sci, 16).ok_or_else(invalid)? as u64,
+                an: an.parse().map_err(|_| invalid())?,
+            },
+            ["ipsec", spi, dst_ip, mode @ ..] if mode.len() <= 1 => FlowId::IPsec {
+                spi: hex(spi, 8).ok_or_else(invalid)? as u32,
+                dst_ip: dst_ip.parse().map_err(|_| invalid())?,
+                mode: match mode.first() {
+                    Some(mode) => Some(mode.parse().map_err(|_| invalid())?),
+                    None => None,
+                },
+            },
+            ["l3", protocol, src_ip, dst_ip, src_port, dst_port] => FlowId::GenericL3 {
+                src_ip: ip(src_ip).ok_or_else(invalid)?,
+                dst_ip: ip(dst_ip).ok_or_else(invalid)?,
+                src_port: hex(src_port, 4).ok_or_else(invalid)? as u16,
+                dst_port: hex(dst_port, 4).ok_or_else(invalid)? as u16,
+                protocol: hex(protocol, 2).ok_or_else(invalid)? as u8,
+            },
+            _ => return Err(invalid()),
+        };
+        Ok(flow_id)
+    }
 }
 
 impl fmt::Display for FlowId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            FlowId::MACsec { sci } => {
-                write!(f, "MACsec {{ sci: 0x{:016x} }}", sci)
+            FlowId::MACsec { sci, an } => {
+                write!(f, "MACsec {{ sci: 0x{:016x}, an: {} }}", sci, an)
             }
-            FlowId::IPsec { spi, dst_ip } => {
-                write!(f, "IPsec {{ spi: 0x{:08x}, dst: {} }}", spi, dst_ip)
+            FlowId::IPsec { spi, dst_ip, mode } => {
+                write!(f, "IPsec {{ spi: 0x{:08x}, dst: {}", spi, dst_ip)?;
+                if let Some(mode) = mode {
+                    write!(f, ", mode: {}", mode)?;
+                }
+                write!(f, " }}")
             }
             FlowId::GenericL3 {
                 src_ip,
                 dst_ip,
                 src_port,
                 dst_port,
-                protocol,
+                ..
             } => {
-                let proto_name = match *protocol {
-                    6 => "TCP",
-                    17 => "UDP",
-                    _ => "Unknown",
-                };
+                write!(f, "{}", self.protocol_name())?;
                 write!(
                     f,
-                    "{} {{ {}:{} -> {}:{} }}",
-                    proto_name, src_ip, src_port, dst_ip, dst_port
+                    " {{ {}:{} -> {}:{} }}",
+                    src_ip, src_port, dst_ip, dst_port
                 )
             }
         }
     }
 }
 
+/// Parses the `Display` output back into a `FlowId`
+///
+/// Accepted forms:
+/// - `MACsec { sci: 0x0011223344556677, an: 1 }`
+/// - `IPsec { spi: 0x0000002a, dst: 10.0.0.1 }` (optionally `, mode: tunnel`)
+/// - `TCP { 10.0.0.1:1234 -> 10.0.0.2:80 }` (also `UDP`, `IP/<proto>`)
+impl FromStr for FlowId {
+    type Err = crate::error::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || crate::error::ParseError::InvalidFormat(format!("invalid flow id: {:?}", s));
+
+        let (kind, body) = s.trim().split_once(' ').ok_or_else(invalid)?;
+        let body = body
+            .trim()
+            .strip_prefix('{')
+            .and_then(|b| b.strip_suffix('}'))
+            .map(str::trim)
+            .ok_or_else(invalid)?;
+
+        match kind {
+            "MACsec" => {
+                let (sci, an) = body.split_once(',').ok_or_else(invalid)?;
+                let sci = sci
+                    .trim()
+                    .strip_prefix("sci: 0x")
+                    .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+                    .ok_or_else(invalid)?;
+                let an = an
+                    .trim()
+                    .strip_prefix("an: ")
+                    .and_then(|digits| digits.parse().ok())
+                    .ok_or_else(invalid)?;
+                Ok(FlowId::MACsec { sci, an })
+            }
+            "IPsec" => {
+                let mut fields = body.split(',');
+                let spi = fields
+                    .next()
+                    .and_then(|spi| spi.trim().strip_prefix("spi: 0x"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .ok_or_else(invalid)?;
+                let dst_ip = fields
+                    .next()
+                    .and_then(|dst| dst.trim().strip_prefix("dst: "))
+                    .and_then(|ip| ip.parse().ok())
+                    .ok_or_else(invalid)?;
+                let mode = match fields.next() {
+                    Some(mode) => Some(
+                        mode.trim()
+                            .strip_prefix("mode: ")
+                            .and_then(|mode| mode.parse().ok())
+                            .ok_or_else(invalid)?,
+                    ),
+                    None => None,
+                };
+                if fields.next().is_some() {
+                    return Err(invalid());
+                }
+                Ok(FlowId::IPsec { spi, dst_ip, mode })
+            }
+            _ => {
+                let protocol = match kind {
+                    "TCP" => 6,
+                    "UDP" => 17,
+                    other => other
+                        .strip_prefix("IP/")
+                        .and_then(|n| n.parse().ok())
+                        .ok_or_else(invalid)?,
+                };
+
+                // Port is after the last ':' so IPv6 addresses parse too
+                let parse_endpoint = |endpoint: &str| -> Option<(IpAddr, u16)> {
+                    let (ip, port) = endpoint.trim().rsplit_once(':')?;
+                    Some((ip.parse().ok()?, port.parse().ok()?))
+                };
+
+                let (src, dst) = body.split_once("->").ok_or_else(invalid)?;
+                let (src_ip, src_port) = parse_endpoint(src).ok_or_else(invalid)?;
+                let (dst_ip, dst_port) = parse_endpoint(dst).ok_or_else(invalid)?;
+                Ok(FlowId::GenericL3 {
+                    src_ip,
+                    dst_ip,
+                    src_port,
+                    dst_port,
+                    protocol,
+                })
+            }
+        }
+    }
+}
+
 /// Gap detected in packet sequence
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "rest-api", derive(Serialize, Deserialize))]
@@ -135,10 +465,14 @@ pub struct SequenceGap {
     pub gap_size: u32,
     #[cfg_attr(feature = "rest-api", serde(serialize_with = "serialize_systemtime"))]
     pub timestamp: SystemTime,
+    /// Time from detection until late packets filled every missing sequence
+    /// number; None while the gap is still open
+    #[cfg_attr(feature = "rest-api", serde(default))]
+    pub recovery_time: Option<Duration>,
 }
 
 /// Statistics for a single flow
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "rest-api", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "rest-api", serde(crate = "serde"))]
 pub struct FlowStats {
@@ -153,6 +487,11 @@ pub struct FlowStats {
     pub min_gap: Option<u32>,
     pub max_gap: Option<u32>,
 
+    /// MACsec only: AN changes seen on this flow's Secure Channel (same SCI)
+    /// up to the latest packet of this flow. Always 0 for IPsec/GenericL3.
+    #[cfg_attr(feature = "rest-api", serde(default))]
+    pub an_rotations: u64,
+
     // Enhanced statistics
     pub total_bytes: u64,
     #[cfg_attr(feature = "rest-api", serde(serialize_with = "serialize_systemtime_option"))]
@@ -168,6 +507,293 @@ pub struct FlowStats {
     // For GenericL3: already in FlowId, so this is for inner protocols if needed
     #[cfg_attr(feature = "rest-api", serde(skip))]  // Skip HashMap in JSON
     pub protocol_distribution: HashMap<u8, u64>,
+
+    /// Packets seen per protocol version (see `SequenceInfo::proto_version`)
+    #[cfg_attr(feature = "rest-api", serde(default))]
+    pub protocol_version_distribution: HashMap<u8, u64>,
+    /// Packets seen per DSCP class (see `SequenceInfo::dscp`)
+    pub dscp_distribution: HashMap<u8, u64>,
+    /// Packets marked ECN Congestion Experienced (CE)
+    pub ecn_congestion_signals: u64,
+
+    /// Latest estimated one-way delay in microseconds (MACsec/IPsec only).
+    /// Absolute only when sender and receiver clocks are synchronized (PTP/GPS);
+    /// otherwise relative to the first packet of the flow.
+    pub estimated_owd_us: Option<i64>,
+    /// Standard deviation of the OWD estimates over the last 60 seconds
+    pub owd_std_dev_us: Option<f64>,
+
+    /// Variance of inter-arrival times (microseconds squared)
+    #[cfg_attr(feature = "rest-api", serde(default))]
+    pub inter_arrival_variance_us2: u64,
+    /// Standard deviation of inter-arrival times (microseconds)
+    #[cfg_attr(feature = "rest-api", serde(default))]
+    pub std_dev_us: u64,
+    /// Coefficient of variation of inter-arrival times (std dev / mean).
+    /// Near 0 for constant-bitrate flows, above 1 for bursty traffic.
+    #[cfg_attr(feature = "rest-api", serde(default))]
+    pub burstiness_index: f64,
+    /// Percentage of detected gaps later filled by out-of-order packets
+    #[cfg_attr(feature = "rest-api", serde(default))]
+    pub pct_gaps_recovered: f64,
+    /// Stale reorder-buffer entries that had already been passed by the
+    /// expected sequence number when they were pruned
+    #[cfg_attr(feature = "rest-api", serde(default))]
+    pub duplicate_packets: u64,
+    /// Exponentially weighted moving average of the per-gap loss fraction,
+    /// updated on each gap detection. Tracks recent loss trends.
+    #[cfg_attr(feature = "rest-api", serde(default))]
+    pub loss_rate_ema: f64,
+    /// Loss fraction of the most recent gap: `gap_size / (gap_size + 1)`
+    #[cfg_attr(feature = "rest-api", serde(default))]
+    pub loss_rate_instantaneous: f64,
+}
+
+impl FlowStats {
+    /// Time between the first and last packet of the flow, in seconds
+    fn duration_secs(&self) -> Option<f64> {
+        self.first_timestamp
+            .zip(self.last_timestamp)
+            .and_then(|(first, last)| last.duration_since(first).ok())
+            .map(|d| d.as_secs_f64())
+    }
+
+    /// Lost packets as a percentage of expected packets, clamped to 0-100
+    ///
+    /// A flow with no packets received or lost has 0% loss.
+    pub fn packet_loss_percent(&self) -> f64 {
+        loss_percent(self.packets_received, self.total_lost_packets)
+    }
+
+    /// Same as `packet_loss_percent`
+    pub fn loss_percentage(&self) -> f64 {
+        self.packet_loss_percent()
+    }
+
+    /// Average bandwidth over the flow's lifetime in Mbps
+    ///
+    /// `None` for flows with fewer than two distinct timestamps.
+    pub fn bandwidth_mbps(&self) -> Option<f64> {
+        self.duration_secs()
+            .filter(|&secs| secs > 0.0)
+            .map(|secs| self.total_bytes as f64 * 8.0 / secs / 1_000_000.0)
+    }
+}
+
+/// Lost packets as a percentage of `received + lost`, clamped to 0-100
+fn loss_percent(received: u64, lost: u64) -> f64 {
+    let expected = received.saturating_add(lost);
+    if expected == 0 {
+        0.0
+    } else {
+        (lost as f64 / expected as f64 * 100.0).clamp(0.0, 100.0)
+    }
+}
+
+/// Orders flows by `packets_received`, then by `FlowId`
+///
+/// Flows with the same count and ID but different statistics are unordered,
+/// keeping the ordering consistent with `PartialEq`.
+impl PartialOrd for FlowStats {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self
+            .packets_received
+            .cmp(&other.packets_received)
+            .then_with(|| self.flow_id.cmp(&other.flow_id))
+        {
+            Ordering::Equal if self != other => None,
+            ordering => Some(ordering),
+        }
+    }
+}
+
+/// Sort order for flow tables, highest value first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "rest-api", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "rest-api", serde(rename_all = "snake_case"))]
+pub enum FlowSortMetric {
+    PacketsDesc,
+    BytesDesc,
+    GapsDesc,
+    /// By `FlowStats::loss_percentage`
+    LossRateDesc,
+    /// By `FlowStats::bandwidth_mbps`; flows without a bandwidth sort last
+    BandwidthDesc,
+}
+
+/// Compares flows by a `FlowSortMetric`
+///
+/// Ties are broken by `FlowId` so the order is deterministic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowStatsComparator {
+    pub metric: FlowSortMetric,
+}
+
+impl FlowStatsComparator {
+    pub fn new(metric: FlowSortMetric) -> Self {
+        Self { metric }
+    }
+
+    /// `Less` when `a` should be listed before `b`
+    pub fn compare(&self, a: &FlowStats, b: &FlowStats) -> Ordering {
+        let by_metric = match self.metric {
+            FlowSortMetric::PacketsDesc => b.packets_received.cmp(&a.packets_received),
+            FlowSortMetric::BytesDesc => b.total_bytes.cmp(&a.total_bytes),
+            FlowSortMetric::GapsDesc => b.gaps_detected.cmp(&a.gaps_detected),
+            FlowSortMetric::LossRateDesc => b.loss_percentage().total_cmp(&a.loss_percentage()),
+            // Some > None, so reversing puts flows without a bandwidth last
+            FlowSortMetric::BandwidthDesc => match (a.bandwidth_mbps(), b.bandwidth_mbps()) {
+                (Some(a), Some(b)) => b.total_cmp(&a),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+        };
+        by_metric.then_with(|| a.flow_id.cmp(&b.flow_id))
+    }
+
+    /// Sort `stats` in place by the selected metric
+    pub fn sort_inplace(&self, stats: &mut [FlowStats]) {
+        stats.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+/// Sorting helpers for collections of `FlowStats`
+pub trait FlowStatsSortExt {
+    /// Consume the collection and return it sorted by `metric`
+    fn sorted_by_metric(self, metric: FlowSortMetric) -> Vec<FlowStats>;
+}
+
+impl FlowStatsSortExt for Vec<FlowStats> {
+    fn sorted_by_metric(mut self, metric: FlowSortMetric) -> Vec<FlowStats> {
+        FlowStatsComparator::new(metric).sort_inplace(&mut self);
+        self
+    }
+}
+
+/// Metric used to rank or filter flows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "rest-api", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "rest-api", serde(rename_all = "snake_case"))]
+pub enum FlowMetric {
+    /// Packets per second over the flow's lifetime
+    PacketRate,
+    /// Bytes per second over the flow's lifetime
+    ByteRate,
+    /// Number of sequence gaps detected
+    GapCount,
+    /// Lost packets as a percentage of expected packets (0-100)
+    LossPercentage,
+}
+
+impl FlowMetric {
+    /// Evaluate this metric for a flow
+    ///
+    /// Rates are 0.0 for flows with fewer than two distinct timestamps.
+    pub fn value(&self, stats: &FlowStats) -> f64 {
+        self.value_from_counters(
+            stats.packets_received,
+            stats.total_bytes,
+            stats.gaps_detected,
+            stats.total_lost_packets,
+            stats.duration_secs(),
+        )
+    }
+
+    /// Same as `value`, from the raw counters instead of a `FlowStats`
+    ///
+    /// Lets the flow trackers rank flows without building a snapshot of each.
+    pub(crate) fn value_from_counters(
+        &self,
+        packets_received: u64,
+        total_bytes: u64,
+        gaps_detected: u64,
+        total_lost_packets: u64,
+        duration_secs: Option<f64>,
+    ) -> f64 {
+        let per_second = |count: u64| match duration_secs {
+            Some(secs) if secs > 0.0 => count as f64 / secs,
+            _ => 0.0,
+        };
+        match self {
+            FlowMetric::PacketRate => per_second(packets_received),
+            FlowMetric::ByteRate => per_second(total_bytes),
+            FlowMetric::GapCount => gaps_detected as f64,
+            FlowMetric::LossPercentage => loss_percent(packets_received, total_lost_packets),
+        }
+    }
+}
+
+/// Item paired with its score and tie-break key for heap ordering
+/// (f64 has no total order of its own)
+struct Ranked<K, T> {
+    score: f64,
+    key: K,
+    item: T,
+}
+
+impl<K: Ord, T> PartialEq for Ranked<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K: Ord, T> Eq for Ranked<K, T> {}
+
+impl<K: Ord, T> PartialOrd for Ranked<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> Ord for Ranked<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.key.cmp(&self.key))
+    }
+}
+
+/// Select the `n` highest-scoring items, in descending order of score
+///
+/// Items are `(score, key, item)`; ties go to the smaller key. Keeps a
+/// min-heap of at most `n` entries, so the input is never fully sorted.
+pub(crate) fn top_n_by_score<K, T, I>(scored: I, n: usize) -> Vec<T>
+where
+    K: Ord,
+    I: IntoIterator<Item = (f64, K, T)>,
+{
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let mut heap: BinaryHeap<Reverse<Ranked<K, T>>> = BinaryHeap::with_capacity(n + 1);
+    for (score, key, item) in scored {
+        heap.push(Reverse(Ranked { score, key, item }));
+        if heap.len() > n {
+            heap.pop();
+        }
+    }
+
+    // into_sorted_vec() is ascending in Reverse order, i.e. highest score first
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(ranked)| ranked.item)
+        .collect()
+}
+
+/// Select the `n` flows with the highest value of `metric`, in descending order
+///
+/// Keeps a min-heap of at most `n` entries, so the input is never fully sorted.
+/// Ties are broken by `FlowId` so results are deterministic.
+pub fn top_n_by_metric<I>(flows: I, n: usize, metric: FlowMetric) -> Vec<FlowStats>
+where
+    I: IntoIterator<Item = FlowStats>,
+{
+    top_n_by_score(
+        flows
+            .into_iter()
+            .map(|stats| (metric.value(&stats), stats.flow_id.clone(), stats)),
+        n,
+    )
 }
 
 /// Serialize SystemTime to ISO 8601 string for REST API
@@ -206,6 +832,8 @@ pub struct ProcessMetrics {
     pub detect_us: u128,    // Protocol detection time in microseconds (debug only)
     pub track_us: u128,     // Flow tracking time in microseconds (debug only)
     pub total_us: u128,     // Total processing time including overhead (debug only)
+    /// Per-tier registry timing (debug only, not with a forced parser)
+    pub detection: Option<crate::protocol::DetectionMetrics>,
 }
 
 /// Statistics from packet capture source
@@ -215,22 +843,846 @@ pub struct CaptureStats {
     pub packets_dropped: u64,
 }
 
+/// Traffic counters for one protocol within a report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "rest-api", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "rest-api", serde(crate = "serde"))]
+pub struct ProtocolBreakdown {
+    pub packets: u64,
+    pub flows: u64,
+    pub bytes: u64,
+}
+
 /// Complete analysis report
 #[derive(Debug)]
 pub struct AnalysisReport {
     pub total_packets: u64,
-    pub protocol: String,
+    /// Parsed traffic per protocol, keyed by `FlowId::protocol_name()`
+    pub protocol_breakdown: HashMap<String, ProtocolBreakdown>,
     pub gaps: Vec<SequenceGap>,
     pub flow_stats: Vec<FlowStats>,
+    /// Capture source counters taken after the last packet was read
+    pub capture_stats: Option<CaptureStats>,
+}
+
+impl Default for AnalysisReport {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AnalysisReport {
-    pub fn new(protocol: String) -> Self {
+    /// Gaps shown at the start and end of a flow before rows are omitted
+    const TABLE_EDGE_ROWS: usize = 10;
+
+    pub fn new() -> Self {
         Self {
             total_packets: 0,
-            protocol,
+            protocol_breakdown: HashMap::new(),
             gaps: Vec::new(),
             flow_stats: Vec::new(),
+            capture_stats: None,
+        }
+    }
+
+    /// Share of parsed packets belonging to `protocol`, in percent
+    ///
+    /// Packets no parser recognised are not counted, so the shares of all
+    /// protocols add up to 100 whenever anything was parsed.
+    pub fn packet_percentage(&self, protocol: &str) -> f64 {
+        let parsed: u64 = self.protocol_breakdown.values().map(|b| b.packets).sum();
+        match self.protocol_breakdown.get(protocol) {
+            Some(breakdown) if parsed > 0 => breakdown.packets as f64 * 100.0 / parsed as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// Gaps in `gaps` per million packets read
+    ///
+    /// Reports from `PacketAnalyzer::analyze_streaming` keep no gaps, so
+    /// this is 0 for them.
+    pub fn gap_rate_per_million_packets(&self) -> f64 {
+        if self.total_packets == 0 {
+            return 0.0;
+        }
+        self.gaps.len() as f64 / self.total_packets as f64 * 1_000_000.0
+    }
+
+    /// Packets missing from `gaps` as a percentage of all expected packets
+    ///
+    /// The expected count is `total_packets` plus the missing ones. Like
+    /// `gap_rate_per_million_packets`, this only sees buffered gaps.
+    pub fn loss_percent(&self) -> f64 {
+        if self.total_packets == 0 {
+            return 0.0;
         }
+        let lost: u64 = self.gaps.iter().map(|gap| gap.gap_size as u64).sum();
+        lost as f64 * 100.0 / (self.total_packets + lost) as f64
+    }
+
+    /// Aggregate metrics across every flow, see `StatisticalSummary`
+    pub fn statistical_summary(&self) -> StatisticalSummary {
+        let flows = &self.flow_stats;
+        let total_packets: u64 = flows.iter().map(|s| s.packets_received).sum();
+        let total_lost: u64 = flows.iter().map(|s| s.total_lost_packets).sum();
+        let expected = total_packets + total_lost;
+
+        let bandwidths: Vec<f64> = flows.iter().filter_map(FlowStats::bandwidth_mbps).collect();
+        let jitters: Vec<u64> = flows
+            .iter()
+            .filter(|s| s.avg_inter_arrival.is_some())
+            .map(|s| s.std_dev_us)
+            .collect();
+
+        let first = flows.iter().filter_map(|s| s.first_timestamp).min();
+        let last = flows.iter().filter_map(|s| s.last_timestamp).max();
+
+        StatisticalSummary {
+            total_flows: flows.len(),
+            total_packets,
+            total_bytes: flows.iter().map(|s| s.total_bytes).sum(),
+            total_gaps: flows.iter().map(|s| s.gaps_detected).sum(),
+            loss_pct: if expected > 0 {
+                total_lost as f64 * 100.0 / expected as f64
+            } else {
+                0.0
+            },
+            peak_bandwidth_mbps: bandwidths.iter().copied().fold(0.0, f64::max),
+            avg_bandwidth_mbps: if bandwidths.is_empty() {
+                0.0
+            } else {
+                bandwidths.iter().sum::<f64>() / bandwidths.len() as f64
+            },
+            max_jitter_us: jitters.iter().copied().max().unwrap_or(0),
+            avg_jitter_us: if jitters.is_empty() {
+                0
+            } else {
+                jitters.iter().sum::<u64>() / jitters.len() as u64
+            },
+            capture_duration: first
+                .zip(last)
+                .and_then(|(first, last)| last.duration_since(first).ok())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Fixed-width table of every gap: expected vs received sequence
+    ///
+    /// Rows are sorted by flow ID, then timestamp. Flows with more than 20
+    /// gaps show the first 10 and last 10 with an omission line in between.
+    pub fn expected_vs_actual_table(&self) -> String {
+        let rows = self.gap_rows();
+        let flow_width = rows
+            .iter()
+            .map(|row| row.flow_id.len())
+            .max()
+            .unwrap_or(0)
+            .max("Flow ID".len());
+
+        let mut table = format!(
+            "{:<fw$} | {:>10} | {:>10} | {:>8} | {:>7} | {:>17}\n",
+            "Flow ID", "Expected", "Received", "Gap Size", "% Loss", "Timestamp",
+            fw = flow_width
+        );
+        table.push_str(&format!(
+            "{}-+-{}-+-{}-+-{}-+-{}-+-{}\n",
+            "-".repeat(flow_width),
+            "-".repeat(10),
+            "-".repeat(10),
+            "-".repeat(8),
+            "-".repeat(7),
+            "-".repeat(17)
+        ));
+
+        for flow_rows in rows.chunk_by(|a, b| a.flow_id == b.flow_id) {
+            let omitted = flow_rows.len().saturating_sub(2 * Self::TABLE_EDGE_ROWS);
+            for (i, row) in flow_rows.iter().enumerate() {
+                if omitted > 0 && i == Self::TABLE_EDGE_ROWS {
+                    table.push_str(&format!("... {} gaps omitted ...\n", omitted));
+                }
+                if omitted > 0
+                    && i >= Self::TABLE_EDGE_ROWS
+                    && i < Self::TABLE_EDGE_ROWS + omitted
+                {
+                    continue;
+                }
+                table.push_str(&format!(
+                    "{:<fw$} | {:>10} | {:>10} | {:>8} | {:>6.2}% | {:>17.6}\n",
+                    row.flow_id,
+                    row.gap.expected,
+                    row.gap.received,
+                    row.gap.gap_size,
+                    row.loss_pct,
+                    row.timestamp_secs,
+                    fw = flow_width
+                ));
+            }
+        }
+
+        table
+    }
+
+    /// All gaps as CSV, in the same order as `expected_vs_actual_table()`
+    ///
+    /// Flow IDs contain commas, so they are always quoted.
+    pub fn gaps_csv(&self) -> String {
+        let mut csv = String::from("flow_id,expected,received,gap_size,loss_pct,timestamp\n");
+        for row in self.gap_rows() {
+            csv.push_str(&format!(
+                "\"{}\",{},{},{},{:.4},{:.6}\n",
+                row.flow_id.replace('"', "\"\""),
+                row.gap.expected,
+                row.gap.received,
+                row.gap.gap_size,
+                row.loss_pct,
+                row.timestamp_secs
+            ));
+        }
+        csv
+    }
+
+    /// Gaps sorted by flow ID then timestamp, with per-gap loss percentage
+    ///
+    /// Loss is the gap size relative to the flow's expected packet count
+    /// (received + lost); it is 100% when the flow has no statistics.
+    fn gap_rows(&self) -> Vec<GapRow<'_>> {
+        let expected_per_flow: HashMap<&FlowId, u64> = self
+            .flow_stats
+            .iter()
+            .map(|s| (&s.flow_id, s.packets_received + s.total_lost_packets))
+            .collect();
+
+        let mut rows: Vec<GapRow> = self
+            .gaps
+            .iter()
+            .map(|gap| {
+                let expected = expected_per_flow
+                    .get(&gap.flow_id)
+                    .copied()
+                    .unwrap_or(0)
+                    .max(gap.gap_size as u64);
+                GapRow {
+                    flow_id: gap.flow_id.to_string(),
+                    gap,
+                    loss_pct: if expected > 0 {
+                        gap.gap_size as f64 * 100.0 / expected as f64
+                    } else {
+                        0.0
+                    },
+                    timestamp_secs: gap
+                        .timestamp
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .map(|d| d.as_secs_f64())
+                        .unwrap_or(0.0),
+                }
+            })
+            .collect();
+
+        rows.sort_by(|a, b| {
+            a.flow_id
+                .cmp(&b.flow_id)
+                .then(a.gap.timestamp.cmp(&b.gap.timestamp))
+        });
+        rows
+    }
+}
+
+/// Whole-capture metrics, printed as one line by `Display`
+///
+/// Computed from `AnalysisReport::flow_stats`. Bandwidth figures are
+/// per-flow averages (peak is the busiest flow); jitter is the per-flow
+/// inter-arrival standard deviation, over flows with at least two packets.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatisticalSummary {
+    pub total_flows: usize,
+    pub total_packets: u64,
+    pub total_bytes: u64,
+    pub total_gaps: u64,
+    /// Lost packets as a percentage of expected packets, across all flows
+    pub loss_pct: f64,
+    pub peak_bandwidth_mbps: f64,
+    pub avg_bandwidth_mbps: f64,
+    pub max_jitter_us: u64,
+    pub avg_jitter_us: u64,
+    /// Earliest first packet to latest last packet over all flows
+    pub capture_duration: Duration,
+}
+
+impl fmt::Display for StatisticalSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} flows, {} packets, {} gaps, {:.4}% loss, peak {:.0} Mbps, avg jitter {} \u{b5}s",
+            self.total_flows,
+            self.total_packets,
+            self.total_gaps,
+            self.loss_pct,
+            self.peak_bandwidth_mbps,
+            self.avg_jitter_us,
+        )
+    }
+}
+
+/// Hosts and the traffic between them, built from `GenericL3` flows
+///
+/// Flows between the same two hosts on different ports are merged into one
+/// edge. MACsec and IPsec flows don't identify both hosts and are skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlowGraph {
+    /// Every host seen as a source or destination, sorted
+    pub nodes: Vec<IpAddr>,
+    /// `(src_ip, dst_ip, total_bytes)` per direction, sorted by host pair
+    pub edges: Vec<(IpAddr, IpAddr, u64)>,
+}
+
+impl FlowGraph {
+    /// Aggregate `GenericL3` flows into a host graph
+    pub fn from_stats<'a>(stats: impl IntoIterator<Item = &'a FlowStats>) -> Self {
+        Self::from_byte_counts(stats.into_iter().map(|s| (&s.flow_id, s.total_bytes)))
+    }
+
+    /// Same as `from_stats`, from `(flow_id, total_bytes)` pairs
+    ///
+    /// Lets callers build the graph without a full `FlowStats` per flow.
+    pub fn from_byte_counts<F>(flows: impl IntoIterator<Item = (F, u64)>) -> Self
+    where
+        F: std::borrow::Borrow<FlowId>,
+    {
+        let mut edges: BTreeMap<(IpAddr, IpAddr), u64> = BTreeMap::new();
+        for (flow_id, bytes) in flows {
+            if let FlowId::GenericL3 { src_ip, dst_ip, .. } = *flow_id.borrow() {
+                *edges.entry((src_ip, dst_ip)).or_default() += bytes;
+            }
+        }
+
+        let nodes: BTreeSet<IpAddr> = edges.keys().flat_map(|&(src, dst)| [src, dst]).collect();
+        Self {
+            nodes: nodes.into_iter().collect(),
+            edges: edges
+                .into_iter()
+                .map(|((src, dst), bytes)| (src, dst, bytes))
+                .collect(),
+        }
+    }
+
+    /// Render as a Graphviz `digraph`, edges labelled with their byte count
+    ///
+    /// `dot -Tsvg graph.dot -o graph.svg` turns the output into an image.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph flows {\n");
+        for node in &self.nodes {
+            dot.push_str(&format!("    \"{}\";\n", node));
+        }
+        for (src, dst, bytes) in &self.edges {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{} bytes\"];\n",
+                src, dst, bytes
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// One gap prepared for tabular output
+struct GapRow<'a> {
+    flow_id: String,
+    gap: &'a SequenceGap,
+    loss_pct: f64,
+    timestamp_secs: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    /// Small deterministic generator (xorshift64) for roundtrip coverage
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn ip(&mut self) -> IpAddr {
+            if self.next().is_multiple_of(2) {
+                IpAddr::V4(Ipv4Addr::from(self.next() as u32))
+            } else {
+                IpAddr::V6(Ipv6Addr::from(
+                    ((self.next() as u128) << 64) | self.next() as u128,
+                ))
+            }
+        }
+
+        fn flow_id(&mut self) -> FlowId {
+            match self.next() % 3 {
+                0 => FlowId::MACsec {
+                    sci: self.next(),
+                    an: (self.next() % 4) as u8,
+                },
+                1 => FlowId::IPsec {
+                    spi: self.next() as u32,
+                    dst_ip: self.ip(),
+                    mode: match self.next() % 3 {
+                        0 => None,
+                        1 => Some(IpsecMode::Transport),
+                        _ => Some(IpsecMode::Tunnel),
+                    },
+                },
+                _ => FlowId::GenericL3 {
+                    src_ip: self.ip(),
+                    dst_ip: self.ip(),
+                    src_port: self.next() as u16,
+                    dst_port: self.next() as u16,
+                    protocol: self.next() as u8,
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn test_flow_id_from_str_examples() {
+        assert_eq!(
+            "MACsec { sci: 0x0011223344556677, an: 2 }".parse::<FlowId>().unwrap(),
+            FlowId::MACsec { sci: 0x0011223344556677, an: 2 }
+        );
+        assert_eq!(
+            "IPsec { spi: 0x0000002a, dst: 1.2.3.4 }".parse::<FlowId>().unwrap(),
+            FlowId::IPsec { spi: 42, dst_ip: IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), mode: None }
+        );
+        assert_eq!(
+            "IPsec { spi: 0x0000002a, dst: 1.2.3.4, mode: tunnel }".parse::<FlowId>().unwrap(),
+            FlowId::IPsec {
+                spi: 42,
+                dst_ip: IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)),
+                mode: Some(IpsecMode::Tunnel),
+            }
+        );
+        assert_eq!(
+            "UDP { ::1:53 -> 10.0.0.1:5353 }".parse::<FlowId>().unwrap(),
+            FlowId::GenericL3 {
+                src_ip: IpAddr::V6(Ipv6Addr::LOCALHOST),
+                dst_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                src_port: 53,
+                dst_port: 5353,
+                protocol: 17,
+            }
+        );
+    }
+
+    #[test]
+    fn test_flow_id_from_str_rejects_malformed() {
+        for bad in [
+            "",
+            "MACsec",
+            "MACsec { sci: 0xzz, an: 0 }",
+            "MACsec { sci: 0x01, an: 300 }",
+            "IPsec { spi: 0x01, dst: not-an-ip }",
+            "IPsec { spi: 0x01, dst: 1.2.3.4, mode: beet }",
+            "IPsec { spi: 0x01, dst: 1.2.3.4, mode: tunnel, extra: 1 }",
+            "TCP { 1.2.3.4 -> 5.6.7.8:80 }",
+            "SCTP { 1.2.3.4:1 -> 5.6.7.8:80 }",
+        ] {
+            assert!(
+                matches!(bad.parse::<FlowId>(), Err(crate::error::ParseError::InvalidFormat(_))),
+                "{:?} should not parse",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_flow_id_display_roundtrip() {
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..10_000 {
+            let flow_id = rng.flow_id();
+            assert_eq!(flow_id.to_string().parse::<FlowId>().unwrap(), flow_id);
+        }
+    }
+
+    #[test]
+    fn test_flow_id_compact_string_examples() {
+        let macsec = FlowId::MACsec { sci: 0x1234, an: 0 };
+        assert_eq!(macsec.to_compact_string(), "macsec-0000000000001234-0");
+
+        let ipsec = FlowId::IPsec {
+            spi: 1,
+            dst_ip: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+            mode: None,
+        };
+        assert_eq!(ipsec.to_compact_string(), "ipsec-00000001-192.168.1.1");
+        let tunnel = FlowId::IPsec {
+            spi: 1,
+            dst_ip: IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+            mode: Some(IpsecMode::Tunnel),
+        };
+        assert_eq!(
+            tunnel.to_compact_string(),
+            "ipsec-00000001-192.168.1.1-tunnel"
+        );
+
+        let tcp = FlowId::from_five_tuple(
+            6,
+            IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)),
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            12345,
+            80,
+        );
+        assert_eq!(tcp.to_compact_string(), "l3-06-c0a80101-0a000001-3039-0050");
+
+        for flow_id in [macsec, ipsec, tunnel, tcp] {
+            let compact = flow_id.to_compact_string();
+            assert!(compact
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.'));
+            assert_eq!(FlowId::from_compact_string(&compact).unwrap(), flow_id);
+        }
+    }
+
+    #[test]
+    fn test_flow_id_compact_string_roundtrip() {
+        let mut rng = XorShift(0xD1B5_4A32_D192_ED03);
+        for _ in 0..10_000 {
+            let flow_id = rng.flow_id();
+            let compact = flow_id.to_compact_string();
+            assert!(!compact.contains(|c: char| c.is_whitespace() || c.is_ascii_uppercase()));
+            assert_eq!(FlowId::from_compact_string(&compact).unwrap(), flow_id);
+        }
+    }
+
+    #[test]
+    fn test_flow_id_from_compact_string_rejects_malformed() {
+        for bad in [
+            "",
+            "macsec",
+            "macsec-1234-0",
+            "macsec-000000000000ABCD-0",
+            "macsec-0000000000001234-300",
+            "macsec-0000000000001234-0-1",
+            "ipsec-00000001-not.an.ip",
+            "ipsec-00000001-1.2.3.4-beet",
+            "ipsec-00000001-1.2.3.4-tunnel-extra",
+            "l3-06-c0a80101-0a000001-3039",
+            "l3-06-c0a801-0a000001-3039-0050",
+            "l3-6-c0a80101-0a000001-3039-0050",
+            "l3-06-c0a80101-0a000001-+039-0050",
+            "MACsec { sci: 0x0000000000001234, an: 0 }",
+        ] {
+            assert!(
+                matches!(
+                    FlowId::from_compact_string(bad),
+                    Err(crate::error::ParseError::InvalidFormat(_))
+                ),
+                "{:?} should not parse",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_flow_id_new_uses_exact_parse() {
+        let flow_id = FlowId::IPsec { spi: 7, dst_ip: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 9)), mode: None };
+        assert_eq!(FlowId::new(flow_id.to_string()), flow_id);
+    }
+
+    #[test]
+    fn test_flow_id_convenience_constructors() {
+        const SRC: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        const DST: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        const WEB: FlowId = FlowId::from_five_tuple(6, SRC, DST, 50000, 443);
+
+        assert_eq!(WEB.five_tuple(), Some((6, SRC, DST, 50000, 443)));
+        assert_eq!(WEB.protocol_type(), "GenericL3");
+        assert_eq!(WEB.protocol_name(), "TCP");
+
+        let macsec = FlowId::from_macsec_sci(0xABCD);
+        assert_eq!(macsec, FlowId::MACsec { sci: 0xABCD, an: 0 });
+        assert_eq!(macsec.protocol_type(), "MACsec");
+        assert_eq!(macsec.five_tuple(), None);
+
+        let ipsec = FlowId::from_ipsec(42, DST);
+        assert_eq!(ipsec, FlowId::IPsec { spi: 42, dst_ip: DST, mode: None });
+        assert_eq!(ipsec.protocol_type(), "IPsec");
+        assert_eq!(ipsec.five_tuple(), None);
+    }
+
+    fn report_with_gaps(flow: FlowId, count: u32) -> AnalysisReport {
+        let base = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut report = AnalysisReport::new();
+        // Insert in reverse to check sorting by timestamp
+        for i in (0..count).rev() {
+            report.gaps.push(SequenceGap {
+                flow_id: flow.clone(),
+                expected: i * 10 + 1,
+                received: i * 10 + 3,
+                gap_size: 2,
+                timestamp: base + Duration::from_secs(i as u64),
+                recovery_time: None,
+            });
+        }
+        report
+    }
+
+    #[test]
+    fn test_expected_vs_actual_table_omits_middle_gaps() {
+        let flow = FlowId::MACsec { sci: 0x1, an: 0 };
+        let report = report_with_gaps(flow.clone(), 25);
+
+        let table = report.expected_vs_actual_table();
+        let lines: Vec<&str> = table.lines().collect();
+
+        // Header + separator + 10 rows + omission line + 10 rows
+        assert_eq!(lines.len(), 23);
+        assert!(lines[0].starts_with("Flow ID"));
+        assert!(lines[0].contains("|   Expected |   Received |"));
+        assert!(lines[0].ends_with("Timestamp"));
+        assert_eq!(lines[12], "... 5 gaps omitted ...");
+
+        // First rows are the earliest gaps, last rows the latest
+        assert!(lines[2].starts_with(&flow.to_string()));
+        assert!(lines[2].contains("|          1 |          3 |"));
+        assert!(lines[11].contains("|         91 |         93 |"));
+        assert!(lines[13].contains("|        151 |        153 |"));
+        assert!(lines[22].contains("|        241 |        243 |"));
+
+        // Fixed width: every data row lines up with the header
+        for line in lines.iter().filter(|l| !l.starts_with("...")) {
+            assert_eq!(line.len(), lines[0].len());
+        }
+    }
+
+    #[test]
+    fn test_expected_vs_actual_table_sorts_flows_and_loss() {
+        let flow_b = FlowId::MACsec { sci: 0xB, an: 0 };
+        let flow_a = FlowId::MACsec { sci: 0xA, an: 0 };
+        let mut report = report_with_gaps(flow_b.clone(), 2);
+        report.gaps.extend(report_with_gaps(flow_a.clone(), 1).gaps);
+
+        report.flow_stats.push(FlowStats {
+            flow_id: flow_a.clone(),
+            packets_received: 98,
+            gaps_detected: 1,
+            total_lost_packets: 2,
+            first_sequence: None,
+            last_sequence: None,
+            min_gap: None,
+            max_gap: None,
+            an_rotations: 0,
+            total_bytes: 0,
+            first_timestamp: None,
+            last_timestamp: None,
+            min_inter_arrival: None,
+            max_inter_arrival: None,
+            avg_inter_arrival: None,
+            protocol_distribution: Default::default(),
+            protocol_version_distribution: Default::default(),
+            dscp_distribution: Default::default(),
+            ecn_congestion_signals: 0,
+            estimated_owd_us: None,
+            owd_std_dev_us: None,
+            inter_arrival_variance_us2: 0,
+            std_dev_us: 0,
+            burstiness_index: 0.0,
+            pct_gaps_recovered: 0.0,
+            duplicate_packets: 0,
+            loss_rate_ema: 0.0,
+            loss_rate_instantaneous: 0.0,
+        });
+
+        let table = report.expected_vs_actual_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[2].starts_with(&flow_a.to_string()));
+        assert!(lines[2].contains("|   2.00% |"));
+        assert!(lines[3].starts_with(&flow_b.to_string()));
+        assert!(lines[4].starts_with(&flow_b.to_string()));
+        // No flow stats: the gap is the only thing known about the flow
+        assert!(lines[3].contains("| 100.00% |"));
+    }
+
+    fn sortable_stats(sci: u64, packets: u64, bytes: u64, gaps: u64, lost: u64, secs: u64) -> FlowStats {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        FlowStats {
+            flow_id: FlowId::MACsec { sci, an: 0 },
+            packets_received: packets,
+            gaps_detected: gaps,
+            total_lost_packets: lost,
+            first_sequence: None,
+            last_sequence: None,
+            min_gap: None,
+            max_gap: None,
+            an_rotations: 0,
+            total_bytes: bytes,
+            first_timestamp: Some(start),
+            last_timestamp: Some(start + Duration::from_secs(secs)),
+            min_inter_arrival: None,
+            max_inter_arrival: None,
+            avg_inter_arrival: None,
+            protocol_distribution: Default::default(),
+            protocol_version_distribution: Default::default(),
+            dscp_distribution: Default::default(),
+            ecn_congestion_signals: 0,
+            estimated_owd_us: None,
+            owd_std_dev_us: None,
+            inter_arrival_variance_us2: 0,
+            std_dev_us: 0,
+            burstiness_index: 0.0,
+            pct_gaps_recovered: 0.0,
+            duplicate_packets: 0,
+            loss_rate_ema: 0.0,
+            loss_rate_instantaneous: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_flow_stats_computed_properties() {
+        let stats = sortable_stats(1, 90, 1_250_000, 1, 10, 10);
+        assert_eq!(stats.loss_percentage(), 10.0);
+        assert_eq!(stats.bandwidth_mbps(), Some(1.0));
+
+        let instant = sortable_stats(2, 0, 100, 0, 0, 0);
+        assert_eq!(instant.loss_percentage(), 0.0);
+        assert_eq!(instant.bandwidth_mbps(), None);
+    }
+
+    #[test]
+    fn test_packet_loss_percent_edges() {
+        let lossless = sortable_stats(1, 500, 0, 0, 0, 1);
+        assert_eq!(lossless.packet_loss_percent(), 0.0);
+
+        // Nothing received at all, every expected packet lost
+        let dead = sortable_stats(2, 0, 0, 1, 40, 1);
+        assert_eq!(dead.packet_loss_percent(), 100.0);
+
+        let empty = sortable_stats(3, 0, 0, 0, 0, 0);
+        assert_eq!(empty.packet_loss_percent(), 0.0);
+
+        // The expected count saturates instead of overflowing
+        let huge = sortable_stats(4, u64::MAX, 0, 1, u64::MAX, 1);
+        assert!((0.0..=100.0).contains(&huge.packet_loss_percent()));
+    }
+
+    #[test]
+    fn test_statistical_summary() {
+        let mut jittery = sortable_stats(1, 90, 1_250_000, 1, 10, 10); // 1 Mbps
+        jittery.avg_inter_arrival = Some(Duration::from_micros(100));
+        jittery.std_dev_us = 4;
+        let mut steady = sortable_stats(2, 10, 1_250_000, 2, 0, 5); // 2 Mbps
+        steady.avg_inter_arrival = Some(Duration::from_micros(100));
+        steady.std_dev_us = 2;
+        let single = sortable_stats(3, 0, 0, 0, 0, 0);
+
+        let report = AnalysisReport {
+            flow_stats: vec![jittery, steady, single],
+            ..AnalysisReport::new()
+        };
+        let summary = report.statistical_summary();
+
+        assert_eq!(summary.total_flows, 3);
+        assert_eq!(summary.total_packets, 100);
+        assert_eq!(summary.total_bytes, 2_500_000);
+        assert_eq!(summary.total_gaps, 3);
+        assert!((summary.loss_pct - 10.0 * 100.0 / 110.0).abs() < 1e-9);
+        assert_eq!(summary.peak_bandwidth_mbps, 2.0);
+        assert_eq!(summary.avg_bandwidth_mbps, 1.5);
+        // The single-timestamp flow has no inter-arrival data
+        assert_eq!(summary.max_jitter_us, 4);
+        assert_eq!(summary.avg_jitter_us, 3);
+        assert_eq!(summary.capture_duration, Duration::from_secs(10));
+        assert_eq!(
+            summary.to_string(),
+            "3 flows, 100 packets, 3 gaps, 9.0909% loss, peak 2 Mbps, avg jitter 3 \u{b5}s"
+        );
+
+        assert_eq!(
+            AnalysisReport::new().statistical_summary(),
+            StatisticalSummary::default()
+        );
+    }
+
+    #[test]
+    fn test_gap_rate_and_loss_percent() {
+        // 4 gaps of 2 missing packets each
+        let mut report = report_with_gaps(FlowId::MACsec { sci: 1, an: 0 }, 4);
+        assert_eq!(report.gap_rate_per_million_packets(), 0.0);
+        assert_eq!(report.loss_percent(), 0.0);
+
+        report.total_packets = 992;
+        assert!((report.gap_rate_per_million_packets() - 4.0 / 992.0 * 1e6).abs() < 1e-9);
+        // 8 lost out of 1,000 expected
+        assert!((report.loss_percent() - 0.8).abs() < 1e-9);
+
+        report.gaps.clear();
+        assert_eq!(report.gap_rate_per_million_packets(), 0.0);
+        assert_eq!(report.loss_percent(), 0.0);
+    }
+
+    #[test]
+    fn test_flow_stats_partial_ord() {
+        let small = sortable_stats(9, 10, 0, 0, 0, 1);
+        let large = sortable_stats(1, 20, 0, 0, 0, 1);
+        assert!(small < large);
+        assert_eq!(small.partial_cmp(&small.clone()), Some(Ordering::Equal));
+
+        // Same packets: FlowId decides
+        let other = sortable_stats(10, 10, 0, 0, 0, 1);
+        assert!(small < other);
+
+        // Same packets and ID but different contents: unordered
+        let mut changed = small.clone();
+        changed.total_bytes = 1;
+        assert_eq!(small.partial_cmp(&changed), None);
+    }
+
+    #[test]
+    fn test_sorted_by_metric() {
+        // sci, packets, bytes, gaps, lost, seconds
+        let flows = vec![
+            sortable_stats(1, 100, 1_000, 0, 0, 10),     // 0.0008 Mbps
+            sortable_stats(2, 500, 500_000, 3, 100, 10), // 0.4 Mbps, 16.7% loss
+            sortable_stats(3, 300, 2_000_000, 1, 3, 0),  // no bandwidth, 1% loss
+            sortable_stats(4, 300, 250_000, 5, 0, 1),    // 2 Mbps
+        ];
+        let order = |metric| {
+            flows
+                .clone()
+                .sorted_by_metric(metric)
+                .iter()
+                .map(|s| match s.flow_id {
+                    FlowId::MACsec { sci, .. } => sci,
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Flows 3 and 4 tie on packets: FlowId breaks the tie
+        assert_eq!(order(FlowSortMetric::PacketsDesc), vec![2, 3, 4, 1]);
+        assert_eq!(order(FlowSortMetric::BytesDesc), vec![3, 2, 4, 1]);
+        assert_eq!(order(FlowSortMetric::GapsDesc), vec![4, 2, 3, 1]);
+        assert_eq!(order(FlowSortMetric::LossRateDesc), vec![2, 3, 1, 4]);
+        assert_eq!(order(FlowSortMetric::BandwidthDesc), vec![4, 2, 1, 3]);
+
+        let mut in_place = flows.clone();
+        FlowStatsComparator::new(FlowSortMetric::GapsDesc).sort_inplace(&mut in_place);
+        assert_eq!(in_place, flows.clone().sorted_by_metric(FlowSortMetric::GapsDesc));
+    }
+
+    #[test]
+    fn test_gaps_csv() {
+        let flow = FlowId::MACsec { sci: 0x1, an: 0 };
+        let csv = report_with_gaps(flow.clone(), 25).gaps_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        // CSV is never truncated
+        assert_eq!(lines.len(), 26);
+        assert_eq!(lines[0], "flow_id,expected,received,gap_size,loss_pct,timestamp");
+        assert_eq!(
+            lines[1],
+            format!("\"{}\",1,3,2,100.0000,1700000000.000000", flow)
+        );
     }
 }
@@ -0,0 +1,46 @@
+//! Sequential vs concurrent parsing of a ~100 MB buffer of valid messages
+//!
+//! Run with: cargo bench --features parallel
+
+use binary_protocol_parser::{parse_multiple, parse_multiple_concurrent, Message};
+use std::sync::Arc;
+use std::time::Instant;
+
+const TARGET_BYTES: usize = 100 * 1024 * 1024;
+
+fn main() {
+    // Mix of payload sizes, all with valid checksums
+    let mut data = Vec::with_capacity(TARGET_BYTES + 1024);
+    let mut i = 0u32;
+    while data.len() < TARGET_BYTES {
+        let payload: Vec<u8> = (0..(i % 1000)).map(|b| b as u8).collect();
+        data.extend_from_slice(&Message::new(1, (i % 256) as u8, payload).to_bytes());
+        i += 1;
+    }
+    let mb = data.len() as f64 / (1024.0 * 1024.0);
+    println!("Buffer: {:.1} MB, {} messages", mb, i);
+
+    let start = Instant::now();
+    let sequential = parse_multiple(&data).expect("sequential parse failed");
+    let elapsed = start.elapsed();
+    println!(
+        "sequential:      {:>8.1} ms ({:.0} MB/s)",
+        elapsed.as_secs_f64() * 1000.0,
+        mb / elapsed.as_secs_f64()
+    );
+
+    let data: Arc<[u8]> = Arc::from(data);
+    for threads in [1, 2, 4, 8] {
+        let start = Instant::now();
+        let concurrent =
+            parse_multiple_concurrent(Arc::clone(&data), threads).expect("concurrent parse failed");
+        let elapsed = start.elapsed();
+        assert_eq!(concurrent.len(), sequential.len());
+        println!(
+            "concurrent ({} t): {:>8.1} ms ({:.0} MB/s)",
+            threads,
+            elapsed.as_secs_f64() * 1000.0,
+            mb / elapsed.as_secs_f64()
+        );
+    }
+}
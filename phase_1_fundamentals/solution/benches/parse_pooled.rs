@@ -0,0 +1,31 @@
+//! `parse()` vs `parse_pooled()` for a range of payload sizes
+//!
+//! Run with: cargo bench --bench parse_pooled
+
+use binary_protocol_parser::pool::MessagePool;
+use binary_protocol_parser::{parse, parse_pooled, Message};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+fn parse_and_discard(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_and_discard");
+
+    for size in [0usize, 16, 256, 4096, 65535] {
+        let bytes = Message::new(1, 5, (0..size).map(|b| b as u8).collect()).to_bytes();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("parse", size), &bytes, |b, bytes| {
+            b.iter(|| black_box(parse(bytes).unwrap()))
+        });
+
+        // After the first iteration every message comes from the pool
+        let pool = MessagePool::new();
+        group.bench_with_input(BenchmarkId::new("parse_pooled", size), &bytes, |b, bytes| {
+            b.iter(|| black_box(parse_pooled(bytes, &pool).unwrap()))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parse_and_discard);
+criterion_main!(benches);
@@ -0,0 +1,33 @@
+//! `parse()` vs `parse_zero_copy()` for a range of payload sizes
+//!
+//! Run with: cargo bench --bench parse_zero_copy --features zero-copy
+
+use binary_protocol_parser::{parse, parse_zero_copy, Message};
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+fn parse_owned_vs_borrowed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_owned_vs_borrowed");
+
+    for size in [0usize, 16, 256, 4096, 65535] {
+        let bytes = Message::new(1, 5, (0..size).map(|b| b as u8).collect()).to_bytes();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("parse", size), &bytes, |b, bytes| {
+            b.iter(|| black_box(parse(bytes).unwrap()))
+        });
+
+        // Cloning `Bytes` only bumps a reference count
+        let shared = Bytes::from(bytes);
+        group.bench_with_input(
+            BenchmarkId::new("parse_zero_copy", size),
+            &shared,
+            |b, shared| b.iter(|| black_box(parse_zero_copy(shared.clone()).unwrap())),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parse_owned_vs_borrowed);
+criterion_main!(benches);
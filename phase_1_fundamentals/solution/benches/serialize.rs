@@ -0,0 +1,43 @@
+//! `to_bytes()` vs `to_bytes_into()` vs `to_writer()` for a range of payload sizes
+//!
+//! Run with: cargo bench --bench serialize
+
+use binary_protocol_parser::Message;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+fn serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialize");
+
+    for size in [0usize, 16, 256, 4096, 65535] {
+        let msg = Message::new(1, 5, (0..size).map(|b| b as u8).collect());
+        group.throughput(Throughput::Bytes(msg.serialized_len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("to_bytes", size), &msg, |b, msg| {
+            b.iter(|| black_box(msg.to_bytes()))
+        });
+
+        // Reuse one buffer of the right capacity, so no call allocates
+        let mut buf = Vec::with_capacity(msg.serialized_len());
+        group.bench_with_input(BenchmarkId::new("to_bytes_into", size), &msg, |b, msg| {
+            b.iter(|| {
+                msg.to_bytes_into(&mut buf);
+                black_box(&buf);
+            })
+        });
+
+        // Reuse one buffer, as a writer to a socket or file would
+        let mut out = Vec::with_capacity(msg.serialized_len());
+        group.bench_with_input(BenchmarkId::new("to_writer", size), &msg, |b, msg| {
+            b.iter(|| {
+                out.clear();
+                msg.to_writer(&mut out).unwrap();
+                black_box(&out);
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, serialize);
+criterion_main!(benches);
@@ -0,0 +1,227 @@
+//! Serializing many messages at once
+//!
+//! Calling `to_bytes()` per message allocates one `Vec` per message. A
+//! `MessageBatch` sizes a single buffer up front and writes every message
+//! into it, or splits the batch into size-limited frames for the network.
+
+use crate::error::ParseError;
+use crate::{parse_multiple, Message};
+
+/// An ordered collection of messages serialized back to back
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::batch::MessageBatch;
+/// use binary_protocol_parser::Message;
+///
+/// let mut batch = MessageBatch::new();
+/// batch.push(Message::new(1, 5, vec![1, 2, 3]));
+/// batch.push(Message::new(1, 6, vec![4]));
+///
+/// let bytes = batch.to_bytes_batch();
+/// assert_eq!(bytes.len(), 8 + 6);
+/// assert_eq!(MessageBatch::from_bytes_batch(&bytes).unwrap(), batch);
+/// ```
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MessageBatch {
+    messages: Vec<Message>,
+}
+
+impl MessageBatch {
+    /// Creates an empty batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a message to the end of the batch
+    pub fn push(&mut self, msg: Message) {
+        self.messages.push(msg);
+    }
+
+    /// Iterates over the messages in order
+    pub fn iter(&self) -> impl Iterator<Item = &Message> {
+        self.messages.iter()
+    }
+
+    /// Number of messages in the batch
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Whether the batch holds no messages
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Total bytes `to_bytes_batch()` produces
+    pub fn serialized_len(&self) -> usize {
+        self.messages.iter().map(Message::serialized_len).sum()
+    }
+
+    /// Serializes every message into one contiguous buffer
+    ///
+    /// The buffer is allocated once at its final size.
+    pub fn to_bytes_batch(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.serialized_len());
+        for msg in &self.messages {
+            msg.to_writer(&mut out).expect("writing to a Vec cannot fail");
+        }
+        out
+    }
+
+    /// Parses back-to-back messages, as produced by `to_bytes_batch()`
+    ///
+    /// Errors carry the byte offset of the failing message (see
+    /// `parse_multiple`).
+    pub fn from_bytes_batch(data: &[u8]) -> Result<Self, ParseError> {
+        parse_multiple(data).map(Self::from)
+    }
+
+    /// Splits the batch into frames of at most `max_frame_size` bytes
+    ///
+    /// Messages are never split: each frame holds whole messages, packed in
+    /// order. A single message larger than `max_frame_size` gets a frame of
+    /// its own, which is the only case where a frame exceeds the limit.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::batch::MessageBatch;
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let batch: MessageBatch = (0..5).map(|t| Message::new(1, t, vec![0; 5])).collect();
+    ///
+    /// // Each message is 10 bytes, so two fit in a 25-byte frame
+    /// let frames = batch.into_framed_bytes(25);
+    /// let sizes: Vec<usize> = frames.iter().map(Vec::len).collect();
+    /// assert_eq!(sizes, vec![20, 20, 10]);
+    /// ```
+    pub fn into_framed_bytes(self, max_frame_size: usize) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        let mut frame: Vec<u8> = Vec::new();
+        // Bytes not yet written, so a new frame never reserves more than
+        // the rest of the batch however large `max_frame_size` is
+        let mut remaining = self.serialized_len();
+
+        for msg in &self.messages {
+            let len = msg.serialized_len();
+            if !frame.is_empty() && frame.len() + len > max_frame_size {
+                frames.push(std::mem::take(&mut frame));
+            }
+            if frame.is_empty() {
+                frame.reserve_exact(max_frame_size.min(remaining).max(len));
+            }
+            msg.to_writer(&mut frame).expect("writing to a Vec cannot fail");
+            remaining -= len;
+        }
+
+        if !frame.is_empty() {
+            frames.push(frame);
+        }
+        frames
+    }
+}
+
+impl From<Vec<Message>> for MessageBatch {
+    fn from(messages: Vec<Message>) -> Self {
+        Self { messages }
+    }
+}
+
+impl FromIterator<Message> for MessageBatch {
+    fn from_iter<I: IntoIterator<Item = Message>>(iter: I) -> Self {
+        Self {
+            messages: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for MessageBatch {
+    type Item = Message;
+    type IntoIter = std::vec::IntoIter<Message>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.messages.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_batch() -> MessageBatch {
+        (0..10u8)
+            .map(|i| Message::new(1, i, vec![i; i as usize * 3]))
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_size_is_sum_of_messages() {
+        let batch = sample_batch();
+        let expected: usize = batch.iter().map(Message::serialized_len).sum();
+
+        let bytes = batch.to_bytes_batch();
+        assert_eq!(bytes.len(), expected);
+        assert_eq!(bytes.capacity(), expected);
+
+        let concatenated: Vec<u8> = batch.iter().flat_map(Message::to_bytes).collect();
+        assert_eq!(bytes, concatenated);
+    }
+
+    #[test]
+    fn test_batch_roundtrip() {
+        let batch = sample_batch();
+        let parsed = MessageBatch::from_bytes_batch(&batch.to_bytes_batch()).unwrap();
+        assert_eq!(parsed, batch);
+        assert_eq!(MessageBatch::from_bytes_batch(&[]).unwrap(), MessageBatch::new());
+    }
+
+    #[test]
+    fn test_from_bytes_batch_reports_offset() {
+        let batch = sample_batch();
+        let mut bytes = batch.to_bytes_batch();
+        // Corrupt the checksum of the second message (5 + 8 bytes in)
+        bytes[12] ^= 0xFF;
+
+        let err = MessageBatch::from_bytes_batch(&bytes).unwrap_err();
+        assert_eq!(err.offset(), Some(12));
+        assert!(matches!(err, ParseError::WithContext { offset: 5, .. }));
+    }
+
+    #[test]
+    fn test_framed_bytes_respect_limit() {
+        let batch = sample_batch();
+        let total = batch.serialized_len();
+        let frames = batch.into_framed_bytes(40);
+
+        assert!(frames.iter().all(|f| f.len() <= 40));
+        assert_eq!(frames.iter().map(Vec::len).sum::<usize>(), total);
+
+        // Frames reassemble into the original messages
+        let rejoined: Vec<u8> = frames.concat();
+        assert_eq!(MessageBatch::from_bytes_batch(&rejoined).unwrap(), sample_batch());
+    }
+
+    #[test]
+    fn test_oversized_message_gets_own_frame() {
+        let batch: MessageBatch = vec![
+            Message::new(1, 1, vec![0; 2]),
+            Message::new(1, 2, vec![0; 100]),
+            Message::new(1, 3, vec![0; 2]),
+        ]
+        .into();
+
+        let sizes: Vec<usize> = batch.into_framed_bytes(20).iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![7, 105, 7]);
+    }
+
+    #[test]
+    fn test_huge_frame_limit_reserves_only_batch_size() {
+        let batch = sample_batch();
+        let total = batch.serialized_len();
+
+        let frames = batch.into_framed_bytes(usize::MAX);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].len(), total);
+        assert_eq!(frames[0].capacity(), total);
+    }
+}
@@ -0,0 +1,160 @@
+//! Building messages field by field
+//!
+//! `Message::new` takes a finished payload `Vec`. A `MessageBuilder` lets
+//! multi-field payloads be written in order, with integers encoded
+//! big-endian like the rest of the protocol.
+
+use crate::error::ParseError;
+use crate::{Message, LENGTH_OFFSET, MAX_PAYLOAD_SIZE};
+
+/// Step-by-step construction of a `Message`
+///
+/// Version defaults to 1 and message type to 0. The checksum is computed
+/// by `build()`, once the payload is complete.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::builder::MessageBuilder;
+///
+/// let msg = MessageBuilder::new()
+///     .message_type(7)
+///     .push_byte(0x01)
+///     .push_u16_be(0x0203)
+///     .push_u32_be(0x0405_0607)
+///     .build()
+///     .unwrap();
+/// assert_eq!(msg.payload, vec![1, 2, 3, 4, 5, 6, 7]);
+/// assert!(msg.validate().is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageBuilder {
+    version: u8,
+    message_type: u8,
+    payload: Vec<u8>,
+}
+
+impl Default for MessageBuilder {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            message_type: 0,
+            payload: Vec::new(),
+        }
+    }
+}
+
+impl MessageBuilder {
+    /// Starts a version 1, type 0 message with an empty payload
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the protocol version
+    pub fn version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Sets the type/command identifier
+    pub fn message_type(mut self, message_type: u8) -> Self {
+        self.message_type = message_type;
+        self
+    }
+
+    /// Replaces the payload built so far
+    pub fn payload(mut self, payload: impl Into<Vec<u8>>) -> Self {
+        self.payload = payload.into();
+        self
+    }
+
+    /// Appends one byte to the payload
+    pub fn push_byte(mut self, byte: u8) -> Self {
+        self.payload.push(byte);
+        self
+    }
+
+    /// Appends a u16 to the payload, big-endian
+    pub fn push_u16_be(mut self, value: u16) -> Self {
+        self.payload.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    /// Appends a u32 to the payload, big-endian
+    pub fn push_u32_be(mut self, value: u32) -> Self {
+        self.payload.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    /// Finishes the message and calculates its checksum
+    ///
+    /// # Returns
+    /// * `Ok(Message)` with a valid checksum
+    /// * `Err(ParseError::PayloadTooLarge)` if the payload no longer fits
+    ///   the 2-byte length field
+    pub fn build(self) -> Result<Message, ParseError> {
+        if self.payload.len() > MAX_PAYLOAD_SIZE {
+            return Err(ParseError::PayloadTooLarge {
+                size: self.payload.len(),
+                max: MAX_PAYLOAD_SIZE,
+                offset: LENGTH_OFFSET,
+            });
+        }
+
+        Ok(Message::new(self.version, self.message_type, self.payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_builder_defaults() {
+        let msg = MessageBuilder::new().build().unwrap();
+        assert_eq!(msg, Message::new(1, 0, vec![]));
+    }
+
+    #[test]
+    fn test_builder_matches_message_new() {
+        let built = MessageBuilder::new()
+            .version(2)
+            .message_type(9)
+            .payload(&b"abc"[..])
+            .push_byte(b'd')
+            .build()
+            .unwrap();
+
+        assert_eq!(built, Message::new(2, 9, b"abcd".to_vec()));
+        assert_eq!(built.checksum, (b'a' ^ b'b' ^ b'c' ^ b'd') as u32);
+    }
+
+    #[test]
+    fn test_builder_round_trip() {
+        let msg = MessageBuilder::new()
+            .message_type(3)
+            .push_u16_be(0xABCD)
+            .push_u32_be(1)
+            .build()
+            .unwrap();
+
+        assert_eq!(msg.payload, vec![0xAB, 0xCD, 0, 0, 0, 1]);
+        assert_eq!(parse(&msg.to_bytes()).unwrap(), msg);
+    }
+
+    #[test]
+    fn test_builder_rejects_oversized_payload() {
+        let at_limit = MessageBuilder::new().payload(vec![0; MAX_PAYLOAD_SIZE]);
+        assert!(at_limit.clone().build().is_ok());
+
+        let result = at_limit.push_byte(0).build();
+        assert!(matches!(
+            result,
+            Err(ParseError::PayloadTooLarge {
+                size: 65536,
+                max: MAX_PAYLOAD_SIZE,
+                offset: 2
+            })
+        ));
+    }
+}
@@ -0,0 +1,198 @@
+//! `tokio_util::codec` integration
+//!
+//! `MessageCodec` turns any `AsyncRead + AsyncWrite` into a stream and sink
+//! of `Message`s:
+//!
+//! ```ignore
+//! let mut framed = Framed::new(tcp_stream, MessageCodec::new());
+//! framed.send(Message::new(1, 5, vec![1, 2, 3])).await?;
+//! while let Some(msg) = framed.next().await {
+//!     println!("{}", msg?);
+//! }
+//! ```
+//!
+//! The protocol header already carries the payload length, so no extra
+//! framing is added on the wire. `MessageCodec::with_config` reads frames
+//! with a non-default checksum trailer or length byte order.
+
+use crate::error::ParseError;
+use crate::{frame_len, parse_with_config, Message, ParseConfig, HEADER_SIZE};
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Frames `Message`s on a byte stream using the protocol header
+///
+/// Decoding follows `parse()` rules unless created with `with_config()`.
+/// Encoding writes each message with its own checksum algorithm.
+#[derive(Debug, Clone, Default)]
+pub struct MessageCodec {
+    cfg: ParseConfig,
+}
+
+impl MessageCodec {
+    /// Creates a codec using the strict parse rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a codec that decodes frames following `cfg`
+    ///
+    /// `cfg.checksum` sets the trailer width, so both ends must agree on it.
+    pub fn with_config(cfg: ParseConfig) -> Self {
+        Self { cfg }
+    }
+}
+
+impl Decoder for MessageCodec {
+    type Item = Message;
+    type Error = ParseError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Message>, ParseError> {
+        // Wait until the length field has arrived
+        if src.len() < HEADER_SIZE {
+            src.reserve(HEADER_SIZE - src.len());
+            return Ok(None);
+        }
+
+        let frame_len = frame_len(src, &self.cfg);
+        if src.len() < frame_len {
+            src.reserve(frame_len - src.len());
+            return Ok(None);
+        }
+
+        // Consume the frame even if it fails to parse, so the next
+        // call starts at the following message
+        let frame = src.split_to(frame_len);
+        parse_with_config(&frame, &self.cfg).map(Some)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Message>, ParseError> {
+        match self.decode(src)? {
+            Some(msg) => Ok(Some(msg)),
+            None if src.is_empty() => Ok(None),
+            None => {
+                // Stream ended mid-message: report what was missing
+                let err = parse_with_config(src, &self.cfg).err().unwrap_or(ParseError::TruncatedStream {
+                    expected: HEADER_SIZE,
+                    available: src.len(),
+                });
+                src.advance(src.len());
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Encoder<Message> for MessageCodec {
+    type Error = ParseError;
+
+    fn encode(&mut self, msg: Message, dst: &mut BytesMut) -> Result<(), ParseError> {
+        // Write straight into the frame buffer, without an intermediate Vec
+        dst.reserve(msg.serialized_len());
+        msg.to_writer(&mut dst.writer())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChecksumAlgorithm;
+    use futures::{SinkExt, StreamExt};
+    use tokio_test::io::Builder;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    #[tokio::test]
+    async fn test_decode_messages_split_across_reads() {
+        let first = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        let second = Message::new(1, 10, vec![4, 5, 6, 7]).to_bytes();
+
+        // Second message arrives in two pieces, the first split mid-header
+        let mock = Builder::new()
+            .read(&first)
+            .read(&second[..2])
+            .read(&second[2..])
+            .build();
+        let mut framed = FramedRead::new(mock, MessageCodec::new());
+
+        let msg = framed.next().await.unwrap().unwrap();
+        assert_eq!(msg.message_type, 5);
+        assert_eq!(msg.payload, vec![1, 2, 3]);
+
+        let msg = framed.next().await.unwrap().unwrap();
+        assert_eq!(msg.message_type, 10);
+        assert_eq!(msg.payload, vec![4, 5, 6, 7]);
+
+        assert!(framed.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_decode_truncated_stream() {
+        let bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        let mock = Builder::new().read(&bytes[..6]).build();
+        let mut framed = FramedRead::new(mock, MessageCodec::new());
+
+        let err = framed.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::IncompletePayload {
+                expected: 8,
+                actual: 6,
+                offset: 4
+            }
+        ));
+    }
+
+    #[test]
+    fn test_decode_consumes_bad_frame() {
+        let mut bad = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        *bad.last_mut().unwrap() ^= 0xFF;
+        let good = Message::new(1, 6, vec![9]).to_bytes();
+
+        let mut buf = BytesMut::from(&bad[..]);
+        buf.extend_from_slice(&good);
+
+        assert!(matches!(
+            MessageCodec::new().decode(&mut buf),
+            Err(ParseError::ChecksumMismatch { .. })
+        ));
+        // The bad frame was dropped, so decoding resumes at the next message
+        let msg = MessageCodec::new().decode(&mut buf).unwrap().unwrap();
+        assert_eq!(msg.message_type, 6);
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_encode_writes_wire_format() {
+        let msg = Message::new(1, 5, vec![1, 2, 3]);
+        let mock = Builder::new().write(&msg.to_bytes()).build();
+        let mut framed = FramedWrite::new(mock, MessageCodec::new());
+
+        framed.send(Message::new(1, 5, vec![1, 2, 3])).await.unwrap();
+    }
+
+    #[test]
+    fn test_crc_round_trip_with_config() {
+        for checksum in [ChecksumAlgorithm::Crc16, ChecksumAlgorithm::Crc32] {
+            let cfg = ParseConfig {
+                checksum,
+                ..ParseConfig::strict()
+            };
+            let mut codec = MessageCodec::with_config(cfg.clone());
+            let msg = Message::new_with_config(1, 5, vec![1, 2, 3], &cfg);
+
+            let mut buf = BytesMut::new();
+            codec
+                .encode(Message::new_with_config(1, 5, vec![1, 2, 3], &cfg), &mut buf)
+                .unwrap();
+            assert_eq!(buf.len(), msg.serialized_len());
+
+            // One byte short of the trailer is not a frame yet
+            let last = buf.split_off(buf.len() - 1);
+            assert!(codec.decode(&mut buf).unwrap().is_none());
+            buf.unsplit(last);
+            assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), msg);
+            assert!(buf.is_empty());
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Routing parsed messages to per-type handlers
+//!
+//! Replaces hand-written `match msg.message_type { ... }` blocks with a
+//! table of handlers keyed by message type.
+
+use crate::error::DispatchError;
+use crate::Message;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Processes messages of one (or, as the default handler, any) type
+pub trait MessageHandler: Send {
+    fn handle(&self, msg: Message) -> Result<(), Box<dyn Error>>;
+}
+
+/// Closures can be used directly as handlers
+impl<F> MessageHandler for F
+where
+    F: Fn(Message) -> Result<(), Box<dyn Error>> + Send,
+{
+    fn handle(&self, msg: Message) -> Result<(), Box<dyn Error>> {
+        self(msg)
+    }
+}
+
+/// Routes each message to the handler registered for its `message_type`
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::dispatch::MessageTypeDispatcher;
+/// use binary_protocol_parser::error::DispatchError;
+/// use binary_protocol_parser::Message;
+///
+/// let mut dispatcher = MessageTypeDispatcher::new();
+/// dispatcher.register(1, Box::new(|msg: Message| {
+///     println!("ping with {} bytes", msg.payload.len());
+///     Ok(())
+/// }));
+///
+/// assert!(dispatcher.dispatch(Message::new(1, 1, vec![])).is_ok());
+/// assert!(matches!(
+///     dispatcher.dispatch(Message::new(1, 7, vec![])),
+///     Err(DispatchError::UnknownType(7))
+/// ));
+/// ```
+#[derive(Default)]
+pub struct MessageTypeDispatcher {
+    handlers: HashMap<u8, Box<dyn MessageHandler>>,
+    default_handler: Option<Box<dyn MessageHandler>>,
+}
+
+impl MessageTypeDispatcher {
+    /// Creates a dispatcher with no handlers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the handler for `message_type`, replacing any previous one
+    pub fn register(&mut self, message_type: u8, handler: Box<dyn MessageHandler>) {
+        self.handlers.insert(message_type, handler);
+    }
+
+    /// Registers the handler used for types with no handler of their own
+    pub fn register_default(&mut self, handler: Box<dyn MessageHandler>) {
+        self.default_handler = Some(handler);
+    }
+
+    /// Passes `msg` to the handler for its type, falling back to the default
+    ///
+    /// # Returns
+    /// * `Ok(())` if the handler succeeded
+    /// * `Err(DispatchError::UnknownType)` if no handler applies
+    /// * `Err(DispatchError::HandlerError)` if the handler failed
+    pub fn dispatch(&self, msg: Message) -> Result<(), DispatchError> {
+        let handler = self
+            .handlers
+            .get(&msg.message_type)
+            .or(self.default_handler.as_ref())
+            .ok_or(DispatchError::UnknownType(msg.message_type))?;
+
+        handler.handle(msg).map_err(DispatchError::HandlerError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Counts calls; fails on messages with an empty payload
+    struct CountingHandler {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl MessageHandler for CountingHandler {
+        fn handle(&self, msg: Message) -> Result<(), Box<dyn Error>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if msg.payload.is_empty() {
+                return Err(Box::new(ParseError::MessageTooShort { actual: 0 }));
+            }
+            Ok(())
+        }
+    }
+
+    fn counting_handler() -> (Box<dyn MessageHandler>, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler = CountingHandler {
+            calls: Arc::clone(&calls),
+        };
+        (Box::new(handler), calls)
+    }
+
+    #[test]
+    fn test_dispatch_routes_by_type() {
+        let mut dispatcher = MessageTypeDispatcher::new();
+        let (handler1, calls1) = counting_handler();
+        let (handler2, calls2) = counting_handler();
+        dispatcher.register(1, handler1);
+        dispatcher.register(2, handler2);
+
+        for message_type in [1, 2, 2, 1, 2] {
+            dispatcher
+                .dispatch(Message::new(1, message_type, vec![1, 2, 3]))
+                .expect("Dispatch failed");
+        }
+
+        assert_eq!(calls1.load(Ordering::SeqCst), 2);
+        assert_eq!(calls2.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_dispatch_unknown_type() {
+        let mut dispatcher = MessageTypeDispatcher::new();
+        let (handler1, calls1) = counting_handler();
+        dispatcher.register(1, handler1);
+
+        assert!(matches!(
+            dispatcher.dispatch(Message::new(1, 3, vec![1])),
+            Err(DispatchError::UnknownType(3))
+        ));
+        assert_eq!(calls1.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_dispatch_default_handler() {
+        let mut dispatcher = MessageTypeDispatcher::new();
+        let (handler1, calls1) = counting_handler();
+        let (fallback, fallback_calls) = counting_handler();
+        dispatcher.register(1, handler1);
+        dispatcher.register_default(fallback);
+
+        for message_type in [1, 3, 4, 200] {
+            dispatcher
+                .dispatch(Message::new(1, message_type, vec![1]))
+                .expect("Dispatch failed");
+        }
+
+        assert_eq!(calls1.load(Ordering::SeqCst), 1);
+        assert_eq!(fallback_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_dispatch_handler_error() {
+        let mut dispatcher = MessageTypeDispatcher::new();
+        let (handler1, calls1) = counting_handler();
+        dispatcher.register(1, handler1);
+
+        let result = dispatcher.dispatch(Message::new(1, 1, vec![]));
+        assert!(matches!(result, Err(DispatchError::HandlerError(_))));
+        assert_eq!(calls1.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_register_replaces_handler() {
+        let mut dispatcher = MessageTypeDispatcher::new();
+        let (first, first_calls) = counting_handler();
+        let (second, second_calls) = counting_handler();
+        dispatcher.register(1, first);
+        dispatcher.register(1, second);
+
+        dispatcher.dispatch(Message::new(1, 1, vec![1])).expect("Dispatch failed");
+        assert_eq!(first_calls.load(Ordering::SeqCst), 0);
+        assert_eq!(second_calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -5,26 +5,133 @@
 
 use std::error::Error;
 use std::fmt;
+use std::time::Duration;
 
 /// Represents failures that can occur during protocol parsing
 ///
 /// Each variant includes relevant context to help debug parsing issues.
+/// Where a variant has an `offset`, it counts bytes from the start of the
+/// message (for `tlv` errors, from the start of the TLV data).
+///
+/// With the `serde` feature, `Io` is serialized as its message text and
+/// comes back as an `io::ErrorKind::Other` error.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParseError {
     /// Message data is shorter than the minimum required (5 bytes)
+    ///
+    /// `parse()` now reports short input as `TruncatedStream` or
+    /// `IncompletePayload`; this variant is kept for existing callers.
     MessageTooShort { actual: usize },
 
-    /// Protocol version is not supported (only version 1 is valid)
+    /// Protocol version is not accepted (see `ValidationConfig::allowed_versions`)
     InvalidVersion { version: u8 },
 
-    /// Extracted payload length exceeds remaining data
+    /// Data ends before the 4-byte header (version, type, length) is complete,
+    /// e.g. a partial last packet at the end of a stream
+    TruncatedStream { expected: usize, available: usize },
+
+    /// Header is present, but the payload length field claims more bytes
+    /// than the message contains; `offset` is where the payload starts
+    IncompletePayload {
+        expected: usize,
+        actual: usize,
+        offset: usize,
+    },
+
+    /// A complete message is smaller than its version allows, e.g. a
+    /// version 2 message without its flags byte (see
+    /// `min_message_size_for_version`)
+    BelowVersionMinimum {
+        version: u8,
+        min_size: usize,
+        actual: usize,
+    },
+
+    /// Old, misspelled name of `IncompletePayload`
+    ///
+    /// Never returned by this crate; match on `IncompletePayload` instead.
+    #[deprecated(note = "renamed to `IncompletePayload`")]
     IncompletPayload { expected: usize, actual: usize },
 
-    /// Checksum verification failed
-    ChecksumMismatch { expected: u8, calculated: u8 },
+    /// Checksum verification failed; `offset` is where the checksum trailer
+    /// starts
+    ChecksumMismatch {
+        expected: u32,
+        calculated: u32,
+        offset: usize,
+    },
+
+    /// Payload size exceeds reasonable limits; `offset` is where the length
+    /// field starts
+    PayloadTooLarge {
+        size: usize,
+        max: usize,
+        offset: usize,
+    },
+
+    /// A `Read`-based source closed mid-message after `at_byte` bytes of it
+    /// had been read (see `parse_from_reader`)
+    UnexpectedEof { at_byte: usize },
+
+    /// Reading or writing the underlying stream failed (see `MessageCodec`)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_support::io_error"))]
+    Io(std::io::Error),
+
+    /// The deadline passed to `parse_with_deadline` was reached; `elapsed`
+    /// is the time spent parsing before giving up
+    DeadlineExceeded { elapsed: Duration },
+
+    /// `source` occurred in the message starting `offset` bytes into the
+    /// input (see `parse_multiple`)
+    WithContext {
+        offset: usize,
+        source: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    /// Wraps this error with the byte offset of the message it occurred in
+    pub(crate) fn at_offset(self, offset: usize) -> ParseError {
+        ParseError::WithContext {
+            offset,
+            source: Box::new(self),
+        }
+    }
+
+    /// Byte offset at which the error occurred, if known
+    ///
+    /// Offsets of nested contexts are added together, since each one is
+    /// relative to the data its caller was given.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            ParseError::WithContext { offset, source } => {
+                Some(offset + source.offset().unwrap_or(0))
+            }
+            ParseError::IncompletePayload { offset, .. }
+            | ParseError::ChecksumMismatch { offset, .. }
+            | ParseError::PayloadTooLarge { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
 
-    /// Payload size exceeds reasonable limits
-    PayloadTooLarge { size: usize, max: usize },
+    /// The underlying error with any `WithContext` wrappers removed
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::error::ParseError;
+    /// use binary_protocol_parser::parse_multiple;
+    ///
+    /// let err = parse_multiple(&[1, 5, 0, 0, 0, 1, 5]).unwrap_err();
+    /// assert_eq!(err.offset(), Some(5));
+    /// assert!(matches!(err.root_cause(), ParseError::TruncatedStream { .. }));
+    /// ```
+    pub fn root_cause(&self) -> &ParseError {
+        match self {
+            ParseError::WithContext { source, .. } => source.root_cause(),
+            other => other,
+        }
+    }
 }
 
 impl fmt::Display for ParseError {
@@ -41,12 +148,41 @@ impl fmt::Display for ParseError {
                 )
             }
             ParseError::InvalidVersion { version } => {
+                write!(f, "Unsupported protocol version: {}", version)
+            }
+            ParseError::TruncatedStream {
+                expected,
+                available,
+            } => {
                 write!(
                     f,
-                    "Unsupported protocol version: {} (only version 1 is supported)",
-                    version
+                    "Truncated stream: header needs {} bytes, but only {} available",
+                    expected, available
                 )
             }
+            ParseError::IncompletePayload {
+                expected,
+                actual,
+                offset,
+            } => {
+                write!(
+                    f,
+                    "Incomplete payload: expected {} bytes, but only {} available at byte offset {}",
+                    expected, actual, offset
+                )
+            }
+            ParseError::BelowVersionMinimum {
+                version,
+                min_size,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "Version {} requires at least {} bytes, but the message has {}",
+                    version, min_size, actual
+                )
+            }
+            #[allow(deprecated)]
             ParseError::IncompletPayload { expected, actual } => {
                 write!(
                     f,
@@ -57,29 +193,122 @@ impl fmt::Display for ParseError {
             ParseError::ChecksumMismatch {
                 expected,
                 calculated,
+                offset,
             } => {
                 write!(
                     f,
-                    "Checksum mismatch: expected 0x{:02X}, but calculated 0x{:02X}",
-                    expected, calculated
+                    "Checksum mismatch: expected 0x{:02X}, but calculated 0x{:02X} at byte offset {}",
+                    expected, calculated, offset
                 )
             }
-            ParseError::PayloadTooLarge { size, max } => {
+            ParseError::PayloadTooLarge { size, max, offset } => {
                 write!(
                     f,
-                    "Payload too large: {} bytes (maximum {} allowed)",
-                    size, max
+                    "Payload too large: {} bytes (maximum {} allowed) at byte offset {}",
+                    size, max, offset
                 )
             }
+            ParseError::UnexpectedEof { at_byte } => {
+                write!(
+                    f,
+                    "unexpected end of stream after reading {} bytes",
+                    at_byte
+                )
+            }
+            ParseError::Io(err) => write!(f, "I/O error: {}", err),
+            ParseError::DeadlineExceeded { elapsed } => {
+                write!(f, "Parse deadline exceeded after {:?}", elapsed)
+            }
+            ParseError::WithContext { offset, source } => {
+                write!(f, "at byte offset {}: {}", offset, source)
+            }
         }
     }
 }
 
 /// Error trait implementation
 ///
-/// This empty implementation is required for ParseError to be a proper Error type.
-/// The Display implementation above provides the error message.
-impl Error for ParseError {}
+/// The Display implementation above provides the error message; a
+/// `WithContext` error exposes the wrapped error as its source.
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::WithContext { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Lets `?` and `tokio_util::codec` convert stream errors into `ParseError`
+impl From<std::io::Error> for ParseError {
+    fn from(err: std::io::Error) -> Self {
+        ParseError::Io(err)
+    }
+}
+
+/// Lets `?` return a `ParseError` from functions using `std::io::Result`
+///
+/// An `Io` error is returned as is. Anything else becomes an `io::Error`
+/// wrapping the `ParseError`, with the kind chosen from the root cause:
+/// running out of data is `UnexpectedEof`, a bad version `InvalidInput`,
+/// a bad checksum, oversized payload or undersized message `InvalidData`.
+impl From<ParseError> for std::io::Error {
+    fn from(err: ParseError) -> Self {
+        use std::io::ErrorKind;
+
+        let kind = match err.root_cause() {
+            ParseError::Io(io_err) => io_err.kind(),
+            #[allow(deprecated)]
+            ParseError::MessageTooShort { .. }
+            | ParseError::TruncatedStream { .. }
+            | ParseError::IncompletePayload { .. }
+            | ParseError::IncompletPayload { .. }
+            | ParseError::UnexpectedEof { .. } => ErrorKind::UnexpectedEof,
+            ParseError::InvalidVersion { .. } => ErrorKind::InvalidInput,
+            ParseError::ChecksumMismatch { .. }
+            | ParseError::PayloadTooLarge { .. }
+            | ParseError::BelowVersionMinimum { .. } => ErrorKind::InvalidData,
+            ParseError::DeadlineExceeded { .. } => ErrorKind::TimedOut,
+            ParseError::WithContext { .. } => unreachable!("root_cause() unwraps all contexts"),
+        };
+
+        match err {
+            ParseError::Io(io_err) => io_err,
+            other => std::io::Error::new(kind, other),
+        }
+    }
+}
+
+/// Represents failures when routing a message to its handler
+#[derive(Debug)]
+pub enum DispatchError {
+    /// No handler registered for this message type, and no default handler
+    UnknownType(u8),
+
+    /// The handler ran but returned an error
+    HandlerError(Box<dyn Error>),
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DispatchError::UnknownType(message_type) => {
+                write!(f, "No handler registered for message type {}", message_type)
+            }
+            DispatchError::HandlerError(err) => write!(f, "Handler failed: {}", err),
+        }
+    }
+}
+
+/// Exposes the handler's error as the source, so callers can inspect it
+impl Error for DispatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DispatchError::UnknownType(_) => None,
+            DispatchError::HandlerError(err) => Some(err.as_ref()),
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -98,7 +327,118 @@ mod tests {
     fn test_error_display_invalid_version() {
         let err = ParseError::InvalidVersion { version: 5 };
         assert!(err.to_string().contains("version: 5"));
-        assert!(err.to_string().contains("only version 1"));
+        assert_eq!(err.to_string(), "Unsupported protocol version: 5");
+    }
+
+    #[test]
+    fn test_error_display_truncated_stream() {
+        let err = ParseError::TruncatedStream {
+            expected: 4,
+            available: 2,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Truncated stream: header needs 4 bytes, but only 2 available"
+        );
+    }
+
+    #[test]
+    fn test_error_display_unexpected_eof() {
+        let err = ParseError::UnexpectedEof { at_byte: 6 };
+        assert_eq!(
+            err.to_string(),
+            "unexpected end of stream after reading 6 bytes"
+        );
+        assert_eq!(err.offset(), None);
+    }
+
+    #[test]
+    fn test_into_io_error_kind() {
+        use std::io::ErrorKind;
+
+        let cases = [
+            (
+                ParseError::MessageTooShort { actual: 3 },
+                ErrorKind::UnexpectedEof,
+            ),
+            (
+                ParseError::UnexpectedEof { at_byte: 6 },
+                ErrorKind::UnexpectedEof,
+            ),
+            (
+                ParseError::InvalidVersion { version: 2 },
+                ErrorKind::InvalidInput,
+            ),
+            (
+                ParseError::ChecksumMismatch {
+                    expected: 1,
+                    calculated: 2,
+                    offset: 7,
+                },
+                ErrorKind::InvalidData,
+            ),
+            (
+                ParseError::BelowVersionMinimum {
+                    version: 2,
+                    min_size: 6,
+                    actual: 5,
+                },
+                ErrorKind::InvalidData,
+            ),
+            (
+                ParseError::DeadlineExceeded {
+                    elapsed: Duration::from_millis(5),
+                },
+                ErrorKind::TimedOut,
+            ),
+            (
+                ParseError::InvalidVersion { version: 2 }.at_offset(10),
+                ErrorKind::InvalidInput,
+            ),
+        ];
+        for (err, kind) in cases {
+            let message = err.to_string();
+            let io_err = std::io::Error::from(err);
+            assert_eq!(io_err.kind(), kind);
+            assert_eq!(io_err.to_string(), message);
+            assert!(io_err.get_ref().unwrap().is::<ParseError>());
+        }
+
+        // An I/O error comes back unwrapped
+        let err = ParseError::Io(std::io::Error::new(ErrorKind::BrokenPipe, "closed"));
+        let io_err = std::io::Error::from(err);
+        assert_eq!(io_err.kind(), ErrorKind::BrokenPipe);
+        assert_eq!(io_err.to_string(), "closed");
+    }
+
+    #[test]
+    fn test_dispatch_error_display_and_source() {
+        let err = DispatchError::UnknownType(9);
+        assert_eq!(err.to_string(), "No handler registered for message type 9");
+        assert!(err.source().is_none());
+
+        let inner = ParseError::InvalidVersion { version: 2 };
+        let err = DispatchError::HandlerError(Box::new(inner));
+        assert!(err.to_string().starts_with("Handler failed: Unsupported protocol version"));
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn test_with_context_display_offset_and_source() {
+        let inner = ParseError::InvalidVersion { version: 3 };
+        let err = ParseError::WithContext {
+            offset: 40,
+            source: Box::new(ParseError::WithContext {
+                offset: 7,
+                source: Box::new(inner),
+            }),
+        };
+        assert!(err.to_string().starts_with("at byte offset 40: at byte offset 7: Unsupported"));
+        assert_eq!(err.offset(), Some(47));
+        assert!(matches!(err.root_cause(), ParseError::InvalidVersion { version: 3 }));
+        assert!(err.source().is_some());
+
+        assert_eq!(ParseError::MessageTooShort { actual: 1 }.offset(), None);
     }
 
     #[test]
@@ -106,8 +446,37 @@ mod tests {
         let err = ParseError::ChecksumMismatch {
             expected: 0xAB,
             calculated: 0xCD,
+            offset: 7,
         };
         assert!(err.to_string().contains("Checksum mismatch"));
         assert!(err.to_string().contains("0xAB"));
+        assert!(err.to_string().ends_with(" at byte offset 7"));
+        assert_eq!(err.offset(), Some(7));
+    }
+
+    #[test]
+    fn test_error_display_payload_offsets() {
+        let err = ParseError::IncompletePayload {
+            expected: 12,
+            actual: 9,
+            offset: 4,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Incomplete payload: expected 12 bytes, but only 9 available at byte offset 4"
+        );
+
+        let err = ParseError::PayloadTooLarge {
+            size: 10,
+            max: 4,
+            offset: 2,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Payload too large: 10 bytes (maximum 4 allowed) at byte offset 2"
+        );
+
+        // Offsets inside a message add to the message's own offset
+        assert_eq!(err.at_offset(40).offset(), Some(42));
     }
 }
@@ -12,6 +12,17 @@
 //! Last byte:  Checksum (u8, XOR of all payload bytes)
 //! ```
 //!
+//! With `ChecksumAlgorithm::Crc16` or `Crc32` selected in a `ParseConfig`,
+//! the trailer widens and carries a CRC of the payload instead:
+//!
+//! ```text
+//! Crc16:  Last 2 bytes: Checksum (u16, big-endian, CRC-16/CCITT-FALSE)
+//! Crc32:  Last 4 bytes: Checksum (u32, big-endian, CRC-32/ISO-HDLC)
+//! ```
+//!
+//! The header is unchanged. Both ends must agree on the algorithm, since the
+//! trailer width isn't recorded on the wire.
+//!
 //! ## Example
 //!
 //! ```
@@ -29,19 +40,274 @@
 //! assert_eq!(parsed.version, 1);
 //! ```
 
+pub mod batch;
+pub mod builder;
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod dispatch;
 pub mod error;
+pub mod pool;
+#[cfg(feature = "serde")]
+mod serde_support;
+pub mod stream;
+pub mod tlv;
+#[cfg(feature = "zero-copy")]
+pub mod zero_copy;
 
 use error::ParseError;
+use pool::{MessagePool, PooledMessage};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::io;
+#[cfg(feature = "parallel")]
+use std::ops::Range;
+use std::ops::RangeInclusive;
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+use std::time::Instant;
+use tlv::TlvField;
+#[cfg(feature = "async")]
+use tokio::io::AsyncReadExt;
+#[cfg(feature = "zero-copy")]
+use zero_copy::MessageRef;
 
 /// Maximum allowed payload size (in bytes)
 const MAX_PAYLOAD_SIZE: usize = 65535;
 
+/// Header size: version (1) + message type (1) + payload length (2)
+const HEADER_SIZE: usize = 4;
+
+/// Position of the 2-byte payload length field within the header
+const LENGTH_OFFSET: usize = 2;
+
+/// Protocol versions accepted by a `ValidationConfig`
+///
+/// A range suits a contiguous span such as `1..=2` during a rolling upgrade;
+/// a set covers anything else. Both convert from the matching std type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SupportedVersions {
+    /// Every version in the range
+    Range(RangeInclusive<u8>),
+
+    /// Exactly the listed versions
+    Set(HashSet<u8>),
+}
+
+impl SupportedVersions {
+    /// Returns true if `version` is accepted
+    pub fn contains(&self, version: u8) -> bool {
+        match self {
+            SupportedVersions::Range(range) => range.contains(&version),
+            SupportedVersions::Set(set) => set.contains(&version),
+        }
+    }
+}
+
+impl From<RangeInclusive<u8>> for SupportedVersions {
+    fn from(range: RangeInclusive<u8>) -> Self {
+        SupportedVersions::Range(range)
+    }
+}
+
+impl From<HashSet<u8>> for SupportedVersions {
+    fn from(set: HashSet<u8>) -> Self {
+        SupportedVersions::Set(set)
+    }
+}
+
+impl From<Vec<u8>> for SupportedVersions {
+    fn from(versions: Vec<u8>) -> Self {
+        SupportedVersions::Set(versions.into_iter().collect())
+    }
+}
+
+/// How the message trailer is computed from the payload
+///
+/// `Xor` is the original 1-byte checksum. It misses any even number of flips
+/// in the same bit position; the CRCs detect every single-bit error and,
+/// for `Crc32`, every burst of up to 32 bits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChecksumAlgorithm {
+    /// XOR of all payload bytes, 1-byte trailer
+    #[default]
+    Xor,
+
+    /// CRC-16/CCITT-FALSE (poly 0x1021, init 0xFFFF), 2-byte trailer
+    Crc16,
+
+    /// CRC-32/ISO-HDLC as used by Ethernet and zlib, 4-byte trailer
+    Crc32,
+}
+
+impl ChecksumAlgorithm {
+    /// Checksum of `data`, widened to u32
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::ChecksumAlgorithm;
+    ///
+    /// assert_eq!(ChecksumAlgorithm::Xor.compute(&[1, 2, 3]), 0);
+    /// assert_eq!(ChecksumAlgorithm::Crc16.compute(b"123456789"), 0x29B1);
+    /// assert_eq!(ChecksumAlgorithm::Crc32.compute(b"123456789"), 0xCBF4_3926);
+    /// ```
+    pub fn compute(&self, data: &[u8]) -> u32 {
+        match self {
+            ChecksumAlgorithm::Xor => calculate_checksum(data) as u32,
+            ChecksumAlgorithm::Crc16 => crc16(data) as u32,
+            ChecksumAlgorithm::Crc32 => crc32(data),
+        }
+    }
+
+    /// Checks that `expected` is the checksum of `payload`
+    ///
+    /// # Returns
+    /// * `Ok(())` if the checksums match
+    /// * `Err(ParseError::ChecksumMismatch)` otherwise, with the offset the
+    ///   trailer has in a message carrying `payload`
+    pub fn verify(&self, payload: &[u8], expected: u32) -> Result<(), ParseError> {
+        let calculated = self.compute(payload);
+        if calculated != expected {
+            return Err(ParseError::ChecksumMismatch {
+                expected,
+                calculated,
+                offset: HEADER_SIZE + payload.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Bytes the checksum takes on the wire
+    pub fn trailer_len(&self) -> usize {
+        match self {
+            ChecksumAlgorithm::Xor => 1,
+            ChecksumAlgorithm::Crc16 => 2,
+            ChecksumAlgorithm::Crc32 => 4,
+        }
+    }
+}
+
+/// Byte order of the 2-byte payload length field
+///
+/// The protocol is big-endian; `LittleEndian` is for legacy devices that
+/// write the length low byte first. Nothing else in the frame is affected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ByteOrder {
+    /// High byte first (the protocol default)
+    #[default]
+    BigEndian,
+
+    /// Low byte first
+    LittleEndian,
+}
+
+impl ByteOrder {
+    /// Reads a length field from the first two bytes of `bytes`
+    fn read_length(&self, bytes: &[u8]) -> u16 {
+        match self {
+            ByteOrder::BigEndian => bytes_to_u16(bytes),
+            ByteOrder::LittleEndian => bytes_to_u16(&[bytes[1], bytes[0]]),
+        }
+    }
+
+    /// Encodes a length field
+    fn write_length(&self, length: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::BigEndian => u16_to_bytes(length),
+            ByteOrder::LittleEndian => length.to_le_bytes(),
+        }
+    }
+}
+
+/// Controls which checks `validate_with_config` and `parse_with_config` apply
+///
+/// Use `strict()` for normal traffic and `permissive()` when dealing with
+/// legacy (v0) or diagnostic messages that don't follow the standard rules.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::{Message, ValidationConfig};
+///
+/// let msg = Message::new(2, 5, vec![1, 2, 3]);
+/// let cfg = ValidationConfig {
+///     allowed_versions: (1..=2).into(),
+///     ..ValidationConfig::strict()
+/// };
+/// assert!(msg.validate_with_config(&cfg).is_ok());
+/// assert!(msg.validate().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationConfig {
+    /// Versions accepted by validation
+    pub allowed_versions: SupportedVersions,
+
+    /// Skip checksum verification
+    pub skip_checksum: bool,
+
+    /// Largest payload accepted (in bytes)
+    pub max_payload_len: usize,
+
+    /// Checksum algorithm of parsed frames; also sets the trailer width
+    pub checksum: ChecksumAlgorithm,
+
+    /// Byte order of the payload length field
+    pub length_byte_order: ByteOrder,
+}
+
+impl ValidationConfig {
+    /// Standard rules: version 1 only, XOR checksum verified, `MAX_PAYLOAD_SIZE` limit
+    pub fn strict() -> Self {
+        ValidationConfig {
+            allowed_versions: SupportedVersions::Range(1..=1),
+            skip_checksum: false,
+            max_payload_len: MAX_PAYLOAD_SIZE,
+            checksum: ChecksumAlgorithm::Xor,
+            length_byte_order: ByteOrder::BigEndian,
+        }
+    }
+
+    /// Relaxed rules: any version, no checksum check, `MAX_PAYLOAD_SIZE` limit
+    ///
+    /// Frames still carry the 1-byte XOR trailer.
+    pub fn permissive() -> Self {
+        ValidationConfig {
+            allowed_versions: SupportedVersions::Range(0..=u8::MAX),
+            skip_checksum: true,
+            max_payload_len: MAX_PAYLOAD_SIZE,
+            checksum: ChecksumAlgorithm::Xor,
+            length_byte_order: ByteOrder::BigEndian,
+        }
+    }
+
+    /// Returns true if `version` is in `allowed_versions`
+    pub fn allows_version(&self, version: u8) -> bool {
+        self.allowed_versions.contains(version)
+    }
+}
+
+/// `ValidationConfig` under the name parse-side call sites use
+pub type ParseConfig = ValidationConfig;
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        ValidationConfig::strict()
+    }
+}
+
 /// Represents a parsed binary protocol message
 ///
 /// Contains all the fields from a protocol message including version,
 /// message type, payload, and checksum for integrity verification.
-#[derive(Debug, PartialEq, Eq)]
+///
+/// Equality compares the wire fields only; `metadata` is ignored.
+///
+/// With the `serde` feature the payload is (de)serialized as a base64
+/// string, and `checksum` is recalculated on deserialization rather than
+/// read from the input.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "serde_support::MessageFields"))]
 pub struct Message {
     /// Protocol version (typically 1)
     pub version: u8,
@@ -50,12 +316,38 @@ pub struct Message {
     pub message_type: u8,
 
     /// Message payload data
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "serde_support::base64::serialize")
+    )]
     pub payload: Vec<u8>,
 
-    /// XOR checksum of payload for integrity verification
-    pub checksum: u8,
+    /// Checksum of payload for integrity verification
+    ///
+    /// Computed with `checksum_algorithm`; a XOR checksum never exceeds 0xFF.
+    pub checksum: u32,
+
+    /// Algorithm behind `checksum`, which also sets the trailer width
+    pub checksum_algorithm: ChecksumAlgorithm,
+
+    /// Out-of-band annotations (source interface, capture time, ...)
+    ///
+    /// Never serialized by `to_bytes()`; parsing always leaves it empty.
+    pub metadata: HashMap<String, String>,
+}
+
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version
+            && self.message_type == other.message_type
+            && self.payload == other.payload
+            && self.checksum == other.checksum
+            && self.checksum_algorithm == other.checksum_algorithm
+    }
 }
 
+impl Eq for Message {}
+
 impl Message {
     /// Creates a new message with automatically calculated checksum
     ///
@@ -73,15 +365,100 @@ impl Message {
     /// assert_eq!(msg.checksum, 0); // 1 ^ 2 ^ 3 = 0
     /// ```
     pub fn new(version: u8, message_type: u8, payload: Vec<u8>) -> Self {
-        let checksum = calculate_checksum(&payload);
+        Message::with_checksum_algorithm(version, message_type, payload, ChecksumAlgorithm::Xor)
+    }
+
+    /// Creates a new message whose checksum follows `cfg.checksum`
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::{parse_with_config, ChecksumAlgorithm, Message, ParseConfig};
+    ///
+    /// let cfg = ParseConfig {
+    ///     checksum: ChecksumAlgorithm::Crc32,
+    ///     ..ParseConfig::strict()
+    /// };
+    /// let msg = Message::new_with_config(1, 5, vec![1, 2, 3], &cfg);
+    /// assert_eq!(msg.serialized_len(), 4 + 3 + 4);
+    /// assert_eq!(parse_with_config(&msg.to_bytes(), &cfg).unwrap(), msg);
+    /// ```
+    pub fn new_with_config(
+        version: u8,
+        message_type: u8,
+        payload: Vec<u8>,
+        cfg: &ParseConfig,
+    ) -> Self {
+        Message::with_checksum_algorithm(version, message_type, payload, cfg.checksum)
+    }
+
+    fn with_checksum_algorithm(
+        version: u8,
+        message_type: u8,
+        payload: Vec<u8>,
+        checksum_algorithm: ChecksumAlgorithm,
+    ) -> Self {
+        let checksum = checksum_algorithm.compute(&payload);
         Message {
             version,
             message_type,
             payload,
             checksum,
+            checksum_algorithm,
+            metadata: HashMap::new(),
         }
     }
 
+    /// Attaches an out-of-band annotation, replacing any previous value
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let msg = Message::new(1, 5, vec![1, 2, 3]).with_metadata("capture_interface", "eth0");
+    /// assert_eq!(msg.metadata["capture_interface"], "eth0");
+    /// assert_eq!(msg.to_bytes(), Message::new(1, 5, vec![1, 2, 3]).to_bytes());
+    /// ```
+    pub fn with_metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Replaces the payload and recalculates the checksum
+    ///
+    /// Version, message type, checksum algorithm and metadata are kept.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let msg = Message::new(1, 5, vec![1, 2, 3]).with_payload(vec![1, 2]);
+    /// assert_eq!(msg.payload, vec![1, 2]);
+    /// assert_eq!(msg.checksum, 3); // 1 ^ 2
+    /// assert!(msg.validate().is_ok());
+    /// ```
+    pub fn with_payload(mut self, payload: Vec<u8>) -> Self {
+        self.checksum = self.checksum_algorithm.compute(&payload);
+        self.payload = payload;
+        self
+    }
+
+    /// Replaces the message type
+    ///
+    /// The checksum only covers the payload, so it is left unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let msg = Message::new(1, 5, vec![1, 2, 3]).with_message_type(9);
+    /// assert_eq!(msg.message_type, 9);
+    /// assert_eq!(msg.to_bytes()[1], 9);
+    /// ```
+    pub fn with_message_type(mut self, message_type: u8) -> Self {
+        self.message_type = message_type;
+        self
+    }
+
     /// Serializes the message to protocol format bytes
     ///
     /// Returns a vector of bytes following the protocol specification:
@@ -100,32 +477,254 @@ impl Message {
     /// assert_eq!(bytes[4..7], [1, 2, 3][..]);  // payload
     /// ```
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut result = Vec::new();
+        let mut result = Vec::with_capacity(self.serialized_len());
+        self.to_bytes_into(&mut result);
+        result
+    }
+
+    /// Serializes the message into `buf`, replacing its contents
+    ///
+    /// Produces the same bytes as `to_bytes()`. `buf` is cleared first and
+    /// only reallocates if its capacity is below `serialized_len()`, so one
+    /// buffer can be reused across many messages.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let mut buf = Vec::with_capacity(64);
+    /// for t in 0..3 {
+    ///     let msg = Message::new(1, t, vec![t; 8]);
+    ///     msg.to_bytes_into(&mut buf);
+    ///     assert_eq!(buf, msg.to_bytes());
+    /// }
+    /// ```
+    pub fn to_bytes_into(&self, buf: &mut Vec<u8>) {
+        buf.clear();
+        self.append_frame(buf, ByteOrder::BigEndian);
+    }
+
+    /// Serializes the message with the length field in `cfg.length_byte_order`
+    ///
+    /// The checksum trailer still follows `checksum_algorithm`. With the
+    /// default (big-endian) order this is the same as `to_bytes()`.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::{parse_with_config, ByteOrder, Message, ParseConfig};
+    ///
+    /// let cfg = ParseConfig {
+    ///     length_byte_order: ByteOrder::LittleEndian,
+    ///     ..ParseConfig::strict()
+    /// };
+    /// let msg = Message::new(1, 5, vec![1, 2, 3]);
+    /// let bytes = msg.to_bytes_with_config(&cfg);
+    /// assert_eq!(bytes[2..4], [3, 0]); // length, low byte first
+    /// assert_eq!(parse_with_config(&bytes, &cfg).unwrap(), msg);
+    /// ```
+    pub fn to_bytes_with_config(&self, cfg: &ParseConfig) -> Vec<u8> {
+        let mut result = Vec::with_capacity(self.serialized_len());
+        self.append_frame(&mut result, cfg.length_byte_order);
+        result
+    }
+
+    /// Appends the serialized message to `buf`
+    fn append_frame(&self, buf: &mut Vec<u8>, length_byte_order: ByteOrder) {
+        buf.reserve(self.serialized_len());
 
         // Add version
-        result.push(self.version);
+        buf.push(self.version);
 
         // Add message type
-        result.push(self.message_type);
+        buf.push(self.message_type);
 
-        // Add payload length as big-endian u16
-        let length_bytes = u16_to_bytes(self.payload.len() as u16);
-        result.extend_from_slice(&length_bytes);
+        // Add payload length as a u16 in the requested byte order
+        let length_bytes = length_byte_order.write_length(self.payload.len() as u16);
+        buf.extend_from_slice(&length_bytes);
 
         // Add payload
-        result.extend_from_slice(&self.payload);
+        buf.extend_from_slice(&self.payload);
 
         // Add checksum
-        result.push(self.checksum);
+        buf.extend_from_slice(self.checksum_trailer().as_slice());
+    }
 
-        result
+    /// Writes the message in protocol format directly to `writer`
+    ///
+    /// Produces the same bytes as `to_bytes()` without allocating: the
+    /// payload slice is written as-is. Wrap unbuffered writers (files,
+    /// sockets) in a `BufWriter`, since each field is a separate write.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let msg = Message::new(1, 5, vec![1, 2, 3]);
+    /// let mut out = Vec::with_capacity(msg.serialized_len());
+    /// msg.to_writer(&mut out).unwrap();
+    /// assert_eq!(out, msg.to_bytes());
+    /// ```
+    pub fn to_writer<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[self.version])?;
+        writer.write_all(&[self.message_type])?;
+        writer.write_all(&u16_to_bytes(self.payload.len() as u16))?;
+        writer.write_all(&self.payload)?;
+        writer.write_all(self.checksum_trailer().as_slice())
+    }
+
+    /// The checksum as it appears on the wire: its low bytes, big-endian
+    fn checksum_trailer(&self) -> ChecksumTrailer {
+        ChecksumTrailer {
+            bytes: self.checksum.to_be_bytes(),
+            len: self.checksum_algorithm.trailer_len(),
+        }
+    }
+
+    /// Reads one message from `reader`
+    ///
+    /// Reads the 4-byte header first, then exactly the payload length it
+    /// announces, then the checksum byte, so nothing past the message is
+    /// consumed. The message is validated with `ValidationConfig::strict()`.
+    ///
+    /// # Returns
+    /// * `Ok(Message)` if a complete, valid message was read
+    /// * `Err(ParseError::UnexpectedEof)` if the reader ends mid-message,
+    ///   with the number of bytes received so far
+    /// * `Err(ParseError::Io)` for any other read failure
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    /// use std::io::Cursor;
+    ///
+    /// let bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+    /// let msg = Message::from_reader(&mut Cursor::new(bytes)).unwrap();
+    /// assert_eq!(msg.payload, vec![1, 2, 3]);
+    /// ```
+    pub fn from_reader<R: io::Read>(reader: &mut R) -> Result<Message, ParseError> {
+        Message::from_reader_with_config(reader, &ValidationConfig::strict())
+    }
+
+    /// Reads one message from `reader` using custom validation rules
+    ///
+    /// Same as `from_reader()`, but the frame is read and checked following
+    /// `cfg`; `cfg.checksum` decides how many trailer bytes are read.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::{ChecksumAlgorithm, Message, ParseConfig};
+    /// use std::io::Cursor;
+    ///
+    /// let cfg = ParseConfig {
+    ///     checksum: ChecksumAlgorithm::Crc16,
+    ///     ..ParseConfig::strict()
+    /// };
+    /// let bytes = Message::new_with_config(1, 5, vec![1, 2, 3], &cfg).to_bytes();
+    /// let msg = Message::from_reader_with_config(&mut Cursor::new(bytes), &cfg).unwrap();
+    /// assert_eq!(msg.checksum_algorithm, ChecksumAlgorithm::Crc16);
+    /// ```
+    pub fn from_reader_with_config<R: io::Read>(
+        reader: &mut R,
+        cfg: &ParseConfig,
+    ) -> Result<Message, ParseError> {
+        let mut header = [0u8; HEADER_SIZE];
+        read_field(reader, &mut header, 0)?;
+
+        let length = cfg
+            .length_byte_order
+            .read_length(&header[LENGTH_OFFSET..HEADER_SIZE]) as usize;
+        let mut payload = vec![0u8; length];
+        read_field(reader, &mut payload, HEADER_SIZE)?;
+
+        let mut trailer = [0u8; 4];
+        let trailer = &mut trailer[..cfg.checksum.trailer_len()];
+        read_field(reader, trailer, HEADER_SIZE + length)?;
+
+        Message::from_read_parts(header, payload, trailer_value(trailer), cfg)
+    }
+
+    /// Async version of `from_reader()` for tokio readers
+    ///
+    /// Same read order and errors as `from_reader()`.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+    /// let msg = Message::from_async_reader(&mut bytes.as_slice()).await.unwrap();
+    /// assert_eq!(msg.payload, vec![1, 2, 3]);
+    /// # });
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn from_async_reader<R: AsyncReadExt + Unpin>(
+        reader: &mut R,
+    ) -> Result<Message, ParseError> {
+        Message::from_async_reader_with_config(reader, &ValidationConfig::strict()).await
+    }
+
+    /// Async version of `from_reader_with_config()` for tokio readers
+    #[cfg(feature = "async")]
+    pub async fn from_async_reader_with_config<R: AsyncReadExt + Unpin>(
+        reader: &mut R,
+        cfg: &ParseConfig,
+    ) -> Result<Message, ParseError> {
+        let mut header = [0u8; HEADER_SIZE];
+        read_field_async(reader, &mut header, 0).await?;
+
+        let length = cfg
+            .length_byte_order
+            .read_length(&header[LENGTH_OFFSET..HEADER_SIZE]) as usize;
+        let mut payload = vec![0u8; length];
+        read_field_async(reader, &mut payload, HEADER_SIZE).await?;
+
+        let mut trailer = [0u8; 4];
+        let trailer = &mut trailer[..cfg.checksum.trailer_len()];
+        read_field_async(reader, trailer, HEADER_SIZE + length).await?;
+
+        Message::from_read_parts(header, payload, trailer_value(trailer), cfg)
+    }
+
+    /// Builds and validates a message from the pieces read off a stream
+    fn from_read_parts(
+        header: [u8; HEADER_SIZE],
+        payload: Vec<u8>,
+        checksum: u32,
+        cfg: &ParseConfig,
+    ) -> Result<Message, ParseError> {
+        let message = Message {
+            version: header[0],
+            message_type: header[1],
+            payload,
+            checksum,
+            checksum_algorithm: cfg.checksum,
+            metadata: HashMap::new(),
+        };
+        message.validate_with_config(cfg)?;
+        Ok(message)
+    }
+
+    /// Number of bytes `to_bytes()` / `to_writer()` produce
+    ///
+    /// Header (4) + payload + checksum (1, 2 or 4 depending on the algorithm).
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let msg = Message::new(1, 5, vec![1, 2, 3]);
+    /// assert_eq!(msg.serialized_len(), 8);
+    /// ```
+    pub fn serialized_len(&self) -> usize {
+        HEADER_SIZE + self.payload.len() + self.checksum_algorithm.trailer_len()
     }
 
     /// Validates message integrity
     ///
     /// Verifies that:
     /// - Version is valid (must be 1)
-    /// - Checksum matches the calculated value
+    /// - Checksum matches the value `checksum_algorithm` calculates
     /// - Message is not malformed
     ///
     /// # Returns
@@ -140,61 +739,208 @@ impl Message {
     /// assert!(msg.validate().is_ok());
     /// ```
     pub fn validate(&self) -> Result<(), ParseError> {
-        // Verify version
-        if self.version != 1 {
-            return Err(ParseError::InvalidVersion {
-                version: self.version,
-            });
-        }
-
-        // Verify checksum
-        let calculated = calculate_checksum(&self.payload);
-        if calculated != self.checksum {
-            return Err(ParseError::ChecksumMismatch {
-                expected: self.checksum,
-                calculated,
-            });
-        }
-
-        Ok(())
+        self.validate_with_config(&ValidationConfig::strict())
     }
-}
 
-impl fmt::Display for Message {
-    /// Pretty-prints the message in human-readable format
+    /// Validates message integrity using custom rules
     ///
-    /// Shows version, message type, payload length, and checksum
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "Message v{} type={} payload_len={} checksum=0x{:02X}",
+    /// Verifies that:
+    /// - Version is in `cfg.allowed_versions`
+    /// - Message is at least `min_message_size_for_version` bytes
+    /// - Payload is no longer than `cfg.max_payload_len`
+    /// - Checksum matches the value `checksum_algorithm` calculates (unless `cfg.skip_checksum`)
+    ///
+    /// `cfg.checksum` is not used: it only tells parsers how to read a frame,
+    /// and a message always carries the algorithm its checksum was made with.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::{Message, ValidationConfig};
+    ///
+    /// let legacy = Message::new(0, 5, vec![1, 2, 3]);
+    /// assert!(legacy.validate_with_config(&ValidationConfig::permissive()).is_ok());
+    /// ```
+    pub fn validate_with_config(&self, cfg: &ValidationConfig) -> Result<(), ParseError> {
+        validate_fields(
             self.version,
-            self.message_type,
-            self.payload.len(),
-            self.checksum
+            &self.payload,
+            self.checksum,
+            self.checksum_algorithm,
+            cfg,
         )
     }
-}
 
-/// Parses a byte slice into a Message
-///
-/// # Arguments
-/// * `data` - The bytes to parse (must follow protocol format)
-///
-/// # Returns
-/// * `Ok(Message)` if parsing succeeds
-/// * `Err(ParseError)` if parsing fails
-///
-/// # Protocol Format
-/// The byte stream must follow this format:
-/// - Byte 0: Version (must be 1)
-/// - Byte 1: Message Type
-/// - Bytes 2-3: Payload length as big-endian u16
-/// - Bytes 4..4+length: Payload data
-/// - Last byte: Checksum (XOR of all payload bytes)
-///
-/// # Example
-/// ```
+    /// Overwrites part of the payload and recomputes the checksum
+    ///
+    /// Replaces `payload[offset..offset + data.len()]` with `data`. The
+    /// payload length never changes; use `resize_payload()` for that.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the patch fits inside the payload
+    /// * `Err(ParseError::MessageTooShort)` if it would run past the end
+    ///   (the message is left unchanged)
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let mut msg = Message::new(1, 5, vec![1, 2, 3, 4]);
+    /// msg.patch_payload(1, &[0xAA, 0xBB]).unwrap();
+    /// assert_eq!(msg.payload, vec![1, 0xAA, 0xBB, 4]);
+    /// assert!(msg.validate().is_ok());
+    /// ```
+    pub fn patch_payload(&mut self, offset: usize, data: &[u8]) -> Result<(), ParseError> {
+        let end = offset
+            .checked_add(data.len())
+            .filter(|&end| end <= self.payload.len())
+            .ok_or(ParseError::MessageTooShort {
+                actual: self.payload.len(),
+            })?;
+
+        self.payload[offset..end].copy_from_slice(data);
+        self.checksum = self.checksum_algorithm.compute(&self.payload);
+        Ok(())
+    }
+
+    /// Grows or shrinks the payload to `new_len` bytes and recomputes the checksum
+    ///
+    /// New bytes are set to `fill_byte`; shrinking drops bytes from the end.
+    ///
+    /// # Panics
+    /// Panics if `new_len` exceeds the maximum payload size (65535), since the
+    /// length would no longer fit in the 2-byte length field.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let mut msg = Message::new(1, 5, vec![1, 2, 3]);
+    /// msg.resize_payload(5, 0xFF);
+    /// assert_eq!(msg.payload, vec![1, 2, 3, 0xFF, 0xFF]);
+    /// assert!(msg.validate().is_ok());
+    /// ```
+    pub fn resize_payload(&mut self, new_len: usize, fill_byte: u8) {
+        assert!(
+            new_len <= MAX_PAYLOAD_SIZE,
+            "payload length {} exceeds maximum {}",
+            new_len,
+            MAX_PAYLOAD_SIZE
+        );
+        self.payload.resize(new_len, fill_byte);
+        self.checksum = self.checksum_algorithm.compute(&self.payload);
+    }
+
+    /// Borrows the payload as a UTF-8 string
+    ///
+    /// # Returns
+    /// * `Ok(&str)` if the payload is valid UTF-8
+    /// * `Err(Utf8Error)` otherwise; `valid_up_to()` gives the position
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let msg = Message::new(1, 5, b"Hello".to_vec());
+    /// assert_eq!(msg.payload_as_utf8().unwrap(), "Hello");
+    /// ```
+    pub fn payload_as_utf8(&self) -> Result<&str, std::str::Utf8Error> {
+        std::str::from_utf8(&self.payload)
+    }
+
+    /// Decodes the payload as UTF-8, replacing invalid sequences with U+FFFD
+    ///
+    /// Borrows the payload when it is valid UTF-8 and only allocates
+    /// otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let msg = Message::new(1, 5, b"Hello".to_vec());
+    /// assert_eq!(msg.payload_as_utf8_lossy(), "Hello");
+    ///
+    /// let msg = Message::new(1, 5, b"Hello\xFF".to_vec());
+    /// assert_eq!(msg.payload_as_utf8_lossy(), "Hello\u{FFFD}");
+    /// ```
+    pub fn payload_as_utf8_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.payload)
+    }
+
+    /// Decodes the payload as a sequence of TLV records
+    ///
+    /// See the `tlv` module for the encoding. Values are not decoded
+    /// further; pass one to `tlv::parse_fields()` for nested records.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<TlvField>)` with one entry per record, in order
+    /// * `Err(ParseError::TruncatedStream)` if a record header is cut short
+    /// * `Err(ParseError::IncompletePayload)` if a value runs past the end
+    pub fn parse_tlv_fields(&self) -> Result<Vec<TlvField>, ParseError> {
+        tlv::parse_fields(&self.payload)
+    }
+
+    /// Creates a message whose payload is `fields` encoded as TLV records
+    ///
+    /// # Returns
+    /// * `Ok(Message)` with a calculated checksum
+    /// * `Err(ParseError::PayloadTooLarge)` if a value is longer than 255
+    ///   bytes or the encoded records don't fit in a payload
+    ///
+    /// # Example
+    /// ```
+    /// use binary_protocol_parser::tlv::TlvField;
+    /// use binary_protocol_parser::Message;
+    ///
+    /// let fields = vec![TlvField::new(1, b"id".to_vec()), TlvField::new(2, [7])];
+    /// let msg = Message::build_from_tlv(1, 5, &fields).unwrap();
+    /// assert_eq!(msg.payload, vec![1, 2, b'i', b'd', 2, 1, 7]);
+    /// assert_eq!(msg.parse_tlv_fields().unwrap(), fields);
+    /// ```
+    pub fn build_from_tlv(
+        version: u8,
+        message_type: u8,
+        fields: &[TlvField],
+    ) -> Result<Message, ParseError> {
+        let payload = tlv::encode_fields(fields)?;
+        Ok(Message::new(version, message_type, payload))
+    }
+}
+
+impl fmt::Display for Message {
+    /// Pretty-prints the message in human-readable format
+    ///
+    /// Shows version, message type, payload length, and checksum
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Message v{} type={} payload_len={} checksum=0x{:02X}",
+            self.version,
+            self.message_type,
+            self.payload.len(),
+            self.checksum
+        )
+    }
+}
+
+/// Parses a byte slice into a Message
+///
+/// # Arguments
+/// * `data` - The bytes to parse (must follow protocol format)
+///
+/// # Returns
+/// * `Ok(Message)` if parsing succeeds
+/// * `Err(ParseError)` if parsing fails
+///
+/// # Protocol Format
+/// The byte stream must follow this format:
+/// - Byte 0: Version (must be 1)
+/// - Byte 1: Message Type
+/// - Bytes 2-3: Payload length as big-endian u16
+/// - Bytes 4..4+length: Payload data
+/// - Last byte: Checksum (XOR of all payload bytes)
+///
+/// # Example
+/// ```
 /// use binary_protocol_parser::parse;
 ///
 /// let packet = vec![1, 5, 0, 3, 1, 2, 3, 0]; // v1, type5, len3, payload[1,2,3], checksum
@@ -204,63 +950,433 @@ impl fmt::Display for Message {
 /// assert_eq!(msg.payload, vec![1, 2, 3]);
 /// ```
 pub fn parse(data: &[u8]) -> Result<Message, ParseError> {
-    // Check minimum length (version + type + length + checksum = 5 bytes minimum)
-    if data.len() < 5 {
-        return Err(ParseError::MessageTooShort {
-            actual: data.len(),
+    parse_with_config(data, &ValidationConfig::strict())
+}
+
+/// Parses a byte slice into a Message using custom validation rules
+///
+/// Same as `parse()`, but version, payload size and checksum checks follow
+/// `cfg` instead of the strict defaults. `cfg.checksum` also decides how
+/// many trailer bytes are read.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::{parse, parse_with_config, ValidationConfig};
+///
+/// let legacy = vec![0, 5, 0, 1, 7, 0xFF]; // v0 with a bogus checksum
+/// assert!(parse(&legacy).is_err());
+///
+/// let msg = parse_with_config(&legacy, &ValidationConfig::permissive()).unwrap();
+/// assert_eq!(msg.version, 0);
+/// ```
+pub fn parse_with_config(data: &[u8], cfg: &ValidationConfig) -> Result<Message, ParseError> {
+    let (version, message_type, payload, checksum) = split_frame(data, cfg, None)?;
+
+    // Create message and validate
+    let message = Message {
+        version,
+        message_type,
+        payload: payload.to_vec(),
+        checksum,
+        checksum_algorithm: cfg.checksum,
+        metadata: HashMap::new(),
+    };
+
+    // Verify checksum
+    message.validate_with_config(cfg)?;
+
+    Ok(message)
+}
+
+/// Reads and parses one message from a file, socket or other `io::Read`
+///
+/// Same as `Message::from_reader()`: reads the 4-byte header, then exactly
+/// `length + 1` bytes (payload and checksum), so the reader is left at the
+/// start of the next message. Wrap unbuffered readers in a `BufReader`.
+///
+/// # Returns
+/// * `Ok(Message)` if a complete, valid message was read
+/// * `Err(ParseError::UnexpectedEof)` if the reader ends mid-message
+/// * `Err(ParseError::Io)` for any other read failure
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::{parse_from_reader, Message};
+/// use std::io::Cursor;
+///
+/// let mut reader = Cursor::new(Message::new(1, 5, vec![1, 2, 3]).to_bytes());
+/// let msg = parse_from_reader(&mut reader).unwrap();
+/// assert_eq!(msg.payload, vec![1, 2, 3]);
+/// ```
+pub fn parse_from_reader<R: io::Read>(reader: &mut R) -> Result<Message, ParseError> {
+    Message::from_reader(reader)
+}
+
+/// Async version of `parse_from_reader()` for tokio sockets and files
+///
+/// Awaits the header, then the payload and checksum, without blocking the
+/// runtime thread. Same errors as `parse_from_reader()`. Requires the
+/// `async` feature.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::{parse_from_async_reader, Message};
+///
+/// # tokio_test::block_on(async {
+/// let bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+/// let msg = parse_from_async_reader(&mut bytes.as_slice()).await.unwrap();
+/// assert_eq!(msg.payload, vec![1, 2, 3]);
+/// # });
+/// ```
+#[cfg(feature = "async")]
+pub async fn parse_from_async_reader<R: AsyncReadExt + Unpin>(
+    reader: &mut R,
+) -> Result<Message, ParseError> {
+    Message::from_async_reader(reader).await
+}
+
+/// Parses a byte slice into a `Message` taken from `pool`
+///
+/// Same checks as `parse()`. The message's payload buffer is reused from an
+/// earlier message when it is large enough, and the message goes back to the
+/// pool when the returned `PooledMessage` is dropped.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::pool::MessagePool;
+/// use binary_protocol_parser::{parse_pooled, Message};
+///
+/// let pool = MessagePool::new();
+/// let msg = parse_pooled(&Message::new(1, 5, vec![1, 2, 3]).to_bytes(), &pool).unwrap();
+/// assert_eq!(msg.payload, vec![1, 2, 3]);
+/// ```
+pub fn parse_pooled<'pool>(
+    data: &[u8],
+    pool: &'pool MessagePool,
+) -> Result<PooledMessage<'pool>, ParseError> {
+    let cfg = ValidationConfig::strict();
+    let (version, message_type, payload, checksum) = split_frame(data, &cfg, None)?;
+
+    let mut message = pool.acquire();
+    message.version = version;
+    message.message_type = message_type;
+    message.payload.clear();
+    message.payload.extend_from_slice(payload);
+    message.checksum = checksum;
+    message.checksum_algorithm = cfg.checksum;
+
+    // On failure the message is dropped back into the pool
+    message.validate_with_config(&cfg)?;
+
+    Ok(message)
+}
+
+/// Parses a message whose payload stays inside `data`
+///
+/// Same checks as `parse()`. Instead of copying the payload into a `Vec`,
+/// the returned `MessageRef` holds a `Bytes` slice sharing `data`'s
+/// allocation, so parsing allocates nothing.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::{parse_zero_copy, Message};
+/// use bytes::Bytes;
+///
+/// let data = Bytes::from(Message::new(1, 5, vec![1, 2, 3]).to_bytes());
+/// let msg = parse_zero_copy(data.clone()).unwrap();
+/// assert_eq!(msg.payload, &data[4..7]);
+/// assert_eq!(msg.to_owned(), Message::new(1, 5, vec![1, 2, 3]));
+/// ```
+#[cfg(feature = "zero-copy")]
+pub fn parse_zero_copy(data: bytes::Bytes) -> Result<MessageRef, ParseError> {
+    let cfg = ValidationConfig::strict();
+    let (version, message_type, payload, checksum) = split_frame(&data, &cfg, None)?;
+    validate_fields(version, payload, checksum, cfg.checksum, &cfg)?;
+
+    let payload = HEADER_SIZE..HEADER_SIZE + payload.len();
+    Ok(MessageRef {
+        version,
+        message_type,
+        payload: data.slice(payload),
+        checksum,
+    })
+}
+
+/// Smallest valid message for `version`, in bytes
+///
+/// Version 1 is header (4) + empty payload + checksum (1) = 5 bytes.
+/// Version 2 carries a flags byte as the first payload byte, so its
+/// smallest message is 6 bytes. Returns `None` for versions with no
+/// defined layout.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::error::ParseError;
+/// use binary_protocol_parser::{min_message_size_for_version, parse, parse_with_config};
+/// use binary_protocol_parser::{Message, ValidationConfig};
+///
+/// assert_eq!(min_message_size_for_version(1), Some(5));
+/// assert_eq!(min_message_size_for_version(2), Some(6));
+/// assert_eq!(min_message_size_for_version(7), None);
+/// assert!(parse(&[1, 5, 0, 0, 0]).is_ok());
+///
+/// let cfg = ValidationConfig {
+///     allowed_versions: vec![1, 2].into(),
+///     ..ValidationConfig::strict()
+/// };
+/// let no_flags = Message::new(2, 5, vec![]).to_bytes();
+/// assert!(matches!(
+///     parse_with_config(&no_flags, &cfg),
+///     Err(ParseError::BelowVersionMinimum { version: 2, min_size: 6, actual: 5 })
+/// ));
+/// ```
+pub const fn min_message_size_for_version(version: u8) -> Option<usize> {
+    match version {
+        1 => Some(HEADER_SIZE + 1),
+        2 => Some(HEADER_SIZE + 2),
+        _ => None,
+    }
+}
+
+/// Parses a byte slice into a `Message`, giving up once `deadline` is reached
+///
+/// Same checks as `parse()`. The deadline is checked after the version
+/// check, after the length is read and after the payload is extracted;
+/// reaching it returns `ParseError::DeadlineExceeded` instead of a message.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::{parse_with_deadline, Message};
+/// use binary_protocol_parser::error::ParseError;
+/// use std::time::{Duration, Instant};
+///
+/// let bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+///
+/// let generous = Instant::now() + Duration::from_secs(1);
+/// assert!(parse_with_deadline(&bytes, generous).is_ok());
+///
+/// let already_passed = Instant::now();
+/// assert!(matches!(
+///     parse_with_deadline(&bytes, already_passed),
+///     Err(ParseError::DeadlineExceeded { .. })
+/// ));
+/// ```
+pub fn parse_with_deadline(data: &[u8], deadline: Instant) -> Result<Message, ParseError> {
+    parse_before(data, &Deadline::new(deadline))
+}
+
+/// Parses sequential messages like `parse_multiple`, within one time budget
+///
+/// `deadline` covers the whole input, not each message. Errors, including
+/// `DeadlineExceeded`, carry the byte offset of the message being parsed.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::{parse_multiple_with_deadline, Message};
+/// use std::time::{Duration, Instant};
+///
+/// let mut data = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+/// data.extend_from_slice(&Message::new(1, 6, vec![4]).to_bytes());
+///
+/// let deadline = Instant::now() + Duration::from_secs(1);
+/// assert_eq!(parse_multiple_with_deadline(&data, deadline).unwrap().len(), 2);
+/// ```
+pub fn parse_multiple_with_deadline(
+    data: &[u8],
+    deadline: Instant,
+) -> Result<Vec<Message>, ParseError> {
+    let deadline = Deadline::new(deadline);
+    let mut messages = Vec::new();
+    let mut position = 0;
+
+    while position < data.len() {
+        let message =
+            parse_before(&data[position..], &deadline).map_err(|err| err.at_offset(position))?;
+        position += message.serialized_len();
+        messages.push(message);
+    }
+
+    Ok(messages)
+}
+
+/// Deadline for one parsing call, remembering when the call started
+struct Deadline {
+    start: Instant,
+    deadline: Instant,
+}
+
+impl Deadline {
+    fn new(deadline: Instant) -> Self {
+        Self {
+            start: Instant::now(),
+            deadline,
+        }
+    }
+
+    fn check(&self) -> Result<(), ParseError> {
+        let now = Instant::now();
+        if now >= self.deadline {
+            return Err(ParseError::DeadlineExceeded {
+                elapsed: now.duration_since(self.start),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn parse_before(data: &[u8], deadline: &Deadline) -> Result<Message, ParseError> {
+    let cfg = ValidationConfig::strict();
+    let (version, message_type, payload, checksum) = split_frame(data, &cfg, Some(deadline))?;
+
+    let message = Message {
+        version,
+        message_type,
+        payload: payload.to_vec(),
+        checksum,
+        checksum_algorithm: cfg.checksum,
+        metadata: HashMap::new(),
+    };
+    message.validate_with_config(&cfg)?;
+
+    Ok(message)
+}
+
+/// Checks a message's version, size and checksum against `cfg`
+///
+/// `checksum` is verified with `algorithm`. Shared by
+/// `Message::validate_with_config` and the borrowed-payload parsers.
+fn validate_fields(
+    version: u8,
+    payload: &[u8],
+    checksum: u32,
+    algorithm: ChecksumAlgorithm,
+    cfg: &ValidationConfig,
+) -> Result<(), ParseError> {
+    // Verify version
+    if !cfg.allows_version(version) {
+        return Err(ParseError::InvalidVersion { version });
+    }
+
+    // Verify the message is not smaller than its version allows. The
+    // per-version sizes assume the 1-byte XOR trailer.
+    if let Some(min) = min_message_size_for_version(version) {
+        if HEADER_SIZE + payload.len() + 1 < min {
+            let trailer_len = algorithm.trailer_len();
+            return Err(ParseError::BelowVersionMinimum {
+                version,
+                min_size: min - 1 + trailer_len,
+                actual: HEADER_SIZE + payload.len() + trailer_len,
+            });
+        }
+    }
+
+    // Verify payload size
+    if payload.len() > cfg.max_payload_len {
+        return Err(ParseError::PayloadTooLarge {
+            size: payload.len(),
+            max: cfg.max_payload_len,
+            offset: LENGTH_OFFSET,
         });
     }
 
+    // Verify checksum
+    if !cfg.skip_checksum {
+        algorithm.verify(payload, checksum)?;
+    }
+
+    Ok(())
+}
+
+/// Checks the header and length of one message and splits out its fields
+///
+/// Returns `(version, message_type, payload, checksum)`; the checksum is not
+/// verified here. With a `deadline`, each stage checks it before moving on.
+fn split_frame<'a>(
+    data: &'a [u8],
+    cfg: &ValidationConfig,
+    deadline: Option<&Deadline>,
+) -> Result<(u8, u8, &'a [u8], u32), ParseError> {
+    let checkpoint = || deadline.map_or(Ok(()), Deadline::check);
+
     // Extract version (byte 0)
-    let version = data[0];
+    let Some(&version) = data.first() else {
+        return Err(ParseError::TruncatedStream {
+            expected: HEADER_SIZE,
+            available: 0,
+        });
+    };
 
     // Verify version is supported
-    if version != 1 {
+    if !cfg.allows_version(version) {
         return Err(ParseError::InvalidVersion { version });
     }
+    checkpoint()?;
+
+    // Fail fast if not even the smallest message of this version fits.
+    // Versions without a layout of their own (accepted through `cfg`) are
+    // framed like version 1. The per-version sizes assume the 1-byte XOR
+    // trailer, so adjust for wider checksums.
+    let trailer_len = cfg.checksum.trailer_len();
+    let min_size =
+        min_message_size_for_version(version).unwrap_or(HEADER_SIZE + 1) - 1 + trailer_len;
+    if data.len() < min_size {
+        if data.len() < HEADER_SIZE {
+            // Without the full header (version + type + length) we can't
+            // tell how long the message should be: the stream was cut short
+            return Err(ParseError::TruncatedStream {
+                expected: HEADER_SIZE,
+                available: data.len(),
+            });
+        }
+        // A frame that is complete but below the version minimum is
+        // rejected as `BelowVersionMinimum` once its fields are checked
+        let declared = frame_len(data, cfg);
+        if declared > data.len() {
+            return Err(ParseError::IncompletePayload {
+                expected: min_size.max(declared),
+                actual: data.len(),
+                offset: HEADER_SIZE,
+            });
+        }
+    }
 
     // Extract message type (byte 1)
     let message_type = data[1];
 
-    // Extract payload length from bytes 2-3 (big-endian)
-    let length = bytes_to_u16(&data[2..4]) as usize;
+    // Extract payload length from bytes 2-3 (big-endian unless configured)
+    let length = cfg
+        .length_byte_order
+        .read_length(&data[LENGTH_OFFSET..HEADER_SIZE]) as usize;
+    checkpoint()?;
 
     // Verify we have enough data for the payload
-    // Format: version(1) + type(1) + length(2) + payload(length) + checksum(1)
-    let required_length = 4 + length + 1;
+    // Format: version(1) + type(1) + length(2) + payload(length) + checksum(1, 2 or 4)
+    let required_length = 4 + length + trailer_len;
     if data.len() < required_length {
-        return Err(ParseError::IncompletPayload {
+        return Err(ParseError::IncompletePayload {
             expected: required_length,
             actual: data.len(),
+            offset: HEADER_SIZE,
         });
     }
 
     // Verify payload size is reasonable
-    if length > MAX_PAYLOAD_SIZE {
+    if length > cfg.max_payload_len {
         return Err(ParseError::PayloadTooLarge {
             size: length,
-            max: MAX_PAYLOAD_SIZE,
+            max: cfg.max_payload_len,
+            offset: LENGTH_OFFSET,
         });
     }
 
     // Extract payload (bytes 4..4+length)
-    let payload = data[4..4 + length].to_vec();
-
-    // Extract checksum (last byte of payload section)
-    let checksum = data[4 + length];
+    let payload = &data[4..4 + length];
 
-    // Create message and validate
-    let message = Message {
-        version,
-        message_type,
-        payload,
-        checksum,
-    };
+    // Extract checksum (big-endian trailer after the payload)
+    let checksum = trailer_value(&data[4 + length..required_length]);
+    checkpoint()?;
 
-    // Verify checksum
-    message.validate()?;
-
-    Ok(message)
+    Ok((version, message_type, payload, checksum))
 }
 
 /// Parses multiple sequential messages from a byte stream
@@ -272,7 +1388,8 @@ pub fn parse(data: &[u8]) -> Result<Message, ParseError> {
 ///
 /// # Returns
 /// * `Ok(Vec<Message>)` if all messages parse successfully
-/// * `Err(ParseError)` if parsing fails (partially parsed messages are discarded)
+/// * `Err(ParseError::WithContext)` if parsing fails, carrying the byte offset
+///   of the failing message (partially parsed messages are discarded)
 ///
 /// # Example
 /// ```
@@ -293,11 +1410,10 @@ pub fn parse_multiple(data: &[u8]) -> Result<Vec<Message>, ParseError> {
 
     while position < data.len() {
         // Parse one message starting at position
-        let message = parse(&data[position..])?;
+        let message = parse(&data[position..]).map_err(|err| err.at_offset(position))?;
 
         // Calculate how many bytes this message consumed
-        let message_bytes = message.to_bytes();
-        let message_length = message_bytes.len();
+        let message_length = message.serialized_len();
 
         // Add to results
         messages.push(message);
@@ -309,6 +1425,204 @@ pub fn parse_multiple(data: &[u8]) -> Result<Vec<Message>, ParseError> {
     Ok(messages)
 }
 
+/// Parses sequential messages one at a time as the iterator is advanced
+///
+/// Same rules as `parse_multiple`, but only the current position is kept,
+/// so memory use does not grow with the input. Iteration ends when `data`
+/// is used up, or right after the first error.
+///
+/// # Returns
+/// An iterator yielding `Ok(Message)` for each message in order, and at
+/// most one `Err(ParseError::WithContext)` carrying the byte offset of the
+/// failing message
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::{parse_multiple_lazy, Message};
+///
+/// let mut data = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+/// data.extend_from_slice(&Message::new(1, 10, vec![4, 5]).to_bytes());
+/// data.push(2); // start of a message with a bad version
+///
+/// let mut messages = parse_multiple_lazy(&data);
+/// assert_eq!(messages.next().unwrap().unwrap().message_type, 5);
+/// assert_eq!(messages.next().unwrap().unwrap().message_type, 10);
+/// assert_eq!(messages.next().unwrap().unwrap_err().offset(), Some(15));
+/// assert!(messages.next().is_none());
+/// ```
+pub fn parse_multiple_lazy(data: &[u8]) -> impl Iterator<Item = Result<Message, ParseError>> + '_ {
+    let mut position = 0;
+    std::iter::from_fn(move || {
+        if position >= data.len() {
+            return None;
+        }
+
+        match parse(&data[position..]) {
+            Ok(message) => {
+                position += message.serialized_len();
+                Some(Ok(message))
+            }
+            Err(err) => {
+                let err = err.at_offset(position);
+                // Stop after the error
+                position = data.len();
+                Some(Err(err))
+            }
+        }
+    })
+}
+
+/// Parses the complete messages at the start of `data` and returns the rest
+///
+/// Like `parse_multiple`, but a trailing partial message is not an error:
+/// its bytes are returned as the remainder, to be prepended to the next
+/// chunk read from the stream. Only complete frames are validated, so a bad
+/// header in the remainder is reported once the rest of it arrives.
+///
+/// # Returns
+/// * `Ok((messages, remainder))` with every complete message in order
+/// * `Err(ParseError::WithContext)` if a complete frame fails to parse,
+///   carrying its byte offset
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::{parse_multiple_with_remainder, Message};
+///
+/// let mut data = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+/// let next = Message::new(1, 6, vec![4, 5]).to_bytes();
+/// data.extend_from_slice(&next[..3]);
+///
+/// let (messages, remainder) = parse_multiple_with_remainder(&data).unwrap();
+/// assert_eq!(messages.len(), 1);
+/// assert_eq!(remainder, &next[..3]);
+/// ```
+pub fn parse_multiple_with_remainder(data: &[u8]) -> Result<(Vec<Message>, &[u8]), ParseError> {
+    let cfg = ValidationConfig::strict();
+    let mut messages = Vec::new();
+    let mut position = 0;
+
+    loop {
+        let remaining = &data[position..];
+        if remaining.len() < HEADER_SIZE {
+            break;
+        }
+
+        let frame_len = frame_len(remaining, &cfg);
+        if remaining.len() < frame_len {
+            break;
+        }
+
+        let message = parse_with_config(&remaining[..frame_len], &cfg)
+            .map_err(|err| err.at_offset(position))?;
+        messages.push(message);
+        position += frame_len;
+    }
+
+    Ok((messages, &data[position..]))
+}
+
+/// Parses multiple sequential messages using a thread pool
+///
+/// Produces the same result as `parse_multiple`, including which error is
+/// reported, but splits the work in two phases:
+/// 1. A sequential scan reads only the length fields to find where each
+///    message starts and ends.
+/// 2. The messages are parsed and validated in parallel on a Rayon pool with
+///    `num_threads` threads (0 lets Rayon pick).
+///
+/// A corrupt length field stops the scan; messages before it are still
+/// checked so that an earlier error wins, as in the sequential parser.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::{Message, parse_multiple_concurrent};
+/// use std::sync::Arc;
+///
+/// let mut data = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+/// data.extend_from_slice(&Message::new(1, 10, vec![4]).to_bytes());
+///
+/// let messages = parse_multiple_concurrent(Arc::from(data), 4).unwrap();
+/// assert_eq!(messages[1].message_type, 10);
+/// ```
+#[cfg(feature = "parallel")]
+pub fn parse_multiple_concurrent(
+    data: Arc<[u8]>,
+    num_threads: usize,
+) -> Result<Vec<Message>, ParseError> {
+    use rayon::prelude::*;
+
+    // Phase 1: find message boundaries
+    let (ranges, scan_result) = message_ranges(&data, &ValidationConfig::strict());
+
+    // Phase 2: parse each range in parallel. An indexed parallel collect
+    // keeps results in offset order.
+    let parse_all = || -> Vec<Result<Message, ParseError>> {
+        ranges
+            .par_iter()
+            .map(|range| parse_at_offset(&data, range.clone()))
+            .collect()
+    };
+    let results = match rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+    {
+        Ok(pool) => pool.install(parse_all),
+        // Can't start threads: still parse, on the global pool
+        Err(_) => parse_all(),
+    };
+
+    let messages = results.into_iter().collect::<Result<Vec<_>, _>>()?;
+    scan_result?;
+    Ok(messages)
+}
+
+/// Parses the single message occupying `range` of `data`
+#[cfg(feature = "parallel")]
+fn parse_at_offset(data: &[u8], range: Range<usize>) -> Result<Message, ParseError> {
+    let offset = range.start;
+    parse(&data[range]).map_err(|err| err.at_offset(offset))
+}
+
+/// Byte ranges of the messages in `data`, found from the length fields alone
+///
+/// Frames are sized following `cfg`. Scanning stops at the first header or
+/// payload that runs past the end of the buffer; the ranges found up to that
+/// point are returned with the error.
+#[cfg(feature = "parallel")]
+fn message_ranges(
+    data: &[u8],
+    cfg: &ValidationConfig,
+) -> (Vec<Range<usize>>, Result<(), ParseError>) {
+    let mut ranges = Vec::new();
+    let mut position = 0;
+
+    while position < data.len() {
+        let remaining = data.len() - position;
+        if remaining < HEADER_SIZE {
+            let err = ParseError::TruncatedStream {
+                expected: HEADER_SIZE,
+                available: remaining,
+            };
+            return (ranges, Err(err.at_offset(position)));
+        }
+
+        let message_length = frame_len(&data[position..], cfg);
+        if remaining < message_length {
+            let err = ParseError::IncompletePayload {
+                expected: message_length,
+                actual: remaining,
+                offset: HEADER_SIZE,
+            };
+            return (ranges, Err(err.at_offset(position)));
+        }
+
+        ranges.push(position..position + message_length);
+        position += message_length;
+    }
+
+    (ranges, Ok(()))
+}
+
 // ============================================================================
 // Utility Functions
 // ============================================================================
@@ -316,7 +1630,8 @@ pub fn parse_multiple(data: &[u8]) -> Result<Vec<Message>, ParseError> {
 /// Calculates the XOR checksum of a byte slice
 ///
 /// The checksum is computed by XORing all bytes together,
-/// which provides a simple integrity check.
+/// which provides a simple integrity check. This is the checksum
+/// `Message::new()` stores.
 ///
 /// # Arguments
 /// * `data` - The bytes to checksum
@@ -326,14 +1641,93 @@ pub fn parse_multiple(data: &[u8]) -> Result<Vec<Message>, ParseError> {
 ///
 /// # Example
 /// ```
-/// // Internally used by Message::new()
-/// // 0x01 ^ 0x02 ^ 0x03 = 0x00
+/// use binary_protocol_parser::calculate_checksum;
+///
+/// assert_eq!(calculate_checksum(&[0x01, 0x02, 0x03]), 0x00);
+/// assert_eq!(calculate_checksum(&[0xAA, 0x55]), 0xFF);
 /// ```
-fn calculate_checksum(data: &[u8]) -> u8 {
+pub fn calculate_checksum(data: &[u8]) -> u8 {
     // XOR all bytes together, starting with 0
     data.iter().fold(0u8, |acc, &byte| acc ^ byte)
 }
 
+/// Checks a payload against a XOR checksum received alongside it
+///
+/// For frames that arrive through other means than `parse()`.
+///
+/// # Returns
+/// * `Ok(())` if `expected` is the XOR checksum of `payload`
+/// * `Err(ParseError::ChecksumMismatch)` otherwise
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::verify_checksum;
+///
+/// assert!(verify_checksum(&[0x11, 0x22], 0x33).is_ok());
+/// assert!(verify_checksum(&[0x11, 0x22], 0x00).is_err());
+/// ```
+pub fn verify_checksum(payload: &[u8], expected: u8) -> Result<(), ParseError> {
+    ChecksumAlgorithm::Xor.verify(payload, expected as u32)
+}
+
+/// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xFFFF, no reflection
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// CRC-32/ISO-HDLC: reflected polynomial 0xEDB88320, inverted in and out
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(0xFFFF_FFFF, |mut crc, &byte| {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+/// Length of the frame starting at `header`: header + payload + checksum
+///
+/// `header` must hold at least `HEADER_SIZE` bytes. The length field is read
+/// in `cfg.length_byte_order` and the trailer width follows `cfg.checksum`.
+fn frame_len(header: &[u8], cfg: &ValidationConfig) -> usize {
+    let length = cfg
+        .length_byte_order
+        .read_length(&header[LENGTH_OFFSET..HEADER_SIZE]) as usize;
+    HEADER_SIZE + length + cfg.checksum.trailer_len()
+}
+
+/// Value of a big-endian checksum trailer of any width
+fn trailer_value(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0u32, |acc, &byte| (acc << 8) | byte as u32)
+}
+
+/// Wire bytes of a checksum: the last `len` bytes of its big-endian form
+struct ChecksumTrailer {
+    bytes: [u8; 4],
+    len: usize,
+}
+
+impl ChecksumTrailer {
+    fn as_slice(&self) -> &[u8] {
+        &self.bytes[4 - self.len..]
+    }
+}
+
 /// Converts two bytes into a big-endian u16
 ///
 /// The first byte is the high byte, the second is the low byte.
@@ -372,6 +1766,70 @@ fn u16_to_bytes(value: u16) -> [u8; 2] {
     [(value >> 8) as u8, (value & 0xFF) as u8]
 }
 
+/// Fills `buf` from `reader`; `offset` bytes of the message were read before
+///
+/// Unlike `read_exact`, keeps count of a partial fill, so running out of
+/// input reports exactly how many bytes of the message arrived.
+fn read_field<R: io::Read>(
+    reader: &mut R,
+    buf: &mut [u8],
+    offset: usize,
+) -> Result<(), ParseError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = match reader.read(&mut buf[filled..]) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(read_error(e, offset + filled)),
+        };
+        if n == 0 {
+            return Err(read_error(
+                io::ErrorKind::UnexpectedEof.into(),
+                offset + filled,
+            ));
+        }
+        filled += n;
+    }
+    Ok(())
+}
+
+/// Async version of `read_field()`
+#[cfg(feature = "async")]
+async fn read_field_async<R: AsyncReadExt + Unpin>(
+    reader: &mut R,
+    buf: &mut [u8],
+    offset: usize,
+) -> Result<(), ParseError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = match reader.read(&mut buf[filled..]).await {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(read_error(e, offset + filled)),
+        };
+        if n == 0 {
+            return Err(read_error(
+                io::ErrorKind::UnexpectedEof.into(),
+                offset + filled,
+            ));
+        }
+        filled += n;
+    }
+    Ok(())
+}
+
+/// Maps a failed read to a `ParseError`
+///
+/// Running out of input is `UnexpectedEof` at byte `at_byte` of the message;
+/// anything else stays an I/O error.
+fn read_error(err: io::Error, at_byte: usize) -> ParseError {
+    if err.kind() == io::ErrorKind::UnexpectedEof {
+        ParseError::UnexpectedEof { at_byte }
+    } else {
+        ParseError::Io(err)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -386,6 +1844,20 @@ mod tests {
         assert_eq!(calculate_checksum(&[1, 2, 3]), 0);  // 1 ^ 2 ^ 3 = 0
     }
 
+    #[test]
+    fn test_verify_checksum() {
+        assert!(verify_checksum(&[], 0).is_ok());
+        assert!(verify_checksum(&[1, 2, 3], 0).is_ok());
+        assert!(matches!(
+            verify_checksum(&[1, 2, 4], 0),
+            Err(ParseError::ChecksumMismatch {
+                expected: 0,
+                calculated: 7,
+                offset: 7
+            })
+        ));
+    }
+
     #[test]
     fn test_calculate_checksum_hello_world() {
         // "Hello World" = H(0x48) e(0x65) l(0x6C) l(0x6C) o(0x6F) space(0x20) W(0x57) o(0x6F) r(0x72) l(0x6C) d(0x64)
@@ -459,6 +1931,8 @@ mod tests {
             message_type: 5,
             payload: vec![1, 2, 3],
             checksum: 0,
+            checksum_algorithm: ChecksumAlgorithm::Xor,
+            metadata: HashMap::new(),
         };
         assert!(msg.validate().is_err());
     }
@@ -470,69 +1944,357 @@ mod tests {
             message_type: 5,
             payload: vec![1, 2, 3],
             checksum: 99,  // Wrong checksum
+            checksum_algorithm: ChecksumAlgorithm::Xor,
+            metadata: HashMap::new(),
         };
         assert!(msg.validate().is_err());
     }
 
     #[test]
-    fn test_message_display() {
-        let msg = Message::new(1, 5, vec![1, 2, 3, 4, 5]);
-        let display_str = format!("{}", msg);
-        assert!(display_str.contains("v1"));
-        assert!(display_str.contains("type=5"));
-        assert!(display_str.contains("payload_len=5"));
+    fn test_metadata_is_out_of_band() {
+        let plain = Message::new(1, 5, vec![1, 2, 3]);
+        let annotated = Message::new(1, 5, vec![1, 2, 3])
+            .with_metadata("capture_interface", "eth0")
+            .with_metadata("packet_index", "42");
+
+        assert_eq!(annotated.metadata.len(), 2);
+        assert_eq!(annotated.to_bytes(), plain.to_bytes());
+        assert!(annotated.validate().is_ok());
+        assert_eq!(annotated, plain);
+
+        // Metadata never survives a round trip or a trip through the pool
+        assert!(parse(&annotated.to_bytes()).unwrap().metadata.is_empty());
+        let pool = MessagePool::new();
+        let mut pooled = parse_pooled(&plain.to_bytes(), &pool).unwrap();
+        pooled.metadata.insert("packet_index".to_string(), "1".to_string());
+        drop(pooled);
+        assert!(parse_pooled(&plain.to_bytes(), &pool).unwrap().metadata.is_empty());
+    }
+
+    #[test]
+    fn test_message_display() {
+        let msg = Message::new(1, 5, vec![1, 2, 3, 4, 5]);
+        let display_str = format!("{}", msg);
+        assert!(display_str.contains("v1"));
+        assert!(display_str.contains("type=5"));
+        assert!(display_str.contains("payload_len=5"));
+    }
+
+    // ========== Parsing Tests ==========
+
+    #[test]
+    fn test_parse_valid_simple() {
+        // Manually construct valid packet
+        let packet = vec![
+            0x01,           // version
+            0x05,           // message_type
+            0x00, 0x03,     // length = 3
+            0x01, 0x02, 0x03,  // payload
+            0x00,           // checksum (1 ^ 2 ^ 3 = 0)
+        ];
+
+        let msg = parse(&packet).expect("Parse failed");
+        assert_eq!(msg.version, 1);
+        assert_eq!(msg.message_type, 5);
+        assert_eq!(msg.payload, vec![1, 2, 3]);
+        assert_eq!(msg.checksum, 0);
+    }
+
+    #[test]
+    fn test_parse_message_too_short() {
+        let packet = vec![0x01, 0x05, 0x00];  // Only 3 bytes
+        assert!(parse(&packet).is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_version() {
+        let packet = vec![
+            0x02,           // invalid version
+            0x05,
+            0x00, 0x00,
+            0x00,
+        ];
+        let result = parse(&packet);
+        assert!(matches!(result, Err(ParseError::InvalidVersion { version: 2 })));
+    }
+
+    #[test]
+    fn test_parse_incomplete_payload() {
+        let packet = vec![
+            0x01,           // version
+            0x05,           // message_type
+            0x00, 0x05,     // length = 5 (but we won't provide it)
+            0x01, 0x02,     // only 2 bytes of payload
+            0x00,           // checksum
+        ];
+        assert!(matches!(
+            parse(&packet),
+            Err(ParseError::IncompletePayload { .. })
+        ));
+    }
+
+    #[test]
+    fn test_patch_payload_at_various_offsets() {
+        for offset in [0, 1, 7, 12, 15] {
+            let mut msg = Message::new(1, 7, (0u8..16).collect());
+            let patch = [0xDE, 0xAD, 0xBE, 0xEF];
+            let len = patch.len().min(16 - offset);
+            msg.patch_payload(offset, &patch[..len]).expect("Patch failed");
+
+            assert_eq!(&msg.payload[offset..offset + len], &patch[..len]);
+            assert_eq!(msg.payload.len(), 16);
+            assert!(msg.validate().is_ok());
+            // Round trip through the wire format
+            assert_eq!(parse(&msg.to_bytes()).expect("Parse failed"), msg);
+        }
+    }
+
+    #[test]
+    fn test_patch_payload_out_of_bounds() {
+        let mut msg = Message::new(1, 7, vec![1, 2, 3]);
+
+        assert!(matches!(
+            msg.patch_payload(2, &[9, 9]),
+            Err(ParseError::MessageTooShort { actual: 3 })
+        ));
+        assert!(msg.patch_payload(usize::MAX, &[9]).is_err());
+        assert_eq!(msg, Message::new(1, 7, vec![1, 2, 3]));
+
+        // Empty patch at the very end is a no-op
+        assert!(msg.patch_payload(3, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_resize_payload() {
+        let mut msg = Message::new(1, 7, vec![1, 2, 3]);
+
+        msg.resize_payload(6, 0x10);
+        assert_eq!(msg.payload, vec![1, 2, 3, 0x10, 0x10, 0x10]);
+        assert!(msg.validate().is_ok());
+
+        msg.resize_payload(1, 0x00);
+        assert_eq!(msg.payload, vec![1]);
+        assert!(msg.validate().is_ok());
+
+        msg.resize_payload(0, 0x00);
+        assert_eq!(parse(&msg.to_bytes()).expect("Parse failed"), msg);
+    }
+
+    #[test]
+    fn test_parse_truncated_stream() {
+        // Only version and type: the length field is cut off
+        assert!(matches!(
+            parse(&[0x01, 0x05]),
+            Err(ParseError::TruncatedStream {
+                expected: 4,
+                available: 2
+            })
+        ));
+        assert!(matches!(
+            parse(&[]),
+            Err(ParseError::TruncatedStream { available: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_min_message_size_v1() {
+        let min = min_message_size_for_version(1).unwrap();
+        let smallest = Message::new(1, 5, vec![]).to_bytes();
+        assert_eq!(smallest.len(), min);
+        assert_eq!(parse(&smallest).unwrap().payload, Vec::<u8>::new());
+        assert!(parse(&smallest[..min - 1]).is_err());
+    }
+
+    #[test]
+    fn test_min_message_size_v2() {
+        let cfg = ValidationConfig {
+            allowed_versions: vec![1, 2].into(),
+            ..ValidationConfig::strict()
+        };
+        let min = min_message_size_for_version(2).unwrap();
+        assert_eq!(min, 6);
+
+        // Flags byte only
+        let smallest = Message::new(2, 5, vec![0x80]).to_bytes();
+        assert_eq!(smallest.len(), min);
+        assert_eq!(parse_with_config(&smallest, &cfg).unwrap().payload, vec![0x80]);
+
+        // A complete frame without the flags byte is too small for v2
+        let no_flags = Message::new(2, 5, vec![]).to_bytes();
+        let err = parse_with_config(&no_flags, &cfg).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::BelowVersionMinimum {
+                version: 2,
+                min_size: 6,
+                actual: 5
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Version 2 requires at least 6 bytes, but the message has 5"
+        );
+        assert!(matches!(
+            Message::new(2, 5, vec![]).validate_with_config(&cfg),
+            Err(ParseError::BelowVersionMinimum { .. })
+        ));
+
+        // A header announcing more data is still just incomplete
+        assert!(matches!(
+            parse_with_config(&smallest[..5], &cfg),
+            Err(ParseError::IncompletePayload {
+                expected: 6,
+                actual: 5,
+                offset: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_min_message_size_v2_with_wide_checksum() {
+        let cfg = ValidationConfig {
+            allowed_versions: vec![2].into(),
+            checksum: ChecksumAlgorithm::Crc32,
+            ..ValidationConfig::strict()
+        };
+        let smallest = Message::new_with_config(2, 5, vec![0], &cfg).to_bytes();
+        assert_eq!(smallest.len(), 9);
+        assert!(parse_with_config(&smallest, &cfg).is_ok());
+
+        let no_flags = Message::new_with_config(2, 5, vec![], &cfg).to_bytes();
+        assert!(matches!(
+            parse_with_config(&no_flags, &cfg),
+            Err(ParseError::BelowVersionMinimum {
+                version: 2,
+                min_size: 9,
+                actual: 8
+            })
+        ));
+    }
+
+    #[test]
+    fn test_min_message_size_unknown_versions_use_v1_layout() {
+        assert_eq!(min_message_size_for_version(0), None);
+        assert_eq!(min_message_size_for_version(3), None);
+
+        // Accepted through the config, framed like v1
+        let cfg = ValidationConfig {
+            allowed_versions: vec![1, 3].into(),
+            ..ValidationConfig::strict()
+        };
+        let smallest = Message::new(3, 5, vec![]).to_bytes();
+        assert!(parse_with_config(&smallest, &cfg).is_ok());
+        assert!(matches!(
+            parse_with_config(&smallest[..4], &cfg),
+            Err(ParseError::IncompletePayload {
+                expected: 5,
+                actual: 4,
+                offset: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_short_input_checks_version_first() {
+        // Too short for any message, but the version alone is already wrong
+        assert!(matches!(
+            parse(&[9, 5]),
+            Err(ParseError::InvalidVersion { version: 9 })
+        ));
     }
 
-    // ========== Parsing Tests ==========
+    #[test]
+    fn test_parse_with_past_deadline() {
+        let bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        let past = Instant::now() - std::time::Duration::from_millis(10);
+
+        let err = parse_with_deadline(&bytes, past).unwrap_err();
+        assert!(matches!(err, ParseError::DeadlineExceeded { .. }));
+        assert!(err.to_string().starts_with("Parse deadline exceeded after"));
+
+        // Structural errors found before the first checkpoint still win
+        assert!(matches!(
+            parse_with_deadline(&[9, 5, 0, 0, 0], past),
+            Err(ParseError::InvalidVersion { version: 9 })
+        ));
+    }
 
     #[test]
-    fn test_parse_valid_simple() {
-        // Manually construct valid packet
-        let packet = vec![
-            0x01,           // version
-            0x05,           // message_type
-            0x00, 0x03,     // length = 3
-            0x01, 0x02, 0x03,  // payload
-            0x00,           // checksum (1 ^ 2 ^ 3 = 0)
-        ];
+    fn test_parse_multiple_with_deadline_is_total_budget() {
+        let data: Vec<u8> = (0..3u8)
+            .flat_map(|t| Message::new(1, t, vec![t; 4]).to_bytes())
+            .collect();
 
-        let msg = parse(&packet).expect("Parse failed");
-        assert_eq!(msg.version, 1);
-        assert_eq!(msg.message_type, 5);
-        assert_eq!(msg.payload, vec![1, 2, 3]);
-        assert_eq!(msg.checksum, 0);
+        let generous = Instant::now() + std::time::Duration::from_secs(60);
+        assert_eq!(parse_multiple_with_deadline(&data, generous).unwrap().len(), 3);
+
+        let err = parse_multiple_with_deadline(&data, Instant::now()).unwrap_err();
+        assert_eq!(err.offset(), Some(0));
+        assert!(matches!(err.root_cause(), ParseError::DeadlineExceeded { .. }));
     }
 
     #[test]
-    fn test_parse_message_too_short() {
-        let packet = vec![0x01, 0x05, 0x00];  // Only 3 bytes
-        assert!(parse(&packet).is_err());
+    fn test_parse_header_only_is_incomplete_payload() {
+        // Length field is readable (0) but the checksum byte is missing
+        assert!(matches!(
+            parse(&[0x01, 0x05, 0x00, 0x00]),
+            Err(ParseError::IncompletePayload {
+                expected: 5,
+                actual: 4,
+                offset: 4
+            })
+        ));
     }
 
     #[test]
-    fn test_parse_invalid_version() {
-        let packet = vec![
-            0x02,           // invalid version
-            0x05,
-            0x00, 0x00,
-            0x00,
-        ];
-        let result = parse(&packet);
-        assert!(matches!(result, Err(ParseError::InvalidVersion { version: 2 })));
+    fn test_parse_multiple_partial_last_packet() {
+        let mut data = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        data.extend_from_slice(&[0x01, 0x05, 0x00]); // second header cut short
+
+        let err = parse_multiple(&data).unwrap_err();
+        assert_eq!(err.offset(), Some(8));
+        assert!(matches!(
+            err.root_cause(),
+            ParseError::TruncatedStream { available: 3, .. }
+        ));
     }
 
     #[test]
-    fn test_parse_incomplete_payload() {
-        let packet = vec![
-            0x01,           // version
-            0x05,           // message_type
-            0x00, 0x05,     // length = 5 (but we won't provide it)
-            0x01, 0x02,     // only 2 bytes of payload
-            0x00,           // checksum
+    fn test_parse_multiple_with_remainder_across_chunks() {
+        let messages = vec![
+            Message::new(1, 1, vec![1, 2, 3]),
+            Message::new(1, 2, vec![]),
+            Message::new(1, 3, vec![9; 40]),
         ];
+        let stream: Vec<u8> = messages.iter().flat_map(Message::to_bytes).collect();
+
+        // Split the stream at every position into two reads
+        for split in 0..=stream.len() {
+            let (first, remainder) = parse_multiple_with_remainder(&stream[..split]).unwrap();
+
+            let mut carried = remainder.to_vec();
+            carried.extend_from_slice(&stream[split..]);
+            let (second, rest) = parse_multiple_with_remainder(&carried).unwrap();
+
+            assert!(rest.is_empty(), "split at {}", split);
+            let combined: Vec<Message> = first.into_iter().chain(second).collect();
+            assert_eq!(combined, messages, "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn test_parse_multiple_with_remainder_reports_bad_frame() {
+        let mut data = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        let mut bad = Message::new(1, 6, vec![4]).to_bytes();
+        bad[5] ^= 0xFF; // corrupt checksum
+        data.extend_from_slice(&bad);
+
+        let err = parse_multiple_with_remainder(&data).unwrap_err();
+        // Message at 8, its checksum trailer 5 bytes in
+        assert_eq!(err.offset(), Some(13));
         assert!(matches!(
-            parse(&packet),
-            Err(ParseError::IncompletPayload { .. })
+            err.root_cause(),
+            ParseError::ChecksumMismatch { .. }
         ));
     }
 
@@ -577,7 +2339,7 @@ mod tests {
         let result = parse(&packet);
         assert!(matches!(
             result,
-            Err(ParseError::PayloadTooLarge { .. }) | Err(ParseError::IncompletPayload { .. })
+            Err(ParseError::PayloadTooLarge { .. }) | Err(ParseError::IncompletePayload { .. })
         ));
     }
 
@@ -596,6 +2358,297 @@ mod tests {
         assert_eq!(messages[1].payload.len(), 4);
     }
 
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parse_multiple_concurrent_matches_sequential() {
+        let mut data = Vec::new();
+        for i in 0..500u32 {
+            let payload = (0..(i % 40) as u8).collect();
+            data.extend_from_slice(&Message::new(1, (i % 256) as u8, payload).to_bytes());
+        }
+
+        let sequential = parse_multiple(&data).unwrap();
+        let concurrent = parse_multiple_concurrent(std::sync::Arc::from(data), 4).unwrap();
+        assert_eq!(concurrent, sequential);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parse_multiple_concurrent_corrupt_length() {
+        let mut data = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        // Second message claims 0x0100 payload bytes but only has 1
+        data.extend_from_slice(&[0x01, 0x06, 0x01, 0x00, 0x07, 0x07]);
+
+        let err = parse_multiple_concurrent(std::sync::Arc::from(data), 2).unwrap_err();
+        // Message at 8, its payload 4 bytes in
+        assert_eq!(err.offset(), Some(12));
+        assert!(matches!(
+            err.root_cause(),
+            ParseError::IncompletePayload {
+                expected: 261,
+                actual: 6,
+                offset: 4
+            }
+        ));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parse_multiple_concurrent_reports_earliest_error() {
+        // Bad version in the first message, truncated header at the end
+        let mut data = vec![0x02, 0x05, 0x00, 0x00, 0x00];
+        data.extend_from_slice(&[0x01, 0x05]);
+
+        for err in [
+            parse_multiple_concurrent(std::sync::Arc::from(data.clone()), 2).unwrap_err(),
+            parse_multiple(&data).unwrap_err(),
+        ] {
+            assert_eq!(err.offset(), Some(0));
+            assert!(matches!(err.root_cause(), ParseError::InvalidVersion { version: 2 }));
+        }
+    }
+
+    #[test]
+    fn test_to_writer_matches_to_bytes() {
+        for payload in [vec![], vec![0xAB], (0..=255).collect::<Vec<u8>>()] {
+            let msg = Message::new(1, 9, payload);
+            let mut out = Vec::new();
+            msg.to_writer(&mut out).unwrap();
+            assert_eq!(out, msg.to_bytes());
+            assert_eq!(out.len(), msg.serialized_len());
+        }
+    }
+
+    #[test]
+    fn test_to_bytes_into_reuses_buffer() {
+        let large = Message::new(1, 1, vec![7; 300]);
+        let small = Message::new(1, 2, vec![1, 2]);
+
+        let mut buf = vec![0xEE; 10]; // stale contents are discarded
+        large.to_bytes_into(&mut buf);
+        assert_eq!(buf, large.to_bytes());
+
+        let ptr = buf.as_ptr();
+        let capacity = buf.capacity();
+        small.to_bytes_into(&mut buf);
+        assert_eq!(buf, small.to_bytes());
+        assert_eq!(buf.as_ptr(), ptr);
+        assert_eq!(buf.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_to_writer_propagates_errors() {
+        // Room for the header only
+        let msg = Message::new(1, 5, vec![1, 2, 3]);
+        let mut buf = [0u8; 4];
+        let mut writer = &mut buf[..];
+        let err = msg.to_writer(&mut writer).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn test_from_reader_reads_back_to_back_messages() {
+        let first = Message::new(1, 5, vec![1, 2, 3]);
+        let second = Message::new(1, 6, vec![]);
+        let mut bytes = first.to_bytes();
+        bytes.extend_from_slice(&second.to_bytes());
+
+        let mut cursor = io::Cursor::new(bytes);
+        assert_eq!(Message::from_reader(&mut cursor).unwrap(), first);
+        assert_eq!(Message::from_reader(&mut cursor).unwrap(), second);
+        assert!(matches!(
+            Message::from_reader(&mut cursor),
+            Err(ParseError::UnexpectedEof { at_byte: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_from_reader_truncated_payload() {
+        let bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        let mut cursor = io::Cursor::new(&bytes[..6]);
+        assert!(matches!(
+            Message::from_reader(&mut cursor),
+            Err(ParseError::UnexpectedEof { at_byte: 6 })
+        ));
+    }
+
+    #[test]
+    fn test_parse_multiple_lazy_large_buffer() {
+        // Just over 1 MB of messages with 1000-byte payloads
+        let mut data = Vec::new();
+        let mut count = 0;
+        while data.len() < 1 << 20 {
+            let msg = Message::new(1, (count % 256) as u8, vec![count as u8; 1000]);
+            data.extend_from_slice(&msg.to_bytes());
+            count += 1;
+        }
+
+        let messages = parse_multiple_lazy(&data);
+        // Only the input slice and a position are held, never parsed messages
+        assert!(std::mem::size_of_val(&messages) <= 4 * std::mem::size_of::<usize>());
+
+        let mut seen = 0;
+        let mut payload_bytes = 0;
+        for (i, result) in messages.enumerate() {
+            let msg = result.unwrap();
+            assert_eq!(msg.message_type, (i % 256) as u8);
+            payload_bytes += msg.payload.len();
+            seen += 1;
+        }
+        assert_eq!(seen, count);
+        assert_eq!(payload_bytes, count * 1000);
+
+        // Stops after the first error; data after the bad frame is not read
+        let mut bad = data[..2010].to_vec();
+        bad[1005] = 9;
+        let results: Vec<_> = parse_multiple_lazy(&bad).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(err.offset(), Some(1005));
+        assert!(matches!(
+            err.root_cause(),
+            ParseError::InvalidVersion { version: 9 }
+        ));
+
+        assert!(parse_multiple_lazy(&[]).next().is_none());
+    }
+
+    #[test]
+    fn test_with_payload_and_message_type() {
+        let cfg = ParseConfig {
+            checksum: ChecksumAlgorithm::Crc32,
+            ..ParseConfig::strict()
+        };
+        let msg = Message::new_with_config(1, 5, vec![1, 2, 3], &cfg)
+            .with_metadata("source", "test")
+            .with_payload(b"replaced".to_vec())
+            .with_message_type(7);
+
+        let expected = Message::new_with_config(1, 7, b"replaced".to_vec(), &cfg);
+        assert_eq!(msg, expected);
+        assert_eq!(msg.metadata["source"], "test");
+        assert_eq!(parse_with_config(&msg.to_bytes(), &cfg).unwrap(), msg);
+    }
+
+    #[test]
+    fn test_payload_as_utf8() {
+        let msg = Message::new(1, 5, br#"{"cmd":"start"}"#.to_vec());
+        assert_eq!(msg.payload_as_utf8().unwrap(), r#"{"cmd":"start"}"#);
+        assert!(matches!(msg.payload_as_utf8_lossy(), Cow::Borrowed(_)));
+
+        let msg = Message::new(1, 5, vec![b'o', b'k', 0xC3]);
+        assert_eq!(msg.payload_as_utf8().unwrap_err().valid_up_to(), 2);
+        assert_eq!(msg.payload_as_utf8_lossy(), "ok\u{FFFD}");
+
+        assert_eq!(Message::new(1, 5, vec![]).payload_as_utf8().unwrap(), "");
+    }
+
+    #[test]
+    fn test_parse_error_converts_with_question_mark() {
+        fn read_message(data: &[u8]) -> io::Result<Message> {
+            let msg = parse(data)?;
+            Ok(msg)
+        }
+
+        let msg = Message::new(1, 5, vec![1, 2, 3]);
+        assert_eq!(read_message(&msg.to_bytes()).unwrap(), msg);
+
+        let mut bytes = msg.to_bytes();
+        bytes[6] ^= 0xFF;
+        assert_eq!(
+            read_message(&bytes).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            read_message(&bytes[..2]).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_parse_from_reader_cursor() {
+        let messages = [
+            Message::new(1, 1, b"first".to_vec()),
+            Message::new(1, 2, vec![]),
+            Message::new(1, 3, vec![0xAB; 300]),
+        ];
+        let mut bytes: Vec<u8> = messages.iter().flat_map(Message::to_bytes).collect();
+        bytes.extend_from_slice(&[1, 4, 0, 2, 9]); // header and 1 of 2 payload bytes
+
+        let mut cursor = io::Cursor::new(bytes);
+        let mut end = 0;
+        for msg in &messages {
+            assert_eq!(&parse_from_reader(&mut cursor).unwrap(), msg);
+            // Exactly one message was consumed
+            end += msg.serialized_len();
+            assert_eq!(cursor.position() as usize, end);
+        }
+        assert!(matches!(
+            parse_from_reader(&mut cursor),
+            Err(ParseError::UnexpectedEof { at_byte: 5 })
+        ));
+    }
+
+    #[test]
+    fn test_from_reader_validates() {
+        let mut bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        bytes[6] ^= 0xFF;
+        assert!(matches!(
+            Message::from_reader(&mut io::Cursor::new(bytes)),
+            Err(ParseError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_from_async_reader() {
+        let msg = Message::new(1, 5, vec![1, 2, 3]);
+        let bytes = msg.to_bytes();
+
+        let mut cursor = io::Cursor::new(bytes.clone());
+        assert_eq!(Message::from_async_reader(&mut cursor).await.unwrap(), msg);
+
+        let mut cursor = io::Cursor::new(&bytes[..bytes.len() - 1]);
+        assert!(matches!(
+            Message::from_async_reader(&mut cursor).await,
+            Err(ParseError::UnexpectedEof { at_byte: 7 })
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_parse_from_async_reader_duplex() {
+        use tokio::io::AsyncWriteExt;
+
+        let messages = vec![
+            Message::new(1, 1, b"first".to_vec()),
+            Message::new(1, 2, vec![0x5A; 200]),
+        ];
+        let bytes: Vec<u8> = messages.iter().flat_map(Message::to_bytes).collect();
+
+        // A small buffer forces the writer to wait for the reader
+        let (mut client, mut server) = tokio::io::duplex(16);
+        let writer = async move {
+            for chunk in bytes.chunks(3) {
+                client.write_all(chunk).await.unwrap();
+            }
+            // Dropping `client` closes the stream
+        };
+        let reader = async {
+            let mut received = Vec::new();
+            for _ in 0..messages.len() {
+                received.push(parse_from_async_reader(&mut server).await.unwrap());
+            }
+            let end = parse_from_async_reader(&mut server).await;
+            (received, end)
+        };
+
+        let ((), (received, end)) = tokio::join!(writer, reader);
+        assert_eq!(received, messages);
+        assert!(matches!(end, Err(ParseError::UnexpectedEof { at_byte: 0 })));
+    }
+
     #[test]
     fn test_round_trip() {
         let original = Message::new(1, 10, vec![0x48, 0x65, 0x6C, 0x6C, 0x6F]);
@@ -607,4 +2660,303 @@ mod tests {
         assert_eq!(parsed.payload, original.payload);
         assert_eq!(parsed.checksum, original.checksum);
     }
+
+    // ========== Validation Config Tests ==========
+
+    #[test]
+    fn test_validate_with_config_allows_listed_version() {
+        let msg = Message::new(2, 5, vec![1, 2, 3]);
+        let cfg = ValidationConfig {
+            allowed_versions: vec![1, 2].into(),
+            ..ValidationConfig::strict()
+        };
+
+        assert!(msg.validate_with_config(&cfg).is_ok());
+        assert!(matches!(
+            msg.validate(),
+            Err(ParseError::InvalidVersion { version: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_validate_with_config_skip_checksum() {
+        let msg = Message {
+            version: 1,
+            message_type: 5,
+            payload: vec![1, 2, 3],
+            checksum: 99,  // Wrong checksum
+            checksum_algorithm: ChecksumAlgorithm::Xor,
+            metadata: HashMap::new(),
+        };
+        let cfg = ValidationConfig {
+            skip_checksum: true,
+            ..ValidationConfig::strict()
+        };
+
+        assert!(msg.validate_with_config(&cfg).is_ok());
+        assert!(msg.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_with_config_max_payload_len() {
+        let msg = Message::new(1, 5, vec![0; 10]);
+        let cfg = ValidationConfig {
+            max_payload_len: 4,
+            ..ValidationConfig::strict()
+        };
+
+        assert!(matches!(
+            msg.validate_with_config(&cfg),
+            Err(ParseError::PayloadTooLarge {
+                size: 10,
+                max: 4,
+                offset: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_permissive_config() {
+        let cfg = ValidationConfig::permissive();
+        assert!(cfg.allows_version(0));
+        assert!(cfg.allows_version(255));
+        assert!(cfg.skip_checksum);
+        assert_eq!(cfg.max_payload_len, MAX_PAYLOAD_SIZE);
+        assert_eq!(ValidationConfig::default(), ValidationConfig::strict());
+    }
+
+    #[test]
+    fn test_parse_with_config_accepts_v1_and_v2() {
+        let cfg = ParseConfig {
+            allowed_versions: (1..=2).into(),
+            ..ParseConfig::strict()
+        };
+        let v1 = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        let v2 = Message::new(2, 5, vec![1, 2, 3]).to_bytes();
+        let v3 = Message::new(3, 5, vec![1, 2, 3]).to_bytes();
+
+        assert_eq!(parse_with_config(&v1, &cfg).unwrap().version, 1);
+        assert_eq!(parse_with_config(&v2, &cfg).unwrap().version, 2);
+        assert!(matches!(
+            parse_with_config(&v3, &cfg),
+            Err(ParseError::InvalidVersion { version: 3 })
+        ));
+        // parse() still accepts version 1 only
+        assert!(matches!(
+            parse(&v2),
+            Err(ParseError::InvalidVersion { version: 2 })
+        ));
+    }
+
+    // ========== Length Byte Order Tests ==========
+
+    fn config_with_order(length_byte_order: ByteOrder) -> ParseConfig {
+        ParseConfig {
+            length_byte_order,
+            ..ParseConfig::strict()
+        }
+    }
+
+    #[test]
+    fn test_big_endian_frame_misread_as_little_endian() {
+        let big = config_with_order(ByteOrder::BigEndian);
+        let little = config_with_order(ByteOrder::LittleEndian);
+        let msg = Message::new(1, 5, vec![1, 2, 3]);
+
+        // Length [0x00, 0x03] read low byte first claims 0x0300 bytes
+        let bytes = msg.to_bytes_with_config(&big);
+        assert!(matches!(
+            parse_with_config(&bytes, &little),
+            Err(ParseError::IncompletePayload {
+                expected: 773,
+                actual: 8,
+                offset: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_length_byte_order_round_trip() {
+        let msg = Message::new(1, 5, (0..=255).collect());
+        for order in [ByteOrder::BigEndian, ByteOrder::LittleEndian] {
+            let cfg = config_with_order(order);
+            let bytes = msg.to_bytes_with_config(&cfg);
+            assert_eq!(parse_with_config(&bytes, &cfg).unwrap(), msg, "{:?}", order);
+        }
+
+        // Big-endian is what to_bytes() writes; little-endian swaps the length only
+        let big = msg.to_bytes_with_config(&config_with_order(ByteOrder::BigEndian));
+        let little = msg.to_bytes_with_config(&config_with_order(ByteOrder::LittleEndian));
+        assert_eq!(big, msg.to_bytes());
+        assert_eq!(big[2..4], [0x01, 0x00]);
+        assert_eq!(little[2..4], [0x00, 0x01]);
+        assert_eq!(big[4..], little[4..]);
+    }
+
+    // ========== Checksum Algorithm Tests ==========
+
+    fn config_with(checksum: ChecksumAlgorithm) -> ParseConfig {
+        ParseConfig {
+            checksum,
+            ..ParseConfig::strict()
+        }
+    }
+
+    #[test]
+    fn test_crc_check_values() {
+        // Standard check input for both CRC catalog entries
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc16(&[]), 0xFFFF);
+        assert_eq!(crc32(&[]), 0);
+    }
+
+    #[test]
+    fn test_crc_trailer_layout() {
+        let cfg = config_with(ChecksumAlgorithm::Crc16);
+        let msg = Message::new_with_config(1, 5, b"123456789".to_vec(), &cfg);
+        let bytes = msg.to_bytes();
+
+        assert_eq!(bytes.len(), msg.serialized_len());
+        assert_eq!(&bytes[bytes.len() - 2..], &[0x29, 0xB1]);
+        assert_eq!(parse_with_config(&bytes, &cfg).unwrap(), msg);
+
+        let mut out = Vec::new();
+        msg.to_writer(&mut out).unwrap();
+        assert_eq!(out, bytes);
+    }
+
+    #[test]
+    fn test_crc32_round_trip_and_truncation() {
+        let cfg = config_with(ChecksumAlgorithm::Crc32);
+        let msg = Message::new_with_config(1, 9, vec![0xDE, 0xAD, 0xBE, 0xEF], &cfg);
+        let bytes = msg.to_bytes();
+        assert_eq!(bytes.len(), 4 + 4 + 4);
+
+        let parsed = parse_with_config(&bytes, &cfg).unwrap();
+        assert_eq!(parsed.checksum, crc32(&[0xDE, 0xAD, 0xBE, 0xEF]));
+        assert_eq!(parsed.checksum_algorithm, ChecksumAlgorithm::Crc32);
+
+        assert!(matches!(
+            parse_with_config(&bytes[..bytes.len() - 1], &cfg),
+            Err(ParseError::IncompletePayload {
+                expected: 12,
+                actual: 11,
+                offset: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_crc_messages_validate_with_own_algorithm() {
+        for checksum in [ChecksumAlgorithm::Crc16, ChecksumAlgorithm::Crc32] {
+            let cfg = config_with(checksum);
+            let mut msg = Message::new_with_config(1, 5, b"Hello World".to_vec(), &cfg);
+            assert!(msg.validate().is_ok());
+
+            msg = msg.with_payload(vec![0xAA; 40]);
+            assert!(msg.validate().is_ok());
+            msg.patch_payload(3, &[1, 2, 3]).unwrap();
+            assert!(msg.validate().is_ok());
+            msg.resize_payload(7, 0x55);
+            assert!(msg.validate().is_ok());
+            assert_eq!(msg.checksum, checksum.compute(&msg.payload));
+
+            // Strict rules other than the checksum still apply
+            assert!(msg.validate_with_config(&ValidationConfig::strict()).is_ok());
+            msg.checksum ^= 1;
+            assert!(matches!(
+                msg.validate(),
+                Err(ParseError::ChecksumMismatch { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_crc_from_reader_round_trip() {
+        for checksum in [ChecksumAlgorithm::Crc16, ChecksumAlgorithm::Crc32] {
+            let cfg = config_with(checksum);
+            let first = Message::new_with_config(1, 5, vec![1, 2, 3], &cfg);
+            let second = Message::new_with_config(1, 6, vec![], &cfg);
+            let mut bytes = first.to_bytes();
+            bytes.extend_from_slice(&second.to_bytes());
+
+            let mut reader = io::Cursor::new(bytes);
+            assert_eq!(Message::from_reader_with_config(&mut reader, &cfg).unwrap(), first);
+            assert_eq!(Message::from_reader_with_config(&mut reader, &cfg).unwrap(), second);
+            assert!(matches!(
+                Message::from_reader_with_config(&mut reader, &cfg),
+                Err(ParseError::UnexpectedEof { at_byte: 0 })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_crc16_detects_every_single_bit_flip() {
+        // Exhaustive over each payload's bits, trailer included
+        let cfg = config_with(ChecksumAlgorithm::Crc16);
+        let payloads: [&[u8]; 4] = [&[], &[0x00], b"Hello World", &[0xFF; 64]];
+
+        for payload in payloads {
+            let bytes = Message::new_with_config(1, 5, payload.to_vec(), &cfg).to_bytes();
+            for bit in HEADER_SIZE * 8..bytes.len() * 8 {
+                let mut corrupted = bytes.clone();
+                corrupted[bit / 8] ^= 1 << (bit % 8);
+                assert!(
+                    matches!(
+                        parse_with_config(&corrupted, &cfg),
+                        Err(ParseError::ChecksumMismatch { .. })
+                    ),
+                    "flip of bit {} in {:?} went undetected",
+                    bit,
+                    payload
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_xor_misses_paired_flips_crc16_does_not() {
+        let payload = vec![0x10, 0x20, 0x30];
+        for algorithm in [ChecksumAlgorithm::Xor, ChecksumAlgorithm::Crc16] {
+            let cfg = config_with(algorithm);
+            let mut bytes = Message::new_with_config(1, 5, payload.clone(), &cfg).to_bytes();
+            // Same bit flipped in two payload bytes
+            bytes[HEADER_SIZE] ^= 0x01;
+            bytes[HEADER_SIZE + 1] ^= 0x01;
+
+            let result = parse_with_config(&bytes, &cfg);
+            match algorithm {
+                ChecksumAlgorithm::Xor => assert!(result.is_ok()),
+                _ => assert!(result.is_err()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_supported_versions_set() {
+        let versions = SupportedVersions::from(vec![1, 4]);
+        assert!(versions.contains(1));
+        assert!(versions.contains(4));
+        assert!(!versions.contains(2));
+        assert_eq!(versions, SupportedVersions::from(HashSet::from([4, 1])));
+    }
+
+    #[test]
+    fn test_parse_with_config_legacy_v0() {
+        let packet = vec![
+            0x00,           // legacy version
+            0x05,           // message_type
+            0x00, 0x02,     // length = 2
+            0x01, 0x02,     // payload
+            0x00,           // wrong checksum (should be 0x03)
+        ];
+
+        assert!(parse(&packet).is_err());
+
+        let msg = parse_with_config(&packet, &ValidationConfig::permissive())
+            .expect("Parse failed");
+        assert_eq!(msg.version, 0);
+        assert_eq!(msg.payload, vec![1, 2]);
+    }
 }
@@ -0,0 +1,180 @@
+//! Reusing `Message` allocations across parses
+//!
+//! `parse()` allocates a fresh payload `Vec` for every message. When messages
+//! are parsed, processed and dropped in a tight loop, a `MessagePool` hands
+//! out `Message`s whose payload buffers are kept between uses, so steady-state
+//! parsing allocates nothing.
+
+use crate::Message;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// A free list of `Message`s shared by the parses that use it
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::pool::MessagePool;
+/// use binary_protocol_parser::{parse_pooled, Message};
+///
+/// let pool = MessagePool::new();
+/// let bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+///
+/// for _ in 0..3 {
+///     let msg = parse_pooled(&bytes, &pool).unwrap();
+///     assert_eq!(msg.payload, vec![1, 2, 3]);
+/// } // each message goes back to the pool here
+///
+/// assert_eq!(pool.alloc_count(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct MessagePool {
+    free: Mutex<Vec<Message>>,
+    alloc_count: AtomicU64,
+}
+
+impl MessagePool {
+    /// Creates an empty pool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes a message from the pool, allocating one if the pool is empty
+    ///
+    /// The message's payload is empty but may have capacity left over from
+    /// an earlier use.
+    pub fn acquire(&self) -> PooledMessage<'_> {
+        let reused = self.lock_free().pop();
+        let inner = reused.unwrap_or_else(|| {
+            self.alloc_count.fetch_add(1, Ordering::Relaxed);
+            Message::new(0, 0, Vec::new())
+        });
+
+        PooledMessage {
+            inner: Some(inner),
+            pool: self,
+        }
+    }
+
+    /// Number of messages this pool has had to allocate
+    pub fn alloc_count(&self) -> u64 {
+        self.alloc_count.load(Ordering::Relaxed)
+    }
+
+    /// Number of messages waiting to be reused
+    pub fn available(&self) -> usize {
+        self.lock_free().len()
+    }
+
+    /// Returns a message to the free list, keeping its payload capacity
+    fn release(&self, mut message: Message) {
+        message.payload.clear();
+        message.metadata.clear();
+        self.lock_free().push(message);
+    }
+
+    /// The free list is always left consistent, so a poisoned lock is safe to use
+    fn lock_free(&self) -> std::sync::MutexGuard<'_, Vec<Message>> {
+        self.free.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A `Message` borrowed from a `MessagePool`, returned to it on drop
+///
+/// Derefs to `Message`. Use `into_inner()` to keep the message instead.
+#[derive(Debug)]
+pub struct PooledMessage<'pool> {
+    inner: Option<Message>,
+    pool: &'pool MessagePool,
+}
+
+impl PooledMessage<'_> {
+    /// Detaches the message from the pool
+    pub fn into_inner(mut self) -> Message {
+        self.inner.take().expect("message is present until drop")
+    }
+}
+
+impl Deref for PooledMessage<'_> {
+    type Target = Message;
+
+    fn deref(&self) -> &Message {
+        self.inner.as_ref().expect("message is present until drop")
+    }
+}
+
+impl DerefMut for PooledMessage<'_> {
+    fn deref_mut(&mut self) -> &mut Message {
+        self.inner.as_mut().expect("message is present until drop")
+    }
+}
+
+impl Drop for PooledMessage<'_> {
+    fn drop(&mut self) {
+        if let Some(message) = self.inner.take() {
+            self.pool.release(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_pooled;
+
+    #[test]
+    fn test_acquire_reuses_released_messages() {
+        let pool = MessagePool::new();
+
+        let first = pool.acquire();
+        let second = pool.acquire();
+        assert_eq!(pool.alloc_count(), 2);
+
+        drop(first);
+        drop(second);
+        assert_eq!(pool.available(), 2);
+
+        let _again = pool.acquire();
+        assert_eq!(pool.alloc_count(), 2);
+        assert_eq!(pool.available(), 1);
+    }
+
+    #[test]
+    fn test_reused_payload_keeps_capacity() {
+        let pool = MessagePool::new();
+        let big = Message::new(1, 5, vec![7; 1024]).to_bytes();
+        let small = Message::new(1, 6, vec![1, 2]).to_bytes();
+
+        let ptr = {
+            let msg = parse_pooled(&big, &pool).unwrap();
+            msg.payload.as_ptr()
+        };
+
+        let msg = parse_pooled(&small, &pool).unwrap();
+        assert_eq!(msg.message_type, 6);
+        assert_eq!(msg.payload, vec![1, 2]);
+        assert_eq!(msg.checksum, 1 ^ 2);
+        assert!(msg.payload.capacity() >= 1024);
+        assert_eq!(msg.payload.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_failed_parse_returns_message_to_pool() {
+        let pool = MessagePool::new();
+        let mut bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        *bytes.last_mut().unwrap() ^= 0xFF;
+
+        assert!(parse_pooled(&bytes, &pool).is_err());
+        assert_eq!(pool.available(), 1);
+    }
+
+    #[test]
+    fn test_into_inner_detaches_message() {
+        let pool = MessagePool::new();
+        let bytes = Message::new(1, 5, vec![9]).to_bytes();
+
+        let msg = parse_pooled(&bytes, &pool).unwrap().into_inner();
+        assert_eq!(msg.payload, vec![9]);
+        assert_eq!(pool.available(), 0);
+    }
+}
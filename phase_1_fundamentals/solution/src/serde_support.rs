@@ -0,0 +1,149 @@
+//! Helpers behind the `serde` feature
+//!
+//! Payloads are written as base64 strings so that JSON output stays valid
+//! UTF-8 whatever the bytes are. A deserialized `Message` never trusts an
+//! incoming checksum: it is recalculated from the payload.
+
+use crate::{ChecksumAlgorithm, Message};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The fields a `Message` is deserialized from; the checksum is not one
+#[derive(Deserialize)]
+pub(crate) struct MessageFields {
+    version: u8,
+    message_type: u8,
+    #[serde(with = "base64")]
+    payload: Vec<u8>,
+    #[serde(default)]
+    checksum_algorithm: ChecksumAlgorithm,
+    #[serde(default)]
+    metadata: HashMap<String, String>,
+}
+
+impl From<MessageFields> for Message {
+    fn from(fields: MessageFields) -> Self {
+        let mut message = Message::with_checksum_algorithm(
+            fields.version,
+            fields.message_type,
+            fields.payload,
+            fields.checksum_algorithm,
+        );
+        message.metadata = fields.metadata;
+        message
+    }
+}
+
+/// Standard base64 (RFC 4648) with padding, for `#[serde(with = ...)]`
+pub(crate) mod base64 {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode(bytes))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        decode(&text).ok_or_else(|| de::Error::custom("invalid base64 payload"))
+    }
+
+    pub(crate) fn encode(bytes: &[u8]) -> String {
+        let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let b = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let group = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+        let text = text.as_bytes();
+        if !text.len().is_multiple_of(4) {
+            return None;
+        }
+
+        let mut out = Vec::with_capacity(text.len() / 4 * 3);
+        for (index, chunk) in text.chunks(4).enumerate() {
+            let last = index == text.len() / 4 - 1;
+            let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+            if padding > 2 || (padding > 0 && !last) {
+                return None;
+            }
+
+            let mut group = 0u32;
+            for &c in &chunk[..4 - padding] {
+                let value = ALPHABET.iter().position(|&a| a == c)? as u32;
+                group = group << 6 | value;
+            }
+            group <<= 6 * padding;
+
+            let bytes = group.to_be_bytes();
+            out.extend_from_slice(&bytes[1..4 - padding]);
+        }
+        Some(out)
+    }
+}
+
+/// `std::io::Error` as its message text; deserializes to `ErrorKind::Other`
+pub(crate) mod io_error {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::io;
+
+    pub(crate) fn serialize<S: Serializer>(
+        err: &io::Error,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&err.to_string())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<io::Error, D::Error> {
+        String::deserialize(deserializer).map(io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::base64::{decode, encode};
+
+    #[test]
+    fn test_base64_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_base64_rejects_malformed() {
+        assert!(decode("Zm9").is_none());
+        assert!(decode("Zm=v").is_none());
+        assert!(decode("Zg==Zg==").is_none());
+        assert!(decode("Z!==").is_none());
+    }
+}
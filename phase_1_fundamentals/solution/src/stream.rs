@@ -0,0 +1,201 @@
+//! Incremental parsing of a byte stream
+//!
+//! Socket reads rarely line up with message boundaries: one read may end in
+//! the middle of a header, the next may carry the rest of that message and
+//! two more. A `StreamParser` buffers whatever arrives and hands out each
+//! message once all of its bytes are in.
+
+use crate::error::ParseError;
+use crate::{frame_len, parse_with_config, Message, ParseConfig, HEADER_SIZE};
+use std::collections::VecDeque;
+use std::io;
+
+/// Accumulates written bytes and yields complete `Message`s
+///
+/// Write received bytes with `std::io::Write`, then call `next_message()`
+/// until it returns `None`. Messages are parsed with `parse()` rules, or
+/// with those of a `ParseConfig` given to `with_config()`.
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::stream::StreamParser;
+/// use binary_protocol_parser::Message;
+/// use std::io::Write;
+///
+/// let bytes = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+/// let mut parser = StreamParser::new();
+///
+/// parser.write_all(&bytes[..3]).unwrap();
+/// assert!(parser.next_message().is_none()); // header not complete yet
+///
+/// parser.write_all(&bytes[3..]).unwrap();
+/// let msg = parser.next_message().unwrap().unwrap();
+/// assert_eq!(msg.payload, vec![1, 2, 3]);
+/// ```
+#[derive(Debug, Default)]
+pub struct StreamParser {
+    buffer: VecDeque<u8>,
+    cfg: ParseConfig,
+}
+
+impl StreamParser {
+    /// Creates a parser with an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a parser that frames and checks messages following `cfg`
+    ///
+    /// `cfg.checksum` sets the trailer width, so both ends must agree on it.
+    pub fn with_config(cfg: ParseConfig) -> Self {
+        Self {
+            buffer: VecDeque::new(),
+            cfg,
+        }
+    }
+
+    /// Takes the next complete message off the buffer
+    ///
+    /// # Returns
+    /// * `None` if the next message hasn't fully arrived yet
+    /// * `Some(Ok(Message))` for a complete, valid message
+    /// * `Some(Err(ParseError))` for a complete frame that fails validation;
+    ///   the frame is still consumed, so the next call starts at the
+    ///   following message
+    pub fn next_message(&mut self) -> Option<Result<Message, ParseError>> {
+        if self.buffer.len() < HEADER_SIZE {
+            return None;
+        }
+
+        let header = [self.buffer[0], self.buffer[1], self.buffer[2], self.buffer[3]];
+        let frame_len = frame_len(&header, &self.cfg);
+        if self.buffer.len() < frame_len {
+            return None;
+        }
+
+        let frame: Vec<u8> = self.buffer.drain(..frame_len).collect();
+        Some(parse_with_config(&frame, &self.cfg))
+    }
+
+    /// Number of bytes received but not yet returned as messages
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+impl io::Write for StreamParser {
+    /// Buffers all of `buf`; never fails
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChecksumAlgorithm;
+    use std::io::Write;
+
+    fn sample_messages() -> Vec<Message> {
+        vec![
+            Message::new(1, 1, vec![]),
+            Message::new(1, 2, vec![0xAA]),
+            Message::new(1, 3, (0..=200).collect()),
+            Message::new(1, 4, b"Hello World".to_vec()),
+        ]
+    }
+
+    #[test]
+    fn test_tcp_like_small_writes() {
+        let messages = sample_messages();
+        let stream: Vec<u8> = messages.iter().flat_map(Message::to_bytes).collect();
+
+        // Deliver 1, 2, 3, 1, 2, 3, ... bytes per write
+        let mut parser = StreamParser::new();
+        let mut received = Vec::new();
+        let mut position = 0;
+        for chunk_len in [1, 2, 3].into_iter().cycle() {
+            if position == stream.len() {
+                break;
+            }
+            let end = (position + chunk_len).min(stream.len());
+            parser.write_all(&stream[position..end]).unwrap();
+            position = end;
+
+            while let Some(result) = parser.next_message() {
+                received.push(result.unwrap());
+            }
+        }
+
+        assert_eq!(received, messages);
+        assert_eq!(parser.buffered_len(), 0);
+    }
+
+    #[test]
+    fn test_many_messages_in_one_write() {
+        let messages = sample_messages();
+        let mut stream: Vec<u8> = messages.iter().flat_map(Message::to_bytes).collect();
+        // Plus the first two bytes of another message
+        stream.extend_from_slice(&[1, 9]);
+
+        let mut parser = StreamParser::new();
+        parser.write_all(&stream).unwrap();
+
+        let received: Vec<Message> = std::iter::from_fn(|| parser.next_message())
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(received, messages);
+        assert_eq!(parser.buffered_len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_frame_is_skipped() {
+        let mut bad = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        bad[7] ^= 0xFF; // corrupt checksum
+        let good = Message::new(1, 6, vec![4]);
+
+        let mut parser = StreamParser::new();
+        parser.write_all(&bad).unwrap();
+        parser.write_all(&good.to_bytes()).unwrap();
+
+        assert!(matches!(
+            parser.next_message(),
+            Some(Err(ParseError::ChecksumMismatch { .. }))
+        ));
+        assert_eq!(parser.next_message().unwrap().unwrap(), good);
+        assert!(parser.next_message().is_none());
+    }
+
+    #[test]
+    fn test_crc_frames_use_configured_trailer() {
+        for checksum in [ChecksumAlgorithm::Crc16, ChecksumAlgorithm::Crc32] {
+            let cfg = ParseConfig {
+                checksum,
+                ..ParseConfig::strict()
+            };
+            let messages: Vec<Message> = sample_messages()
+                .into_iter()
+                .map(|msg| Message::new_with_config(1, msg.message_type, msg.payload, &cfg))
+                .collect();
+            let stream: Vec<u8> = messages.iter().flat_map(Message::to_bytes).collect();
+
+            let mut parser = StreamParser::with_config(cfg);
+            parser.write_all(&stream[..stream.len() - 1]).unwrap();
+            let mut received: Vec<Message> = std::iter::from_fn(|| parser.next_message())
+                .map(Result::unwrap)
+                .collect();
+            // The last trailer byte is still missing
+            assert_eq!(received.len(), messages.len() - 1);
+
+            parser.write_all(&stream[stream.len() - 1..]).unwrap();
+            received.push(parser.next_message().unwrap().unwrap());
+            assert_eq!(received, messages);
+            assert_eq!(parser.buffered_len(), 0);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Tag-Length-Value records inside a payload
+//!
+//! Each record is 1 tag byte, 1 length byte, then `length` value bytes, so
+//! a single value holds at most 255 bytes. Records follow each other with
+//! no padding. A value may itself be a TLV sequence; decode it with
+//! `parse_fields()` to walk nested records.
+//!
+//! Error offsets count from the start of the TLV data, not the message.
+
+use crate::error::ParseError;
+use crate::MAX_PAYLOAD_SIZE;
+
+/// Tag byte plus length byte in front of every value
+const TLV_HEADER_SIZE: usize = 2;
+
+/// Longest value the 1-byte length field can describe
+pub const MAX_VALUE_LEN: usize = u8::MAX as usize;
+
+/// One Tag-Length-Value record
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TlvField {
+    /// Application-defined record type
+    pub tag: u8,
+
+    /// Record contents (at most 255 bytes)
+    pub value: Vec<u8>,
+}
+
+impl TlvField {
+    /// Creates a record from a tag and its value
+    pub fn new(tag: u8, value: impl Into<Vec<u8>>) -> Self {
+        TlvField {
+            tag,
+            value: value.into(),
+        }
+    }
+
+    /// Number of bytes this record takes once encoded
+    pub fn encoded_len(&self) -> usize {
+        TLV_HEADER_SIZE + self.value.len()
+    }
+}
+
+/// Decodes a run of TLV records
+///
+/// # Returns
+/// * `Ok(Vec<TlvField>)` with one entry per record, in order
+/// * `Err(ParseError::TruncatedStream)` if a record header is cut short
+/// * `Err(ParseError::IncompletePayload)` if a value runs past the end
+///
+/// # Example
+/// ```
+/// use binary_protocol_parser::tlv::{parse_fields, TlvField};
+///
+/// let fields = parse_fields(&[0x01, 0x02, 0xAA, 0xBB, 0x02, 0x00]).unwrap();
+/// assert_eq!(fields, vec![TlvField::new(1, [0xAA, 0xBB]), TlvField::new(2, [])]);
+/// ```
+pub fn parse_fields(data: &[u8]) -> Result<Vec<TlvField>, ParseError> {
+    let mut fields = Vec::new();
+    let mut rest = data;
+
+    while !rest.is_empty() {
+        let position = data.len() - rest.len();
+        if rest.len() < TLV_HEADER_SIZE {
+            return Err(ParseError::TruncatedStream {
+                expected: TLV_HEADER_SIZE,
+                available: rest.len(),
+            });
+        }
+
+        let tag = rest[0];
+        let length = rest[1] as usize;
+        let value = &rest[TLV_HEADER_SIZE..];
+        if value.len() < length {
+            return Err(ParseError::IncompletePayload {
+                expected: length,
+                actual: value.len(),
+                offset: position + TLV_HEADER_SIZE,
+            });
+        }
+
+        fields.push(TlvField::new(tag, &value[..length]));
+        rest = &value[length..];
+    }
+
+    Ok(fields)
+}
+
+/// Encodes records back to back
+///
+/// # Returns
+/// * `Ok(Vec<u8>)` with every record encoded
+/// * `Err(ParseError::PayloadTooLarge)` if a value is longer than 255 bytes
+///   (`offset` is its length byte) or the encoding is longer than a message
+///   payload may be (`offset` is the first record that doesn't fit)
+pub fn encode_fields(fields: &[TlvField]) -> Result<Vec<u8>, ParseError> {
+    let total: usize = fields.iter().map(TlvField::encoded_len).sum();
+    if total > MAX_PAYLOAD_SIZE {
+        let mut position = 0;
+        for field in fields {
+            if position + field.encoded_len() > MAX_PAYLOAD_SIZE {
+                break;
+            }
+            position += field.encoded_len();
+        }
+        return Err(ParseError::PayloadTooLarge {
+            size: total,
+            max: MAX_PAYLOAD_SIZE,
+            offset: position,
+        });
+    }
+
+    let mut out = Vec::with_capacity(total);
+    for field in fields {
+        if field.value.len() > MAX_VALUE_LEN {
+            return Err(ParseError::PayloadTooLarge {
+                size: field.value.len(),
+                max: MAX_VALUE_LEN,
+                offset: out.len() + 1,
+            });
+        }
+        out.push(field.tag);
+        out.push(field.value.len() as u8);
+        out.extend_from_slice(&field.value);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Message};
+
+    #[test]
+    fn test_tlv_round_trip() {
+        let fields = vec![
+            TlvField::new(1, b"alice".to_vec()),
+            TlvField::new(2, []),
+            TlvField::new(3, vec![0xFF; MAX_VALUE_LEN]),
+        ];
+
+        let msg = Message::build_from_tlv(1, 8, &fields).unwrap();
+        assert_eq!(msg.payload.len(), 7 + 2 + 257);
+        assert!(msg.validate().is_ok());
+
+        let parsed = parse(&msg.to_bytes()).unwrap();
+        assert_eq!(parsed.parse_tlv_fields().unwrap(), fields);
+    }
+
+    #[test]
+    fn test_nested_tlv() {
+        let inner = vec![TlvField::new(0x10, [1, 2]), TlvField::new(0x11, [3])];
+        let outer = vec![
+            TlvField::new(0x01, [0x42]),
+            TlvField::new(0x02, encode_fields(&inner).unwrap()),
+        ];
+
+        let msg = Message::build_from_tlv(1, 3, &outer).unwrap();
+        assert_eq!(
+            msg.payload,
+            vec![0x01, 1, 0x42, 0x02, 7, 0x10, 2, 1, 2, 0x11, 1, 3]
+        );
+
+        let fields = msg.parse_tlv_fields().unwrap();
+        assert_eq!(fields, outer);
+        assert_eq!(parse_fields(&fields[1].value).unwrap(), inner);
+    }
+
+    #[test]
+    fn test_payload_overflow() {
+        // 257 records of 2 + 255 bytes: 257 * 257 = 66049 bytes > 65535
+        let fields = vec![TlvField::new(7, vec![0; MAX_VALUE_LEN]); 257];
+        assert!(matches!(
+            Message::build_from_tlv(1, 3, &fields),
+            Err(ParseError::PayloadTooLarge {
+                size: 66049,
+                max: MAX_PAYLOAD_SIZE,
+                offset: 65535
+            })
+        ));
+
+        // 255 such records fill a payload exactly (65535 bytes)
+        let fields = &fields[..255];
+        assert!(Message::build_from_tlv(1, 3, fields).is_ok());
+    }
+
+    #[test]
+    fn test_value_too_long() {
+        let fields = [
+            TlvField::new(1, [0xAA]),
+            TlvField::new(2, vec![0; MAX_VALUE_LEN + 1]),
+        ];
+        assert!(matches!(
+            encode_fields(&fields),
+            Err(ParseError::PayloadTooLarge {
+                size: 256,
+                max: MAX_VALUE_LEN,
+                offset: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn test_malformed_records() {
+        // Tag without a length byte
+        assert!(matches!(
+            Message::new(1, 3, vec![0x01, 0x01, 0xAA, 0x02]).parse_tlv_fields(),
+            Err(ParseError::TruncatedStream {
+                expected: 2,
+                available: 1
+            })
+        ));
+
+        // Length claims 4 bytes, only 2 follow
+        assert!(matches!(
+            parse_fields(&[0x01, 0x00, 0x02, 0x04, 0xAA, 0xBB]),
+            Err(ParseError::IncompletePayload {
+                expected: 4,
+                actual: 2,
+                offset: 4
+            })
+        ));
+
+        assert!(parse_fields(&[]).unwrap().is_empty());
+    }
+}
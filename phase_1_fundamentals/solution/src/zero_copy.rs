@@ -0,0 +1,98 @@
+//! Messages that borrow their payload from the receive buffer
+//!
+//! `parse()` copies every payload into a fresh `Vec`. `parse_zero_copy()`
+//! instead returns a `MessageRef` whose payload is a `bytes::Bytes` slice of
+//! the input: cloning or slicing it only bumps a reference count, and the
+//! buffer is freed once the last message referring to it is dropped.
+
+use crate::{ChecksumAlgorithm, Message};
+use bytes::Bytes;
+use std::collections::HashMap;
+
+/// A parsed message whose payload shares the original buffer
+///
+/// Same fields as `Message` (minus `metadata`), with the payload held as
+/// `Bytes`. Use `to_owned()` when an independent `Message` is needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageRef {
+    /// Protocol version (typically 1)
+    pub version: u8,
+
+    /// Type/command identifier
+    pub message_type: u8,
+
+    /// Message payload, a slice of the parsed buffer
+    pub payload: Bytes,
+
+    /// XOR checksum of payload for integrity verification
+    pub checksum: u32,
+}
+
+impl MessageRef {
+    /// The payload as a plain byte slice
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Copies the payload into an owned `Message`
+    pub fn to_owned(&self) -> Message {
+        Message {
+            version: self.version,
+            message_type: self.message_type,
+            payload: self.payload.to_vec(),
+            checksum: self.checksum,
+            checksum_algorithm: ChecksumAlgorithm::Xor,
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+impl From<MessageRef> for Message {
+    fn from(message: MessageRef) -> Self {
+        message.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ParseError;
+    use crate::{parse, parse_zero_copy, Message};
+    use bytes::Bytes;
+
+    #[test]
+    fn test_payload_shares_input_buffer() {
+        let data = Bytes::from(Message::new(1, 5, vec![10, 20, 30]).to_bytes());
+        let msg = parse_zero_copy(data.clone()).unwrap();
+
+        assert_eq!(msg.payload(), &[10, 20, 30]);
+        assert_eq!(msg.payload.as_ptr(), data[4..].as_ptr());
+    }
+
+    #[test]
+    fn test_matches_parse() {
+        for payload in [vec![], vec![0xAB], (0..=255).collect::<Vec<u8>>()] {
+            let bytes = Message::new(1, 9, payload).to_bytes();
+            let borrowed = parse_zero_copy(Bytes::from(bytes.clone())).unwrap();
+            assert_eq!(Message::from(borrowed), parse(&bytes).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_same_errors_as_parse() {
+        let mut corrupt = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+        corrupt[7] ^= 0xFF;
+        assert!(matches!(
+            parse_zero_copy(Bytes::from(corrupt)),
+            Err(ParseError::ChecksumMismatch { .. })
+        ));
+
+        assert!(matches!(
+            parse_zero_copy(Bytes::from_static(&[2, 5, 0, 0, 0])),
+            Err(ParseError::InvalidVersion { version: 2 })
+        ));
+        assert!(matches!(
+            parse_zero_copy(Bytes::from_static(&[1, 5, 0, 3, 1])),
+            Err(ParseError::IncompletePayload { .. })
+        ));
+    }
+}
@@ -1,6 +1,6 @@
-/// Integration tests for the binary protocol parser
-///
-/// These tests verify end-to-end functionality with complete protocol messages
+//! Integration tests for the binary protocol parser
+//!
+//! These tests verify end-to-end functionality with complete protocol messages
 
 use binary_protocol_parser::{parse, parse_multiple, Message};
 
@@ -36,7 +36,7 @@ fn test_parse_hello_world() {
     assert_eq!(msg.message_type, 5);
     assert_eq!(msg.payload.len(), 11);
     assert_eq!(msg.payload, payload);
-    assert_eq!(msg.checksum, expected_checksum);
+    assert_eq!(msg.checksum, expected_checksum as u32);
 
     // Verify display format includes version, type, and length
     let display_str = msg.to_string();
@@ -99,7 +99,7 @@ fn test_parse_multiple_messages() {
     assert_eq!(messages[1].payload, vec![0xAA, 0xBB, 0xCC, 0xDD]);
 
     assert_eq!(messages[2].message_type, 3);
-    assert_eq!(messages[2].payload.is_empty(), true);
+    assert!(messages[2].payload.is_empty());
 }
 
 /// Test parsing empty payload message
@@ -191,12 +191,12 @@ fn test_binary_payload_with_nulls() {
 fn test_checksum_correctness() {
     // Test specific known checksums
     let tests = vec![
-        (vec![], 0u8),                           // Empty: 0
-        (vec![5], 5u8),                          // Single byte: 5
-        (vec![5, 5], 0u8),                       // 5 ^ 5 = 0
-        (vec![1, 2, 3], 0u8),                    // 1 ^ 2 ^ 3 = 0
-        (vec![0xFF, 0xFF], 0u8),                 // All ones: 0
-        (vec![0xAA, 0x55], 0xFFu8),              // Complementary: 0xFF
+        (vec![], 0u32),                          // Empty: 0
+        (vec![5], 5u32),                         // Single byte: 5
+        (vec![5, 5], 0u32),                      // 5 ^ 5 = 0
+        (vec![1, 2, 3], 0u32),                   // 1 ^ 2 ^ 3 = 0
+        (vec![0xFF, 0xFF], 0u32),                // All ones: 0
+        (vec![0xAA, 0x55], 0xFFu32),             // Complementary: 0xFF
     ];
 
     for (payload, expected_checksum) in tests {
@@ -246,3 +246,149 @@ fn test_stress_many_messages() {
     assert_eq!(messages[50].message_type, 50);
     assert_eq!(messages[99].message_type, 99);
 }
+
+/// Test that a message survives a JSON round trip (requires `--features serde`)
+///
+/// The payload travels as base64 and the checksum is recalculated on the way in.
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_json_round_trip() {
+    use binary_protocol_parser::ChecksumAlgorithm;
+
+    let mut original = Message::new(1, 7, vec![0x00, 0xFF, b'H', b'i']);
+    original
+        .metadata
+        .insert("interface".to_string(), "eth0".to_string());
+
+    let json = serde_json::to_string(&original).unwrap();
+    assert!(json.contains("\"payload\":\"AP9IaQ==\""));
+
+    let decoded: Message = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, original);
+    assert_eq!(decoded.metadata, original.metadata);
+
+    // A tampered checksum in the input is ignored, not trusted
+    let tampered = json.replace(
+        &format!("\"checksum\":{}", original.checksum),
+        "\"checksum\":0",
+    );
+    let decoded: Message = serde_json::from_str(&tampered).unwrap();
+    assert_eq!(decoded.checksum, original.checksum);
+    assert!(decoded.validate().is_ok());
+
+    // Checksum algorithm and metadata are optional in the input
+    let minimal = r#"{"version":1,"message_type":2,"payload":"AQID"}"#;
+    let decoded: Message = serde_json::from_str(minimal).unwrap();
+    assert_eq!(decoded, Message::new(1, 2, vec![1, 2, 3]));
+    assert_eq!(decoded.checksum_algorithm, ChecksumAlgorithm::Xor);
+
+    let invalid = r#"{"version":1,"message_type":2,"payload":"not base64"}"#;
+    assert!(serde_json::from_str::<Message>(invalid).is_err());
+}
+
+/// Test that parse errors, including `Io`, serialize to JSON and back
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_json_parse_error_round_trip() {
+    use binary_protocol_parser::error::ParseError;
+    use std::time::Duration;
+
+    let mut corrupt = Message::new(1, 5, vec![1, 2, 3]).to_bytes();
+    corrupt[7] ^= 0xFF;
+    let err = parse(&corrupt).unwrap_err();
+    let json = serde_json::to_string(&err).unwrap();
+    let decoded: ParseError = serde_json::from_str(&json).unwrap();
+    assert!(matches!(decoded, ParseError::ChecksumMismatch { .. }));
+    assert_eq!(decoded.to_string(), err.to_string());
+
+    let nested = ParseError::WithContext {
+        offset: 12,
+        source: Box::new(ParseError::DeadlineExceeded {
+            elapsed: Duration::from_millis(5),
+        }),
+    };
+    let json = serde_json::to_string(&nested).unwrap();
+    assert!(matches!(
+        serde_json::from_str(&json).unwrap(),
+        ParseError::WithContext { offset: 12, source }
+            if matches!(*source, ParseError::DeadlineExceeded { elapsed } if elapsed == Duration::from_millis(5))
+    ));
+
+    let io = ParseError::Io(std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        "connection closed",
+    ));
+    let json = serde_json::to_string(&io).unwrap();
+    assert_eq!(json, r#"{"Io":"connection closed"}"#);
+    match serde_json::from_str(&json).unwrap() {
+        ParseError::Io(err) => assert_eq!(err.to_string(), "connection closed"),
+        other => panic!("expected Io, got {:?}", other),
+    }
+}
+
+/// Test framing messages both ways over an in-memory duplex stream
+/// (requires `--features codec`)
+///
+/// The 16-byte duplex buffer splits larger messages across many reads.
+#[cfg(feature = "codec")]
+#[tokio::test]
+async fn test_codec_framed_over_duplex() {
+    use binary_protocol_parser::codec::MessageCodec;
+    use futures::{SinkExt, StreamExt};
+    use tokio_util::codec::Framed;
+
+    let (client, server) = tokio::io::duplex(16);
+    let mut client = Framed::new(client, MessageCodec::new());
+    let mut server = Framed::new(server, MessageCodec::new());
+
+    // Echo every message back with its type incremented
+    let echo = tokio::spawn(async move {
+        let mut echoed = 0;
+        while let Some(msg) = server.next().await {
+            let msg = msg.expect("server decode failed");
+            let reply_type = msg.message_type + 1;
+            let reply = msg.with_message_type(reply_type);
+            server.send(reply).await.unwrap();
+            echoed += 1;
+        }
+        echoed
+    });
+
+    let requests = [(1, b"Hello".to_vec()), (3, vec![]), (5, vec![0xA5; 300])];
+    for (message_type, payload) in &requests {
+        let request = Message::new(1, *message_type, payload.clone());
+        client.send(request).await.unwrap();
+        let reply = client.next().await.unwrap().expect("client decode failed");
+        assert_eq!(reply, Message::new(1, message_type + 1, payload.clone()));
+    }
+
+    // Closing the client ends the server's stream cleanly
+    drop(client);
+    assert_eq!(echo.await.unwrap(), requests.len());
+}
+
+/// Test that a stream closed mid-message is reported, not silently dropped
+/// (requires `--features codec`)
+#[cfg(feature = "codec")]
+#[tokio::test]
+async fn test_codec_duplex_closed_mid_message() {
+    use binary_protocol_parser::codec::MessageCodec;
+    use binary_protocol_parser::error::ParseError;
+    use futures::StreamExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio_util::codec::FramedRead;
+
+    let (mut client, server) = tokio::io::duplex(64);
+    let mut server = FramedRead::new(server, MessageCodec::new());
+
+    let complete = Message::new(1, 5, vec![1, 2, 3]);
+    let partial = Message::new(1, 6, vec![4, 5, 6, 7]).to_bytes();
+    client.write_all(&complete.to_bytes()).await.unwrap();
+    client.write_all(&partial[..6]).await.unwrap();
+    drop(client);
+
+    assert_eq!(server.next().await.unwrap().unwrap(), complete);
+    let err = server.next().await.unwrap().unwrap_err();
+    assert!(matches!(err, ParseError::IncompletePayload { .. }));
+    assert!(server.next().await.is_none());
+}